@@ -0,0 +1,93 @@
+//! Derive macros for the `datacake-rpc` framework.
+//!
+//! These are re-exported by `datacake-rpc` and should be used from there.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{parenthesized, parse_macro_input, DeriveInput, LitStr, Token, Type};
+
+#[proc_macro_derive(RpcService, attributes(rpc))]
+/// Derives `RpcService`, registering each of the listed message handlers.
+///
+/// See `datacake_rpc::RpcService` for the supported attributes.
+pub fn derive_rpc_service(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_rpc_service(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct ServiceAttrs {
+    name: Option<LitStr>,
+    handles: Vec<Type>,
+    cacheable: Vec<Type>,
+}
+
+fn expand_rpc_service(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let attrs = parse_attrs(&input)?;
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let service_name = attrs.name.map(|name| {
+        quote! {
+            fn service_name() -> &'static str {
+                #name
+            }
+        }
+    });
+    let handles = attrs.handles;
+    let cacheable = attrs.cacheable;
+
+    Ok(quote! {
+        impl #impl_generics ::datacake_rpc::RpcService for #ident #ty_generics #where_clause {
+            #service_name
+
+            fn register_handlers(registry: &mut ::datacake_rpc::ServiceRegistry<Self>) {
+                #(registry.add_handler::<#handles>();)*
+                #(registry.add_cacheable_handler::<#cacheable>();)*
+            }
+        }
+    })
+}
+
+fn parse_attrs(input: &DeriveInput) -> syn::Result<ServiceAttrs> {
+    let mut attrs = ServiceAttrs::default();
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("rpc"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                if attrs.name.is_some() {
+                    return Err(meta.error("duplicate `name` attribute"));
+                }
+                attrs.name = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("handles") {
+                attrs.handles.extend(parse_types(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("cacheable") {
+                attrs.cacheable.extend(parse_types(&meta)?);
+                Ok(())
+            } else {
+                Err(meta.error(
+                    "unknown rpc attribute, expected `name`, `handles` or `cacheable`",
+                ))
+            }
+        })?;
+    }
+
+    Ok(attrs)
+}
+
+fn parse_types(meta: &syn::meta::ParseNestedMeta) -> syn::Result<Vec<Type>> {
+    let content;
+    parenthesized!(content in meta.input);
+    let types = Punctuated::<Type, Token![,]>::parse_terminated(&content)?;
+    Ok(types.into_iter().collect())
+}
@@ -0,0 +1,109 @@
+//! Measures the round trip of a ping RPC.
+//!
+//! Compares a unit message, which is sent as an empty body and viewed
+//! without being read or validated, against a message carrying a single
+//! integer which goes through the regular serialization path.
+//!
+//! Run via `cargo bench -p datacake-rpc --bench ping`.
+
+use std::future::Future;
+use std::hint::black_box;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::runtime::Runtime;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive)]
+#[archive(check_bytes)]
+pub struct PingSeq(u64);
+
+pub struct PingService;
+
+impl RpcService for PingService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<()>();
+        registry.add_handler::<PingSeq>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<()> for PingService {
+    type Reply = ();
+
+    async fn on_message(&self, _msg: Request<()>) -> Result<Self::Reply, Status> {
+        Ok(())
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<PingSeq> for PingService {
+    type Reply = ();
+
+    async fn on_message(&self, _msg: Request<PingSeq>) -> Result<Self::Reply, Status> {
+        Ok(())
+    }
+}
+
+const MIN_DURATION: Duration = Duration::from_secs(2);
+
+fn measure<F, Fut>(rt: &Runtime, mut op: F) -> Duration
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    rt.block_on(async {
+        // Warm up the connection.
+        op().await;
+
+        let mut iterations = 0;
+        let start = Instant::now();
+        while start.elapsed() < MIN_DURATION {
+            op().await;
+            iterations += 1;
+        }
+        start.elapsed() / iterations
+    })
+}
+
+fn main() {
+    let rt = Runtime::new().unwrap();
+
+    let addr = "127.0.0.1:0".parse::<SocketAddr>().unwrap();
+    let server = rt.block_on(Server::listen(addr)).unwrap();
+    server.add_service(PingService);
+    let client = rt.block_on(async {
+        // Negotiates sending unit messages as an empty body.
+        let channel = Channel::connect(server.local_addr());
+        channel.warmup().await.unwrap();
+        RpcClient::<PingService>::new(channel)
+    });
+
+    let seq = measure(&rt, || async {
+        black_box(client.send(&PingSeq(1)).await.unwrap());
+    });
+    let unit = measure(&rt, || async {
+        black_box(client.send(&()).await.unwrap());
+    });
+
+    println!("{:>14} {:>14} {:>8}", "serialized", "empty", "speedup");
+    println!(
+        "{:>14?} {:>14?} {:>7.2}x",
+        seq,
+        unit,
+        seq.as_secs_f64() / unit.as_secs_f64(),
+    );
+
+    server.shutdown();
+}
@@ -0,0 +1,65 @@
+//! Measures the cost of turning a message into a request body.
+//!
+//! Compares sending the serializer's buffer as-is against the previous
+//! behaviour of copying it into an intermediate `Vec` first.
+//!
+//! Run via `cargo bench -p datacake-rpc --bench serialize`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use datacake_rpc::{to_view_bytes, Body, TryAsBody};
+use rkyv::{Archive, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Archive)]
+pub struct Payload {
+    key: u64,
+    data: Vec<u8>,
+}
+
+const SIZES: [usize; 4] = [4 << 10, 256 << 10, 4 << 20, 64 << 20];
+const MIN_DURATION: Duration = Duration::from_secs(1);
+
+fn measure(mut op: impl FnMut()) -> Duration {
+    // Warm up the allocator.
+    op();
+
+    let mut iterations = 0;
+    let start = Instant::now();
+    while start.elapsed() < MIN_DURATION {
+        op();
+        iterations += 1;
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    println!(
+        "{:>10} {:>14} {:>14} {:>8}",
+        "size", "copied", "owned", "speedup"
+    );
+
+    for size in SIZES {
+        let payload = Payload {
+            key: 1,
+            data: vec![7; size],
+        };
+
+        let copied = measure(|| {
+            let buffer = to_view_bytes(&payload).unwrap();
+            black_box(Body::from(buffer.to_vec()));
+        });
+        let owned = measure(|| {
+            black_box(payload.try_as_body().unwrap());
+        });
+
+        println!(
+            "{:>10} {:>14?} {:>14?} {:>7.2}x",
+            format!("{}KiB", size >> 10),
+            copied,
+            owned,
+            copied.as_secs_f64() / owned.as_secs_f64(),
+        );
+    }
+}
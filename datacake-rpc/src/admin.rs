@@ -0,0 +1,113 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::server::ServerState;
+use crate::{Handler, Request, RpcService, ServiceRegistry, Status};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug, Clone, PartialEq, Eq)]
+#[archive_attr(derive(Debug))]
+/// Information about a request currently being handled by the server.
+pub struct InflightInfo {
+    /// The unique id of the request within the server.
+    pub id: u64,
+    /// The name of the service handling the request.
+    pub service: String,
+    /// The message path of the request.
+    pub path: String,
+    /// The address of the client which sent the request.
+    pub remote_addr: SocketAddr,
+    /// The time the request started being handled, since the unix epoch.
+    pub started_at: Duration,
+    /// How long the request has been running for at the time of the snapshot.
+    pub elapsed: Duration,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug, Clone, Copy)]
+/// Requests a snapshot of the requests currently being handled by the server.
+///
+/// The server replies with a list of [InflightInfo] entries.
+pub struct InflightRequests;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug, Clone, Copy)]
+/// Aborts the in-flight request with the given id.
+///
+/// The server replies with `true` if the request was found and aborted.
+/// See [Server::abort_request](crate::Server::abort_request).
+pub struct AbortRequest {
+    /// The id of the request to abort.
+    pub id: u64,
+}
+
+/// An administrative service for inspecting a live server remotely.
+///
+/// This is registered with [Server::enable_admin_service](crate::Server::enable_admin_service)
+/// and can be queried with a regular [RpcClient](crate::RpcClient).
+///
+/// ```rust
+/// use datacake_rpc::{AdminService, Channel, InflightRequests, RpcClient, Server};
+/// use std::net::SocketAddr;
+///
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let bind = "127.0.0.1:7999".parse::<SocketAddr>()?;
+/// let server = Server::listen(bind).await?;
+/// server.enable_admin_service();
+///
+/// let client = RpcClient::<AdminService>::new(Channel::connect(bind));
+/// let inflight = client.send(&InflightRequests).await?;
+/// for request in inflight.iter() {
+///     println!("{}/{} running for {:?}", request.service, request.path, request.elapsed);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct AdminService {
+    state: ServerState,
+}
+
+impl AdminService {
+    pub(crate) fn new(state: ServerState) -> Self {
+        Self { state }
+    }
+}
+
+impl RpcService for AdminService {
+    fn service_name() -> &'static str {
+        "datacake-admin"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<InflightRequests>();
+        registry.add_handler::<AbortRequest>();
+    }
+}
+
+#[async_trait]
+impl Handler<InflightRequests> for AdminService {
+    type Reply = Vec<InflightInfo>;
+
+    async fn on_message(
+        &self,
+        _msg: Request<InflightRequests>,
+    ) -> Result<Self::Reply, Status> {
+        Ok(self.state.inflight_requests())
+    }
+}
+
+#[async_trait]
+impl Handler<AbortRequest> for AdminService {
+    type Reply = bool;
+
+    async fn on_message(
+        &self,
+        msg: Request<AbortRequest>,
+    ) -> Result<Self::Reply, Status> {
+        Ok(self.state.abort_request(msg.id))
+    }
+}
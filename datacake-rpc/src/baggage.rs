@@ -0,0 +1,315 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+
+use http::{HeaderMap, HeaderValue};
+
+/// The W3C baggage header.
+pub const BAGGAGE_HEADER: &str = "baggage";
+
+/// The maximum size of an encoded baggage header in bytes, as set by the
+/// W3C baggage specification.
+const MAX_HEADER_LEN: usize = 8192;
+
+tokio::task_local! {
+    static CURRENT: Baggage;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Key-value pairs propagated alongside requests, i.e. the tenant, locale or
+/// feature flags a call graph is running with.
+///
+/// Baggage is sent in the [BAGGAGE_HEADER] header using the
+/// [W3C baggage](https://www.w3.org/TR/baggage/) encoding and can be read by
+/// handlers via [Request::baggage](crate::Request::baggage). While a handler runs,
+/// its request's baggage is the [current](Baggage::current) baggage, which every
+/// [RpcClient](crate::RpcClient) call made from within the handler attaches to
+/// its own request. This propagates the baggage through the call graph without
+/// any manual plumbing.
+///
+/// The current baggage is task-local, calls made from tasks spawned by the
+/// handler only propagate it if the task is run within [Baggage::scope].
+///
+/// ```rust
+/// use datacake_rpc::Baggage;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut baggage = Baggage::new();
+/// baggage.insert("tenant", "acme");
+///
+/// baggage
+///     .scope(async {
+///         // Any requests sent here carry the tenant.
+///         assert_eq!(Baggage::current().get("tenant"), Some("acme"));
+///     })
+///     .await;
+/// # }
+/// ```
+pub struct Baggage {
+    entries: BTreeMap<String, String>,
+}
+
+impl Baggage {
+    /// Creates empty baggage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The baggage of the current task, empty if it is not running
+    /// within a [Baggage::scope] or handler.
+    pub fn current() -> Self {
+        CURRENT.try_with(Self::clone).unwrap_or_default()
+    }
+
+    /// Runs the future with this as the [current](Baggage::current) baggage.
+    pub async fn scope<F>(self, future: F) -> F::Output
+    where
+        F: Future,
+    {
+        CURRENT.scope(self, future).await
+    }
+
+    /// Sets the value of a key, returning its previous value.
+    ///
+    /// Keys must be a valid HTTP token, entries with other keys are dropped
+    /// when the baggage is sent. Values can be any string.
+    pub fn insert(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Option<String> {
+        self.entries.insert(key.into(), value.into())
+    }
+
+    /// Removes a key, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.entries.remove(key)
+    }
+
+    /// The value of a key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+
+    /// Iterates over the entries, ordered by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds the entries of `other`, replacing the value of any existing keys.
+    pub fn extend(&mut self, other: Baggage) {
+        self.entries.extend(other.entries);
+    }
+
+    /// Reads the baggage from every [BAGGAGE_HEADER] header.
+    ///
+    /// Entry properties are ignored, as are any malformed entries.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut baggage = Self::new();
+
+        let members = headers
+            .get_all(BAGGAGE_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','));
+        for member in members {
+            // Properties follow the value, separated by `;`.
+            let pair = member.split(';').next().unwrap_or_default();
+            let Some((key, value)) = pair.split_once('=') else {
+                continue;
+            };
+
+            let key = key.trim();
+            if !is_token(key) {
+                continue;
+            }
+            if let Some(value) = percent_decode(value.trim()) {
+                baggage.insert(key, value);
+            }
+        }
+
+        baggage
+    }
+
+    /// Encodes the baggage as a header value, `None` if it is empty.
+    ///
+    /// Entries are dropped if their key is not a valid token or once the
+    /// header would exceed the 8192 bytes allowed by the specification.
+    pub fn to_header_value(&self) -> Option<HeaderValue> {
+        let mut encoded = String::new();
+        for (key, value) in self.iter() {
+            if !is_token(key) {
+                debug!(key, "Dropping baggage entry with an invalid key.");
+                continue;
+            }
+
+            let mut member = format!("{key}=");
+            percent_encode(value, &mut member);
+            let separator = usize::from(!encoded.is_empty());
+            if encoded.len() + separator + member.len() > MAX_HEADER_LEN {
+                warn!(key, "Dropping baggage entry exceeding the maximum size.");
+                continue;
+            }
+
+            if separator != 0 {
+                encoded.push(',');
+            }
+            encoded.push_str(&member);
+        }
+
+        if encoded.is_empty() {
+            return None;
+        }
+        Some(HeaderValue::try_from(encoded).expect("Baggage is encoded as ASCII"))
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for Baggage
+where
+    K: Into<String>,
+    V: Into<String>,
+{
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let entries = iter
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        Self { entries }
+    }
+}
+
+/// Attaches the [current](Baggage::current) baggage to an outgoing request
+/// unless it already carries baggage.
+pub(crate) fn propagate(headers: &mut HeaderMap) {
+    if headers.contains_key(BAGGAGE_HEADER) {
+        return;
+    }
+
+    let value = CURRENT
+        .try_with(|baggage| baggage.to_header_value())
+        .ok()
+        .flatten();
+    if let Some(value) = value {
+        headers.insert(BAGGAGE_HEADER, value);
+    }
+}
+
+fn is_token(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+/// Encodes any characters which are not allowed in a baggage value.
+fn percent_encode(value: &str, out: &mut String) {
+    for b in value.bytes() {
+        // Excludes whitespace, `"`, `,`, `;`, `\\` and non-ASCII characters.
+        let allowed = matches!(
+            b,
+            0x21 | 0x23..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E
+        );
+        if allowed && b != b'%' {
+            out.push(b as char);
+        } else {
+            let _ = write!(out, "%{b:02X}");
+        }
+    }
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let mut decoded = Vec::with_capacity(value.len());
+    let mut bytes = value.bytes();
+    while let Some(b) = bytes.next() {
+        if b != b'%' {
+            decoded.push(b);
+            continue;
+        }
+
+        let hex = [bytes.next()?, bytes.next()?];
+        let hex = std::str::from_utf8(&hex).ok()?;
+        decoded.push(u8::from_str_radix(hex, 16).ok()?);
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_roundtrip() {
+        let baggage = Baggage::from_iter([
+            ("tenant", "acme"),
+            ("locale", "fr-FR"),
+            ("note", "50% off, \"today\"; ünïcode"),
+        ]);
+
+        let value = baggage.to_header_value().unwrap();
+        assert_eq!(
+            value,
+            "locale=fr-FR,note=50%25%20off%2C%20%22today%22%3B%20%C3%BCn%C3%AFcode,tenant=acme"
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(BAGGAGE_HEADER, value);
+        assert_eq!(Baggage::from_headers(&headers), baggage);
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            BAGGAGE_HEADER,
+            HeaderValue::from_static("a = 1 ; prop=x, b=2,malformed, bad key=3"),
+        );
+        headers.append(BAGGAGE_HEADER, HeaderValue::from_static("c=%3, d=%34"));
+
+        let baggage = Baggage::from_headers(&headers);
+        assert_eq!(
+            baggage.iter().collect::<Vec<_>>(),
+            [("a", "1"), ("b", "2"), ("d", "4")]
+        );
+    }
+
+    #[test]
+    fn test_invalid_and_oversized_entries() {
+        let mut baggage = Baggage::new();
+        baggage.insert("bad key", "1");
+        assert!(baggage.to_header_value().is_none());
+
+        baggage.insert("a", "x".repeat(MAX_HEADER_LEN - 2));
+        baggage.insert("b", "y");
+        let value = baggage.to_header_value().unwrap();
+        assert_eq!(value.len(), MAX_HEADER_LEN);
+        assert!(!value.to_str().unwrap().contains("b=y"));
+    }
+
+    #[tokio::test]
+    async fn test_propagate_current() {
+        let mut headers = HeaderMap::new();
+        propagate(&mut headers);
+        assert!(headers.is_empty());
+
+        let baggage = Baggage::from_iter([("tenant", "acme")]);
+        baggage
+            .scope(async {
+                propagate(&mut headers);
+            })
+            .await;
+        assert_eq!(headers[BAGGAGE_HEADER], "tenant=acme");
+    }
+}
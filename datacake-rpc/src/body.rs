@@ -1,23 +1,275 @@
 use std::ops::{Deref, DerefMut};
+use std::time::Instant;
 
-use rkyv::{Archive, Serialize};
+use bytes::{Bytes, BytesMut};
+use http::{HeaderMap, HeaderValue};
+use hyper::body::HttpBody;
+use rkyv::{AlignedVec, Archive, Serialize};
+use tokio::io::AsyncRead;
+use tokio_util::io::poll_read_buf;
 
-use crate::rkyv_tooling::DatacakeSerializer;
-use crate::Status;
+use crate::rkyv_tooling::{DatacakeSerializer, SerdeConfig};
+use crate::stream::{check_stream_status, encode_stream_status, STREAM_OK};
+use crate::{Status, STREAM_STATUS_TRAILER};
+
+/// The maximum number of bytes read from an [AsyncRead] at once.
+const READ_CHUNK_SIZE: usize = 64 << 10;
 
 /// A wrapper type around the internal [hyper::Body]
-pub struct Body(pub(crate) hyper::Body);
+///
+/// A body can also carry a set of headers, any headers attached to a
+/// reply body are sent to the client as part of the response and
+/// bodies received by the client carry the headers of the response.
+///
+/// # Bytes
+///
+/// A body can be created from [Bytes] via [From] without copying, reference
+/// counted buffers are sent as-is, and [Body::into_bytes] collects a body back
+/// into [Bytes]. A [BytesMut](bytes::BytesMut) can be converted by first freezing it.
+///
+/// Note that rkyv requires messages to be aligned before they are accessed as
+/// an archived value, a message which does not arrive as a single chunk aligned
+/// to its [RequestContents::ALIGNMENT](crate::RequestContents::ALIGNMENT) is copied
+/// into an aligned buffer, see [Body::ensure_aligned].
+///
+/// # Streaming
+///
+/// Handlers taking a [Body] as their message are dispatched as soon as the
+/// request headers arrive, the body is handed over without being buffered.
+/// Replying with a [Body] likewise sends the response headers as soon as the
+/// handler returns, so a handler can begin replying while the request is
+/// still being streamed in, i.e. when proxying to an upstream.
+///
+/// Within a single call the following ordering is guaranteed:
+///
+/// - The request and reply chunks are each delivered in the order they were sent.
+/// - The client's call completes once the reply headers arrive, the reply body
+///   may then be read while the request body is still being written.
+/// - The request body is not required to be complete before the reply is; a
+///   handler which finishes its reply early does not wait for the remaining request.
+///
+/// A body can also be produced incrementally by an I/O source via
+/// [Body::from_async_read], i.e. when serving a file or proxying a socket.
+///
+/// Beyond that, how request and reply chunks interleave is up to the handler,
+/// if it reads the whole request before replying, the client will not see any
+/// reply until the request is complete. Handlers
+/// registered via [ServiceRegistry::add_cacheable_handler](crate::ServiceRegistry::add_cacheable_handler)
+/// always buffer the request while a reply cache is enabled.
+pub struct Body {
+    inner: hyper::Body,
+    headers: HeaderMap,
+}
 
 impl Body {
     /// Creates a new body.
     pub fn new(inner: hyper::Body) -> Self {
-        Self(inner)
+        Self::with_headers(inner, HeaderMap::new())
+    }
+
+    /// Creates a new body with a set of headers attached.
+    pub fn with_headers(inner: hyper::Body, headers: HeaderMap) -> Self {
+        Self { inner, headers }
+    }
+
+    /// Creates a body which sends the serialized buffer as-is.
+    ///
+    /// The body takes ownership of the buffer rather than copying it, so the
+    /// serializer's output is handed to the transport directly.
+    pub(crate) fn from_aligned(buffer: AlignedVec) -> Self {
+        Self::from(Bytes::from_owner(buffer))
+    }
+
+    /// Creates a body which streams the bytes produced by the reader.
+    ///
+    /// The reader is read from a background task in chunks, only reading more
+    /// once the previous chunk has been sent, so at most a single chunk is
+    /// buffered regardless of how quickly the reader produces data.
+    ///
+    /// If the reader fails, the body is terminated with an internal error
+    /// [Status] which the client receives from [Body::into_bytes] or
+    /// [Body::next_chunk] after the data read before the failure.
+    /// The body is sent as opaque bytes, it is not an rkyv message.
+    ///
+    /// ```rust
+    /// use datacake_rpc::{Body, Handler, Request, RpcService, ServiceRegistry, Status};
+    /// # use rkyv::{Archive, Deserialize, Serialize};
+    /// #
+    /// # #[repr(C)]
+    /// # #[derive(Serialize, Deserialize, Archive, Debug)]
+    /// # #[archive(check_bytes)]
+    /// # pub struct Download(String);
+    ///
+    /// pub struct FileService;
+    ///
+    /// impl RpcService for FileService {
+    ///     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+    ///         registry.add_handler::<Download>();
+    ///     }
+    /// }
+    ///
+    /// #[datacake_rpc::async_trait]
+    /// impl Handler<Download> for FileService {
+    ///     type Reply = Body;
+    ///
+    ///     async fn on_message(&self, msg: Request<Download>) -> Result<Self::Reply, Status> {
+    ///         let file = tokio::fs::File::open(msg.0.as_str())
+    ///             .await
+    ///             .map_err(|e| Status::not_found(e))?;
+    ///         Ok(Body::from_async_read(file))
+    ///     }
+    /// }
+    /// ```
+    pub fn from_async_read<R>(reader: R) -> Self
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        let (sender, body) = hyper::Body::channel();
+        crate::runtime::spawn(pump_reader(Box::pin(reader), sender));
+        Self::new(body)
+    }
+
+    /// Receives the next chunk of the body.
+    ///
+    /// Returns `None` once the body has been fully received. If the body was
+    /// produced by a reader which failed, i.e. via [Body::from_async_read],
+    /// the final item is the error [Status] it was terminated with.
+    pub async fn next_chunk(&mut self) -> Option<Result<Bytes, Status>> {
+        if let Some(chunk) = self.inner.data().await {
+            return Some(chunk.map_err(Status::connection));
+        }
+
+        match self.inner.trailers().await {
+            Ok(trailers) => check_stream_status(trailers.as_ref()).err().map(Err),
+            Err(e) => Some(Err(Status::connection(e))),
+        }
     }
 
     /// Consumes the body returning the inner hyper object.
     pub fn into_inner(self) -> hyper::Body {
-        self.0
+        self.inner
+    }
+
+    /// Consumes the body returning the inner hyper object and headers.
+    pub fn into_parts(self) -> (hyper::Body, HeaderMap) {
+        (self.inner, self.headers)
     }
+
+    #[inline]
+    /// The headers attached to the body.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    #[inline]
+    /// A mutable reference to the headers attached to the body.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    /// Collects the body into a single contiguous buffer.
+    ///
+    /// If the body is made up of a single chunk, i.e. it was created from a
+    /// [Bytes] value, the chunk is returned as-is without being copied.
+    ///
+    /// If the body was terminated with an error [Status], i.e. the reader of
+    /// a [Body::from_async_read] failed, the status is returned.
+    pub async fn into_bytes(mut self) -> Result<Bytes, Status> {
+        let bytes = hyper::body::to_bytes(&mut self.inner)
+            .await
+            .map_err(Status::connection)?;
+
+        let trailers = self.inner.trailers().await.map_err(Status::connection)?;
+        check_stream_status(trailers.as_ref())?;
+        Ok(bytes)
+    }
+
+    /// Buffers the body into a single chunk starting at an address aligned to
+    /// `align` bytes, returning `true` if the data had to be copied to realign it.
+    ///
+    /// A body which is already made up of a single aligned chunk, i.e. one
+    /// created from the [Bytes] of a serialized message, is kept as-is. Otherwise
+    /// the chunks are copied into a new aligned buffer, which is what happens
+    /// implicitly when a message is received. Transports which deliver aligned
+    /// buffers avoid this copy, which can be confirmed via
+    /// [Request::is_realigned](crate::Request::is_realigned).
+    ///
+    /// Like when a message is received, any trailers of the body are discarded.
+    ///
+    /// # Panics
+    ///
+    /// If `align` is not a power of two or is greater than [AlignedVec::ALIGNMENT].
+    pub async fn ensure_aligned(&mut self, align: usize) -> Result<bool, Status> {
+        assert!(
+            align.is_power_of_two() && align <= AlignedVec::ALIGNMENT,
+            "Alignment must be a power of two no greater than {}",
+            AlignedVec::ALIGNMENT,
+        );
+
+        let (bytes, realigned) = crate::utils::to_aligned_bytes(&mut self.inner, align)
+            .await
+            .map_err(Status::connection)?;
+
+        self.inner = bytes.into();
+        Ok(realigned)
+    }
+
+    #[inline]
+    /// The length of the body in bytes, if known.
+    ///
+    /// This is known for fully buffered bodies and for received bodies
+    /// which specified a `Content-Length`, streaming bodies of unknown
+    /// length return `None`. This does not consume any of the body.
+    pub fn len(&self) -> Option<usize> {
+        self.inner
+            .size_hint()
+            .exact()
+            .and_then(|len| usize::try_from(len).ok())
+    }
+
+    #[inline]
+    /// Returns `true` if the body is known to be empty.
+    ///
+    /// Streaming bodies of unknown length are never considered empty,
+    /// even if they end up producing no data.
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+}
+
+/// Sends the data read from the reader to the body until the reader
+/// completes or the receiver disconnects.
+async fn pump_reader(
+    mut reader: std::pin::Pin<Box<dyn AsyncRead + Send>>,
+    mut sender: hyper::body::Sender,
+) {
+    // Only the bytes read are handed off, so the unfilled capacity is reused
+    // for the next read rather than being held by the queued chunk.
+    let mut chunk = BytesMut::new();
+    let status = loop {
+        chunk.reserve(READ_CHUNK_SIZE);
+        let result =
+            std::future::poll_fn(|cx| poll_read_buf(reader.as_mut(), cx, &mut chunk))
+                .await;
+
+        match result {
+            Err(e) => break encode_stream_status(&Status::from_error(&e)),
+            Ok(0) => break Some(HeaderValue::from_static(STREAM_OK)),
+            Ok(_) => {
+                if sender.send_data(chunk.split().freeze()).await.is_err() {
+                    return;
+                }
+            },
+        }
+    };
+
+    let Some(status) = status else {
+        sender.abort();
+        return;
+    };
+    let mut trailers = HeaderMap::new();
+    trailers.insert(STREAM_STATUS_TRAILER, status);
+    let _ = sender.send_trailers(trailers).await;
 }
 
 impl<T> From<T> for Body
@@ -25,7 +277,7 @@ where
     T: Into<hyper::Body>,
 {
     fn from(value: T) -> Self {
-        Self(value.into())
+        Self::new(value.into())
     }
 }
 
@@ -33,13 +285,13 @@ impl Deref for Body {
     type Target = hyper::Body;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.inner
     }
 }
 
 impl DerefMut for Body {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.inner
     }
 }
 
@@ -49,10 +301,43 @@ impl DerefMut for Body {
 /// stream data across the RPC system which may not fit in memory.
 ///
 /// Any types which implement [TryAsBody] will implement this type.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be used as an RPC reply",
+    label = "`{Self}` cannot be converted into a reply body",
+    note = "replies must derive rkyv's `Archive` and `Serialize`, or be a `Body` or `ReplyStream`"
+)]
 pub trait TryIntoBody {
+    /// If the reply is byte oriented, allowing the server to send
+    /// only part of it to ranged requests.
+    ///
+    /// See [ByteRange](crate::ByteRange) for more information.
+    const RANGEABLE: bool = false;
+
     /// Try convert the reply into a body or return an error
     /// status.
     fn try_into_body(self) -> Result<Body, Status>;
+
+    /// Try convert the reply into a body using the given [SerdeConfig]
+    /// or return an error status.
+    ///
+    /// By default this ignores the config and calls [Self::try_into_body].
+    fn try_into_body_with_config(self, _config: &SerdeConfig) -> Result<Body, Status>
+    where
+        Self: Sized,
+    {
+        self.try_into_body()
+    }
+
+    /// Try convert the reply into a body, giving up with a
+    /// [Status::timeout] if it is not complete by the deadline.
+    ///
+    /// By default this ignores the deadline and calls [Self::try_into_body].
+    fn try_into_body_before(self, _deadline: Instant) -> Result<Body, Status>
+    where
+        Self: Sized,
+    {
+        self.try_into_body()
+    }
 }
 
 /// The serializer trait for converting replies into hyper bodies
@@ -61,21 +346,67 @@ pub trait TryIntoBody {
 /// This will work for most implementations but if you want to stream
 /// hyper bodies for example, you cannot implement this trait.
 pub trait TryAsBody {
+    /// If the value is archived as the unit type `()`, so it carries no data
+    /// and can be sent as an empty request body without being serialized.
+    ///
+    /// By default this is `false`.
+    fn is_unit() -> bool {
+        false
+    }
+
     /// Try convert the reply into a body or return an error
     /// status.
     fn try_as_body(&self) -> Result<Body, Status>;
+
+    /// Try convert the reply into a body using the given [SerdeConfig]
+    /// or return an error status.
+    ///
+    /// By default this ignores the config and calls [Self::try_as_body].
+    fn try_as_body_with_config(&self, _config: &SerdeConfig) -> Result<Body, Status> {
+        self.try_as_body()
+    }
+
+    /// Try convert the reply into a body, giving up with a
+    /// [Status::timeout] if it is not complete by the deadline.
+    ///
+    /// By default this ignores the deadline and calls [Self::try_as_body].
+    fn try_as_body_before(&self, _deadline: Instant) -> Result<Body, Status> {
+        self.try_as_body()
+    }
 }
 
 impl<T> TryAsBody for T
 where
     T: Archive + Serialize<DatacakeSerializer>,
 {
+    #[inline]
+    fn is_unit() -> bool {
+        // The archived type is not necessarily `'static`, so its `TypeId`
+        // cannot be compared. Other zero-sized types, i.e. unit structs, are
+        // still serialized as the server could not tell if they are inhabited.
+        std::any::type_name::<T::Archived>() == "()"
+    }
+
     #[inline]
     fn try_as_body(&self) -> Result<Body, Status> {
         crate::rkyv_tooling::to_view_bytes(self)
-            .map(|v| Body::from(v.to_vec()))
+            .map(Body::from_aligned)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+
+    #[inline]
+    fn try_as_body_with_config(&self, config: &SerdeConfig) -> Result<Body, Status> {
+        crate::rkyv_tooling::to_view_bytes_with_config(self, config)
+            .map(Body::from_aligned)
             .map_err(|e| Status::internal(e.to_string()))
     }
+
+    #[inline]
+    fn try_as_body_before(&self, deadline: Instant) -> Result<Body, Status> {
+        crate::rkyv_tooling::to_view_bytes_before(self, deadline)
+            .map(Body::from_aligned)
+            .map_err(crate::rkyv_tooling::serialize_error_status)
+    }
 }
 
 impl<T> TryIntoBody for T
@@ -86,11 +417,117 @@ where
     fn try_into_body(self) -> Result<Body, Status> {
         <Self as TryAsBody>::try_as_body(&self)
     }
+
+    #[inline]
+    fn try_into_body_with_config(self, config: &SerdeConfig) -> Result<Body, Status> {
+        <Self as TryAsBody>::try_as_body_with_config(&self, config)
+    }
+
+    #[inline]
+    fn try_into_body_before(self, deadline: Instant) -> Result<Body, Status> {
+        <Self as TryAsBody>::try_as_body_before(&self, deadline)
+    }
 }
 
 impl TryIntoBody for Body {
+    const RANGEABLE: bool = true;
+
     #[inline]
     fn try_into_body(self) -> Result<Body, Status> {
         Ok(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_body_into_bytes() {
+        let data = Bytes::from(vec![1u8; 64]);
+        let body = Body::from(data.clone());
+        assert_eq!(body.len(), Some(64));
+
+        let bytes = body.into_bytes().await.unwrap();
+        assert_eq!(bytes, data);
+        assert_eq!(
+            bytes.as_ptr(),
+            data.as_ptr(),
+            "Single chunk should not be copied"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_serialized_body_is_not_copied() {
+        let buffer = crate::rkyv_tooling::to_view_bytes(&vec![1u64; 64]).unwrap();
+        let expected = buffer.as_ptr();
+
+        let body = Body::from_aligned(buffer);
+        let bytes = body.into_bytes().await.unwrap();
+        assert_eq!(
+            bytes.as_ptr(),
+            expected,
+            "Serialized buffer should not be copied"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_body_ensure_aligned() {
+        let buffer = crate::rkyv_tooling::to_view_bytes(&vec![1u64; 64]).unwrap();
+        let aligned = buffer.clone();
+        let expected = aligned.as_ptr();
+
+        let mut body = Body::from_aligned(aligned);
+        assert!(!body.ensure_aligned(AlignedVec::ALIGNMENT).await.unwrap());
+        let bytes = body.into_bytes().await.unwrap();
+        assert_eq!(
+            bytes.as_ptr(),
+            expected,
+            "Aligned body should not be copied"
+        );
+
+        let mut shifted = AlignedVec::new();
+        shifted.push(0);
+        shifted.extend_from_slice(&buffer);
+        let misaligned = Bytes::from_owner(shifted).slice(1..);
+        let mut body = Body::from(misaligned);
+        assert!(body.ensure_aligned(AlignedVec::ALIGNMENT).await.unwrap());
+        assert!(!body.ensure_aligned(AlignedVec::ALIGNMENT).await.unwrap());
+        let bytes = body.into_bytes().await.unwrap();
+        assert!((bytes.as_ptr() as usize).is_multiple_of(AlignedVec::ALIGNMENT));
+        assert_eq!(&bytes[..], buffer.as_slice());
+
+        let (mut sender, stream) = hyper::Body::channel();
+        let chunks = buffer.clone();
+        tokio::spawn(async move {
+            for chunk in chunks.chunks(100) {
+                sender
+                    .send_data(Bytes::copy_from_slice(chunk))
+                    .await
+                    .unwrap();
+            }
+        });
+        let mut body = Body::new(stream);
+        assert!(
+            body.ensure_aligned(1).await.unwrap(),
+            "Chunks should be copied"
+        );
+        assert_eq!(&body.into_bytes().await.unwrap()[..], buffer.as_slice());
+    }
+
+    #[test]
+    fn test_body_len() {
+        let body = Body::from(vec![0u8; 32]);
+        assert_eq!(body.len(), Some(32));
+        assert!(!body.is_empty());
+
+        let body = Body::new(hyper::Body::empty());
+        assert_eq!(body.len(), Some(0));
+        assert!(body.is_empty());
+
+        let (_sender, stream) = hyper::Body::channel();
+        let body = Body::new(stream);
+        assert_eq!(body.len(), None);
+        assert!(!body.is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use http::HeaderMap;
+use parking_lot::Mutex;
+
+#[derive(Debug, Clone)]
+/// Configuration of the server's reply cache.
+///
+/// See [Server::enable_reply_cache](crate::Server::enable_reply_cache).
+pub struct ReplyCacheConfig {
+    /// The maximum number of replies kept in the cache.
+    ///
+    /// Once full, the oldest entries are evicted first.
+    pub max_entries: usize,
+    /// How long a cached reply can be returned for before the handler
+    /// must be run again.
+    pub ttl: Duration,
+}
+
+impl Default for ReplyCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 1024,
+            ttl: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+/// Access statistics of the server's reply cache.
+pub struct ReplyCacheStats {
+    /// The number of requests served from the cache.
+    pub hits: u64,
+    /// The number of cacheable requests which had to run their handler.
+    pub misses: u64,
+    /// The number of replies currently cached.
+    pub entries: usize,
+}
+
+#[derive(Clone)]
+/// A serialized reply stored in the cache.
+pub(crate) struct CachedReply {
+    pub(crate) body: Bytes,
+    pub(crate) headers: HeaderMap,
+}
+
+struct CacheEntry {
+    uri: String,
+    request: Bytes,
+    reply: CachedReply,
+    inserted_at: Instant,
+}
+
+/// A cache of serialized replies keyed on the request path and body.
+pub(crate) struct ReplyCache {
+    config: ReplyCacheConfig,
+    entries: Mutex<CacheEntries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Default)]
+struct CacheEntries {
+    lookup: HashMap<u64, CacheEntry>,
+    /// The keys in insertion order, used for eviction.
+    order: VecDeque<(u64, Instant)>,
+}
+
+impl ReplyCache {
+    pub(crate) fn new(config: ReplyCacheConfig) -> Self {
+        Self {
+            config,
+            entries: Mutex::new(CacheEntries::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Gets the cached reply for the request if it exists and has not expired.
+    pub(crate) fn get(&self, uri: &str, request: &[u8]) -> Option<CachedReply> {
+        let key = cache_key(uri, request);
+
+        let reply = {
+            let entries = self.entries.lock();
+            entries
+                .lookup
+                .get(&key)
+                .filter(|entry| {
+                    entry.uri == uri
+                        && entry.request == request
+                        && entry.inserted_at.elapsed() < self.config.ttl
+                })
+                .map(|entry| entry.reply.clone())
+        };
+
+        match reply {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+
+        reply
+    }
+
+    /// Caches the reply for the given request.
+    pub(crate) fn insert(&self, uri: &str, request: Bytes, reply: CachedReply) {
+        if self.config.max_entries == 0 {
+            return;
+        }
+
+        let key = cache_key(uri, &request);
+        let now = Instant::now();
+
+        let mut entries = self.entries.lock();
+        while entries.lookup.len() >= self.config.max_entries {
+            let Some((oldest, inserted_at)) = entries.order.pop_front() else {
+                break;
+            };
+
+            // Only evict the entry if it has not since been replaced.
+            let is_current = entries
+                .lookup
+                .get(&oldest)
+                .is_some_and(|entry| entry.inserted_at == inserted_at);
+            if is_current {
+                entries.lookup.remove(&oldest);
+            }
+        }
+
+        let entry = CacheEntry {
+            uri: uri.to_string(),
+            request,
+            reply,
+            inserted_at: now,
+        };
+        entries.lookup.insert(key, entry);
+        entries.order.push_back((key, now));
+
+        // Replaced entries leave stale keys behind, which are compacted
+        // before they can build up.
+        if entries.order.len() > self.config.max_entries.saturating_mul(2) {
+            let CacheEntries { lookup, order } = &mut *entries;
+            order.retain(|(key, inserted_at)| {
+                lookup
+                    .get(key)
+                    .is_some_and(|entry| entry.inserted_at == *inserted_at)
+            });
+        }
+    }
+
+    pub(crate) fn stats(&self) -> ReplyCacheStats {
+        ReplyCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().lookup.len(),
+        }
+    }
+}
+
+fn cache_key(uri: &str, request: &[u8]) -> u64 {
+    crate::hash(&(uri, request))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(data: &'static [u8]) -> CachedReply {
+        CachedReply {
+            body: Bytes::from_static(data),
+            headers: HeaderMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_cache_hit_and_miss() {
+        let cache = ReplyCache::new(ReplyCacheConfig::default());
+
+        assert!(cache.get("/svc/a", b"req").is_none());
+        cache.insert("/svc/a", Bytes::from_static(b"req"), reply(b"reply"));
+
+        let cached = cache.get("/svc/a", b"req").expect("Reply should be cached");
+        assert_eq!(cached.body, Bytes::from_static(b"reply"));
+        assert!(cache.get("/svc/b", b"req").is_none());
+        assert!(cache.get("/svc/a", b"other").is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 3);
+        assert_eq!(stats.entries, 1);
+    }
+
+    #[test]
+    fn test_cache_expiry() {
+        let cache = ReplyCache::new(ReplyCacheConfig {
+            ttl: Duration::ZERO,
+            ..Default::default()
+        });
+
+        cache.insert("/svc/a", Bytes::from_static(b"req"), reply(b"reply"));
+        assert!(cache.get("/svc/a", b"req").is_none());
+    }
+
+    #[test]
+    fn test_cache_eviction() {
+        let cache = ReplyCache::new(ReplyCacheConfig {
+            max_entries: 2,
+            ..Default::default()
+        });
+
+        cache.insert("/svc/a", Bytes::from_static(b"1"), reply(b"1"));
+        cache.insert("/svc/a", Bytes::from_static(b"2"), reply(b"2"));
+        cache.insert("/svc/a", Bytes::from_static(b"3"), reply(b"3"));
+
+        assert!(
+            cache.get("/svc/a", b"1").is_none(),
+            "Oldest entry should be evicted"
+        );
+        assert!(cache.get("/svc/a", b"2").is_some());
+        assert!(cache.get("/svc/a", b"3").is_some());
+        assert_eq!(cache.stats().entries, 2);
+    }
+}
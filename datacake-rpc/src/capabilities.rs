@@ -0,0 +1,125 @@
+use std::collections::BTreeSet;
+
+use http::{HeaderMap, HeaderValue};
+
+/// The header used to advertise the optional features supported by a peer.
+pub const CAPABILITIES_HEADER: &str = "x-datacake-capabilities";
+
+/// Byte ranges of replies can be requested via [RpcClient::send_ranged](crate::RpcClient::send_ranged).
+pub const RANGES_CAPABILITY: &str = "ranges";
+
+/// Progress updates can be received via [RpcClient::send_with_progress](crate::RpcClient::send_with_progress).
+pub const PROGRESS_CAPABILITY: &str = "progress";
+
+/// Unit messages, i.e. `()`, are sent as an empty body rather than being
+/// serialized.
+///
+/// Unlike other features, this is never negotiated on demand, unit messages
+/// are only sent as an empty body once the channel has been warmed up.
+pub const EMPTY_BODY_CAPABILITY: &str = "empty-body";
+
+/// The optional features supported by this version of the RPC system.
+const LOCAL_CAPABILITIES: &[&str] =
+    &[RANGES_CAPABILITY, PROGRESS_CAPABILITY, EMPTY_BODY_CAPABILITY];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// A set of optional protocol features supported by a peer.
+///
+/// When a client warms up its [Channel](crate::Channel), it advertises the
+/// features it supports and the server replies with the features it supports.
+/// The negotiated capabilities are the features supported by both, the client
+/// silently falls back to the baseline protocol for any others.
+///
+/// Servers which predate capability negotiation never advertise any features,
+/// so every optional feature is disabled when talking to them.
+pub struct Capabilities {
+    features: BTreeSet<String>,
+}
+
+impl Capabilities {
+    /// The features supported by this version of the RPC system.
+    pub(crate) fn local() -> Self {
+        Self {
+            features: LOCAL_CAPABILITIES.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    /// Parses the capabilities advertised in the given headers.
+    ///
+    /// Returns an empty set if the peer did not advertise any.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let features = headers
+            .get_all(CAPABILITIES_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|feature| !feature.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        Self { features }
+    }
+
+    /// The value of the header advertising these capabilities.
+    pub(crate) fn to_header_value(&self) -> HeaderValue {
+        let value = self.iter().collect::<Vec<_>>().join(",");
+        HeaderValue::from_str(&value)
+            .expect("Capabilities should be a valid header value")
+    }
+
+    /// The features supported by both sets of capabilities.
+    pub(crate) fn intersection(&self, other: &Self) -> Self {
+        Self {
+            features: self
+                .features
+                .intersection(&other.features)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    #[inline]
+    /// Returns if the given feature is supported.
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+
+    /// Iterates over the supported features.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.features.iter().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_negotiation() {
+        let local = Capabilities::local();
+        assert!(local.supports(RANGES_CAPABILITY));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CAPABILITIES_HEADER,
+            HeaderValue::from_static("ranges, future-feature"),
+        );
+        let remote = Capabilities::from_headers(&headers);
+        assert!(remote.supports("future-feature"));
+
+        let negotiated = local.intersection(&remote);
+        assert_eq!(
+            negotiated.iter().collect::<Vec<_>>(),
+            vec![RANGES_CAPABILITY]
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CAPABILITIES_HEADER, local.to_header_value());
+        assert_eq!(Capabilities::from_headers(&headers), local);
+
+        let baseline =
+            local.intersection(&Capabilities::from_headers(&HeaderMap::new()));
+        assert_eq!(baseline, Capabilities::default());
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::VecDeque;
+use std::fmt::{Debug, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use bytes::{Bytes, BytesMut};
+use hyper::body::HttpBody;
+use parking_lot::Mutex;
+
+/// Modifies a frame before it is recorded.
+type Redactor = dyn Fn(&mut CapturedFrame) + Send + Sync;
+
+#[derive(Clone)]
+/// Configuration of the server's request and reply capture.
+///
+/// See [Server::enable_capture](crate::Server::enable_capture).
+pub struct CaptureConfig {
+    /// The file frames are written to, see [CapturedFrame::read_all] for
+    /// the format.
+    ///
+    /// If `None` the frames are kept in an in-memory ring buffer instead,
+    /// which can be read via [Server::captured_frames](crate::Server::captured_frames).
+    pub path: Option<PathBuf>,
+    /// The maximum number of body bytes captured.
+    ///
+    /// Once reached, nothing more is written to the file while the ring buffer
+    /// evicts its oldest frames. A single body larger than this is not captured.
+    pub max_bytes: u64,
+    /// Called with each frame before it is recorded, i.e. to blank out
+    /// sensitive fields of the body.
+    pub redact: Option<Arc<Redactor>>,
+}
+
+impl CaptureConfig {
+    /// Sets the function called with each frame before it is recorded.
+    pub fn with_redaction<F>(mut self, redact: F) -> Self
+    where
+        F: Fn(&mut CapturedFrame) + Send + Sync + 'static,
+    {
+        self.redact = Some(Arc::new(redact));
+        self
+    }
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            max_bytes: 16 << 20,
+            redact: None,
+        }
+    }
+}
+
+impl Debug for CaptureConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CaptureConfig")
+            .field("path", &self.path)
+            .field("max_bytes", &self.max_bytes)
+            .field("redact", &self.redact.is_some())
+            .finish()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// If a captured frame is the body of a request or its reply.
+pub enum FrameKind {
+    /// The body sent by the client.
+    Request,
+    /// The body sent back by the server.
+    Reply,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A request or reply body recorded by the server as it was sent on the wire.
+pub struct CapturedFrame {
+    /// If the frame is a request or a reply.
+    pub kind: FrameKind,
+    /// The id of the request within the server, shared by a request and its reply.
+    pub request_id: u64,
+    /// The wire path of the request, i.e. `/{service}/{path}`.
+    pub path: String,
+    /// The raw bytes of the body.
+    pub body: Bytes,
+}
+
+impl CapturedFrame {
+    /// Reads every frame from a capture file.
+    ///
+    /// Each frame is encoded as the kind, `0` for requests and `1` for replies,
+    /// as a `u8`, the request id as a `u64`, the length of the path as a `u32`
+    /// followed by the UTF-8 path and the length of the body as a `u64` followed
+    /// by the body. All integers are little-endian.
+    ///
+    /// The requests can then be replayed against a server, i.e. via
+    /// [ReplayClient](crate::ReplayClient) or
+    /// [RpcClient::send_raw](crate::RpcClient::send_raw).
+    pub fn read_all(mut reader: impl Read) -> io::Result<Vec<Self>> {
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer)?;
+
+        let mut frames = Vec::new();
+        let mut remaining = buffer.as_slice();
+        while !remaining.is_empty() {
+            frames.push(Self::decode(&mut remaining)?);
+        }
+        Ok(frames)
+    }
+
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        let kind = match self.kind {
+            FrameKind::Request => 0u8,
+            FrameKind::Reply => 1,
+        };
+        buffer.push(kind);
+        buffer.extend_from_slice(&self.request_id.to_le_bytes());
+        buffer.extend_from_slice(&(self.path.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(self.path.as_bytes());
+        buffer.extend_from_slice(&(self.body.len() as u64).to_le_bytes());
+        buffer.extend_from_slice(&self.body);
+    }
+
+    fn decode(buffer: &mut &[u8]) -> io::Result<Self> {
+        let kind = match take(buffer, 1)?[0] {
+            0 => FrameKind::Request,
+            1 => FrameKind::Reply,
+            _ => return Err(invalid_data("Unknown frame kind")),
+        };
+        let request_id = u64::from_le_bytes(take(buffer, 8)?.try_into().unwrap());
+        let path_len = u32::from_le_bytes(take(buffer, 4)?.try_into().unwrap());
+        let path = std::str::from_utf8(take(buffer, path_len as usize)?)
+            .map_err(|_| invalid_data("The path is not valid UTF-8"))?
+            .to_string();
+        let body_len = u64::from_le_bytes(take(buffer, 8)?.try_into().unwrap());
+        let body_len = usize::try_from(body_len)
+            .map_err(|_| invalid_data("The body is too large"))?;
+        let body = Bytes::copy_from_slice(take(buffer, body_len)?);
+
+        Ok(Self {
+            kind,
+            request_id,
+            path,
+            body,
+        })
+    }
+
+    /// The number of bytes the frame counts towards the capture's limit.
+    fn len(&self) -> u64 {
+        self.body.len() as u64
+    }
+}
+
+fn take<'a>(buffer: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+    if buffer.len() < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "The capture ends within a frame",
+        ));
+    }
+    let (head, tail) = buffer.split_at(len);
+    *buffer = tail;
+    Ok(head)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+enum Sink {
+    Ring {
+        frames: VecDeque<CapturedFrame>,
+        bytes: u64,
+    },
+    File {
+        file: File,
+        bytes: u64,
+    },
+}
+
+/// Records the bodies of the server's requests and replies.
+pub(crate) struct Capture {
+    max_bytes: u64,
+    redact: Option<Arc<Redactor>>,
+    sink: Mutex<Sink>,
+}
+
+impl Capture {
+    /// Creates a new capture, creating or truncating its file if it has one.
+    pub(crate) fn new(config: CaptureConfig) -> io::Result<Self> {
+        let sink = match &config.path {
+            None => Sink::Ring {
+                frames: VecDeque::new(),
+                bytes: 0,
+            },
+            Some(path) => Sink::File {
+                file: OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .truncate(true)
+                    .open(path)?,
+                bytes: 0,
+            },
+        };
+
+        Ok(Self {
+            max_bytes: config.max_bytes,
+            redact: config.redact,
+            sink: Mutex::new(sink),
+        })
+    }
+
+    /// The frames currently held by the ring buffer, oldest first.
+    ///
+    /// This is empty if the frames are written to a file.
+    pub(crate) fn frames(&self) -> Vec<CapturedFrame> {
+        match &*self.sink.lock() {
+            Sink::Ring { frames, .. } => frames.iter().cloned().collect(),
+            Sink::File { .. } => Vec::new(),
+        }
+    }
+
+    /// Replaces the body with one which records a copy of its bytes once it
+    /// has been read to completion.
+    ///
+    /// The body is passed through unchanged, including its trailers, and bodies
+    /// larger than the limit of the capture are not recorded.
+    pub(crate) fn tee(
+        self: &Arc<Self>,
+        kind: FrameKind,
+        request_id: u64,
+        path: &str,
+        mut body: hyper::Body,
+    ) -> hyper::Body {
+        let (mut sender, teed) = hyper::Body::channel();
+        let capture = self.clone();
+        let path = path.to_string();
+
+        crate::runtime::spawn(async move {
+            let mut copy = Some(BytesMut::new());
+            while let Some(chunk) = body.data().await {
+                let Ok(chunk) = chunk else {
+                    sender.abort();
+                    return;
+                };
+
+                if let Some(buffer) = copy.as_mut() {
+                    if (buffer.len() + chunk.len()) as u64 > capture.max_bytes {
+                        copy = None;
+                    } else {
+                        buffer.extend_from_slice(&chunk);
+                    }
+                }
+                if sender.send_data(chunk).await.is_err() {
+                    return;
+                }
+            }
+
+            match body.trailers().await {
+                Ok(Some(trailers)) => {
+                    let _ = sender.send_trailers(trailers).await;
+                },
+                Ok(None) => {},
+                Err(_) => {
+                    sender.abort();
+                    return;
+                },
+            }
+            drop(sender);
+
+            if let Some(buffer) = copy {
+                capture.record(CapturedFrame {
+                    kind,
+                    request_id,
+                    path,
+                    body: buffer.freeze(),
+                });
+            }
+        });
+
+        teed
+    }
+
+    fn record(&self, mut frame: CapturedFrame) {
+        if let Some(redact) = &self.redact {
+            redact(&mut frame);
+        }
+        if frame.len() > self.max_bytes {
+            return;
+        }
+
+        match &mut *self.sink.lock() {
+            Sink::Ring { frames, bytes } => {
+                *bytes += frame.len();
+                frames.push_back(frame);
+                while *bytes > self.max_bytes {
+                    let Some(evicted) = frames.pop_front() else {
+                        break;
+                    };
+                    *bytes -= evicted.len();
+                }
+            },
+            Sink::File { file, bytes } => {
+                if *bytes + frame.len() > self.max_bytes {
+                    return;
+                }
+
+                let mut buffer = Vec::new();
+                frame.encode(&mut buffer);
+                if let Err(e) = file.write_all(&buffer) {
+                    warn!(error = ?e, "Failed to write captured frame.");
+                    return;
+                }
+                *bytes += frame.len();
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(request_id: u64, body: &'static [u8]) -> CapturedFrame {
+        CapturedFrame {
+            kind: FrameKind::Request,
+            request_id,
+            path: "/svc/path".to_string(),
+            body: Bytes::from_static(body),
+        }
+    }
+
+    #[test]
+    fn test_ring_evicts_oldest() {
+        let capture = Capture::new(CaptureConfig {
+            max_bytes: 8,
+            ..Default::default()
+        })
+        .unwrap();
+
+        capture.record(frame(1, b"aaaa"));
+        capture.record(frame(2, b"bbbb"));
+        capture.record(frame(3, b"cc"));
+        capture.record(frame(4, b"too large"));
+
+        let ids = capture
+            .frames()
+            .iter()
+            .map(|frame| frame.request_id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [2, 3]);
+    }
+
+    #[test]
+    fn test_encoding_roundtrip() {
+        let frames = [frame(1, b"hello"), frame(2, b"")];
+        let mut buffer = Vec::new();
+        for frame in &frames {
+            frame.encode(&mut buffer);
+        }
+
+        let decoded = CapturedFrame::read_all(buffer.as_slice()).unwrap();
+        assert_eq!(decoded, frames);
+
+        let error = CapturedFrame::read_all(&buffer[..buffer.len() - 1]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
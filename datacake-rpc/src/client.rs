@@ -1,15 +1,34 @@
+use std::fmt::{Debug, Display, Formatter};
 use std::future::Future;
 use std::marker::PhantomData;
-use std::time::Duration;
+use std::ops::RangeBounds;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
-use http::header::IntoHeaderName;
+use http::header::{IntoHeaderName, RANGE};
 use http::{HeaderMap, HeaderValue, StatusCode};
+use rkyv::ser::ScratchSpace;
+use rkyv::{Archive, Fallible, Serialize};
+use tokio::sync::OwnedSemaphorePermit;
 
+use crate::baggage::{Baggage, BAGGAGE_HEADER};
 use crate::body::{Body, TryAsBody, TryIntoBody};
+use crate::capabilities::{
+    EMPTY_BODY_CAPABILITY,
+    PROGRESS_CAPABILITY,
+    RANGES_CAPABILITY,
+};
 use crate::handler::{Handler, RpcService};
-use crate::net::{Channel, Status};
+use crate::net::{Channel, ErrorFraming, Status, PRIORITY_HEADER};
+use crate::progress::{ProgressCallback, ProgressEvent, ACK_HEADER, PROGRESS_HEADER};
+use crate::range::ByteRange;
 use crate::request::{MessageMetadata, RequestContents};
-use crate::DataView;
+use crate::request_id::REQUEST_ID_HEADER;
+use crate::rkyv_tooling::{LazyScratch, ScratchSerializer, SerializeBuffers};
+use crate::runtime::BoxFuture;
+use crate::schema::SCHEMA_HEADER;
+use crate::{DataView, SerdeConfig};
 
 /// A type alias for the returned data view of the RPC message reply.
 pub type MessageReply<Svc, Msg> =
@@ -75,6 +94,8 @@ where
 {
     channel: Channel,
     timeout: Option<Duration>,
+    skip_validation: bool,
+    check_schemas: bool,
     _p: PhantomData<Svc>,
 }
 
@@ -86,6 +107,8 @@ where
         Self {
             channel: self.channel.clone(),
             timeout: self.timeout,
+            skip_validation: self.skip_validation,
+            check_schemas: self.check_schemas,
             _p: PhantomData,
         }
     }
@@ -102,6 +125,8 @@ where
         Self {
             channel,
             timeout: None,
+            skip_validation: false,
+            check_schemas: false,
             _p: PhantomData,
         }
     }
@@ -109,10 +134,44 @@ where
     /// Sets a timeout of a given amount of time.
     ///
     /// If any requests exceed this amount of time `Status::timeout` is returned.
+    ///
+    /// The timeout covers the whole call, including serializing the message.
+    /// Serialization runs within a single poll of the send future, so dropping
+    /// the future cannot interrupt it, instead the serializer periodically
+    /// checks the timeout and gives up once it has elapsed. Any partially
+    /// serialized data is discarded and nothing is sent to the server.
     pub fn set_timeout(&mut self, timeout: Duration) {
         self.timeout = Some(timeout);
     }
 
+    /// Skips the checksum validation of every reply received by this client.
+    ///
+    /// This removes a full pass over each reply buffer which can be
+    /// pure overhead on trusted links, i.e. over mTLS within a datacenter.
+    ///
+    /// # Safety
+    ///
+    /// **Replies are viewed without any validation.** A corrupted or
+    /// malicious reply will be viewed as if it were valid which is
+    /// undefined behaviour and can lead to memory corruption or crashes.
+    /// This must only be used when the server and the link to it are trusted.
+    pub unsafe fn skip_validation(&mut self) {
+        self.skip_validation = true;
+    }
+
+    /// Sends the [schema hash](RequestContents::schema_hash) of every message
+    /// so the server can check it agrees on the message's layout.
+    ///
+    /// The hash is sent in the [SCHEMA_HEADER](crate::SCHEMA_HEADER) header,
+    /// if it does not match the handler's message the request is rejected with
+    /// [ErrorCode::InvalidPayload](crate::ErrorCode::InvalidPayload) before
+    /// the message is viewed. This gives early warning of a message being
+    /// changed incompatibly across deploys rather than it being misread.
+    /// Servers which predate schema checks ignore the header.
+    pub fn enable_schema_check(&mut self) {
+        self.check_schemas = true;
+    }
+
     #[inline]
     /// Creates a new RPC client which can handle a new service type.
     ///
@@ -125,6 +184,8 @@ where
         RpcClient {
             channel: self.channel.clone(),
             timeout: None,
+            skip_validation: false,
+            check_schemas: false,
             _p: PhantomData,
         }
     }
@@ -146,12 +207,45 @@ where
         Svc: Handler<Msg>,
         // Due to some interesting compiler errors, we couldn't use GATs here to enforce
         // this on the trait side, which is a shame.
-        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody,
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
     {
         let ctx = self.create_rpc_context();
         ctx.send(msg)
     }
 
+    #[inline]
+    /// Sends a message to the server and wait for a reply, failing immediately
+    /// if the channel's concurrency limit has been reached.
+    ///
+    /// Rather than waiting for an earlier request to complete, the call returns
+    /// [ErrorCode::ResourceExhausted](crate::ErrorCode::ResourceExhausted) without
+    /// serializing or sending the message, for callers which prefer to drop or
+    /// redirect work instead, i.e. best-effort telemetry. Once let through, the
+    /// call behaves exactly as [Self::send].
+    ///
+    /// If the channel does not have a limit, see [Channel::with_concurrency_limit],
+    /// the message is always sent.
+    pub async fn try_send<Msg>(
+        &self,
+        msg: &Msg,
+    ) -> Result<MessageReply<Svc, Msg>, Status>
+    where
+        Msg: RequestContents + TryAsBody,
+        Svc: Handler<Msg>,
+        // Due to some interesting compiler errors, we couldn't use GATs here to enforce
+        // this on the trait side, which is a shame.
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
+    {
+        let permit = self
+            .channel
+            .try_acquire_permit()
+            .map_err(Status::resource_exhausted)?;
+
+        let mut ctx = self.create_rpc_context();
+        ctx.permit = permit;
+        ctx.send(msg).await
+    }
+
     #[inline]
     /// Sends a message to the server and wait for a reply using an owned
     /// message value.
@@ -166,12 +260,321 @@ where
         Svc: Handler<Msg>,
         // Due to some interesting compiler errors, we couldn't use GATs here to enforce
         // this on the trait side, which is a shame.
-        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody,
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
     {
         let ctx = self.create_rpc_context();
         ctx.send_owned(msg)
     }
 
+    /// Starts sending a message to the server, returning a handle which
+    /// resolves to the reply once polled to completion.
+    ///
+    /// This is a lower level alternative to [Self::send] for callers managing
+    /// many concurrent requests with their own polling logic, i.e. a set of
+    /// handles along with some metadata. The message is serialized immediately,
+    /// so the handle does not borrow the message or the client. As with any
+    /// future, the request is only sent once the handle is polled and is
+    /// abandoned when the handle is dropped.
+    ///
+    /// Each handle carries a newly generated correlation id, sent in the
+    /// [REQUEST_ID_HEADER](crate::REQUEST_ID_HEADER), which servers using the
+    /// default header record on their tracing spans. The client's timeout
+    /// starts when this is called.
+    pub fn start_send<Msg>(&self, msg: &Msg) -> SendHandle<Svc, Msg>
+    where
+        Msg: RequestContents + TryAsBody,
+        Svc: Handler<Msg> + Send + Sync + 'static,
+        // Due to some interesting compiler errors, we couldn't use GATs here to enforce
+        // this on the trait side, which is a shame.
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
+        MessageReply<Svc, Msg>: Send,
+    {
+        let request_id = crate::request_id::generate_request_id();
+        let started = Instant::now();
+        let metadata = MessageMetadata {
+            service_name: <Svc as RpcService>::service_name(),
+            path: <Svc as Handler<Msg>>::path(),
+        };
+        let deadline = self.timeout.map(|timeout| started + timeout);
+        let body = self.request_body(msg, deadline);
+
+        let client = self.clone();
+        let header = request_id.clone();
+        let future = async move {
+            let mut ctx = client
+                .create_rpc_context()
+                .set_header(REQUEST_ID_HEADER, header);
+            ctx.started = started;
+            ctx.send_inner::<Msg>(body?, metadata).await
+        };
+
+        SendHandle {
+            request_id,
+            future: Box::pin(future),
+        }
+    }
+
+    /// Sends pre-serialized message bytes to the server and wait for a reply.
+    ///
+    /// The bytes are sent as-is to the path of `Msg`, skipping serialization,
+    /// i.e. when forwarding or replaying a message which has already been
+    /// serialized via [to_view_bytes](crate::to_view_bytes). The server validates
+    /// the bytes as usual, rejecting them if they are not a valid `Msg`.
+    ///
+    /// ```rust
+    /// # use rkyv::{Archive, Deserialize, Serialize};
+    /// # use datacake_rpc::{Handler, Request, RpcService, ServiceRegistry, Status};
+    /// use datacake_rpc::{to_view_bytes, RpcClient};
+    /// #
+    /// # #[repr(C)]
+    /// # #[derive(Serialize, Deserialize, Archive)]
+    /// # #[archive(check_bytes)]
+    /// # pub struct Ping(u64);
+    /// #
+    /// # pub struct PingService;
+    /// #
+    /// # impl RpcService for PingService {
+    /// #     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+    /// #         registry.add_handler::<Ping>();
+    /// #     }
+    /// # }
+    /// #
+    /// # #[datacake_rpc::async_trait]
+    /// # impl Handler<Ping> for PingService {
+    /// #     type Reply = u64;
+    /// #
+    /// #     async fn on_message(&self, msg: Request<Ping>) -> Result<Self::Reply, Status> {
+    /// #         Ok(msg.0)
+    /// #     }
+    /// # }
+    ///
+    /// # async fn run(client: RpcClient<PingService>) -> Result<(), Status> {
+    /// let bytes = to_view_bytes(&Ping(1)).unwrap();
+    /// let reply = client.send_raw::<Ping>(&bytes).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_raw<Msg>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<MessageReply<Svc, Msg>, Status>
+    where
+        Msg: RequestContents,
+        Svc: Handler<Msg>,
+        // Due to some interesting compiler errors, we couldn't use GATs here to enforce
+        // this on the trait side, which is a shame.
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
+    {
+        let metadata = MessageMetadata {
+            service_name: <Svc as RpcService>::service_name(),
+            path: <Svc as Handler<Msg>>::path(),
+        };
+
+        let body = Body::from(bytes.to_vec());
+        self.create_rpc_context()
+            .send_inner::<Msg>(body, metadata)
+            .await
+    }
+
+    #[inline]
+    /// Sends a message to the server at the given wire path and wait for a reply.
+    ///
+    /// By default messages are sent to the path `/{service_name}/{path}` as
+    /// defined by [RpcService::service_name] and [Handler::path], this allows
+    /// that path to be overridden for this call, i.e. to call an endpoint which
+    /// is defined externally and does not match the local type names.
+    ///
+    /// The path is used exactly as provided.
+    pub fn send_to<'a, 'slf: 'a, Msg>(
+        &'slf self,
+        path: &'a str,
+        msg: &'a Msg,
+    ) -> impl Future<Output = Result<MessageReply<Svc, Msg>, Status>> + 'a
+    where
+        Msg: RequestContents + TryAsBody,
+        Svc: Handler<Msg>,
+        // Due to some interesting compiler errors, we couldn't use GATs here to enforce
+        // this on the trait side, which is a shame.
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
+    {
+        let ctx = self.create_rpc_context().set_path(path);
+        ctx.send(msg)
+    }
+
+    /// Sends a message to the server requesting only the given range of bytes
+    /// of the reply.
+    ///
+    /// Ranges are only honoured for byte oriented replies, i.e. handlers
+    /// replying with a [Body], which carry a `Content-Range` header describing
+    /// the bytes sent. Ranges extending past the end of the reply are truncated
+    /// and ranges starting beyond it fail with [ErrorCode::OutOfRange](crate::ErrorCode::OutOfRange).
+    /// See [ByteRange] for more information.
+    ///
+    /// Empty ranges cannot be requested and fail with the same error. If the
+    /// channel has not been [warmed up](crate::Channel::warmup), it negotiates
+    /// its capabilities first. If the server does not support ranges, the
+    /// range is not sent and the full reply is returned.
+    pub fn send_ranged<'a, 'slf: 'a, Msg>(
+        &'slf self,
+        msg: &'a Msg,
+        range: impl RangeBounds<u64>,
+    ) -> impl Future<Output = Result<MessageReply<Svc, Msg>, Status>> + 'a
+    where
+        Msg: RequestContents + TryAsBody,
+        Svc: Handler<Msg>,
+        // Due to some interesting compiler errors, we couldn't use GATs here to enforce
+        // this on the trait side, which is a shame.
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
+    {
+        let range = ByteRange::new(range);
+        async move {
+            let range = range
+                .ok_or_else(|| Status::out_of_range("An empty range was requested"))?;
+
+            // Servers which do not support ranges always send the full reply.
+            if !self.channel.negotiate(RANGES_CAPABILITY).await {
+                return self.send(msg).await;
+            }
+
+            self.create_rpc_context()
+                .set_header(RANGE, range.to_header_value())
+                .send(msg)
+                .await
+        }
+    }
+
+    /// Sends a message to the server and wait for a reply, receiving any
+    /// progress updates sent by the handler in the meantime.
+    ///
+    /// Each update sent via [Request::progress_sender](crate::Request::progress_sender)
+    /// is passed to `on_progress` as it arrives, the reply is returned once the
+    /// handler completes as with [Self::send]. If the client has a timeout set,
+    /// it applies to the call as a whole rather than to each update.
+    ///
+    /// If the channel has not been [warmed up](crate::Channel::warmup), it
+    /// negotiates its capabilities first. If the server does not support
+    /// progress updates, the message is sent as usual and `on_progress` is
+    /// never called.
+    pub async fn send_with_progress<Msg, P, F>(
+        &self,
+        msg: &Msg,
+        mut on_progress: F,
+    ) -> Result<MessageReply<Svc, Msg>, Status>
+    where
+        Msg: RequestContents + TryAsBody,
+        Svc: Handler<Msg>,
+        // Due to some interesting compiler errors, we couldn't use GATs here to enforce
+        // this on the trait side, which is a shame.
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
+        P: Archive,
+        P::Archived: 'static,
+        F: FnMut(DataView<P>) + Send,
+    {
+        if !self.channel.negotiate(PROGRESS_CAPABILITY).await {
+            return self.send(msg).await;
+        }
+
+        let metadata = MessageMetadata {
+            service_name: <Svc as RpcService>::service_name(),
+            path: <Svc as Handler<Msg>>::path(),
+        };
+        let ctx = self
+            .create_rpc_context()
+            .set_header(PROGRESS_HEADER, HeaderValue::from_static("1"));
+        let body = self.request_body(msg, ctx.deadline())?;
+
+        let verify_checksum = !self.skip_validation;
+        let mut callback: ProgressCallback<'_> = Box::new(move |event| {
+            if let ProgressEvent::Update(data) = event {
+                let view = DataView::<P>::using_with(data, verify_checksum)
+                    .map_err(|_| Status::invalid())?;
+                on_progress(view);
+            }
+            Ok(())
+        });
+
+        ctx.send_inner_with_progress::<Msg>(body, metadata, Some(&mut callback))
+            .await
+    }
+
+    /// Sends a message to the server and wait for a reply, calling `on_ack`
+    /// once the handler acknowledges the request.
+    ///
+    /// Handlers acknowledge a request via [Request::acknowledge](crate::Request::acknowledge)
+    /// to signal it has been received and is being worked on, before the reply
+    /// is ready. `on_ack` is called at most once and always before the reply is
+    /// returned. If the handler replies without acknowledging the request,
+    /// `on_ack` is never called. If the client has a timeout set, it applies to
+    /// the call as a whole.
+    ///
+    /// If the channel has not been [warmed up](crate::Channel::warmup), it
+    /// negotiates its capabilities first. If the server does not support
+    /// progress updates, the message is sent as usual and `on_ack` is never
+    /// called.
+    pub async fn send_with_ack<Msg, F>(
+        &self,
+        msg: &Msg,
+        on_ack: F,
+    ) -> Result<MessageReply<Svc, Msg>, Status>
+    where
+        Msg: RequestContents + TryAsBody,
+        Svc: Handler<Msg>,
+        // Due to some interesting compiler errors, we couldn't use GATs here to enforce
+        // this on the trait side, which is a shame.
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
+        F: FnOnce() + Send,
+    {
+        if !self.channel.negotiate(PROGRESS_CAPABILITY).await {
+            return self.send(msg).await;
+        }
+
+        let metadata = MessageMetadata {
+            service_name: <Svc as RpcService>::service_name(),
+            path: <Svc as Handler<Msg>>::path(),
+        };
+        let ctx = self
+            .create_rpc_context()
+            .set_header(PROGRESS_HEADER, HeaderValue::from_static("1"))
+            .set_header(ACK_HEADER, HeaderValue::from_static("1"));
+        let body = self.request_body(msg, ctx.deadline())?;
+
+        let mut on_ack = Some(on_ack);
+        let mut callback: ProgressCallback<'_> = Box::new(move |event| {
+            if let ProgressEvent::Acknowledged = event {
+                if let Some(on_ack) = on_ack.take() {
+                    on_ack();
+                }
+            }
+            Ok(())
+        });
+
+        ctx.send_inner_with_progress::<Msg>(body, metadata, Some(&mut callback))
+            .await
+    }
+
+    /// Serializes a message into the body of a request.
+    ///
+    /// Unit messages are sent as an empty body without being serialized,
+    /// provided the server has advertised that it [supports](EMPTY_BODY_CAPABILITY)
+    /// it when the channel was warmed up.
+    fn request_body<Msg>(
+        &self,
+        msg: &Msg,
+        deadline: Option<Instant>,
+    ) -> Result<Body, Status>
+    where
+        Msg: TryAsBody + ?Sized,
+    {
+        if Msg::is_unit() && self.channel.supports(EMPTY_BODY_CAPABILITY) {
+            return Ok(Body::new(hyper::Body::empty()));
+        }
+
+        match deadline {
+            Some(deadline) => msg.try_as_body_before(deadline),
+            None => msg.try_as_body(),
+        }
+    }
+
     #[inline]
     /// Creates a new RPC context which can customise more of
     /// the request than the convenience methods, i.e. Headers.
@@ -179,10 +582,180 @@ where
         RpcContext {
             client: self,
             headers: HeaderMap::new(),
+            path: None,
+            started: Instant::now(),
+            permit: None,
+        }
+    }
+
+    #[inline]
+    /// Creates a [Sender] which reuses its serialization buffers across
+    /// sequential sends.
+    ///
+    /// This avoids allocating a new buffer for every message when sending
+    /// many messages in a loop.
+    pub fn sender(&self) -> Sender<'_, Svc> {
+        Sender {
+            client: self,
+            buffers: SerializeBuffers::default(),
+        }
+    }
+
+    #[inline]
+    /// Creates a [Sender] which serializes messages using the provided
+    /// scratch space.
+    ///
+    /// This gives control over the allocations made while serializing, i.e.
+    /// a fixed size [BufferScratch](rkyv::ser::serializers::BufferScratch)
+    /// never allocates and sends fail with an
+    /// [ErrorCode::InternalError](crate::ErrorCode::InternalError) if a
+    /// message needs more scratch space than it holds, keeping memory use
+    /// predictable on constrained deployments. The default scratch space
+    /// used by [Self::sender] and [Self::send] falls back to the heap.
+    ///
+    /// If a send fails, the scratch space is replaced with its default value
+    /// as it may have been left partially allocated.
+    ///
+    /// ```rust
+    /// # use datacake_rpc::{Handler, Request, RpcService, ServiceRegistry, Status};
+    /// # use rkyv::{Archive, Deserialize, Serialize};
+    /// use datacake_rpc::RpcClient;
+    /// use rkyv::ser::serializers::BufferScratch;
+    /// use rkyv::AlignedBytes;
+    /// #
+    /// # #[repr(C)]
+    /// # #[derive(Serialize, Deserialize, Archive)]
+    /// # #[archive(check_bytes)]
+    /// # pub struct Ping(u64);
+    /// #
+    /// # pub struct PingService;
+    /// #
+    /// # impl RpcService for PingService {
+    /// #     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+    /// #         registry.add_handler::<Ping>();
+    /// #     }
+    /// # }
+    /// #
+    /// # #[datacake_rpc::async_trait]
+    /// # impl Handler<Ping> for PingService {
+    /// #     type Reply = u64;
+    /// #
+    /// #     async fn on_message(&self, msg: Request<Ping>) -> Result<Self::Reply, Status> {
+    /// #         Ok(msg.0)
+    /// #     }
+    /// # }
+    ///
+    /// # async fn run(client: RpcClient<PingService>) -> Result<(), Status> {
+    /// let scratch = BufferScratch::<AlignedBytes<4096>>::default();
+    /// let mut sender = client.sender_with_scratch(scratch);
+    /// let reply = sender.send(&Ping(1)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sender_with_scratch<S>(&self, scratch: S) -> Sender<'_, Svc, S>
+    where
+        S: ScratchSpace + Default,
+    {
+        Sender {
+            client: self,
+            buffers: SerializeBuffers::with_scratch(scratch),
         }
     }
 }
 
+/// A stateful sender which reuses one serialization buffer and scratch
+/// space for every message it sends.
+///
+/// The buffers are cleared between sends, so once they have grown to fit
+/// the largest message, sending does not allocate while serializing.
+/// The serialized buffer is handed to the transport without being copied
+/// and is recovered once the request body has been sent.
+/// Each send must complete before the next can start, which is enforced
+/// by [Sender::send] borrowing the sender mutably.
+///
+/// ```rust
+/// use datacake_rpc::{Channel, RpcClient};
+/// # use datacake_rpc::{Handler, Request, RpcService, ServiceRegistry, Status};
+/// # use rkyv::{Archive, Deserialize, Serialize};
+/// # use std::net::SocketAddr;
+/// #
+/// # #[repr(C)]
+/// # #[derive(Serialize, Deserialize, Archive, Debug)]
+/// # #[archive(check_bytes)]
+/// # pub struct Ping(u64);
+/// #
+/// # pub struct PingService;
+/// #
+/// # impl RpcService for PingService {
+/// #     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+/// #         registry.add_handler::<Ping>();
+/// #     }
+/// # }
+/// #
+/// # #[datacake_rpc::async_trait]
+/// # impl Handler<Ping> for PingService {
+/// #     type Reply = u64;
+/// #
+/// #     async fn on_message(&self, msg: Request<Ping>) -> Result<Self::Reply, Status> {
+/// #         Ok(msg.0)
+/// #     }
+/// # }
+/// #
+/// # async fn run(bind: SocketAddr) -> Result<(), Status> {
+/// let client = RpcClient::<PingService>::new(Channel::connect(bind));
+///
+/// let mut sender = client.sender();
+/// for n in 0..100 {
+///     let reply = sender.send(&Ping(n)).await?;
+///     assert_eq!(*reply, n);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Sender<'a, Svc, S = LazyScratch>
+where
+    Svc: RpcService,
+{
+    client: &'a RpcClient<Svc>,
+    buffers: SerializeBuffers<S>,
+}
+
+impl<'a, Svc, S> Sender<'a, Svc, S>
+where
+    Svc: RpcService,
+    S: ScratchSpace + Default,
+    <S as Fallible>::Error: Display,
+{
+    /// Sends a message to the server and wait for a reply.
+    ///
+    /// The message is serialized into the sender's reused buffers.
+    pub async fn send<Msg>(
+        &mut self,
+        msg: &Msg,
+    ) -> Result<MessageReply<Svc, Msg>, Status>
+    where
+        Msg: RequestContents + Serialize<ScratchSerializer<S>>,
+        Svc: Handler<Msg>,
+        // Due to some interesting compiler errors, we couldn't use GATs here to enforce
+        // this on the trait side, which is a shame.
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
+    {
+        let metadata = MessageMetadata {
+            service_name: <Svc as RpcService>::service_name(),
+            path: <Svc as Handler<Msg>>::path(),
+        };
+
+        let ctx = self.client.create_rpc_context();
+        let buffer = self
+            .buffers
+            .serialize(msg, ctx.deadline())
+            .map_err(crate::rkyv_tooling::serialize_error_status)?;
+        let body = Body::from(buffer);
+
+        ctx.send_inner::<Msg>(body, metadata).await
+    }
+}
+
 /// A configurable RPC context that can be used to
 /// fine tune the request/response of the RPC calls.
 ///
@@ -195,12 +768,26 @@ where
 {
     client: &'a RpcClient<Svc>,
     headers: HeaderMap,
+    path: Option<String>,
+    /// When the call started, the client's timeout applies from here.
+    started: Instant,
+    /// The permit of the channel's concurrency limit, if it was acquired
+    /// before the call started.
+    permit: Option<OwnedSemaphorePermit>,
 }
 
 impl<'a, Svc> RpcContext<'a, Svc>
 where
     Svc: RpcService,
 {
+    /// Overrides the wire path the request is sent to.
+    ///
+    /// See [RpcClient::send_to] for more information.
+    pub fn set_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
     /// Set a single request header.
     pub fn set_header<K>(mut self, key: K, value: HeaderValue) -> Self
     where
@@ -222,6 +809,49 @@ where
         self
     }
 
+    /// Add a value to a request header, keeping any values already set.
+    pub fn append_header<K>(mut self, key: K, value: HeaderValue) -> Self
+    where
+        K: IntoHeaderName,
+    {
+        self.headers.append(key, value);
+        self
+    }
+
+    /// Attaches baggage to the request, on top of the [current](Baggage::current)
+    /// baggage which is otherwise propagated as-is.
+    ///
+    /// Entries of `baggage` replace any current entries with the same key.
+    pub fn set_baggage(self, baggage: Baggage) -> Self {
+        let mut merged = Baggage::current();
+        merged.extend(baggage);
+        match merged.to_header_value() {
+            Some(value) => self.set_header(BAGGAGE_HEADER, value),
+            None => self,
+        }
+    }
+
+    /// Sets the priority the server writes the reply with, relative to the other
+    /// replies sent over the same connection.
+    ///
+    /// The urgency ranges from `0`, the most urgent, to `7`, following RFC 9218.
+    /// While a more urgent reply has data ready to be written, the server holds
+    /// back the less urgent ones sharing its connection, so an interactive request
+    /// is not starved behind a bulk transfer, i.e. sending the transfer with an
+    /// urgency of `7` and the interactive requests with `0`. Only replies to
+    /// requests with a priority are scheduled, streamed errors and progress
+    /// updates are written as usual.
+    ///
+    /// # Panics
+    ///
+    /// If `urgency` is greater than `7`.
+    pub fn set_priority(self, urgency: u8) -> Self {
+        assert!(urgency <= 7, "The urgency must be within 0..=7");
+        let value = HeaderValue::try_from(format!("u={urgency}"))
+            .expect("The priority is a valid header value");
+        self.set_header(PRIORITY_HEADER, value)
+    }
+
     /// Sends a message to the server and wait for a reply.
     ///
     /// This lets you send messages behind a reference which can help
@@ -235,14 +865,14 @@ where
         Svc: Handler<Msg>,
         // Due to some interesting compiler errors, we couldn't use GATs here to enforce
         // this on the trait side, which is a shame.
-        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody,
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
     {
         let metadata = MessageMetadata {
             service_name: <Svc as RpcService>::service_name(),
             path: <Svc as Handler<Msg>>::path(),
         };
 
-        let body = msg.try_as_body()?;
+        let body = self.client.request_body(msg, self.deadline())?;
         self.send_inner(body, metadata).await
     }
 
@@ -259,17 +889,25 @@ where
         Svc: Handler<Msg>,
         // Due to some interesting compiler errors, we couldn't use GATs here to enforce
         // this on the trait side, which is a shame.
-        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody,
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
     {
         let metadata = MessageMetadata {
             service_name: <Svc as RpcService>::service_name(),
             path: <Svc as Handler<Msg>>::path(),
         };
 
-        let body = msg.try_into_body()?;
+        let body = match self.deadline() {
+            Some(deadline) => msg.try_into_body_before(deadline)?,
+            None => msg.try_into_body()?,
+        };
         self.send_inner(body, metadata).await
     }
 
+    /// The time by which the call must complete, if the client has a timeout.
+    fn deadline(&self) -> Option<Instant> {
+        self.client.timeout.map(|timeout| self.started + timeout)
+    }
+
     async fn send_inner<Msg>(
         self,
         body: Body,
@@ -280,28 +918,189 @@ where
         Svc: Handler<Msg>,
         // Due to some interesting compiler errors, we couldn't use GATs here to enforce
         // this on the trait side, which is a shame.
-        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody,
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
     {
-        let future = self.client.channel.send_parts(metadata, self.headers, body);
+        self.send_inner_with_progress::<Msg>(body, metadata, None)
+            .await
+    }
 
-        let response = match self.client.timeout {
-            Some(duration) => tokio::time::timeout(duration, future)
-                .await
-                .map_err(|_| Status::timeout())?
-                .map_err(Status::connection)?,
-            None => future.await.map_err(Status::connection)?,
+    async fn send_inner_with_progress<Msg>(
+        self,
+        body: Body,
+        metadata: MessageMetadata,
+        on_progress: Option<&mut ProgressCallback<'_>>,
+    ) -> Result<MessageReply<Svc, Msg>, Status>
+    where
+        Msg: RequestContents,
+        Svc: Handler<Msg>,
+        // Due to some interesting compiler errors, we couldn't use GATs here to enforce
+        // this on the trait side, which is a shame.
+        <Svc as Handler<Msg>>::Reply: RequestContents + TryIntoBody + Send,
+    {
+        // The request is considered in-flight until its reply has been read.
+        let _guard = self
+            .client
+            .channel
+            .start_request()
+            .map_err(Status::connection)?;
+        let _permit = match self.permit {
+            Some(permit) => Some(permit),
+            None => {
+                let acquire = self.client.channel.acquire_permit();
+                match self.client.timeout {
+                    Some(duration) => crate::runtime::timeout(
+                        remaining(self.started, duration),
+                        acquire,
+                    )
+                    .await
+                    .map_err(|_| Status::timeout())?,
+                    None => acquire.await,
+                }
+            },
+        };
+        let permit = self
+            .client
+            .channel
+            .breaker_permit()
+            .map_err(Status::unavailable)?;
+        let uri_path = self.path.unwrap_or_else(|| metadata.to_uri_path());
+        let mut headers = self.headers;
+        crate::baggage::propagate(&mut headers);
+        if self.client.check_schemas {
+            headers.insert(
+                SCHEMA_HEADER,
+                crate::schema::to_header_value(Msg::schema_hash()),
+            );
+        }
+        #[cfg(feature = "otel")]
+        let span = crate::otel::client_span(
+            &uri_path,
+            self.client.channel.remote_addr(),
+            &mut headers,
+        );
+        let future = self.client.channel.send_parts(&uri_path, headers, body);
+        #[cfg(feature = "otel")]
+        let future = tracing::Instrument::instrument(future, span);
+
+        let result = match self.client.timeout {
+            Some(duration) => {
+                crate::runtime::timeout(remaining(self.started, duration), future)
+                    .await
+                    .map_err(|_| Status::timeout())
+                    .and_then(|result| result.map_err(Status::connection))
+            },
+            None => future.await.map_err(Status::connection),
         };
+        if let Some(permit) = permit {
+            permit.record(result.is_ok());
+        }
+        let response = result?;
 
         let (head, body) = response.into_parts();
 
         if head.status == StatusCode::OK {
-            return <<Svc as Handler<Msg>>::Reply>::from_body(Body::new(body)).await;
+            let body = match on_progress {
+                // The reply follows the progress updates once the handler completes.
+                Some(on_progress) if head.headers.contains_key(PROGRESS_HEADER) => {
+                    let future = crate::progress::read_progress(body, on_progress);
+                    match self.client.timeout {
+                        Some(duration) => crate::runtime::timeout(
+                            remaining(self.started, duration),
+                            future,
+                        )
+                        .await
+                        .map_err(|_| Status::timeout())??,
+                        None => future.await?,
+                    }
+                },
+                _ => Body::with_headers(body, head.headers),
+            };
+
+            if self.client.skip_validation {
+                let config = SerdeConfig {
+                    verify_checksum: false,
+                    ..Default::default()
+                };
+                return <<Svc as Handler<Msg>>::Reply>::from_body_with_config(
+                    body, &config,
+                )
+                .await;
+            }
+
+            return <<Svc as Handler<Msg>>::Reply>::from_body(body).await;
         }
 
-        let buffer = crate::utils::to_aligned(body)
-            .await
-            .map_err(|e| Status::internal(e.message()))?;
-        let status = DataView::<Status>::using(buffer).map_err(|_| Status::invalid())?;
-        Err(status.to_owned().unwrap_or_else(|_| Status::invalid()))
+        Err(read_error_reply(&head.headers, body).await)
+    }
+}
+
+/// Reads the status sent by the server in place of a reply.
+pub(crate) async fn read_error_reply(headers: &HeaderMap, body: hyper::Body) -> Status {
+    let buffer = match crate::utils::to_aligned(body).await {
+        Ok(buffer) => buffer,
+        Err(e) => return Status::internal(e.message()),
+    };
+    if ErrorFraming::of_reply(headers) == ErrorFraming::Simple {
+        return crate::net::decode_simple(&buffer).unwrap_or_else(|status| status);
+    }
+    match DataView::<Status>::using(buffer) {
+        Ok(status) => status.to_owned().unwrap_or_else(|_| Status::invalid()),
+        Err(_) => Status::invalid(),
+    }
+}
+
+#[must_use = "the request is only sent once the handle is polled"]
+/// A request started by [RpcClient::start_send], resolving to its reply.
+pub struct SendHandle<Svc, Msg>
+where
+    Svc: Handler<Msg>,
+    Msg: RequestContents,
+    <Svc as Handler<Msg>>::Reply: RequestContents,
+{
+    request_id: HeaderValue,
+    future: BoxFuture<Result<MessageReply<Svc, Msg>, Status>>,
+}
+
+impl<Svc, Msg> SendHandle<Svc, Msg>
+where
+    Svc: Handler<Msg>,
+    Msg: RequestContents,
+    <Svc as Handler<Msg>>::Reply: RequestContents,
+{
+    /// The correlation id the request was sent with.
+    pub fn request_id(&self) -> &str {
+        // The generated ids are hex digits.
+        self.request_id.to_str().unwrap_or_default()
+    }
+}
+
+impl<Svc, Msg> Future for SendHandle<Svc, Msg>
+where
+    Svc: Handler<Msg>,
+    Msg: RequestContents,
+    <Svc as Handler<Msg>>::Reply: RequestContents,
+{
+    type Output = Result<MessageReply<Svc, Msg>, Status>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.future.as_mut().poll(cx)
+    }
+}
+
+impl<Svc, Msg> Debug for SendHandle<Svc, Msg>
+where
+    Svc: Handler<Msg>,
+    Msg: RequestContents,
+    <Svc as Handler<Msg>>::Reply: RequestContents,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SendHandle")
+            .field("request_id", &self.request_id())
+            .finish_non_exhaustive()
     }
 }
+
+/// The time left before the timeout of a call which started at `started`.
+fn remaining(started: Instant, timeout: Duration) -> Duration {
+    timeout.saturating_sub(started.elapsed())
+}
@@ -1,15 +1,22 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
 use std::marker::PhantomData;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
 
 use async_trait::async_trait;
 use http::HeaderMap;
+use parking_lot::Mutex;
+use tokio_util::sync::CancellationToken;
 
 use crate::body::TryIntoBody;
+use crate::limits::Limits;
 use crate::net::Status;
+use crate::progress::ProgressFrames;
 use crate::request::{Request, RequestContents};
-use crate::Body;
+use crate::validate::{validator, Validate, Validator};
+use crate::{Body, SerdeConfig};
 
 /// A specific handler key.
 ///
@@ -84,20 +91,42 @@ pub type HandlerKey = u64;
 /// ```
 pub struct ServiceRegistry<Svc> {
     handlers: BTreeMap<HandlerKey, Arc<dyn OpaqueMessageHandler>>,
+    /// The paths of the handlers in the order they were registered.
+    paths: Vec<String>,
     service: Arc<Svc>,
+    service_name: String,
+    config: SerdeConfig,
 }
 
 impl<Svc> ServiceRegistry<Svc>
 where
     Svc: RpcService + Send + Sync + 'static,
 {
-    pub(crate) fn new(service: Svc) -> Self {
+    pub(crate) fn new(service: Svc, config: SerdeConfig) -> Self {
+        Self::new_named(Svc::service_name(), service, config)
+    }
+
+    /// Creates a registry for a service registered under the given name
+    /// rather than its [RpcService::service_name].
+    pub(crate) fn new_named(
+        service_name: &str,
+        service: Svc,
+        config: SerdeConfig,
+    ) -> Self {
         Self {
             handlers: BTreeMap::new(),
+            paths: Vec::new(),
             service: Arc::new(service),
+            service_name: service_name.to_string(),
+            config,
         }
     }
 
+    /// The name the service's handlers are registered under.
+    pub fn service_name(&self) -> &str {
+        &self.service_name
+    }
+
     /// Consumes the registry into the produced handlers.
     pub(crate) fn into_handlers(
         self,
@@ -105,22 +134,191 @@ where
         self.handlers
     }
 
+    /// Consumes the registry into the produced handlers along with their
+    /// paths, in the order they were registered.
+    pub(crate) fn into_parts(
+        self,
+    ) -> (
+        BTreeMap<HandlerKey, Arc<dyn OpaqueMessageHandler>>,
+        Vec<String>,
+    ) {
+        (self.handlers, self.paths)
+    }
+
     /// Adds a new handler to the registry.
     ///
     /// This is done in the form of specifying what message types are handled
     /// by the service via the generic.
+    ///
+    /// Registering a message the service does not implement [Handler] for
+    /// is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// use datacake_rpc::{RpcService, ServiceRegistry};
+    ///
+    /// pub struct MyService;
+    ///
+    /// impl RpcService for MyService {
+    ///     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+    ///         // error: `MyService` does not implement `Handler<u64>`
+    ///         registry.add_handler::<u64>();
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if a handler is already registered under the same path for
+    /// this service, i.e. when registering the same message twice.
     pub fn add_handler<Msg>(&mut self)
     where
         Msg: RequestContents + Sync + Send + 'static,
         Svc: Handler<Msg>,
+    {
+        self.add_handler_with_path::<Msg>(<Svc as Handler<Msg>>::path())
+    }
+
+    /// Adds a new handler to the registry under an explicit path.
+    ///
+    /// The handler is registered under the path `/{service_name}/{path}` rather
+    /// than using [Handler::path], this allows the wire path of a message to be
+    /// decoupled from its Rust type name, i.e. for cross-language or versioned endpoints.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a handler is already registered under the same path for
+    /// this service.
+    pub fn add_handler_with_path<Msg>(&mut self, path: &str)
+    where
+        Msg: RequestContents + Sync + Send + 'static,
+        Svc: Handler<Msg>,
+    {
+        self.register::<Msg>(path, false, Limits::default(), None)
+    }
+
+    /// Adds a new handler to the registry with its own request limits.
+    ///
+    /// This allows a single endpoint, i.e. a blob upload, to accept larger
+    /// requests than the rest of the service without loosening the limits
+    /// of every handler. Requests which declare a body larger than
+    /// [Limits::max_body] are rejected before the handler is called, bodies of
+    /// unknown length are cut off once they exceed it. Either way the client
+    /// receives an [ErrorCode::PayloadTooLarge](crate::ErrorCode::PayloadTooLarge).
+    ///
+    /// ```rust
+    /// use datacake_rpc::{Limits, RpcService, ServiceRegistry};
+    /// # use datacake_rpc::{Handler, Request, Status};
+    /// # use rkyv::{Archive, Deserialize, Serialize};
+    /// #
+    /// # #[repr(C)]
+    /// # #[derive(Serialize, Deserialize, Archive)]
+    /// # #[archive(check_bytes)]
+    /// # pub struct UploadBlob(Vec<u8>);
+    /// #
+    /// # #[datacake_rpc::async_trait]
+    /// # impl Handler<UploadBlob> for BlobService {
+    /// #     type Reply = ();
+    /// #
+    /// #     async fn on_message(&self, _msg: Request<UploadBlob>) -> Result<Self::Reply, Status> {
+    /// #         Ok(())
+    /// #     }
+    /// # }
+    ///
+    /// pub struct BlobService;
+    ///
+    /// impl RpcService for BlobService {
+    ///     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+    ///         registry.add_handler_with_limits::<UploadBlob>(Limits {
+    ///             max_body: Some(100 << 20),
+    ///         });
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if a handler is already registered under the same path for
+    /// this service.
+    pub fn add_handler_with_limits<Msg>(&mut self, limits: Limits)
+    where
+        Msg: RequestContents + Sync + Send + 'static,
+        Svc: Handler<Msg>,
+    {
+        self.register::<Msg>(<Svc as Handler<Msg>>::path(), false, limits, None)
+    }
+
+    /// Adds a new handler to the registry whose replies may be cached.
+    ///
+    /// When the server has a reply cache enabled, repeated requests with an
+    /// identical body are answered with the cached reply without running the
+    /// handler again. Only register handlers this way if their reply depends
+    /// solely on the request body, i.e. idempotent lookups.
+    ///
+    /// See [Server::enable_reply_cache](crate::Server::enable_reply_cache).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a handler is already registered under the same path for
+    /// this service.
+    pub fn add_cacheable_handler<Msg>(&mut self)
+    where
+        Msg: RequestContents + Sync + Send + 'static,
+        Svc: Handler<Msg>,
+    {
+        let path = <Svc as Handler<Msg>>::path();
+        self.register::<Msg>(path, true, Limits::default(), None)
+    }
+
+    /// Adds a new handler to the registry which only receives valid messages.
+    ///
+    /// Once a request has been deserialized, it is checked with
+    /// [Validate::validate] before being passed to the handler. Invalid messages
+    /// are rejected with [Status::invalid_argument] and never reach the handler,
+    /// keeping input validation out of the handler itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a handler is already registered under the same path for
+    /// this service.
+    pub fn add_validated_handler<Msg>(&mut self)
+    where
+        Msg: Validate + Sync + Send + 'static,
+        Svc: Handler<Msg>,
+    {
+        let path = <Svc as Handler<Msg>>::path();
+        self.register::<Msg>(path, false, Limits::default(), Some(validator::<Msg>()))
+    }
+
+    fn register<Msg>(
+        &mut self,
+        path: &str,
+        cacheable: bool,
+        limits: Limits,
+        validator: Option<Validator<Msg>>,
+    ) where
+        Msg: RequestContents + Sync + Send + 'static,
+        Svc: Handler<Msg>,
     {
         let phantom = PhantomHandler {
             handler: self.service.clone(),
+            config: self.config.clone(),
+            cacheable,
+            limits,
+            validator,
             _msg: PhantomData::<Msg>::default(),
         };
 
-        let uri = crate::to_uri_path(Svc::service_name(), <Svc as Handler<Msg>>::path());
-        self.handlers.insert(crate::hash(&uri), Arc::new(phantom));
+        let uri = crate::to_uri_path(&self.service_name, path);
+        let key = crate::hash(&uri);
+        if self.handlers.contains_key(&key) {
+            panic!(
+                "Duplicate handler registration for service {:?}: a handler is already \
+                 registered under the path {:?} ({uri})",
+                self.service_name, path,
+            );
+        }
+        self.handlers.insert(key, Arc::new(phantom));
+        self.paths.push(path.to_string());
     }
 }
 
@@ -144,6 +342,30 @@ where
 ///     }
 /// }
 /// ```
+///
+/// Rather than registering each handler manually, the trait can be derived
+/// by listing the messages the service handles. The derive fails to compile
+/// if the service does not implement [Handler] for any of the listed messages.
+///
+/// ```rust
+/// use datacake_rpc::{Handler, Request, RpcService, Status};
+///
+/// // `name` is optional and defaults to the type name of the service.
+/// // Messages listed via `cacheable` are registered with
+/// // `ServiceRegistry::add_cacheable_handler`.
+/// #[derive(RpcService)]
+/// #[rpc(name = "my-lovely-service", handles(u64))]
+/// pub struct MyService;
+///
+/// #[datacake_rpc::async_trait]
+/// impl Handler<u64> for MyService {
+///     type Reply = u64;
+///
+///     async fn on_message(&self, msg: Request<u64>) -> Result<Self::Reply, Status> {
+///         Ok(**msg + 1)
+///     }
+/// }
+/// ```
 pub trait RpcService: Sized {
     /// An optional name of the service.
     ///
@@ -152,6 +374,13 @@ pub trait RpcService: Sized {
     /// message but behave differently, to distinguish between
     /// these services, the message paths also use the service name
     /// to create a unique key.
+    ///
+    /// By default this is the [type name](std::any::type_name) of the service,
+    /// which includes its full module path. Moving the type to another module
+    /// or renaming its crate therefore changes the path requests are sent to,
+    /// breaking clients built before the change. Services which are deployed
+    /// separately from their clients should return a fixed name, or use
+    /// [derive_service_name] which only depends on the name of the type itself.
     fn service_name() -> &'static str {
         std::any::type_name::<Self>()
     }
@@ -162,6 +391,71 @@ pub trait RpcService: Sized {
     fn register_handlers(registry: &mut ServiceRegistry<Self>);
 }
 
+/// Derives a name for `T` from its type name without any module paths.
+///
+/// Unlike [std::any::type_name], the name is unaffected by moving the type
+/// between modules or crates, so it can be used as a stable
+/// [RpcService::service_name] or [Handler::path]. Module paths are also removed
+/// from any generic parameters, i.e. `my_crate::api::Store<my_crate::Key>`
+/// becomes `Store<Key>`. Two types with the same name in different modules
+/// derive the same name, so services using this must be named uniquely.
+///
+/// ```rust
+/// use datacake_rpc::{derive_service_name, RpcService, ServiceRegistry};
+///
+/// mod api {
+///     pub struct KeyValueService;
+/// }
+///
+/// impl RpcService for api::KeyValueService {
+///     fn service_name() -> &'static str {
+///         derive_service_name::<Self>()
+///     }
+///
+///     fn register_handlers(registry: &mut ServiceRegistry<Self>) {}
+/// }
+///
+/// assert_eq!(api::KeyValueService::service_name(), "KeyValueService");
+/// ```
+pub fn derive_service_name<T: ?Sized>() -> &'static str {
+    static NAMES: OnceLock<Mutex<HashMap<&'static str, &'static str>>> = OnceLock::new();
+
+    let type_name = std::any::type_name::<T>();
+    if !type_name.contains("::") {
+        return type_name;
+    }
+
+    // The stripped name is only allocated once per type, which is
+    // then kept for the lifetime of the program.
+    let mut names = NAMES.get_or_init(Default::default).lock();
+    names
+        .entry(type_name)
+        .or_insert_with(|| Box::leak(strip_module_paths(type_name).into_boxed_str()))
+}
+
+/// Removes the module path of each path within the type name.
+fn strip_module_paths(type_name: &str) -> String {
+    let mut stripped = String::with_capacity(type_name.len());
+    // Where the path currently being copied starts.
+    let mut path_start = 0;
+
+    let mut chars = type_name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            stripped.truncate(path_start);
+            continue;
+        }
+
+        stripped.push(c);
+        if !(c.is_alphanumeric() || c == '_') {
+            path_start = stripped.len();
+        }
+    }
+
+    stripped
+}
+
 #[async_trait]
 /// A generic RPC message handler.
 ///
@@ -205,6 +499,19 @@ pub trait RpcService: Sized {
 ///     }
 /// }
 /// ```
+///
+/// # CPU heavy handlers
+///
+/// Handlers run as tasks on the shared runtime, a handler which processes a
+/// large batch without awaiting holds onto its worker thread and delays every
+/// other request scheduled on it. Calling [maybe_yield](crate::maybe_yield)
+/// once per unit of work lets the handler periodically give the other tasks
+/// a chance to run.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not implement `Handler<{Msg}>`",
+    label = "the service `{Self}` cannot handle messages of type `{Msg}`",
+    note = "registering `{Msg}` with `ServiceRegistry<{Self}>` requires an `impl Handler<{Msg}> for {Self}`"
+)]
 pub trait Handler<Msg>: RpcService
 where
     Msg: RequestContents,
@@ -215,6 +522,9 @@ where
 
     /// The path of the message, this is similar to the service name which can
     /// be used to avoid conflicts, by default this uses the name of the message type.
+    ///
+    /// Like [RpcService::service_name], the default includes the module path of
+    /// the message type, see [derive_service_name] for a stable alternative.
     fn path() -> &'static str {
         std::any::type_name::<Msg>()
     }
@@ -230,22 +540,107 @@ where
     async fn on_message(&self, msg: Request<Msg>) -> Result<Self::Reply, Status>;
 }
 
+/// The per-request information passed to a handler by the server.
+pub(crate) struct HandlerContext {
+    /// The address of the client which sent the request.
+    pub(crate) remote_addr: SocketAddr,
+    /// The request headers.
+    pub(crate) headers: HeaderMap,
+    /// If the message body should skip validation.
+    pub(crate) trust_peer: bool,
+    /// The id of the request within the server.
+    pub(crate) request_id: u64,
+    /// When the request was admitted by the server.
+    pub(crate) admitted_at: Instant,
+    /// The token cancelled when the request is aborted.
+    pub(crate) cancellation: CancellationToken,
+    /// The token cancelled once the request has been handled.
+    pub(crate) completed: CancellationToken,
+    /// The token cancelled if the client disconnects before the reply
+    /// has been produced.
+    pub(crate) disconnected: CancellationToken,
+    /// The channel progress updates are sent through if the client is listening.
+    pub(crate) progress: Option<ProgressFrames>,
+}
+
 #[async_trait]
 pub(crate) trait OpaqueMessageHandler: Send + Sync {
-    async fn try_handle(
-        &self,
-        remote_addr: SocketAddr,
-        headers: HeaderMap,
-        body: Body,
-    ) -> Result<Body, Status>;
+    /// Handles the message, skipping the validation of the message
+    /// body if `ctx.trust_peer` is `true`.
+    async fn try_handle(&self, ctx: HandlerContext, body: Body) -> Result<Body, Status>;
+
+    /// If the replies of the handler can be cached.
+    fn cacheable(&self) -> bool;
+
+    /// If the replies of the handler are byte oriented and can be ranged.
+    fn rangeable(&self) -> bool;
+
+    /// The limits applied to the handler's requests.
+    fn limits(&self) -> &Limits;
+
+    /// The schema hash of the handler's message.
+    fn schema_hash(&self) -> u64;
+}
+
+/// Views the body of a request as the handler's message.
+async fn view_request<Msg>(
+    ctx: HandlerContext,
+    body: Body,
+    config: &SerdeConfig,
+) -> Result<Request<Msg>, Status>
+where
+    Msg: RequestContents + Send,
+{
+    let queued_for = ctx.admitted_at.elapsed();
+    trace!(
+        request_id = ctx.request_id,
+        queue_wait = ?queued_for,
+        "Request dequeued."
+    );
+
+    let view = if ctx.trust_peer && config.verify_checksum {
+        let config = SerdeConfig {
+            verify_checksum: false,
+            ..config.clone()
+        };
+        Msg::from_body_with_config(body, &config).await?
+    } else {
+        Msg::from_body_with_config(body, config).await?
+    };
+
+    let msg = Request::<Msg>::new(ctx.remote_addr, ctx.headers, view)
+        .with_tracking(ctx.request_id, queued_for, ctx.cancellation, ctx.completed)
+        .with_progress(ctx.progress);
+    Ok(msg)
+}
+
+/// Serializes the reply of a handler unless the client has disconnected
+/// in the meantime, in which case the reply could never be sent.
+fn serialize_reply<R>(
+    reply: R,
+    config: &SerdeConfig,
+    disconnected: &CancellationToken,
+) -> Result<Body, Status>
+where
+    R: TryIntoBody,
+{
+    if disconnected.is_cancelled() {
+        trace!("Skipping the reply of a request whose client disconnected.");
+        return Err(Status::aborted("The client disconnected"));
+    }
+    reply.try_into_body_with_config(config)
 }
 
 struct PhantomHandler<H, Msg>
 where
     H: Send + Sync + 'static,
-    Msg: Send + 'static,
+    Msg: RequestContents + Send + 'static,
 {
     handler: Arc<H>,
+    config: SerdeConfig,
+    cacheable: bool,
+    limits: Limits,
+    validator: Option<Validator<Msg>>,
     _msg: PhantomData<Msg>,
 }
 
@@ -255,19 +650,112 @@ where
     Msg: RequestContents + Send + Sync + 'static,
     H: Handler<Msg> + Send + Sync + 'static,
 {
-    async fn try_handle(
-        &self,
-        remote_addr: SocketAddr,
-        headers: HeaderMap,
-        body: Body,
-    ) -> Result<Body, Status> {
-        let view = Msg::from_body(body).await?;
-
-        let msg = Request::new(remote_addr, headers, view);
-
-        self.handler
-            .on_message(msg)
-            .await
-            .and_then(|reply| reply.try_into_body())
+    async fn try_handle(&self, ctx: HandlerContext, body: Body) -> Result<Body, Status> {
+        let disconnected = ctx.disconnected.clone();
+        let msg = view_request::<Msg>(ctx, body, &self.config).await?;
+        if let Some(validate) = self.validator {
+            validate(&msg)?;
+        }
+
+        let reply = self.handler.on_message(msg).await?;
+        serialize_reply(reply, &self.config, &disconnected)
+    }
+
+    fn cacheable(&self) -> bool {
+        self.cacheable
+    }
+
+    fn rangeable(&self) -> bool {
+        <H::Reply as TryIntoBody>::RANGEABLE
+    }
+
+    fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    fn schema_hash(&self) -> u64 {
+        Msg::schema_hash()
+    }
+}
+
+/// An async function or closure handling messages of type `Msg`,
+/// see [Server::add_fn_handler](crate::Server::add_fn_handler).
+///
+/// This is implemented for every `Fn(Request<Msg>) -> impl Future<Output = Result<Reply, Status>>`
+/// which can be shared between threads.
+pub trait FnHandler<Msg>:
+    Fn(Request<Msg>) -> Self::Future + Send + Sync + 'static
+where
+    Msg: RequestContents,
+{
+    /// The future returned by the function.
+    type Future: Future<Output = Result<Self::Reply, Status>> + Send;
+    /// The reply produced by the function.
+    type Reply: TryIntoBody + Send;
+}
+
+impl<Msg, F, Fut, Reply> FnHandler<Msg> for F
+where
+    Msg: RequestContents,
+    F: Fn(Request<Msg>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Reply, Status>> + Send,
+    Reply: TryIntoBody + Send,
+{
+    type Future = Fut;
+    type Reply = Reply;
+}
+
+/// Wraps a [FnHandler] as a handler of the server.
+pub(crate) fn fn_handler<Msg, F>(
+    handler: F,
+    config: SerdeConfig,
+) -> Arc<dyn OpaqueMessageHandler>
+where
+    Msg: RequestContents + Send + Sync + 'static,
+    F: FnHandler<Msg>,
+{
+    Arc::new(FnMessageHandler {
+        handler,
+        config,
+        limits: Limits::default(),
+        _msg: PhantomData::<fn(Msg)>,
+    })
+}
+
+struct FnMessageHandler<F, Msg> {
+    handler: F,
+    config: SerdeConfig,
+    limits: Limits,
+    _msg: PhantomData<fn(Msg)>,
+}
+
+#[async_trait]
+impl<F, Msg> OpaqueMessageHandler for FnMessageHandler<F, Msg>
+where
+    Msg: RequestContents + Send + Sync + 'static,
+    F: FnHandler<Msg>,
+{
+    async fn try_handle(&self, ctx: HandlerContext, body: Body) -> Result<Body, Status> {
+        let disconnected = ctx.disconnected.clone();
+        let msg = view_request::<Msg>(ctx, body, &self.config).await?;
+
+        let reply = (self.handler)(msg).await?;
+        serialize_reply(reply, &self.config, &disconnected)
+    }
+
+    fn cacheable(&self) -> bool {
+        false
+    }
+
+    fn rangeable(&self) -> bool {
+        <F::Reply as TryIntoBody>::RANGEABLE
+    }
+
+    fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    fn schema_hash(&self) -> u64 {
+        Msg::schema_hash()
     }
 }
@@ -0,0 +1,308 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use http::HeaderValue;
+use parking_lot::Mutex;
+use tokio::sync::watch;
+
+use crate::cache::CachedReply;
+use crate::{Body, Status};
+
+/// The header carrying the client supplied idempotency key of a request.
+///
+/// See [Server::enable_idempotency](crate::Server::enable_idempotency).
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+#[derive(Debug, Clone)]
+/// Configuration of the server's deduplication of requests.
+///
+/// See [Server::enable_idempotency](crate::Server::enable_idempotency).
+pub struct IdempotencyConfig {
+    /// How long the reply of a request is returned for repeated requests
+    /// with the same key.
+    pub ttl: Duration,
+    /// The maximum number of replies kept.
+    ///
+    /// Once full, the oldest replies are evicted first.
+    pub max_entries: usize,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(300),
+            max_entries: 4096,
+        }
+    }
+}
+
+/// The path of a request and its idempotency key.
+type Key = (String, HeaderValue);
+
+enum Entry {
+    /// The first request with the key is still being handled, the receiver
+    /// is notified once it is done.
+    Pending {
+        claim: u64,
+        done: watch::Receiver<()>,
+    },
+    Complete {
+        reply: CachedReply,
+        inserted_at: Instant,
+    },
+}
+
+/// The replies of requests keyed on their idempotency key.
+pub(crate) struct IdempotencyCache {
+    config: IdempotencyConfig,
+    entries: Mutex<Entries>,
+    next_claim: AtomicU64,
+}
+
+#[derive(Default)]
+struct Entries {
+    lookup: HashMap<Key, Entry>,
+    /// The keys of complete entries in insertion order, used for eviction.
+    order: VecDeque<(Key, Instant)>,
+}
+
+enum Claim {
+    Cached(CachedReply),
+    Wait(watch::Receiver<()>),
+    Owned(ClaimGuard),
+}
+
+impl IdempotencyCache {
+    pub(crate) fn new(config: IdempotencyConfig) -> Self {
+        Self {
+            config,
+            entries: Mutex::new(Entries::default()),
+            next_claim: AtomicU64::new(0),
+        }
+    }
+
+    /// Runs the handling of a request unless a request with the same key
+    /// has already been handled, returning its reply instead.
+    ///
+    /// If a request with the same key is still being handled, this waits
+    /// for it to complete. Only successful replies of a known size are kept,
+    /// if the request fails or streams its reply, the next request with the
+    /// key is handled again.
+    pub(crate) async fn run<F>(
+        self: &Arc<Self>,
+        uri: &str,
+        key: HeaderValue,
+        handle: F,
+    ) -> Result<Body, Status>
+    where
+        F: Future<Output = Result<Body, Status>>,
+    {
+        let key = (uri.to_string(), key);
+        let guard = loop {
+            match self.claim(&key) {
+                Claim::Cached(reply) => {
+                    return Ok(Body::with_headers(reply.body.into(), reply.headers));
+                },
+                Claim::Wait(mut done) => {
+                    // Errors once the first request is done.
+                    let _ = done.changed().await;
+                },
+                Claim::Owned(guard) => break guard,
+            }
+        };
+
+        let reply = handle.await?;
+        if reply.len().is_none() {
+            return Ok(reply);
+        }
+
+        let (body, headers) = reply.into_parts();
+        let body = hyper::body::to_bytes(body)
+            .await
+            .map_err(Status::internal)?;
+        guard.complete(CachedReply {
+            body: body.clone(),
+            headers: headers.clone(),
+        });
+
+        Ok(Body::with_headers(body.into(), headers))
+    }
+
+    fn claim(self: &Arc<Self>, key: &Key) -> Claim {
+        let mut entries = self.entries.lock();
+        match entries.lookup.get(key) {
+            Some(Entry::Pending { done, .. }) => return Claim::Wait(done.clone()),
+            Some(Entry::Complete { reply, inserted_at })
+                if inserted_at.elapsed() < self.config.ttl =>
+            {
+                return Claim::Cached(reply.clone());
+            },
+            _ => {},
+        }
+
+        let claim = self.next_claim.fetch_add(1, Ordering::Relaxed);
+        let (sender, done) = watch::channel(());
+        entries
+            .lookup
+            .insert(key.clone(), Entry::Pending { claim, done });
+
+        Claim::Owned(ClaimGuard {
+            cache: self.clone(),
+            key: key.clone(),
+            claim,
+            _done: sender,
+        })
+    }
+
+    fn insert(&self, key: Key, reply: CachedReply) {
+        let mut entries = self.entries.lock();
+        if self.config.max_entries == 0 {
+            entries.lookup.remove(&key);
+            return;
+        }
+
+        let now = Instant::now();
+        while entries.order.len() >= self.config.max_entries {
+            let Some((oldest, inserted_at)) = entries.order.pop_front() else {
+                break;
+            };
+
+            // Only evict the entry if it has not since been replaced.
+            let is_current = matches!(
+                entries.lookup.get(&oldest),
+                Some(Entry::Complete { inserted_at: current, .. })
+                    if *current == inserted_at,
+            );
+            if is_current {
+                entries.lookup.remove(&oldest);
+            }
+        }
+
+        entries.order.push_back((key.clone(), now));
+        entries.lookup.insert(
+            key,
+            Entry::Complete {
+                reply,
+                inserted_at: now,
+            },
+        );
+    }
+}
+
+/// Marks a key as being handled until the guard is dropped.
+struct ClaimGuard {
+    cache: Arc<IdempotencyCache>,
+    key: Key,
+    claim: u64,
+    /// Notifies any waiting requests once dropped.
+    _done: watch::Sender<()>,
+}
+
+impl ClaimGuard {
+    fn complete(self, reply: CachedReply) {
+        self.cache.insert(self.key.clone(), reply);
+    }
+}
+
+impl Drop for ClaimGuard {
+    fn drop(&mut self) {
+        let mut entries = self.cache.entries.lock();
+        let is_current = matches!(
+            entries.lookup.get(&self.key),
+            Some(Entry::Pending { claim, .. }) if *claim == self.claim,
+        );
+        if is_current {
+            entries.lookup.remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use http::HeaderMap;
+
+    use super::*;
+
+    fn reply(data: &'static [u8]) -> Result<Body, Status> {
+        Ok(Body::from(data.to_vec()))
+    }
+
+    async fn run(
+        cache: &Arc<IdempotencyCache>,
+        key: &'static str,
+        handled: &AtomicU64,
+    ) -> Result<Bytes, Status> {
+        let key = HeaderValue::from_static(key);
+        let handle = async {
+            handled.fetch_add(1, Ordering::Relaxed);
+            reply(b"reply")
+        };
+        let body = cache.run("/svc/a", key, handle).await?;
+        Ok(hyper::body::to_bytes(body.into_inner()).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_repeated_key() {
+        let cache = Arc::new(IdempotencyCache::new(IdempotencyConfig::default()));
+        let handled = AtomicU64::new(0);
+
+        assert_eq!(run(&cache, "a", &handled).await.unwrap(), "reply");
+        assert_eq!(run(&cache, "a", &handled).await.unwrap(), "reply");
+        assert_eq!(handled.load(Ordering::Relaxed), 1);
+
+        run(&cache, "b", &handled).await.unwrap();
+        assert_eq!(handled.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expiry_and_eviction() {
+        let cache = Arc::new(IdempotencyCache::new(IdempotencyConfig {
+            ttl: Duration::ZERO,
+            ..Default::default()
+        }));
+        let handled = AtomicU64::new(0);
+        run(&cache, "a", &handled).await.unwrap();
+        run(&cache, "a", &handled).await.unwrap();
+        assert_eq!(handled.load(Ordering::Relaxed), 2);
+
+        let cache = Arc::new(IdempotencyCache::new(IdempotencyConfig {
+            max_entries: 1,
+            ..Default::default()
+        }));
+        let handled = AtomicU64::new(0);
+        run(&cache, "a", &handled).await.unwrap();
+        run(&cache, "b", &handled).await.unwrap();
+        run(&cache, "a", &handled).await.unwrap();
+        assert_eq!(
+            handled.load(Ordering::Relaxed),
+            3,
+            "Oldest entry should be evicted"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_request_is_not_kept() {
+        let cache = Arc::new(IdempotencyCache::new(IdempotencyConfig::default()));
+        let key = HeaderValue::from_static("a");
+
+        let result = cache
+            .run("/svc/a", key.clone(), async { Err(Status::invalid()) })
+            .await;
+        assert!(result.is_err());
+
+        let reply = CachedReply {
+            body: Bytes::new(),
+            headers: HeaderMap::new(),
+        };
+        let result = cache
+            .run("/svc/a", key, async move {
+                Ok(Body::with_headers(reply.body.into(), reply.headers))
+            })
+            .await;
+        assert!(result.is_ok(), "The request should be handled again");
+    }
+}
@@ -95,36 +95,130 @@
 #[macro_use]
 extern crate tracing;
 
+mod admin;
+mod baggage;
 mod body;
+mod cache;
+mod capabilities;
+mod capture;
 mod client;
 mod handler;
+mod idempotency;
+mod limits;
 mod net;
+#[cfg(feature = "otel")]
+mod otel;
+mod overload;
+mod progress;
+mod queue;
+mod range;
+mod replay;
+mod reply;
 mod request;
+mod request_id;
 mod rkyv_tooling;
+pub mod runtime;
+mod schema;
 mod server;
+mod stream;
+mod subscription;
+mod transform;
 mod utils;
+mod validate;
 
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
 /// A re-export of the async-trait macro.
 pub use async_trait::async_trait;
+/// Derives [RpcService], generating the handler registrations.
+///
+/// See [RpcService] for an example.
+pub use datacake_rpc_derive::RpcService;
 pub use http;
+/// A re-export of the cancellation token used to abort requests.
+pub use tokio_util::sync::CancellationToken;
 
+pub use self::admin::{
+    AbortRequest,
+    AdminService,
+    ArchivedInflightInfo,
+    InflightInfo,
+    InflightRequests,
+};
+pub use self::baggage::{Baggage, BAGGAGE_HEADER};
 pub use self::body::{Body, TryAsBody, TryIntoBody};
-pub use self::client::{MessageReply, RpcClient};
-pub use self::handler::{Handler, RpcService, ServiceRegistry};
+pub use self::cache::{ReplyCacheConfig, ReplyCacheStats};
+pub use self::capabilities::{
+    Capabilities,
+    CAPABILITIES_HEADER,
+    EMPTY_BODY_CAPABILITY,
+    PROGRESS_CAPABILITY,
+    RANGES_CAPABILITY,
+};
+pub use self::capture::{CaptureConfig, CapturedFrame, FrameKind};
+pub use self::client::{MessageReply, RpcClient, SendHandle, Sender};
+pub use self::handler::{
+    derive_service_name,
+    FnHandler,
+    Handler,
+    RpcService,
+    ServiceRegistry,
+};
+pub use self::idempotency::{IdempotencyConfig, IDEMPOTENCY_KEY_HEADER};
+pub use self::limits::Limits;
 pub use self::net::{
     ArchivedErrorCode,
     ArchivedStatus,
+    BreakerConfig,
+    BreakerState,
     Channel,
+    ChannelConfig,
     Error,
     ErrorCode,
+    ErrorFraming,
+    Resolver,
+    ResultExt,
     Status,
+    SystemResolver,
+    ERROR_FRAMING_HEADER,
+    PRIORITY_HEADER,
+    SIMPLE_ERROR_VERSION,
+};
+#[cfg(feature = "otel")]
+pub use self::otel::{TRACEPARENT_HEADER, TRACESTATE_HEADER};
+pub use self::overload::OverloadConfig;
+pub use self::progress::ProgressSender;
+pub use self::queue::{IncomingRequests, QueuedRequest, ResponseSender};
+pub use self::range::ByteRange;
+pub use self::replay::{
+    replay,
+    ReplayClient,
+    ReplayConfig,
+    ReplayOutcome,
+    ReplayReport,
+};
+pub use self::reply::{AnyReply, Empty, SharedReply, REPLY_KIND_HEADER};
+pub use self::request::{HeaderParseError, Request, RequestContents};
+pub use self::request_id::{RequestIdSource, REQUEST_ID_HEADER};
+#[cfg(feature = "test-utils")]
+pub use self::rkyv_tooling::{test_roundtrip, RoundtripError};
+pub use self::rkyv_tooling::{
+    to_view_bytes,
+    to_view_bytes_with_scratch,
+    DataView,
+    InvalidView,
+    LazyScratch,
+    ScratchSerializer,
+    SerdeConfig,
 };
-pub use self::request::{Request, RequestContents};
-pub use self::rkyv_tooling::{to_view_bytes, DataView, InvalidView};
-pub use self::server::Server;
+pub use self::runtime::maybe_yield;
+pub use self::schema::SCHEMA_HEADER;
+pub use self::server::{Server, ServerConfig, ServiceInfo};
+pub use self::stream::{ReplyStream, StreamSender, Streaming, STREAM_STATUS_TRAILER};
+pub use self::subscription::Broadcaster;
+pub use self::transform::BodyTransform;
+pub use self::validate::Validate;
 
 pub(crate) fn hash<H: Hash + ?Sized>(v: &H) -> u64 {
     let mut hasher = DefaultHasher::new();
@@ -136,6 +230,24 @@ pub(crate) fn to_uri_path(service: &str, path: &str) -> String {
     format!("/{}/{}", sanitise(service), sanitise(path))
 }
 
+/// Splits a uri path into its service name and message path.
+pub(crate) fn split_uri_path(uri_path: &str) -> (&str, &str) {
+    let uri_path = uri_path.trim_start_matches('/');
+    uri_path.split_once('/').unwrap_or((uri_path, ""))
+}
+
 fn sanitise(parameter: &str) -> String {
     parameter.replace(['<', '>'], "-")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_uri_path() {
+        let uri = to_uri_path("my-service", "my-path");
+        assert_eq!(split_uri_path(&uri), ("my-service", "my-path"));
+        assert_eq!(split_uri_path("/lonely"), ("lonely", ""));
+    }
+}
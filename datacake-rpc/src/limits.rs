@@ -0,0 +1,116 @@
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use hyper::body::HttpBody;
+
+use crate::Status;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+/// Limits applied to the requests of a single handler.
+///
+/// See [ServiceRegistry::add_handler_with_limits](crate::ServiceRegistry::add_handler_with_limits).
+pub struct Limits {
+    /// The maximum size of a request body in bytes.
+    ///
+    /// Requests which exceed it are rejected with
+    /// [ErrorCode::PayloadTooLarge](crate::ErrorCode::PayloadTooLarge).
+    pub max_body: Option<u64>,
+}
+
+/// Enforces the limits of a handler on the body of a request.
+pub(crate) struct BodyLimiter {
+    max_body: u64,
+    exceeded: Arc<AtomicBool>,
+}
+
+impl BodyLimiter {
+    /// Checks the request body against the limits, returning the body to pass
+    /// to the handler and a limiter if it must be checked as it is read.
+    ///
+    /// Bodies which declare their length are checked up front, bodies of
+    /// unknown length are cut off once they exceed the limit.
+    pub(crate) fn apply(
+        limits: &Limits,
+        body: hyper::Body,
+    ) -> Result<(hyper::Body, Option<Self>), Status> {
+        let Some(max_body) = limits.max_body else {
+            return Ok((body, None));
+        };
+
+        let size_hint = HttpBody::size_hint(&body);
+        if size_hint.lower() > max_body {
+            return Err(too_large(max_body));
+        }
+        if size_hint.exact().is_some() {
+            return Ok((body, None));
+        }
+
+        let exceeded = Arc::new(AtomicBool::new(false));
+        let limited = LimitedBody {
+            inner: body,
+            remaining: max_body,
+            max_body,
+            exceeded: exceeded.clone(),
+        };
+        let limiter = Self { max_body, exceeded };
+
+        Ok((hyper::Body::wrap_stream(limited), Some(limiter)))
+    }
+
+    /// Replaces the error of a request whose body exceeded the limit.
+    ///
+    /// Handlers see the body being cut off as a failure to read it, the
+    /// client is told the actual cause instead.
+    pub(crate) fn check<T>(&self, result: Result<T, Status>) -> Result<T, Status> {
+        match result {
+            Err(_) if self.exceeded.load(Ordering::Acquire) => {
+                Err(too_large(self.max_body))
+            },
+            result => result,
+        }
+    }
+}
+
+fn too_large(max_body: u64) -> Status {
+    Status::payload_too_large(format!(
+        "The request body exceeds the limit of {max_body} bytes"
+    ))
+}
+
+/// A body which fails once more than the allowed number of bytes are read.
+struct LimitedBody {
+    inner: hyper::Body,
+    remaining: u64,
+    max_body: u64,
+    exceeded: Arc<AtomicBool>,
+}
+
+impl Stream for LimitedBody {
+    type Item = Result<Bytes, Status>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let chunk = match ready!(Pin::new(&mut self.inner).poll_data(cx)) {
+            Some(Ok(chunk)) => chunk,
+            Some(Err(e)) => return Poll::Ready(Some(Err(Status::connection(e)))),
+            None => return Poll::Ready(None),
+        };
+
+        match self.remaining.checked_sub(chunk.len() as u64) {
+            Some(remaining) => {
+                self.remaining = remaining;
+                Poll::Ready(Some(Ok(chunk)))
+            },
+            None => {
+                self.exceeded.store(true, Ordering::Release);
+                Poll::Ready(Some(Err(too_large(self.max_body))))
+            },
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+/// A token bucket limiting the rate connections are accepted at.
+///
+/// The bucket holds up to `burst` tokens and is refilled at `per_sec` tokens
+/// per second, each accepted connection takes one token.
+pub(crate) struct AcceptRateLimiter {
+    per_sec: f64,
+    burst: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl AcceptRateLimiter {
+    /// Creates a new limiter which starts with a full bucket.
+    ///
+    /// # Panics
+    ///
+    /// If `per_sec` or `burst` is `0`.
+    pub(crate) fn new(per_sec: u32, burst: u32) -> Self {
+        assert!(
+            per_sec > 0 && burst > 0,
+            "The accept rate and burst must be greater than 0"
+        );
+        Self {
+            per_sec: per_sec as f64,
+            burst: burst as f64,
+            tokens: burst as f64,
+            refilled: Instant::now(),
+        }
+    }
+
+    /// Takes a token for the next connection to be accepted, at `now`.
+    ///
+    /// Returns how long to wait before trying again if the bucket is empty.
+    pub(crate) fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.burst);
+        self.refilled = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+
+        let missing = 1.0 - self.tokens;
+        Err(Duration::from_secs_f64(missing / self.per_sec))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_rate() {
+        let start = Instant::now();
+        let mut limiter = AcceptRateLimiter::new(10, 3);
+        limiter.refilled = start;
+
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(start).is_ok());
+        }
+        let wait = limiter.try_acquire(start).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(100));
+
+        let later = start + Duration::from_millis(50);
+        let wait = limiter.try_acquire(later).unwrap_err();
+        assert!(wait <= Duration::from_millis(50));
+
+        let later = start + Duration::from_millis(100);
+        assert!(limiter.try_acquire(later).is_ok());
+        assert!(limiter.try_acquire(later).is_err());
+
+        // The bucket never holds more than the burst.
+        let later = later + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(later).is_ok());
+        }
+        assert!(limiter.try_acquire(later).is_err());
+    }
+}
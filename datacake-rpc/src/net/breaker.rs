@@ -0,0 +1,199 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+#[derive(Debug, Clone)]
+/// Configuration of a [Channel](crate::Channel)'s circuit breaker.
+///
+/// See [Channel::with_circuit_breaker](crate::Channel::with_circuit_breaker).
+pub struct BreakerConfig {
+    /// The number of consecutive failed requests after which the breaker opens.
+    pub failure_threshold: usize,
+    /// How long the breaker stays open before allowing a probe request through.
+    pub open_duration: Duration,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// The state of a circuit breaker.
+pub enum BreakerState {
+    /// Requests are sent as normal.
+    Closed,
+    /// Requests fail immediately without being sent.
+    Open,
+    /// The open duration has elapsed and a single probe request is allowed
+    /// through, closing the breaker if it succeeds or re-opening it if it fails.
+    HalfOpen,
+}
+
+/// Tracks the failures of a channel's requests, failing fast while open.
+pub(crate) struct CircuitBreaker {
+    config: BreakerConfig,
+    inner: Mutex<BreakerInner>,
+}
+
+struct BreakerInner {
+    consecutive_failures: usize,
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(config: BreakerConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(BreakerInner {
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_in_flight: false,
+            }),
+        }
+    }
+
+    /// The current state of the breaker.
+    pub(crate) fn state(&self) -> BreakerState {
+        let inner = self.inner.lock();
+        match inner.opened_at {
+            None => BreakerState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.config.open_duration => {
+                BreakerState::Open
+            },
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Attempts to let a request through the breaker.
+    ///
+    /// Returns `None` if the breaker is open or a probe is already in-flight.
+    pub(crate) fn try_acquire(self: &Arc<Self>) -> Option<BreakerPermit> {
+        let mut inner = self.inner.lock();
+
+        let probe = match inner.opened_at {
+            None => false,
+            Some(opened_at) if opened_at.elapsed() < self.config.open_duration => {
+                return None;
+            },
+            Some(_) if inner.probe_in_flight => return None,
+            Some(_) => {
+                inner.probe_in_flight = true;
+                true
+            },
+        };
+
+        Some(BreakerPermit {
+            breaker: self.clone(),
+            probe,
+            recorded: false,
+        })
+    }
+
+    fn record(&self, probe: bool, success: bool) {
+        let mut inner = self.inner.lock();
+        if probe {
+            inner.probe_in_flight = false;
+        }
+
+        if success {
+            inner.consecutive_failures = 0;
+            inner.opened_at = None;
+            return;
+        }
+
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        if probe || inner.consecutive_failures >= self.config.failure_threshold {
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+/// Permission for a single request to pass through the breaker.
+///
+/// Dropping the permit without recording an outcome, i.e. if the request
+/// was cancelled, does not count towards the breaker.
+pub(crate) struct BreakerPermit {
+    breaker: Arc<CircuitBreaker>,
+    probe: bool,
+    recorded: bool,
+}
+
+impl BreakerPermit {
+    /// Records the outcome of the request.
+    pub(crate) fn record(mut self, success: bool) {
+        self.recorded = true;
+        self.breaker.record(self.probe, success);
+    }
+}
+
+impl Drop for BreakerPermit {
+    fn drop(&mut self) {
+        if self.probe && !self.recorded {
+            self.breaker.inner.lock().probe_in_flight = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(open_duration: Duration) -> Arc<CircuitBreaker> {
+        Arc::new(CircuitBreaker::new(BreakerConfig {
+            failure_threshold: 2,
+            open_duration,
+        }))
+    }
+
+    #[test]
+    fn test_breaker_opens_after_threshold() {
+        let breaker = breaker(Duration::from_secs(60));
+
+        breaker.try_acquire().unwrap().record(false);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        breaker.try_acquire().unwrap().record(true);
+        breaker.try_acquire().unwrap().record(false);
+        assert_eq!(
+            breaker.state(),
+            BreakerState::Closed,
+            "Successes should reset the failure count"
+        );
+
+        breaker.try_acquire().unwrap().record(false);
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(
+            breaker.try_acquire().is_none(),
+            "Open breaker should fail fast"
+        );
+    }
+
+    #[test]
+    fn test_breaker_half_open_probe() {
+        let breaker = breaker(Duration::ZERO);
+
+        breaker.try_acquire().unwrap().record(false);
+        breaker.try_acquire().unwrap().record(false);
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+
+        let probe = breaker.try_acquire().expect("Probe should be allowed");
+        assert!(breaker.try_acquire().is_none(), "Only one probe at a time");
+        probe.record(false);
+        assert_eq!(
+            breaker.state(),
+            BreakerState::HalfOpen,
+            "Failed probe should re-open the breaker"
+        );
+
+        // A cancelled probe releases its slot without counting.
+        drop(breaker.try_acquire().unwrap());
+        breaker.try_acquire().unwrap().record(true);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+}
@@ -1,66 +1,362 @@
+use std::io;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use http::{HeaderMap, Method, Request, Response};
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
 
+use super::breaker::{BreakerPermit, CircuitBreaker};
+use super::ordering::Sequencer;
+use super::shared::SharedConnection;
 #[cfg(feature = "simulation")]
 use super::simulation::LazyClient;
+#[cfg(not(feature = "simulation"))]
+use super::timeout::TimeoutConnector;
 use crate::body::Body;
-use crate::net::Error;
-use crate::request::MessageMetadata;
+use crate::capabilities::{Capabilities, CAPABILITIES_HEADER};
+use crate::net::{BreakerConfig, BreakerState, Error, Resolver, SystemResolver};
+#[cfg(not(feature = "simulation"))]
+use crate::runtime::HyperExecutor;
+
+#[cfg(not(feature = "simulation"))]
+pub(crate) type Connection = hyper::Client<TimeoutConnector, hyper::Body>;
+#[cfg(feature = "simulation")]
+pub(crate) type Connection = LazyClient;
+
+/// The path of the request used to warm up a channel's connection.
+const WARMUP_PATH: &str = "/datacake-warmup/ping";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Configuration of the connections established by a [Channel].
+pub struct ChannelConfig {
+    /// The maximum amount of time to wait for a connection to be established.
+    pub connect_timeout: Duration,
+    /// The maximum amount of time a connection can go without receiving
+    /// any data while waiting to read.
+    ///
+    /// This is reset whenever bytes arrive, so it detects stalled connections
+    /// rather than limiting requests which are slow but still making progress.
+    ///
+    /// When set, keep-alive pings are sent at half this interval so that idle
+    /// connections to a healthy server are not closed.
+    pub read_timeout: Option<Duration>,
+    /// The maximum amount of time a write to the connection can go without
+    /// making any progress, i.e. while the request is being flushed.
+    pub write_timeout: Option<Duration>,
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(2),
+            read_timeout: None,
+            write_timeout: None,
+        }
+    }
+}
 
 #[derive(Clone)]
 /// A raw client connection which can produce multiplexed streams.
 pub struct Channel {
-    #[cfg(not(feature = "simulation"))]
-    connection: hyper::Client<hyper::client::HttpConnector, hyper::Body>,
-
-    #[cfg(feature = "simulation")]
-    connection: LazyClient,
-
+    connection: Arc<RwLock<Option<Connection>>>,
+    state: Arc<ChannelState>,
+    breaker: Option<Arc<CircuitBreaker>>,
+    sequencer: Option<Arc<Sequencer>>,
+    concurrency: Option<Arc<Semaphore>>,
     remote_addr: SocketAddr,
+    config: ChannelConfig,
 }
 
 impl Channel {
-    #[cfg(not(feature = "simulation"))]
     /// Connects to a remote RPC server.
     pub fn connect(remote_addr: SocketAddr) -> Self {
-        let mut http = hyper::client::HttpConnector::new();
-        http.enforce_http(false);
-        http.set_nodelay(true);
-        http.set_connect_timeout(Some(std::time::Duration::from_secs(2)));
+        Self::connect_with_config(remote_addr, ChannelConfig::default())
+    }
+
+    /// Connects to a remote RPC server by its host name.
+    ///
+    /// The host is resolved once via the system's DNS resolver and the
+    /// channel connects to the first address returned.
+    pub async fn connect_host(host: &str, port: u16) -> io::Result<Self> {
+        Self::connect_host_with_resolver(
+            host,
+            port,
+            Arc::new(SystemResolver),
+            ChannelConfig::default(),
+        )
+        .await
+    }
+
+    /// Connects to a remote RPC server by its host name, resolving the host
+    /// via the provided [Resolver] and using the provided [ChannelConfig].
+    ///
+    /// The channel connects to the first address returned by the resolver.
+    pub async fn connect_host_with_resolver(
+        host: &str,
+        port: u16,
+        resolver: Arc<dyn Resolver>,
+        config: ChannelConfig,
+    ) -> io::Result<Self> {
+        let addrs = resolver.resolve(host, port).await?;
+        let remote_addr = addrs.first().copied().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No addresses were resolved for {host}:{port}"),
+            )
+        })?;
+
+        Ok(Self::connect_with_config(remote_addr, config))
+    }
+
+    /// Connects to a remote RPC server using the provided [ChannelConfig].
+    pub fn connect_with_config(remote_addr: SocketAddr, config: ChannelConfig) -> Self {
+        let connection = create_connection(remote_addr, config.clone());
+        Self::from_connection(connection, remote_addr, config)
+    }
+
+    /// Connects to a remote RPC server, sharing the underlying connection
+    /// with any other shared channels to the same address.
+    ///
+    /// This avoids opening redundant connections when many components
+    /// independently create channels to the same server. The connection is
+    /// closed once the last channel using it has been dropped.
+    ///
+    /// Each channel keeps its own in-flight tracking and circuit breaker,
+    /// closing a shared channel via [Channel::close] only stops it from
+    /// using the connection, other channels are unaffected.
+    pub fn connect_shared(remote_addr: SocketAddr) -> Self {
+        Self::connect_shared_with_config(remote_addr, ChannelConfig::default())
+    }
+
+    /// Connects to a remote RPC server using the provided [ChannelConfig],
+    /// sharing the underlying connection with any other shared channels to
+    /// the same address.
+    ///
+    /// Only channels with the same config share a connection, see
+    /// [Channel::connect_shared].
+    pub fn connect_shared_with_config(
+        remote_addr: SocketAddr,
+        config: ChannelConfig,
+    ) -> Self {
+        let shared =
+            SharedConnection::get_or_connect(remote_addr, config.clone(), |config| {
+                create_connection(remote_addr, config)
+            });
 
-        let client = hyper::Client::builder()
-            .http2_keep_alive_while_idle(true)
-            .http2_only(true)
-            .http2_adaptive_window(true)
-            .build(http);
+        let connection = shared.connection().clone();
+        let channel = Self::from_connection(connection, remote_addr, config);
+        *channel.state.connected_at.lock() = shared.created_at();
+        *channel.state.shared.lock() = Some(shared);
+        channel
+    }
 
+    fn from_connection(
+        connection: Connection,
+        remote_addr: SocketAddr,
+        config: ChannelConfig,
+    ) -> Self {
         Self {
-            connection: client,
+            connection: Arc::new(RwLock::new(Some(connection))),
+            state: Arc::new(ChannelState::new()),
+            breaker: None,
+            sequencer: None,
+            concurrency: None,
             remote_addr,
+            config,
         }
     }
 
-    #[cfg(feature = "simulation")]
-    /// Connects to a remote RPC server with turmoil simulation enabled.
-    pub fn connect(remote_addr: SocketAddr) -> Self {
-        let client = LazyClient::connect(remote_addr);
+    /// Sets the maximum amount of time a connection is used for,
+    /// regardless of its activity.
+    ///
+    /// Once the connection reaches this age, the next request establishes
+    /// a new connection which is used from then on. Requests which are
+    /// already in-flight on the old connection are unaffected, it is closed
+    /// once they have all received their replies. This periodically
+    /// rebalances requests across servers behind a load balancer.
+    ///
+    /// Shared channels move onto a new shared connection, which any other
+    /// channels sharing the old connection also move onto once their own
+    /// lifetime has elapsed. The lifetime applies to all clones of the channel.
+    /// Passing `None` removes the limit.
+    pub fn set_max_connection_lifetime(&self, lifetime: Option<Duration>) {
+        *self.state.max_connection_lifetime.write() = lifetime;
+    }
 
-        Self {
-            connection: client,
-            remote_addr,
+    /// Replaces the connection with a new one once it has exceeded
+    /// the channel's maximum lifetime.
+    fn recycle_expired_connection(&self) {
+        let Some(lifetime) = *self.state.max_connection_lifetime.read() else {
+            return;
+        };
+
+        let mut connected_at = self.state.connected_at.lock();
+        if connected_at.elapsed() < lifetime {
+            return;
+        }
+
+        let mut connection = self.connection.write();
+        // The channel has been closed.
+        if connection.is_none() {
+            return;
+        }
+
+        let mut shared = self.state.shared.lock();
+        match shared.as_ref() {
+            Some(existing) => {
+                let replacement = existing
+                    .reconnect(|config| create_connection(self.remote_addr, config));
+                *connection = Some(replacement.connection().clone());
+                *connected_at = replacement.created_at();
+                *shared = Some(replacement);
+            },
+            None => {
+                let replacement =
+                    create_connection(self.remote_addr, self.config.clone());
+                *connection = Some(replacement);
+                *connected_at = Instant::now();
+            },
+        }
+    }
+
+    /// Adds a circuit breaker to the channel.
+    ///
+    /// Once the configured number of consecutive requests have failed to
+    /// receive a response, i.e. due to connection errors or timeouts, the
+    /// breaker opens and requests fail immediately with
+    /// [ErrorCode::ServiceUnavailable](crate::ErrorCode::ServiceUnavailable)
+    /// without being sent. After the open duration has elapsed, a single probe
+    /// request is let through to check if the server has recovered.
+    ///
+    /// Error replies from the server are responses and do not count towards
+    /// the breaker. The breaker is shared by any clones of the channel made
+    /// after calling this.
+    pub fn with_circuit_breaker(mut self, config: BreakerConfig) -> Self {
+        self.breaker = Some(Arc::new(CircuitBreaker::new(config)));
+        self
+    }
+
+    /// Sends the channel's requests one at a time in the order they are made.
+    ///
+    /// Requests multiplexed over a channel are otherwise sent and handled
+    /// concurrently, so the server may process them in any order. An ordered
+    /// channel only sends a request once the previous request has received
+    /// its response, guaranteeing the server handles them in order regardless
+    /// of how it is configured. Requests are ordered by when their futures are
+    /// first polled and time spent waiting counts towards the client's timeout.
+    ///
+    /// This trades throughput for ordering: every request pays a full round
+    /// trip before the next can be sent and a single slow request holds up
+    /// every request behind it. When the server has
+    /// [ordered connections](crate::Server::set_ordered_connections) enabled
+    /// the requests of a regular channel are already handled in arrival order
+    /// without the extra round trips.
+    ///
+    /// The order is shared by any clones of the channel made after calling this.
+    pub fn ordered(mut self) -> Self {
+        self.sequencer = Some(Arc::new(Sequencer::default()));
+        self
+    }
+
+    /// Limits the number of requests the channel sends at once.
+    ///
+    /// A request holds one of the `limit` permits from when it is sent until
+    /// its reply has been received, requests sent while all permits are taken
+    /// wait for one to be released, which counts towards the client's timeout.
+    /// Alternatively [RpcClient::try_send](crate::RpcClient::try_send) fails
+    /// immediately rather than waiting.
+    ///
+    /// The limit is shared by any clones of the channel made after calling this.
+    pub fn with_concurrency_limit(mut self, limit: usize) -> Self {
+        self.concurrency = Some(Arc::new(Semaphore::new(limit)));
+        self
+    }
+
+    #[inline]
+    /// The number of requests which can currently be sent without waiting,
+    /// if the channel has a concurrency limit.
+    pub fn available_permits(&self) -> Option<usize> {
+        self.concurrency
+            .as_ref()
+            .map(|semaphore| semaphore.available_permits())
+    }
+
+    /// Waits for the concurrency limit to allow a request to be sent.
+    ///
+    /// Returns `None` if the channel does not have a concurrency limit.
+    pub(crate) async fn acquire_permit(&self) -> Option<OwnedSemaphorePermit> {
+        let semaphore = self.concurrency.clone()?;
+        // The semaphore is never closed.
+        semaphore.acquire_owned().await.ok()
+    }
+
+    /// Attempts to let a request through the concurrency limit without waiting.
+    ///
+    /// Returns `None` if the channel does not have a concurrency limit.
+    pub(crate) fn try_acquire_permit(
+        &self,
+    ) -> Result<Option<OwnedSemaphorePermit>, Error> {
+        match self.concurrency.clone() {
+            None => Ok(None),
+            Some(semaphore) => semaphore
+                .try_acquire_owned()
+                .map(Some)
+                .map_err(|_| Error::AtCapacity),
+        }
+    }
+
+    #[inline]
+    /// The state of the channel's circuit breaker, if it has one.
+    pub fn breaker_state(&self) -> Option<BreakerState> {
+        self.breaker.as_ref().map(|breaker| breaker.state())
+    }
+
+    /// Attempts to let a request through the channel's circuit breaker.
+    ///
+    /// Returns `None` if the channel does not have a breaker.
+    pub(crate) fn breaker_permit(&self) -> Result<Option<BreakerPermit>, Error> {
+        match self.breaker.as_ref() {
+            None => Ok(None),
+            Some(breaker) => breaker.try_acquire().map(Some).ok_or(Error::CircuitOpen),
         }
     }
 
+    /// Marks the start of a new request on the channel.
+    ///
+    /// The channel considers the request in-flight until the returned guard
+    /// is dropped, this should be held until the reply has been fully read.
+    pub(crate) fn start_request(&self) -> Result<InFlightGuard, Error> {
+        if self.state.closed.load(Ordering::Acquire) {
+            return Err(Error::Closed);
+        }
+
+        self.state.in_flight.fetch_add(1, Ordering::AcqRel);
+        Ok(InFlightGuard {
+            state: self.state.clone(),
+        })
+    }
+
     /// Sends a message payload the remote server and gets the response
     /// data back.
     pub(crate) async fn send_parts(
         &self,
-        metadata: MessageMetadata,
+        uri_path: &str,
         headers: HeaderMap,
         body: Body,
     ) -> Result<Response<hyper::Body>, Error> {
-        let uri = format!("http://{}{}", self.remote_addr, metadata.to_uri_path(),);
+        let turn = self.sequencer.as_ref().map(|sequencer| sequencer.next());
+        let _turn = match turn {
+            Some(turn) => Some(turn.wait().await),
+            None => None,
+        };
+
+        self.recycle_expired_connection();
+        let connection = self.connection.read().clone().ok_or(Error::Closed)?;
+
+        let uri = format!("http://{}{}", self.remote_addr, uri_path);
         let mut request = Request::builder()
             .method(Method::POST)
             .uri(uri)
@@ -70,19 +366,202 @@ impl Channel {
         (*request.headers_mut()) = headers;
 
         #[cfg(not(feature = "simulation"))]
-        let resp = self.connection.request(request).await?;
+        let resp = connection.request(request).await?;
         #[cfg(feature = "simulation")]
         let resp = {
-            let conn = self.connection.get_or_init().await?;
+            let conn = connection.get_or_init().await?;
             conn.lock().await.send_request(request).await?
         };
 
         Ok(resp)
     }
 
+    /// Eagerly establishes the connection to the remote server.
+    ///
+    /// Connections are otherwise only established once the first request is
+    /// sent, warming up the channel beforehand avoids the first request paying
+    /// for the connection and HTTP/2 handshake. This completes once the server
+    /// has responded, returning an error if the server cannot be reached.
+    ///
+    /// Warming up also negotiates the optional features supported by both the
+    /// client and server, see [Channel::negotiated_capabilities].
+    pub async fn warmup(&self) -> Result<(), Error> {
+        if self.is_closed() {
+            return Err(Error::Closed);
+        }
+
+        let local = Capabilities::local();
+        let mut headers = HeaderMap::new();
+        headers.insert(CAPABILITIES_HEADER, local.to_header_value());
+
+        // Any response will do, the server rejects the unknown path
+        // without dispatching it to a handler.
+        let response = self
+            .send_parts(WARMUP_PATH, headers, Body::from(Vec::new()))
+            .await?;
+
+        let remote = Capabilities::from_headers(response.headers());
+        *self.state.capabilities.write() = Some(local.intersection(&remote));
+
+        Ok(())
+    }
+
+    /// The optional features supported by both the client and server.
+    ///
+    /// Capabilities are negotiated when the channel is warmed up via
+    /// [Channel::warmup], this returns `None` until then. Servers which do
+    /// not support negotiation are treated as supporting no optional features.
+    pub fn negotiated_capabilities(&self) -> Option<Capabilities> {
+        self.state.capabilities.read().clone()
+    }
+
+    /// Returns if the optional feature can be used with the server.
+    ///
+    /// Features are unsupported until the server has advertised them, which
+    /// requires the capabilities to have been negotiated.
+    pub(crate) fn supports(&self, feature: &str) -> bool {
+        self.state
+            .capabilities
+            .read()
+            .as_ref()
+            .is_some_and(|capabilities| capabilities.supports(feature))
+    }
+
+    /// Returns if the optional feature can be used with the server,
+    /// negotiating the capabilities first if the channel was not warmed up.
+    ///
+    /// If the negotiation fails, the feature is treated as unsupported.
+    pub(crate) async fn negotiate(&self, feature: &str) -> bool {
+        if self.state.capabilities.read().is_none() {
+            if let Err(error) = self.warmup().await {
+                debug!(error = ?error, "Failed to negotiate capabilities.");
+                return false;
+            }
+        }
+        self.supports(feature)
+    }
+
+    /// Gracefully closes the channel.
+    ///
+    /// Once called, the channel and all of its clones will reject any new
+    /// requests with [Error::Closed], any requests which are already in-flight
+    /// are given up to `timeout` to receive their replies before the underlying
+    /// connections are closed.
+    ///
+    /// Returns `true` if all in-flight requests completed within the timeout.
+    pub async fn close(&self, timeout: Duration) -> bool {
+        self.state.closed.store(true, Ordering::Release);
+
+        let drained = crate::runtime::timeout(timeout, self.state.wait_idle())
+            .await
+            .is_ok();
+
+        // Dropping our handle to the connection allows it to shutdown
+        // once any remaining requests have released theirs.
+        self.connection.write().take();
+        self.state.shared.lock().take();
+
+        drained
+    }
+
+    #[inline]
+    /// Returns if the channel has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.state.closed.load(Ordering::Acquire)
+    }
+
     #[inline]
     /// The address of the remote connection.
     pub fn remote_addr(&self) -> SocketAddr {
         self.remote_addr
     }
 }
+
+/// The shared state of a channel and its clones.
+struct ChannelState {
+    closed: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+    capabilities: RwLock<Option<Capabilities>>,
+    /// The registry entry keeping the connection shared with other channels.
+    shared: Mutex<Option<Arc<SharedConnection>>>,
+    max_connection_lifetime: RwLock<Option<Duration>>,
+    /// When the current connection was created.
+    connected_at: Mutex<Instant>,
+}
+
+impl ChannelState {
+    fn new() -> Self {
+        Self {
+            closed: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+            idle: Notify::new(),
+            capabilities: RwLock::new(None),
+            shared: Mutex::new(None),
+            max_connection_lifetime: RwLock::new(None),
+            connected_at: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits until there are no in-flight requests.
+    async fn wait_idle(&self) {
+        loop {
+            let notified = self.idle.notified();
+
+            if self.in_flight.load(Ordering::Acquire) == 0 {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+}
+
+#[cfg(not(feature = "simulation"))]
+/// Creates a new connection to the remote server.
+///
+/// The connection is established lazily once the first request is sent.
+pub(crate) fn create_connection(
+    _remote_addr: SocketAddr,
+    config: ChannelConfig,
+) -> Connection {
+    let mut http = hyper::client::HttpConnector::new();
+    http.enforce_http(false);
+    http.set_nodelay(true);
+    http.set_connect_timeout(Some(config.connect_timeout));
+
+    let connector =
+        TimeoutConnector::new(http, config.read_timeout, config.write_timeout);
+    hyper::Client::builder()
+        .executor(HyperExecutor)
+        .http2_keep_alive_while_idle(true)
+        .http2_keep_alive_interval(config.read_timeout.map(|t| t / 2))
+        .http2_only(true)
+        .http2_adaptive_window(true)
+        .build(connector)
+}
+
+#[cfg(feature = "simulation")]
+/// Creates a new connection to the remote server with turmoil
+/// simulation enabled.
+///
+/// The connection is established lazily once the first request is sent.
+pub(crate) fn create_connection(
+    remote_addr: SocketAddr,
+    config: ChannelConfig,
+) -> Connection {
+    LazyClient::connect(remote_addr, config)
+}
+
+/// A guard marking a request as in-flight on the channel.
+pub(crate) struct InFlightGuard {
+    state: Arc<ChannelState>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.state.in_flight.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.state.idle.notify_waiters();
+        }
+    }
+}
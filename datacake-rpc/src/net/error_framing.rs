@@ -0,0 +1,153 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use http::{HeaderMap, HeaderValue};
+
+use super::{ErrorCode, Status};
+
+/// The response header marking an error reply which uses the
+/// [ErrorFraming::Simple] framing, set to `simple`.
+pub const ERROR_FRAMING_HEADER: &str = "x-datacake-error-framing";
+
+/// The first byte of every error reply using the [ErrorFraming::Simple] framing.
+pub const SIMPLE_ERROR_VERSION: u8 = 1;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+/// How the server encodes its error replies.
+///
+/// Error replies are sent with the HTTP status `400 Bad Request` regardless of
+/// the framing, the body of the response differs.
+///
+/// # Rkyv
+///
+/// The default framing is the [Status] archived with rkyv 0.7 followed by a
+/// 4 byte little-endian CRC32 checksum of the archived bytes, as with every
+/// other message. Archived integers use 32 bit sizes in the native byte order,
+/// i.e. little-endian on all common platforms. The root object is the 12 bytes
+/// preceding the checksum:
+///
+/// | Offset | Size | Field                                                  |
+/// |--------|------|--------------------------------------------------------|
+/// | 0      | 1    | The [ErrorCode], see [ErrorCode::as_u16].              |
+/// | 1      | 3    | Padding.                                               |
+/// | 4      | 8    | The message as an archived string.                     |
+///
+/// A message of up to 7 bytes is stored inline, with its bytes at offset 4
+/// and its length in the final byte at offset 11. Otherwise the string is
+/// stored out of line, with its length as a `u32` at offset 4 and the `i32`
+/// offset of its bytes, relative to offset 4, at offset 8. The high bit of the
+/// final byte is set for out of line strings, as their offset is negative.
+///
+/// # Simple
+///
+/// A language neutral framing for clients which do not implement rkyv, the
+/// response carries the [ERROR_FRAMING_HEADER] and the body consists of:
+///
+/// | Offset | Size | Field                                                  |
+/// |--------|------|--------------------------------------------------------|
+/// | 0      | 1    | The framing version, [SIMPLE_ERROR_VERSION].           |
+/// | 1      | 2    | The [ErrorCode] as a big-endian `u16`.                 |
+/// | 3      | ..   | The message as UTF-8, until the end of the body.       |
+///
+/// Clients of this crate understand both framings.
+///
+/// Both framings are stable, new error codes are only ever added after the
+/// existing ones. Errors which occur while a reply is being streamed, i.e.
+/// the failure of a [ReplyStream](crate::ReplyStream) or of a request with
+/// progress updates, are always sent in the rkyv framing.
+pub enum ErrorFraming {
+    #[default]
+    /// The archived [Status], for Rust to Rust communication.
+    Rkyv,
+    /// A fixed header followed by the UTF-8 message.
+    Simple,
+}
+
+impl ErrorFraming {
+    /// Encodes the status in the framing, adding any headers it requires.
+    pub(crate) fn encode(&self, status: &Status, headers: &mut HeaderMap) -> Bytes {
+        match self {
+            Self::Rkyv => {
+                // This should be infallible.
+                let buffer =
+                    crate::rkyv_tooling::to_view_bytes(status).unwrap_or_default();
+                Bytes::from_owner(buffer)
+            },
+            Self::Simple => {
+                headers.insert(ERROR_FRAMING_HEADER, HeaderValue::from_static("simple"));
+                let mut buffer = BytesMut::with_capacity(3 + status.message.len());
+                buffer.put_u8(SIMPLE_ERROR_VERSION);
+                buffer.put_u16(status.code.as_u16());
+                buffer.put_slice(status.message.as_bytes());
+                buffer.freeze()
+            },
+        }
+    }
+
+    /// The framing of an error reply with the given headers.
+    pub(crate) fn of_reply(headers: &HeaderMap) -> Self {
+        match headers.get(ERROR_FRAMING_HEADER) {
+            Some(value) if value == "simple" => Self::Simple,
+            _ => Self::Rkyv,
+        }
+    }
+}
+
+/// Decodes an error reply using the [ErrorFraming::Simple] framing.
+pub(crate) fn decode_simple(buffer: &[u8]) -> Result<Status, Status> {
+    let [version, high, low, message @ ..] = buffer else {
+        return Err(Status::invalid());
+    };
+    if *version != SIMPLE_ERROR_VERSION {
+        return Err(Status::invalid());
+    }
+
+    let code = ErrorCode::from_u16(u16::from_be_bytes([*high, *low]))
+        .ok_or_else(Status::invalid)?;
+    let message = std::str::from_utf8(message).map_err(|_| Status::invalid())?;
+    Ok(Status {
+        code,
+        message: message.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_framing() {
+        let status = Status::not_found("Unknown tenant");
+        let mut headers = HeaderMap::new();
+        let buffer = ErrorFraming::Simple.encode(&status, &mut headers);
+        assert_eq!(ErrorFraming::of_reply(&headers), ErrorFraming::Simple);
+        assert_eq!(&buffer[..3], &[SIMPLE_ERROR_VERSION, 0, 5]);
+        assert_eq!(&buffer[3..], b"Unknown tenant");
+        assert_eq!(decode_simple(&buffer).unwrap(), status);
+
+        assert!(decode_simple(&[SIMPLE_ERROR_VERSION, 0]).is_err());
+        assert!(decode_simple(&[SIMPLE_ERROR_VERSION, 0xFF, 0xFF]).is_err());
+        assert!(decode_simple(&[2, 0, 1]).is_err());
+    }
+
+    #[test]
+    fn test_rkyv_framing_layout() {
+        let mut headers = HeaderMap::new();
+        let status = Status::not_found("missing");
+        let buffer = ErrorFraming::Rkyv.encode(&status, &mut headers);
+        assert!(headers.is_empty());
+
+        let root = &buffer[buffer.len() - 16..buffer.len() - 4];
+        assert_eq!(root[0], 5);
+        assert_eq!(&root[4..11], b"missing");
+        assert_eq!(root[11], 7);
+
+        let status = Status::internal("A message stored out of line");
+        let buffer = ErrorFraming::Rkyv.encode(&status, &mut headers);
+        let start = buffer.len() - 16;
+        let root = &buffer[start..buffer.len() - 4];
+        assert_eq!(root[0], 1);
+        let len = u32::from_le_bytes(root[4..8].try_into().unwrap()) as usize;
+        let offset = i32::from_le_bytes(root[8..12].try_into().unwrap());
+        let message = (start as isize + 4 + offset as isize) as usize;
+        assert_eq!(&buffer[message..message + len], status.message.as_bytes());
+    }
+}
@@ -1,15 +1,34 @@
+mod accept_rate;
+mod breaker;
 mod client;
+mod error_framing;
+mod ordering;
+mod priority;
+mod read_buffer;
+mod resolver;
 mod server;
+mod shared;
 mod status;
+mod timeout;
 
 #[cfg(feature = "simulation")]
 mod simulation;
 
 use std::io;
+use std::net::SocketAddr;
 
-pub use client::Channel;
-pub(crate) use server::start_rpc_server;
-pub use status::{ArchivedErrorCode, ArchivedStatus, ErrorCode, Status};
+pub(crate) use accept_rate::AcceptRateLimiter;
+pub use breaker::{BreakerConfig, BreakerState};
+pub use client::{Channel, ChannelConfig};
+pub(crate) use error_framing::decode_simple;
+pub use error_framing::{ErrorFraming, ERROR_FRAMING_HEADER, SIMPLE_ERROR_VERSION};
+pub use priority::PRIORITY_HEADER;
+pub(crate) use read_buffer::ReadBufferBounds;
+pub use resolver::{Resolver, SystemResolver};
+#[cfg(not(feature = "simulation"))]
+pub(crate) use server::adopt_rpc_server;
+pub(crate) use server::{dispatch_request, start_rpc_server, ServerHandle};
+pub use status::{ArchivedErrorCode, ArchivedStatus, ErrorCode, ResultExt, Status};
 
 #[derive(Debug, thiserror::Error)]
 /// A failure in an RPC operation.
@@ -20,4 +39,51 @@ pub enum Error {
     #[error("Hyper Error: {0}")]
     /// The operation failed due an error originating in hyper.
     Hyper(#[from] hyper::Error),
+    #[error("The channel has been closed")]
+    /// The channel has been closed and can no longer send requests.
+    Closed,
+    #[error("The channel's circuit breaker is open")]
+    /// The channel's circuit breaker is open and the request was not sent.
+    CircuitOpen,
+    #[error("The channel's concurrency limit has been reached")]
+    /// The channel is already sending as many requests as its concurrency
+    /// limit allows and the request was not sent.
+    AtCapacity,
+    #[error("Failed to bind RPC server to {addr}: {}", describe_bind_error(.source))]
+    /// The server failed to bind to the given address.
+    Bind {
+        /// The address the server attempted to bind to.
+        addr: SocketAddr,
+        /// The underlying IO error.
+        source: io::Error,
+    },
+}
+
+impl Error {
+    /// Returns the kind of bind failure if this error originated from
+    /// the server failing to bind to its listen address.
+    ///
+    /// This allows callers to distinguish common deployment issues like
+    /// [io::ErrorKind::AddrInUse] or [io::ErrorKind::PermissionDenied].
+    pub fn bind_error_kind(&self) -> Option<io::ErrorKind> {
+        match self {
+            Error::Bind { source, .. } => Some(source.kind()),
+            _ => None,
+        }
+    }
+}
+
+fn describe_bind_error(error: &io::Error) -> String {
+    let hint = match error.kind() {
+        io::ErrorKind::AddrInUse => "the address is already in use",
+        io::ErrorKind::AddrNotAvailable => {
+            "the address is not available on this machine"
+        },
+        io::ErrorKind::PermissionDenied => {
+            "permission denied, binding to this port may require elevated privileges"
+        },
+        _ => return error.to_string(),
+    };
+
+    format!("{hint} ({error})")
 }
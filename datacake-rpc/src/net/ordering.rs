@@ -0,0 +1,76 @@
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+#[derive(Default)]
+/// Runs operations one at a time in the order they were started.
+pub(crate) struct Sequencer {
+    /// Resolves once the most recently started operation has completed.
+    last: Mutex<Option<oneshot::Receiver<()>>>,
+}
+
+impl Sequencer {
+    /// Reserves the next place in the sequence.
+    ///
+    /// The order is decided when this is called rather than when the
+    /// returned turn is awaited.
+    pub(crate) fn next(&self) -> Turn {
+        let (done, waiter) = oneshot::channel();
+        let previous = self.last.lock().replace(waiter);
+        Turn { previous, done }
+    }
+}
+
+/// A place in a [Sequencer]'s order.
+pub(crate) struct Turn {
+    previous: Option<oneshot::Receiver<()>>,
+    done: oneshot::Sender<()>,
+}
+
+impl Turn {
+    /// Waits until all operations started before this one have completed.
+    ///
+    /// The next operation is let through once the returned guard is dropped.
+    pub(crate) async fn wait(self) -> TurnGuard {
+        if let Some(previous) = self.previous {
+            // The previous operation completing or being dropped both end its turn.
+            let _ = previous.await;
+        }
+        TurnGuard { _done: self.done }
+    }
+}
+
+/// Holds the current turn of a [Sequencer] until dropped.
+pub(crate) struct TurnGuard {
+    _done: oneshot::Sender<()>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_turns_run_in_order() {
+        let sequencer = Sequencer::default();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let turns = (0..5).map(|i| (i, sequencer.next())).collect::<Vec<_>>();
+
+        // Spawned in reverse, each task still waits for those reserved before it.
+        let mut tasks = Vec::new();
+        for (i, turn) in turns.into_iter().rev() {
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                let _guard = turn.wait().await;
+                crate::runtime::yield_now().await;
+                order.lock().push(i);
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(*order.lock(), vec![0, 1, 2, 3, 4]);
+    }
+}
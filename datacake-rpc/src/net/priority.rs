@@ -0,0 +1,154 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use http::HeaderValue;
+use hyper::body::HttpBody;
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+/// The request header carrying the priority of the reply, following the
+/// `u=<urgency>` parameter of RFC 9218, i.e. `u=0` for the most urgent replies.
+pub const PRIORITY_HEADER: &str = "priority";
+
+/// The number of urgency levels, `0` being the most urgent.
+pub(crate) const URGENCY_LEVELS: usize = 8;
+
+/// Parses the urgency of a [PRIORITY_HEADER] value, ignoring any other parameters.
+pub(crate) fn parse_urgency(value: &HeaderValue) -> Option<u8> {
+    let value = value.to_str().ok()?;
+    value
+        .split(',')
+        .find_map(|param| param.trim().strip_prefix("u="))
+        .and_then(|urgency| urgency.parse::<u8>().ok())
+        .filter(|&urgency| (urgency as usize) < URGENCY_LEVELS)
+}
+
+#[derive(Default)]
+/// Schedules the writes of the prioritized replies of a single connection.
+///
+/// A reply only writes its next chunk once no more urgent reply on the same
+/// connection has chunks ready to be written, so a small interactive reply
+/// is not queued behind a bulk transfer. A reply which is waiting on its
+/// producer, i.e. an idle stream, does not hold back less urgent replies.
+pub(crate) struct PriorityScheduler {
+    /// The number of replies of each urgency with chunks ready to be written.
+    writing: Mutex<[usize; URGENCY_LEVELS]>,
+    changed: Notify,
+}
+
+impl PriorityScheduler {
+    /// Replaces the body of a reply with one written according to its urgency.
+    pub(crate) fn schedule(
+        self: &Arc<Self>,
+        body: hyper::Body,
+        urgency: u8,
+    ) -> hyper::Body {
+        let (sender, scheduled) = hyper::Body::channel();
+        crate::runtime::spawn(forward_scheduled(
+            self.clone(),
+            body,
+            sender,
+            urgency as usize,
+        ));
+        scheduled
+    }
+
+    fn is_blocked(&self, urgency: usize) -> bool {
+        self.writing.lock()[..urgency]
+            .iter()
+            .any(|&count| count > 0)
+    }
+
+    /// Waits until no more urgent reply has chunks ready to be written.
+    async fn wait_turn(&self, urgency: usize) {
+        loop {
+            // The notification must be registered before checking the counts
+            // to avoid missing a write completing in between.
+            let changed = self.changed.notified();
+            if !self.is_blocked(urgency) {
+                return;
+            }
+            changed.await;
+        }
+    }
+
+    fn start_write(self: &Arc<Self>, urgency: usize) -> WriteGuard {
+        self.writing.lock()[urgency] += 1;
+        WriteGuard {
+            scheduler: self.clone(),
+            urgency,
+        }
+    }
+}
+
+/// Marks a reply as having chunks ready to be written until dropped.
+struct WriteGuard {
+    scheduler: Arc<PriorityScheduler>,
+    urgency: usize,
+}
+
+impl Drop for WriteGuard {
+    fn drop(&mut self) {
+        self.scheduler.writing.lock()[self.urgency] -= 1;
+        self.scheduler.changed.notify_waiters();
+    }
+}
+
+async fn forward_scheduled(
+    scheduler: Arc<PriorityScheduler>,
+    mut body: hyper::Body,
+    mut sender: hyper::body::Sender,
+    urgency: usize,
+) {
+    // Held for as long as the reply has chunks ready to be written.
+    let mut writing = None;
+    loop {
+        let next = std::future::poll_fn(|cx| {
+            let poll = Pin::new(&mut body).poll_data(cx);
+            if poll.is_pending() {
+                writing = None;
+            }
+            poll
+        })
+        .await;
+        let chunk = match next {
+            None => break,
+            Some(Ok(chunk)) => chunk,
+            Some(Err(_)) => {
+                sender.abort();
+                return;
+            },
+        };
+
+        scheduler.wait_turn(urgency).await;
+        if writing.is_none() {
+            writing = Some(scheduler.start_write(urgency));
+        }
+        // Completes once the connection has taken the chunk.
+        if sender.send_data(chunk).await.is_err() {
+            return;
+        }
+    }
+    drop(writing);
+
+    match body.trailers().await {
+        Ok(Some(trailers)) => {
+            let _ = sender.send_trailers(trailers).await;
+        },
+        Ok(None) => {},
+        Err(_) => sender.abort(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_urgency() {
+        assert_eq!(parse_urgency(&HeaderValue::from_static("u=0")), Some(0));
+        assert_eq!(parse_urgency(&HeaderValue::from_static("i, u=5")), Some(5));
+        assert_eq!(parse_urgency(&HeaderValue::from_static("u=8")), None);
+        assert_eq!(parse_urgency(&HeaderValue::from_static("i")), None);
+    }
+}
@@ -0,0 +1,190 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// The bounds the read buffer of each connection is sized within.
+pub(crate) struct ReadBufferBounds {
+    pub(crate) min: usize,
+    pub(crate) max: usize,
+}
+
+/// A IO wrapper which reads from the inner IO object through a buffer which
+/// adapts its size to the messages being received.
+///
+/// The buffer starts at the minimum size and doubles, up to the maximum, each
+/// time a read fills it completely, so a large message is read in few large
+/// reads. Once a read would have fit into the minimum size, i.e. the large
+/// message has been received and small messages are arriving again, the
+/// buffer is shrunk back down to the minimum to release its memory.
+///
+/// The capacity of the buffer is tracked in the shared `allocated` counter
+/// for as long as the wrapper is alive. Without any bounds, reads are passed
+/// directly to the inner IO object.
+pub(crate) struct BufferedIo<S> {
+    inner: S,
+    bounds: Option<ReadBufferBounds>,
+    buffer: Box<[u8]>,
+    /// The range of `buffer` which has been read but not yet consumed.
+    pos: usize,
+    end: usize,
+    /// The size the buffer is reallocated to before its next fill.
+    target: usize,
+    allocated: Arc<AtomicUsize>,
+}
+
+impl<S> BufferedIo<S> {
+    /// Wraps the given IO object, sizing its read buffer within `bounds`.
+    pub(crate) fn new(
+        inner: S,
+        bounds: Option<ReadBufferBounds>,
+        allocated: Arc<AtomicUsize>,
+    ) -> Self {
+        Self {
+            inner,
+            bounds,
+            buffer: Box::default(),
+            pos: 0,
+            end: 0,
+            target: bounds.map_or(0, |bounds| bounds.min),
+            allocated,
+        }
+    }
+
+    /// Reallocates the empty buffer if its size differs from the target.
+    fn resize(&mut self) {
+        if self.buffer.len() == self.target {
+            return;
+        }
+
+        self.allocated
+            .fetch_sub(self.buffer.len(), Ordering::Relaxed);
+        self.allocated.fetch_add(self.target, Ordering::Relaxed);
+        self.buffer = vec![0; self.target].into_boxed_slice();
+    }
+
+    /// Adjusts the target size based on the number of bytes the last fill read.
+    fn adapt(&mut self, bounds: ReadBufferBounds, read: usize) {
+        let len = self.buffer.len();
+        if read == len {
+            self.target = len.saturating_mul(2).min(bounds.max);
+        } else if read <= bounds.min {
+            self.target = bounds.min;
+        }
+    }
+}
+
+impl<S> Drop for BufferedIo<S> {
+    fn drop(&mut self) {
+        self.allocated
+            .fetch_sub(self.buffer.len(), Ordering::Relaxed);
+    }
+}
+
+impl<S> AsyncRead for BufferedIo<S>
+where
+    S: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let Some(bounds) = this.bounds else {
+            return Pin::new(&mut this.inner).poll_read(cx, buf);
+        };
+
+        if this.pos == this.end {
+            this.resize();
+
+            let mut fill = ReadBuf::new(&mut this.buffer);
+            ready!(Pin::new(&mut this.inner).poll_read(cx, &mut fill))?;
+            let read = fill.filled().len();
+
+            this.pos = 0;
+            this.end = read;
+            this.adapt(bounds, read);
+        }
+
+        let len = buf.remaining().min(this.end - this.pos);
+        buf.put_slice(&this.buffer[this.pos..this.pos + len]);
+        this.pos += len;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> AsyncWrite for BufferedIo<S>
+where
+    S: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_buffer_grows_and_shrinks() {
+        let bounds = ReadBufferBounds { min: 16, max: 256 };
+        let allocated = Arc::new(AtomicUsize::new(0));
+
+        let (mut client, server) = tokio::io::duplex(4096);
+        let mut io = BufferedIo::new(server, Some(bounds), allocated.clone());
+
+        let mut out = vec![0; 4096];
+        client.write_all(&[1; 1024]).await.unwrap();
+        let mut received = 0;
+        while received < 1024 {
+            received += io.read(&mut out).await.unwrap();
+        }
+        assert_eq!(io.buffer.len(), 256, "Buffer should grow to the maximum");
+        assert_eq!(allocated.load(Ordering::Relaxed), 256);
+
+        client.write_all(&[2; 8]).await.unwrap();
+        assert_eq!(io.read(&mut out).await.unwrap(), 8);
+        assert_eq!(&out[..8], &[2; 8]);
+        assert_eq!(io.buffer.len(), 16, "Buffer should shrink to the minimum");
+        assert_eq!(allocated.load(Ordering::Relaxed), 16);
+
+        drop(io);
+        assert_eq!(allocated.load(Ordering::Relaxed), 0);
+    }
+}
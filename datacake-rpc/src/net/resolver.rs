@@ -0,0 +1,29 @@
+use std::io;
+use std::net::SocketAddr;
+
+use async_trait::async_trait;
+
+#[async_trait]
+/// Resolves a host name into the addresses of the servers behind it.
+///
+/// This allows names to be resolved via an external source, i.e. a service
+/// mesh control plane, rather than the system's DNS resolver.
+/// See [Channel::connect_host_with_resolver](crate::Channel::connect_host_with_resolver).
+pub trait Resolver: Send + Sync + 'static {
+    /// Resolves the host and port into a set of socket addresses.
+    ///
+    /// The addresses are tried in the order they are returned.
+    async fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>>;
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+/// The default resolver using the system's DNS resolution.
+pub struct SystemResolver;
+
+#[async_trait]
+impl Resolver for SystemResolver {
+    async fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        let addrs = tokio::net::lookup_host((host, port)).await?;
+        Ok(addrs.collect())
+    }
+}
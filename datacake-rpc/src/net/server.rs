@@ -1,36 +1,94 @@
 use std::convert::Infallible;
+use std::future::Future;
 use std::io;
 use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
 use std::time::Duration;
 
+use bytes::Bytes;
 use http::{Request, Response, StatusCode};
+use hyper::body::{HttpBody, SizeHint};
 use hyper::server::conn::Http;
 use hyper::service::service_fn;
-use rkyv::AlignedVec;
-use tokio::sync::oneshot;
-use tokio::task::JoinHandle;
+use tokio::sync::{oneshot, watch};
+use tracing::{Instrument, Span};
 
+use super::ordering::{Sequencer, Turn};
+use super::priority::{parse_urgency, PriorityScheduler, PRIORITY_HEADER};
+use super::read_buffer::BufferedIo;
+use super::timeout::TimeoutIo;
+use super::{Error, ErrorFraming};
+use crate::admin::AdminService;
+use crate::baggage::Baggage;
 use crate::body::Body;
-use crate::server::ServerState;
+use crate::cache::{CachedReply, ReplyCache};
+use crate::capabilities::{Capabilities, CAPABILITIES_HEADER};
+use crate::capture::FrameKind;
+use crate::handler::{HandlerContext, OpaqueMessageHandler, RpcService};
+use crate::idempotency::IDEMPOTENCY_KEY_HEADER;
+use crate::limits::BodyLimiter;
+use crate::progress::{forward_progress, ProgressFrames, PROGRESS_HEADER};
+use crate::queue::{QueuedRequest, ResponseSender};
+use crate::range::ByteRange;
+use crate::runtime::{Either, HyperExecutor};
+use crate::schema::{check_schema, SCHEMA_HEADER};
+use crate::server::{InflightBytesGuard, ServerSettings, ServerState};
+use crate::transform::{transform_reply, transform_request};
 use crate::Status;
 
+#[cfg(not(feature = "simulation"))]
+type Listener = tokio::net::TcpListener;
+#[cfg(feature = "simulation")]
+type Listener = turmoil::net::TcpListener;
+
 /// Starts the RPC server.
 ///
 /// This takes a binding socket address and server state.
 pub(crate) async fn start_rpc_server(
     bind_addr: SocketAddr,
     state: ServerState,
-) -> io::Result<JoinHandle<()>> {
-    #[cfg(not(feature = "simulation"))]
-    let listener = tokio::net::TcpListener::bind(bind_addr).await?;
-    #[cfg(feature = "simulation")]
-    let listener = turmoil::net::TcpListener::bind(bind_addr).await?;
+) -> Result<ServerHandle, Error> {
+    let listener = Listener::bind(bind_addr)
+        .await
+        .map_err(|source| Error::Bind {
+            addr: bind_addr,
+            source,
+        })?;
+    let handle = serve(listener, state).await?;
+    Ok(handle)
+}
+
+#[cfg(not(feature = "simulation"))]
+/// Starts the RPC server on a listener which is already bound.
+///
+/// The listener is switched to non-blocking mode before it is registered
+/// with the runtime.
+pub(crate) async fn adopt_rpc_server(
+    listener: std::net::TcpListener,
+    state: ServerState,
+) -> io::Result<ServerHandle> {
+    listener.set_nonblocking(true)?;
+    let listener = Listener::from_std(listener)?;
+    serve(listener, state).await
+}
+
+/// Spawns the task accepting connections from the listener.
+async fn serve(listener: Listener, state: ServerState) -> io::Result<ServerHandle> {
+    let local_addr = listener.local_addr()?;
 
     let (ready, waiter) = oneshot::channel();
-    let handle = tokio::spawn(async move {
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    let (exited_tx, exited_rx) = watch::channel(());
+    let accept_loop = async move {
         let _ = ready.send(());
 
         loop {
+            state.wait_for_connection_capacity().await;
+            // Connections arriving in the meantime wait in the OS backlog.
+            state.wait_for_accept_rate().await;
+
             let (io, remote_addr) = match listener.accept().await {
                 Ok(accepted) => accepted,
                 Err(e) => {
@@ -40,28 +98,137 @@ pub(crate) async fn start_rpc_server(
             };
 
             let state = state.clone();
-            tokio::task::spawn(async move {
+            let guard = state.track_connection();
+            crate::runtime::spawn(async move {
+                let _guard = guard;
+                let settings = Arc::new(state.settings());
+                let io =
+                    TimeoutIo::new(io, settings.read_timeout, settings.write_timeout);
+                let io = BufferedIo::new(
+                    io,
+                    settings.read_buffer,
+                    state.read_buffer_bytes().clone(),
+                );
+
+                let aborted = state.aborted().clone();
                 let state = state.clone();
+                let connection_settings = settings.clone();
+                let sequencer = settings.ordered_connections.then(Sequencer::default);
+                let priorities = Arc::new(PriorityScheduler::default());
                 let handler = service_fn(move |req| {
-                    handle_connection(req, state.clone(), remote_addr)
+                    // Hyper calls the service as each stream arrives, so the
+                    // turn is reserved in arrival order.
+                    let turn = sequencer.as_ref().map(Sequencer::next);
+                    handle_connection(
+                        req,
+                        state.clone(),
+                        remote_addr,
+                        connection_settings.clone(),
+                        turn,
+                        priorities.clone(),
+                    )
                 });
 
                 let connection = Http::new()
+                    .with_executor(HyperExecutor)
                     .http2_only(true)
                     .http2_adaptive_window(true)
+                    .http2_keep_alive_interval(settings.read_timeout.map(|t| t / 2))
                     .http2_keep_alive_timeout(Duration::from_secs(10))
                     .serve_connection(io, handler);
+                let mut connection = std::pin::pin!(connection);
 
-                if let Err(e) = connection.await {
+                let cancelled = Box::pin(aborted.cancelled());
+                let expired =
+                    Box::pin(connection_expired(settings.max_connection_lifetime));
+                let closing = Box::pin(crate::runtime::select(cancelled, expired));
+                let result =
+                    match crate::runtime::select(connection.as_mut(), closing).await {
+                        Either::Left(result) => result,
+                        // In-flight requests are completed and their replies, including
+                        // aborted ones, are flushed before the connection closes.
+                        Either::Right(_) => {
+                            connection.as_mut().graceful_shutdown();
+                            connection.await
+                        },
+                    };
+
+                if let Err(e) = result {
                     error!(error = ?e, "Error while serving HTTP connection.");
                 }
             });
         }
+    };
+
+    crate::runtime::spawn(async move {
+        // Dropped once the server exits, resolving any waiters.
+        let _exited = exited_tx;
+
+        let shutdown = async move {
+            // The handle being dropped without signalling does not stop the server.
+            if shutdown_rx.await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        };
+        crate::runtime::select(Box::pin(accept_loop), Box::pin(shutdown)).await;
     });
 
     let _ = waiter.await;
 
-    Ok(handle)
+    Ok(ServerHandle {
+        local_addr,
+        shutdown: shutdown_tx,
+        exited: exited_rx,
+    })
+}
+
+/// Completes once a connection has reached its maximum lifetime, if it has one.
+async fn connection_expired(lifetime: Option<Duration>) {
+    match lifetime {
+        Some(lifetime) => crate::runtime::sleep(lifetime).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// A handle to the running server task.
+pub(crate) struct ServerHandle {
+    local_addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    exited: watch::Receiver<()>,
+}
+
+impl ServerHandle {
+    /// The address the server is bound to.
+    pub(crate) fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Signals the server task to stop accepting connections.
+    pub(crate) fn shutdown(self) {
+        let _ = self.shutdown.send(());
+    }
+
+    /// Waits until the server task exits.
+    pub(crate) async fn wait(self) {
+        let Self {
+            shutdown, exited, ..
+        } = self;
+        wait_exited(exited).await;
+        drop(shutdown);
+    }
+
+    /// Creates a future which completes once the server task exits.
+    ///
+    /// Unlike [Self::wait], this does not keep the server running.
+    pub(crate) fn closed(&self) -> impl Future<Output = ()> + Send + 'static {
+        wait_exited(self.exited.clone())
+    }
+}
+
+/// Waits until the sender held by the server task is dropped.
+async fn wait_exited(mut exited: watch::Receiver<()>) {
+    // No value is ever sent, so this only completes once the sender is dropped.
+    while exited.changed().await.is_ok() {}
 }
 
 /// A single connection handler.
@@ -72,8 +239,11 @@ async fn handle_connection(
     req: Request<hyper::Body>,
     state: ServerState,
     remote_addr: SocketAddr,
+    settings: Arc<ServerSettings>,
+    turn: Option<Turn>,
+    priorities: Arc<PriorityScheduler>,
 ) -> Result<Response<hyper::Body>, Infallible> {
-    match handle_message(req, state, remote_addr).await {
+    match handle_message(req, state, remote_addr, settings, turn, priorities).await {
         Ok(r) => Ok(r),
         Err(e) => {
             let mut response = Response::new(e.to_string().into());
@@ -87,43 +257,388 @@ async fn handle_message(
     req: Request<hyper::Body>,
     state: ServerState,
     remote_addr: SocketAddr,
+    settings: Arc<ServerSettings>,
+    turn: Option<Turn>,
+    priorities: Arc<PriorityScheduler>,
 ) -> anyhow::Result<Response<hyper::Body>> {
-    let reply = try_handle_request(req, state, remote_addr).await;
+    let negotiate = req.headers().contains_key(CAPABILITIES_HEADER);
+    let urgency = req.headers().get(PRIORITY_HEADER).and_then(parse_urgency);
+    let (id_header, correlation_id) = settings.request_id.resolve(req.headers());
+    let framing = settings.error_framing;
+    let span = info_span!(
+        "rpc_request",
+        correlation_id = correlation_id.to_str().unwrap_or_default(),
+    );
+
+    let mut response = if req.headers().contains_key(PROGRESS_HEADER) {
+        handle_with_progress(req, state, remote_addr, settings, turn, span)
+    } else {
+        let reply = try_handle_request(req, state, remote_addr, settings, None, turn)
+            .instrument(span)
+            .await;
+        let mut response = create_reply(reply, framing);
+        if let Some(urgency) = urgency.filter(|_| response.status() == StatusCode::OK) {
+            let body = std::mem::take(response.body_mut());
+            *response.body_mut() = priorities.schedule(body, urgency);
+        }
+        response
+    };
+    response.headers_mut().insert(id_header, correlation_id);
+
+    if negotiate {
+        response
+            .headers_mut()
+            .insert(CAPABILITIES_HEADER, Capabilities::local().to_header_value());
+    }
 
+    Ok(response)
+}
+
+fn create_reply(
+    reply: Result<Body, Status>,
+    framing: ErrorFraming,
+) -> Response<hyper::Body> {
     match reply {
         Ok(body) => {
-            let mut response = Response::new(body.into_inner());
+            let (body, headers) = body.into_parts();
+            let mut response = Response::new(body);
             (*response.status_mut()) = StatusCode::OK;
-            Ok(response)
+            response.headers_mut().extend(headers);
+            response
         },
-        Err(status) => Ok(create_bad_request(&status)),
+        Err(status) => create_bad_request(&status, framing),
     }
 }
 
+/// Handles a request whose client is listening for progress updates.
+///
+/// The response is sent immediately, with the updates streamed to the client
+/// as they are produced by the handler followed by the reply itself.
+fn handle_with_progress(
+    req: Request<hyper::Body>,
+    state: ServerState,
+    remote_addr: SocketAddr,
+    settings: Arc<ServerSettings>,
+    turn: Option<Turn>,
+    span: Span,
+) -> Response<hyper::Body> {
+    let (frames, updates) = crate::progress::channel();
+    let (sender, body) = hyper::Body::channel();
+
+    let reply =
+        try_handle_request(req, state, remote_addr, settings, Some(frames), turn)
+            .instrument(span);
+    crate::runtime::spawn(forward_progress(reply, updates, sender));
+
+    let mut response = Response::new(body);
+    response
+        .headers_mut()
+        .insert(PROGRESS_HEADER, http::HeaderValue::from_static("1"));
+    response
+}
+
 async fn try_handle_request(
     req: Request<hyper::Body>,
     state: ServerState,
     remote_addr: SocketAddr,
+    settings: Arc<ServerSettings>,
+    progress: Option<ProgressFrames>,
+    turn: Option<Turn>,
 ) -> Result<Body, Status> {
+    // Held until the reply has been produced when the connection is ordered.
+    let _turn = match turn {
+        Some(turn) => Some(turn.wait().await),
+        None => None,
+    };
+
     let (req, body) = req.into_parts();
     let uri = req.uri.path();
     let headers = req.headers;
 
-    let handler = state
-        .get_handler(uri)
-        .ok_or_else(|| Status::unavailable(format!("Unknown service {uri}")))?;
+    let handler = state.resolve_handler(uri, &headers)?;
+    if let Some(schema) = headers.get(SCHEMA_HEADER) {
+        check_schema(schema, handler.schema_hash())?;
+    }
+    let (body, limiter) = BodyLimiter::apply(handler.limits(), body)?;
+
+    if let Some(threshold) = settings.size_tracing_threshold {
+        trace_body_size("request", body.size_hint(), threshold, uri, remote_addr);
+    }
+
+    let request_len = body.size_hint().lower();
+    let inflight_bytes = state
+        .reserve_inflight_bytes(usize::try_from(request_len).unwrap_or(usize::MAX))?;
+    let inflight_bytes = Arc::new(inflight_bytes);
+    // Bodies of unknown length are charged as they are read.
+    let body = if body.size_hint().exact().is_some() {
+        body
+    } else {
+        hyper::Body::wrap_stream(ChargedBody {
+            inner: body,
+            reserved: request_len,
+            guard: inflight_bytes.clone(),
+        })
+    };
+
+    let mut inflight = state.track_request(uri, remote_addr);
+    let request_id = inflight.id();
+    let capture = state.capture();
+    let body = match &capture {
+        Some(capture) => capture.tee(FrameKind::Request, request_id, uri, body),
+        None => body,
+    };
+
+    let transforms = state.body_transforms();
+    let (body, headers) = if transforms.is_empty() {
+        (body, headers)
+    } else {
+        let body = Body::with_headers(body, headers);
+        transform_request(&transforms, body).await?.into_parts()
+    };
+
+    #[cfg(feature = "otel")]
+    let trace_context = crate::otel::TraceContext::extract(&headers);
+    let range = handler
+        .rangeable()
+        .then(|| ByteRange::from_headers(&headers))
+        .flatten();
 
-    handler
-        .try_handle(remote_addr, headers, Body::new(body))
+    let ctx = HandlerContext {
+        remote_addr,
+        headers,
+        trust_peer: settings.trust_peers,
+        request_id,
+        admitted_at: inflight.admitted_at(),
+        cancellation: inflight.cancellation().clone(),
+        completed: inflight.completed().clone(),
+        disconnected: inflight.disconnected().clone(),
+        progress,
+    };
+    let cache = state.reply_cache().filter(|_| handler.cacheable());
+    let idempotency = state
+        .idempotency()
+        .zip(ctx.headers.get(IDEMPOTENCY_KEY_HEADER).cloned());
+    let queue = state
+        .request_queue()
+        .filter(|_| crate::split_uri_path(uri).0 != AdminService::service_name());
+    let future = async move {
+        let Some(queue) = queue else {
+            return dispatch_request(uri, handler, cache, ctx, body).await;
+        };
+
+        let request = QueuedRequest {
+            uri: uri.to_string(),
+            handler,
+            cache,
+            ctx,
+            body,
+        };
+        let (sender, reply) = ResponseSender::new();
+        if let Err(rejected) = queue.send((request, sender)) {
+            // The stream was dropped in the meantime.
+            let (request, _) = rejected.0;
+            return request.dispatch().await;
+        }
+
+        reply.await.unwrap_or_else(|_| {
+            Err(Status::internal("The request was dropped without a reply"))
+        })
+    };
+    let future = async move {
+        match idempotency {
+            Some((idempotency, key)) => idempotency.run(uri, key, future).await,
+            None => future.await,
+        }
+    };
+
+    #[cfg(feature = "otel")]
+    let future = crate::otel::instrument_server(uri, trace_context, remote_addr, future);
+
+    let aborted = Box::pin(state.aborted().cancelled());
+    let mut reply = match crate::runtime::select(Box::pin(future), aborted).await {
+        Either::Left(reply) => {
+            inflight.set_replied();
+            let reply = inflight_bytes.check(reply);
+            match &limiter {
+                Some(limiter) => limiter.check(reply)?,
+                None => reply?,
+            }
+        },
+        Either::Right(()) => {
+            return Err(Status::aborted("The server aborted the request"));
+        },
+    };
+
+    if let Some(range) = range {
+        reply = range.apply(reply).await?;
+    }
+
+    if !transforms.is_empty() {
+        reply = transform_reply(&transforms, reply).await?;
+    }
+
+    // The request is released once its reply is produced, which is then
+    // charged itself until it has been written.
+    drop(inflight_bytes);
+    reply = charge_reply(&state, reply).await?;
+
+    if let Some(capture) = capture {
+        let (body, headers) = reply.into_parts();
+        let body = capture.tee(FrameKind::Reply, request_id, uri, body);
+        reply = Body::with_headers(body, headers);
+    }
+
+    if let Some(threshold) = settings.size_tracing_threshold {
+        trace_body_size("reply", reply.size_hint(), threshold, uri, remote_addr);
+    }
+
+    Ok(reply)
+}
+
+/// Reserves the in-flight bytes of a serialized reply until it has been written.
+///
+/// Streaming replies are not counted, as only a chunk is buffered at a time.
+async fn charge_reply(state: &ServerState, reply: Body) -> Result<Body, Status> {
+    let Some(len) = reply.size_hint().exact() else {
+        return Ok(reply);
+    };
+    let guard =
+        state.reserve_inflight_bytes(usize::try_from(len).unwrap_or(usize::MAX))?;
+
+    // Bodies of a known length are already in memory, so this does not copy them.
+    let (body, headers) = reply.into_parts();
+    let data = hyper::body::to_bytes(body)
         .await
+        .map_err(Status::internal)?;
+    let data = Bytes::from_owner(ChargedReply {
+        data,
+        _guard: guard,
+    });
+
+    Ok(Body::with_headers(data.into(), headers))
 }
 
-fn create_bad_request(status: &Status) -> Response<hyper::Body> {
-    // This should be infallible.
-    let buffer =
-        crate::rkyv_tooling::to_view_bytes(status).unwrap_or_else(|_| AlignedVec::new());
+/// A reply buffer holding its in-flight bytes until the body has been written.
+struct ChargedReply {
+    data: Bytes,
+    _guard: InflightBytesGuard,
+}
+
+impl AsRef<[u8]> for ChargedReply {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// A request body of unknown length which reserves in-flight bytes as it is read.
+struct ChargedBody {
+    inner: hyper::Body,
+    /// The bytes reserved up front which have not been read yet.
+    reserved: u64,
+    guard: Arc<InflightBytesGuard>,
+}
+
+impl futures_core::Stream for ChargedBody {
+    type Item = Result<Bytes, Status>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let chunk = match ready!(Pin::new(&mut self.inner).poll_data(cx)) {
+            Some(Ok(chunk)) => chunk,
+            Some(Err(e)) => return Poll::Ready(Some(Err(Status::connection(e)))),
+            None => return Poll::Ready(None),
+        };
+
+        let len = chunk.len() as u64;
+        let unreserved = len.saturating_sub(self.reserved);
+        self.reserved = self.reserved.saturating_sub(len);
+        if unreserved > 0 {
+            let unreserved = usize::try_from(unreserved).unwrap_or(usize::MAX);
+            if let Err(status) = self.guard.grow(unreserved) {
+                return Poll::Ready(Some(Err(status)));
+            }
+        }
+
+        Poll::Ready(Some(Ok(chunk)))
+    }
+}
+
+/// Passes the request to its handler, serving the reply from the cache if possible.
+pub(crate) async fn dispatch_request(
+    uri: &str,
+    handler: Arc<dyn OpaqueMessageHandler>,
+    cache: Option<Arc<ReplyCache>>,
+    ctx: HandlerContext,
+    body: hyper::Body,
+) -> Result<Body, Status> {
+    // The handler propagates the request's baggage to any requests it sends.
+    let baggage = Baggage::from_headers(&ctx.headers);
+    let Some(cache) = cache else {
+        let future = handler.try_handle(ctx, Body::new(body));
+        return baggage.scope(future).await;
+    };
+
+    let request = hyper::body::to_bytes(body)
+        .await
+        .map_err(Status::connection)?;
+    if let Some(reply) = cache.get(uri, &request) {
+        return Ok(Body::with_headers(reply.body.into(), reply.headers));
+    }
+
+    let future = handler.try_handle(ctx, Body::new(request.clone().into()));
+    let reply = baggage.scope(future).await?;
+
+    // Streaming replies are never cached.
+    if reply.size_hint().exact().is_none() {
+        return Ok(reply);
+    }
+
+    let (body, headers) = reply.into_parts();
+    let body = hyper::body::to_bytes(body)
+        .await
+        .map_err(Status::internal)?;
+    let cached = CachedReply {
+        body: body.clone(),
+        headers: headers.clone(),
+    };
+    cache.insert(uri, request, cached);
+
+    Ok(Body::with_headers(body.into(), headers))
+}
+
+/// Records the size of a body, logging a warning if it exceeds the threshold.
+///
+/// Bodies without a known size, i.e. streaming bodies, are not recorded.
+fn trace_body_size(
+    kind: &'static str,
+    size_hint: SizeHint,
+    threshold: u64,
+    uri: &str,
+    remote_addr: SocketAddr,
+) {
+    let Some(size) = size_hint.exact() else {
+        return;
+    };
+
+    if size > threshold {
+        warn!(
+            kind,
+            size,
+            threshold,
+            uri,
+            remote_addr = %remote_addr,
+            "Large {kind} body exceeds the size tracing threshold.",
+        );
+    } else {
+        trace!(kind, size, uri, remote_addr = %remote_addr, "Body size.");
+    }
+}
 
-    let mut response = Response::new(buffer.to_vec().into());
+fn create_bad_request(status: &Status, framing: ErrorFraming) -> Response<hyper::Body> {
+    let mut response = Response::new(hyper::Body::empty());
+    *response.body_mut() = framing.encode(status, response.headers_mut()).into();
     (*response.status_mut()) = StatusCode::BAD_REQUEST;
 
     response
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::Instant;
+
+use parking_lot::Mutex;
+
+use super::client::Connection;
+use crate::net::ChannelConfig;
+
+type Registry = Mutex<HashMap<SharedKey, Weak<SharedConnection>>>;
+
+/// The connections shared between channels created via
+/// [Channel::connect_shared](crate::Channel::connect_shared).
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(Registry::default)
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+/// Channels can only share a connection if they connect to the same
+/// address with the same settings.
+struct SharedKey {
+    remote_addr: SocketAddr,
+    config: ChannelConfig,
+}
+
+/// A connection which is shared by all channels to the same address.
+///
+/// The connection is removed from the registry once the last channel
+/// using it has been dropped, allowing it to close.
+pub(crate) struct SharedConnection {
+    key: SharedKey,
+    connection: Connection,
+    created_at: Instant,
+}
+
+impl SharedConnection {
+    /// Gets the existing connection to the address or creates a new one.
+    pub(crate) fn get_or_connect(
+        remote_addr: SocketAddr,
+        config: ChannelConfig,
+        connect: impl FnOnce(ChannelConfig) -> Connection,
+    ) -> Arc<Self> {
+        let key = SharedKey {
+            remote_addr,
+            config,
+        };
+
+        let mut registry = registry().lock();
+        if let Some(shared) = registry.get(&key).and_then(Weak::upgrade) {
+            return shared;
+        }
+
+        let shared = Arc::new(Self {
+            connection: connect(key.config.clone()),
+            key: key.clone(),
+            created_at: Instant::now(),
+        });
+        registry.insert(key, Arc::downgrade(&shared));
+        shared
+    }
+
+    /// Replaces this connection with a new one for any channels which
+    /// connect from now on, returning the replacement.
+    ///
+    /// If another channel already replaced the connection, its replacement
+    /// is returned instead.
+    pub(crate) fn reconnect(
+        self: &Arc<Self>,
+        connect: impl FnOnce(ChannelConfig) -> Connection,
+    ) -> Arc<Self> {
+        let mut registry = registry().lock();
+        let current = registry.get(&self.key).and_then(Weak::upgrade);
+        if let Some(current) = current.filter(|current| !Arc::ptr_eq(current, self)) {
+            return current;
+        }
+
+        let shared = Arc::new(Self {
+            connection: connect(self.key.config.clone()),
+            key: self.key.clone(),
+            created_at: Instant::now(),
+        });
+        registry.insert(self.key.clone(), Arc::downgrade(&shared));
+        shared
+    }
+
+    #[inline]
+    /// The underlying connection.
+    pub(crate) fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    #[inline]
+    /// When the connection was created.
+    pub(crate) fn created_at(&self) -> Instant {
+        self.created_at
+    }
+}
+
+impl Drop for SharedConnection {
+    fn drop(&mut self) {
+        let mut registry = registry().lock();
+
+        // Another channel may have replaced the entry with a new connection
+        // after the last reference to this one was dropped.
+        let is_dead = registry
+            .get(&self.key)
+            .is_some_and(|shared| shared.strong_count() == 0);
+        if is_dead {
+            registry.remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_registered(remote_addr: SocketAddr) -> bool {
+        registry()
+            .lock()
+            .keys()
+            .any(|key| key.remote_addr == remote_addr)
+    }
+
+    #[test]
+    fn test_shared_connection_registry() {
+        let addr = "127.0.0.1:1".parse().unwrap();
+        let connect = |config| crate::net::client::create_connection(addr, config);
+
+        let first =
+            SharedConnection::get_or_connect(addr, ChannelConfig::default(), connect);
+        let second =
+            SharedConnection::get_or_connect(addr, ChannelConfig::default(), connect);
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let config = ChannelConfig {
+            read_timeout: Some(std::time::Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let other = SharedConnection::get_or_connect(addr, config, connect);
+        assert!(
+            !Arc::ptr_eq(&first, &other),
+            "Channels with different settings should not share connections"
+        );
+
+        drop((first, other));
+        assert!(is_registered(addr));
+        drop(second);
+        assert!(!is_registered(addr), "Unused connections should be removed");
+    }
+}
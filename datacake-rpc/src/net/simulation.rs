@@ -2,14 +2,14 @@ use std::io;
 use std::io::ErrorKind;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Duration;
 
 use hyper::client::conn::SendRequest;
 use hyper::Body;
 use tokio::sync::{Mutex, OnceCell};
-use tokio::time::timeout;
 
-use crate::net::Error;
+use crate::net::timeout::TimeoutIo;
+use crate::net::{ChannelConfig, Error};
+use crate::runtime::HyperExecutor;
 
 #[derive(Clone)]
 /// A client used for simulation testing via turmoil.
@@ -18,14 +18,16 @@ use crate::net::Error;
 /// performance.
 pub struct LazyClient {
     addr: SocketAddr,
+    config: ChannelConfig,
     client: Arc<OnceCell<Mutex<SendRequest<Body>>>>,
 }
 
 impl LazyClient {
     /// Creates a new lazy client.
-    pub fn connect(socket: SocketAddr) -> Self {
+    pub fn connect(socket: SocketAddr, config: ChannelConfig) -> Self {
         Self {
             addr: socket,
+            config,
             client: Arc::new(OnceCell::new()),
         }
     }
@@ -36,8 +38,8 @@ impl LazyClient {
             return Ok(existing);
         }
 
-        let io = timeout(
-            Duration::from_secs(2),
+        let io = crate::runtime::timeout(
+            self.config.connect_timeout,
             turmoil::net::TcpStream::connect(self.addr),
         )
         .await
@@ -47,15 +49,18 @@ impl LazyClient {
                 "Failed to connect within deadline",
             ))
         })??;
+        let io = TimeoutIo::new(io, self.config.read_timeout, self.config.write_timeout);
 
         let (sender, connection) = hyper::client::conn::Builder::new()
+            .executor(HyperExecutor)
             .http2_keep_alive_while_idle(true)
+            .http2_keep_alive_interval(self.config.read_timeout.map(|t| t / 2))
             .http2_only(true)
             .http2_adaptive_window(true)
             .handshake(io)
             .await?;
 
-        tokio::spawn(async move {
+        crate::runtime::spawn(async move {
             if let Err(e) = connection.await {
                 error!(error = ?e, "Error in client connection");
             }
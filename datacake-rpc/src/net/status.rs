@@ -1,5 +1,5 @@
 use std::error::Error;
-use std::fmt::{Debug, Display, Formatter};
+use std::fmt::{Debug, Display, Formatter, Write};
 
 use rkyv::{Archive, Deserialize, Serialize};
 
@@ -45,6 +45,29 @@ impl Status {
         }
     }
 
+    /// The provided message is valid but its contents are not, i.e. a field
+    /// is outside of the range the handler accepts.
+    pub fn invalid_argument(msg: impl Display) -> Self {
+        Self {
+            code: ErrorCode::InvalidPayload,
+            message: msg.to_string(),
+        }
+    }
+
+    /// An internal error caused by the given error.
+    ///
+    /// The message includes the error's chain of sources, from the
+    /// outermost error to the root cause, separated by `: `.
+    pub fn from_error(error: &dyn Error) -> Self {
+        let mut message = error.to_string();
+        let mut source = error.source();
+        while let Some(cause) = source {
+            let _ = write!(message, ": {cause}");
+            source = cause.source();
+        }
+        Self::internal(message)
+    }
+
     /// The connection is closed or interrupted during the operation.
     pub fn connection(msg: impl Display) -> Self {
         Self {
@@ -60,6 +83,48 @@ impl Status {
             message: "The operation took to long to be completed.".to_string(),
         }
     }
+
+    /// The requested resource, i.e. a tenant, does not exist on the server.
+    pub fn not_found(msg: impl Display) -> Self {
+        Self {
+            code: ErrorCode::NotFound,
+            message: msg.to_string(),
+        }
+    }
+
+    /// The server does not have the resources available to handle the request
+    /// at this time, i.e. it is under memory pressure.
+    pub fn resource_exhausted(msg: impl Display) -> Self {
+        Self {
+            code: ErrorCode::ResourceExhausted,
+            message: msg.to_string(),
+        }
+    }
+
+    /// The requested range lies outside of the bounds of the reply.
+    pub fn out_of_range(msg: impl Display) -> Self {
+        Self {
+            code: ErrorCode::OutOfRange,
+            message: msg.to_string(),
+        }
+    }
+
+    /// The request body exceeds the maximum size accepted by the handler.
+    pub fn payload_too_large(msg: impl Display) -> Self {
+        Self {
+            code: ErrorCode::PayloadTooLarge,
+            message: msg.to_string(),
+        }
+    }
+
+    /// The server stopped handling the request before it completed,
+    /// i.e. as it is shutting down.
+    pub fn aborted(msg: impl Display) -> Self {
+        Self {
+            code: ErrorCode::Aborted,
+            message: msg.to_string(),
+        }
+    }
 }
 
 impl Display for Status {
@@ -79,6 +144,47 @@ impl Debug for Status {
 
 impl Error for Status {}
 
+impl From<anyhow::Error> for Status {
+    /// Converts the error into an internal error, keeping its chain of
+    /// sources in the message as with [Status::from_error].
+    fn from(error: anyhow::Error) -> Self {
+        Self::internal(format!("{error:#}"))
+    }
+}
+
+/// Converts the errors of a [Result] into a [Status].
+///
+/// This avoids building a [Status] for every fallible call within a handler:
+///
+/// ```rust
+/// use datacake_rpc::{ResultExt, Status};
+///
+/// fn parse_limit(raw: &str) -> Result<u64, Status> {
+///     let limit = raw.parse::<u64>().map_err_to_status()?;
+///     Ok(limit)
+/// }
+///
+/// let status = parse_limit("ten").unwrap_err();
+/// assert_eq!(status.message, "invalid digit found in string");
+/// ```
+///
+/// A [Status] is itself an error, so an existing status would be converted
+/// into an internal error, use `?` to return it as-is instead.
+pub trait ResultExt<T> {
+    /// Converts the error into an internal error, see [Status::from_error].
+    fn map_err_to_status(self) -> Result<T, Status>;
+}
+
+impl<T, E> ResultExt<T> for Result<T, E>
+where
+    E: Error,
+{
+    #[inline]
+    fn map_err_to_status(self) -> Result<T, Status> {
+        self.map_err(|error| Status::from_error(&error))
+    }
+}
+
 #[repr(C)]
 #[derive(Serialize, Deserialize, Archive, PartialEq, Eq, Debug)]
 #[archive(compare(PartialEq))]
@@ -97,6 +203,55 @@ pub enum ErrorCode {
     ConnectionError,
     /// The operation took too long to be completed and was aborted.
     Timeout,
+    /// The requested resource, i.e. a tenant, does not exist on the server.
+    NotFound,
+    /// The server does not have the resources available to handle the request
+    /// at this time, i.e. it is under memory pressure.
+    ResourceExhausted,
+    /// The requested range lies outside of the bounds of the reply.
+    OutOfRange,
+    /// The server stopped handling the request before it completed,
+    /// i.e. as it is shutting down.
+    Aborted,
+    /// The request body exceeds the maximum size accepted by the handler.
+    PayloadTooLarge,
+}
+
+impl ErrorCode {
+    /// The stable number of the code on the wire, in declaration order
+    /// starting from `0`, see [ErrorFraming](crate::ErrorFraming).
+    pub fn as_u16(&self) -> u16 {
+        match self {
+            Self::ServiceUnavailable => 0,
+            Self::InternalError => 1,
+            Self::InvalidPayload => 2,
+            Self::ConnectionError => 3,
+            Self::Timeout => 4,
+            Self::NotFound => 5,
+            Self::ResourceExhausted => 6,
+            Self::OutOfRange => 7,
+            Self::Aborted => 8,
+            Self::PayloadTooLarge => 9,
+        }
+    }
+
+    /// The code with the given number on the wire, if it is known.
+    pub fn from_u16(code: u16) -> Option<Self> {
+        let code = match code {
+            0 => Self::ServiceUnavailable,
+            1 => Self::InternalError,
+            2 => Self::InvalidPayload,
+            3 => Self::ConnectionError,
+            4 => Self::Timeout,
+            5 => Self::NotFound,
+            6 => Self::ResourceExhausted,
+            7 => Self::OutOfRange,
+            8 => Self::Aborted,
+            9 => Self::PayloadTooLarge,
+            _ => return None,
+        };
+        Some(code)
+    }
 }
 
 #[cfg(test)]
@@ -126,5 +281,33 @@ mod tests {
         test_status_variant(Status::connection("Test connection failed."));
         test_status_variant(Status::unavailable("Test unavailable."));
         test_status_variant(Status::internal("Test internal error."));
+        test_status_variant(Status::not_found("Test not found."));
+        test_status_variant(Status::resource_exhausted("Test resource exhausted."));
+        test_status_variant(Status::out_of_range("Test out of range."));
+        test_status_variant(Status::invalid_argument("Test invalid argument."));
+        test_status_variant(Status::aborted("Test aborted."));
+        test_status_variant(Status::payload_too_large("Test payload too large."));
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("Failed to load the index")]
+    struct LoadError(#[source] std::io::Error);
+
+    #[test]
+    fn test_error_chain() {
+        let error = LoadError(std::io::Error::other("disk is full"));
+
+        let status = Status::from_error(&error);
+        assert_eq!(status.code, ErrorCode::InternalError);
+        assert_eq!(status.message, "Failed to load the index: disk is full");
+
+        let result: Result<(), _> = Err(error);
+        let status = result.map_err_to_status().unwrap_err();
+        assert_eq!(status.message, "Failed to load the index: disk is full");
+
+        let error = anyhow::anyhow!("disk is full").context("Failed to load the index");
+        let status = Status::from(error);
+        assert_eq!(status.code, ErrorCode::InternalError);
+        assert_eq!(status.message, "Failed to load the index: disk is full");
     }
 }
@@ -0,0 +1,279 @@
+use std::io;
+use std::io::ErrorKind;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::runtime::BoxFuture;
+
+/// A IO wrapper which applies inactivity timeouts to the reads and writes
+/// of the inner IO object.
+///
+/// The read timeout is only armed while a read is pending and is reset
+/// whenever any bytes arrive, so a slow but steady transfer is not aborted,
+/// only a stalled one. The write timeout behaves in the same way for writes
+/// and flushes which are unable to make progress.
+pub(crate) struct TimeoutIo<S> {
+    inner: S,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    read_deadline: Option<BoxFuture<()>>,
+    write_deadline: Option<BoxFuture<()>>,
+}
+
+impl<S> TimeoutIo<S> {
+    /// Wraps the given IO object with the provided timeouts.
+    pub(crate) fn new(
+        inner: S,
+        read_timeout: Option<Duration>,
+        write_timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            inner,
+            read_timeout,
+            write_timeout,
+            read_deadline: None,
+            write_deadline: None,
+        }
+    }
+
+    #[cfg(not(feature = "simulation"))]
+    /// A reference to the inner IO object.
+    pub(crate) fn get_ref(&self) -> &S {
+        &self.inner
+    }
+}
+
+/// Polls the given deadline, arming it if required.
+///
+/// Returns an error if the deadline has elapsed.
+fn poll_deadline(
+    deadline: &mut Option<BoxFuture<()>>,
+    timeout: Option<Duration>,
+    cx: &mut Context<'_>,
+) -> io::Result<()> {
+    let Some(timeout) = timeout else {
+        return Ok(());
+    };
+
+    let sleep = deadline.get_or_insert_with(|| crate::runtime::sleep(timeout));
+    match sleep.as_mut().poll(cx) {
+        Poll::Ready(()) => Err(io::Error::new(
+            ErrorKind::TimedOut,
+            "The connection has stalled and timed out",
+        )),
+        Poll::Pending => Ok(()),
+    }
+}
+
+impl<S> AsyncRead for TimeoutIo<S>
+where
+    S: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        match Pin::new(&mut this.inner).poll_read(cx, buf) {
+            Poll::Ready(result) => {
+                this.read_deadline = None;
+                Poll::Ready(result)
+            },
+            Poll::Pending => {
+                poll_deadline(&mut this.read_deadline, this.read_timeout, cx)?;
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl<S> AsyncWrite for TimeoutIo<S>
+where
+    S: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        match Pin::new(&mut this.inner).poll_write(cx, buf) {
+            Poll::Ready(result) => {
+                this.write_deadline = None;
+                Poll::Ready(result)
+            },
+            Poll::Pending => {
+                poll_deadline(&mut this.write_deadline, this.write_timeout, cx)?;
+                Poll::Pending
+            },
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        match Pin::new(&mut this.inner).poll_flush(cx) {
+            Poll::Ready(result) => {
+                this.write_deadline = None;
+                Poll::Ready(result)
+            },
+            Poll::Pending => {
+                poll_deadline(&mut this.write_deadline, this.write_timeout, cx)?;
+                Poll::Pending
+            },
+        }
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        match Pin::new(&mut this.inner).poll_write_vectored(cx, bufs) {
+            Poll::Ready(result) => {
+                this.write_deadline = None;
+                Poll::Ready(result)
+            },
+            Poll::Pending => {
+                poll_deadline(&mut this.write_deadline, this.write_timeout, cx)?;
+                Poll::Pending
+            },
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+#[cfg(not(feature = "simulation"))]
+pub(crate) use self::connector::TimeoutConnector;
+
+#[cfg(not(feature = "simulation"))]
+mod connector {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+
+    use http::Uri;
+    use hyper::client::connect::{Connected, Connection};
+    use hyper::client::HttpConnector;
+    use hyper::service::Service;
+    use tokio::net::TcpStream;
+
+    use super::TimeoutIo;
+
+    #[derive(Clone)]
+    /// A HTTP connector which applies read and write timeouts to
+    /// the connections it establishes.
+    pub(crate) struct TimeoutConnector {
+        http: HttpConnector,
+        read_timeout: Option<Duration>,
+        write_timeout: Option<Duration>,
+    }
+
+    impl TimeoutConnector {
+        pub(crate) fn new(
+            http: HttpConnector,
+            read_timeout: Option<Duration>,
+            write_timeout: Option<Duration>,
+        ) -> Self {
+            Self {
+                http,
+                read_timeout,
+                write_timeout,
+            }
+        }
+    }
+
+    impl Service<Uri> for TimeoutConnector {
+        type Response = TimeoutIo<TcpStream>;
+        type Error = Box<dyn std::error::Error + Send + Sync>;
+        type Future =
+            Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.http.poll_ready(cx).map_err(Into::into)
+        }
+
+        fn call(&mut self, uri: Uri) -> Self::Future {
+            let connecting = self.http.call(uri);
+            let read_timeout = self.read_timeout;
+            let write_timeout = self.write_timeout;
+
+            Box::pin(async move {
+                let stream = connecting.await?;
+                Ok(TimeoutIo::new(stream, read_timeout, write_timeout))
+            })
+        }
+    }
+
+    impl Connection for TimeoutIo<TcpStream> {
+        fn connected(&self) -> Connected {
+            self.get_ref().connected()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_timeout_on_stall() {
+        let (client, _server) = tokio::io::duplex(64);
+        let mut io = TimeoutIo::new(client, Some(Duration::from_millis(50)), None);
+
+        let mut buf = [0; 8];
+        let err = io.read(&mut buf).await.expect_err("Read should time out");
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout_resets_on_progress() {
+        let (client, mut server) = tokio::io::duplex(64);
+        let mut io = TimeoutIo::new(client, Some(Duration::from_millis(100)), None);
+
+        tokio::spawn(async move {
+            for _ in 0..5 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                server.write_all(b"a").await.unwrap();
+            }
+        });
+
+        let mut buf = [0; 5];
+        io.read_exact(&mut buf)
+            .await
+            .expect("Steady reads should not time out");
+    }
+
+    #[tokio::test]
+    async fn test_write_timeout_on_stall() {
+        let (client, _server) = tokio::io::duplex(4);
+        let mut io = TimeoutIo::new(client, None, Some(Duration::from_millis(50)));
+
+        let err = io
+            .write_all(b"Hello, world!")
+            .await
+            .expect_err("Write should time out");
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,258 @@
+//! OpenTelemetry compatible tracing for RPC calls.
+//!
+//! Spans are named and annotated following the OpenTelemetry RPC semantic
+//! conventions (`rpc.system`, `rpc.service`, `rpc.method`) along with the
+//! `otel.name` and `otel.kind` fields understood by `tracing-opentelemetry`.
+//!
+//! W3C trace context (`traceparent` & `tracestate`) is extracted from inbound
+//! requests and recorded on the server span. Any requests sent while a
+//! handler is running continue the same trace, with a new parent id being
+//! generated for each outbound call.
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use http::{HeaderMap, HeaderValue};
+use tracing::{field, Instrument, Span};
+
+use crate::split_uri_path;
+
+/// The W3C trace parent header.
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+/// The W3C trace state header.
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+const RPC_SYSTEM: &str = "datacake";
+
+tokio::task_local! {
+    static CURRENT_CONTEXT: Option<TraceContext>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A parsed W3C trace context.
+pub(crate) struct TraceContext {
+    trace_id: String,
+    parent_id: String,
+    flags: String,
+    state: Option<HeaderValue>,
+}
+
+impl TraceContext {
+    /// Extracts the trace context from the given headers.
+    ///
+    /// Returns `None` if the `traceparent` header is missing or malformed.
+    pub(crate) fn extract(headers: &HeaderMap) -> Option<Self> {
+        let parent = headers.get(TRACEPARENT_HEADER)?.to_str().ok()?;
+        let mut parts = parent.trim().split('-');
+
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let parent_id = parts.next()?;
+        let flags = parts.next()?;
+
+        // Future versions may append additional fields, version 00 may not.
+        if version == "00" && parts.next().is_some() {
+            return None;
+        }
+
+        if !is_hex(version, 2)
+            || version == "ff"
+            || !is_hex(trace_id, 32)
+            || !is_hex(parent_id, 16)
+            || !is_hex(flags, 2)
+            || is_zero(trace_id)
+            || is_zero(parent_id)
+        {
+            return None;
+        }
+
+        Some(Self {
+            trace_id: trace_id.to_ascii_lowercase(),
+            parent_id: parent_id.to_ascii_lowercase(),
+            flags: flags.to_ascii_lowercase(),
+            state: headers.get(TRACESTATE_HEADER).cloned(),
+        })
+    }
+
+    /// Creates a child context continuing the same trace with a new parent id.
+    fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id.clone(),
+            parent_id: new_span_id(),
+            flags: self.flags.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Injects the context into the given headers.
+    fn inject(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&self.traceparent()) {
+            headers.insert(TRACEPARENT_HEADER, value);
+        }
+        if let Some(state) = self.state.clone() {
+            headers.insert(TRACESTATE_HEADER, state);
+        }
+    }
+
+    fn traceparent(&self) -> String {
+        format!("00-{}-{}-{}", self.trace_id, self.parent_id, self.flags)
+    }
+}
+
+/// Runs a server side handler future within an RPC server span.
+///
+/// The trace context extracted from the inbound request is made available to any
+/// clients sending requests from within the future.
+pub(crate) async fn instrument_server<F, T, E>(
+    uri_path: &str,
+    context: Option<TraceContext>,
+    remote_addr: SocketAddr,
+    future: F,
+) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let (service, method) = split_uri_path(uri_path);
+
+    let span = tracing::info_span!(
+        "rpc.server",
+        otel.name = %format_args!("{service}/{method}"),
+        otel.kind = "server",
+        otel.status_code = field::Empty,
+        rpc.system = RPC_SYSTEM,
+        rpc.service = %service,
+        rpc.method = %method,
+        net.peer.addr = %remote_addr,
+        traceparent = field::Empty,
+    );
+    if let Some(context) = context.as_ref() {
+        span.record("traceparent", context.traceparent().as_str());
+    }
+
+    let result = CURRENT_CONTEXT
+        .scope(context, future.instrument(span.clone()))
+        .await;
+
+    if result.is_err() {
+        span.record("otel.status_code", "ERROR");
+    }
+
+    result
+}
+
+/// Creates the RPC client span for an outbound request.
+///
+/// If no trace context has been explicitly set on the request headers and
+/// the request is being sent from within a handler, the inbound trace
+/// context is propagated with a new parent id.
+pub(crate) fn client_span(
+    uri_path: &str,
+    remote_addr: SocketAddr,
+    headers: &mut HeaderMap,
+) -> Span {
+    let (service, method) = split_uri_path(uri_path);
+
+    let span = tracing::info_span!(
+        "rpc.client",
+        otel.name = %format_args!("{service}/{method}"),
+        otel.kind = "client",
+        rpc.system = RPC_SYSTEM,
+        rpc.service = %service,
+        rpc.method = %method,
+        net.peer.addr = %remote_addr,
+        traceparent = field::Empty,
+    );
+
+    if headers.contains_key(TRACEPARENT_HEADER) {
+        return span;
+    }
+
+    let context = CURRENT_CONTEXT
+        .try_with(|context| context.as_ref().map(TraceContext::child))
+        .ok()
+        .flatten();
+    if let Some(context) = context {
+        context.inject(headers);
+        span.record("traceparent", context.traceparent().as_str());
+    }
+
+    span
+}
+
+fn is_hex(value: &str, len: usize) -> bool {
+    value.len() == len && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn is_zero(value: &str) -> bool {
+    value.bytes().all(|b| b == b'0')
+}
+
+fn new_span_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(1);
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    if let Ok(elapsed) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(elapsed.as_nanos());
+    }
+
+    // A span id of all zeroes is invalid.
+    format!("{:016x}", hasher.finish().max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers_with(parent: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(TRACEPARENT_HEADER, HeaderValue::from_str(parent).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_extract_trace_context() {
+        let mut headers =
+            headers_with("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01");
+        headers.insert(TRACESTATE_HEADER, HeaderValue::from_static("foo=bar"));
+
+        let context = TraceContext::extract(&headers).expect("Valid context");
+        assert_eq!(context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(context.parent_id, "00f067aa0ba902b7");
+        assert_eq!(context.flags, "01");
+        assert_eq!(context.state, Some(HeaderValue::from_static("foo=bar")));
+
+        let child = context.child();
+        assert_eq!(child.trace_id, context.trace_id);
+        assert_ne!(child.parent_id, context.parent_id);
+
+        let mut injected = HeaderMap::new();
+        child.inject(&mut injected);
+        assert_eq!(TraceContext::extract(&injected), Some(child));
+    }
+
+    #[test]
+    fn test_extract_invalid_trace_context() {
+        for parent in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+        ] {
+            assert_eq!(
+                TraceContext::extract(&headers_with(parent)),
+                None,
+                "Trace parent {parent:?} should be rejected"
+            );
+        }
+
+        assert_eq!(TraceContext::extract(&HeaderMap::new()), None);
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+#[derive(Debug, Clone)]
+/// Configuration of the server's overload detection.
+///
+/// See [Server::set_overload_detector](crate::Server::set_overload_detector).
+pub struct OverloadConfig {
+    /// How often the runtime's scheduling delay is measured.
+    pub probe_interval: Duration,
+    /// The scheduling delay above which the runtime is considered overloaded.
+    pub delay_threshold: Duration,
+    /// How long new requests are shed for once the runtime is overloaded.
+    ///
+    /// Shedding continues for this long after the last measurement which
+    /// exceeded the threshold, so the server does not flap between shedding
+    /// and accepting requests on every measurement.
+    pub shed_duration: Duration,
+}
+
+impl Default for OverloadConfig {
+    fn default() -> Self {
+        Self {
+            probe_interval: Duration::from_millis(100),
+            delay_threshold: Duration::from_millis(50),
+            shed_duration: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Measures how long tasks wait to be scheduled by the runtime.
+pub(crate) struct OverloadDetector {
+    config: OverloadConfig,
+    inner: Mutex<DetectorInner>,
+}
+
+#[derive(Default)]
+struct DetectorInner {
+    last_delay: Option<Duration>,
+    overloaded_until: Option<Instant>,
+}
+
+impl OverloadDetector {
+    /// Creates a new detector, spawning the task measuring the scheduling delay.
+    ///
+    /// The task stops once the detector has been dropped.
+    pub(crate) fn start(config: OverloadConfig) -> Arc<Self> {
+        let detector = Arc::new(Self {
+            config,
+            inner: Mutex::new(DetectorInner::default()),
+        });
+
+        crate::runtime::spawn(probe_scheduling_delay(Arc::downgrade(&detector)));
+        detector
+    }
+
+    /// The most recently measured scheduling delay.
+    pub(crate) fn scheduling_delay(&self) -> Option<Duration> {
+        self.inner.lock().last_delay
+    }
+
+    /// Returns if new requests should be shed.
+    pub(crate) fn is_overloaded(&self) -> bool {
+        self.inner
+            .lock()
+            .overloaded_until
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    fn record(&self, delay: Duration) {
+        let mut inner = self.inner.lock();
+        inner.last_delay = Some(delay);
+
+        if delay <= self.config.delay_threshold {
+            return;
+        }
+
+        let now = Instant::now();
+        if inner.overloaded_until.is_none_or(|until| until <= now) {
+            warn!(
+                delay = ?delay,
+                threshold = ?self.config.delay_threshold,
+                "Runtime is overloaded, shedding new requests.",
+            );
+        }
+        inner.overloaded_until = Some(now + self.config.shed_duration);
+    }
+}
+
+/// Periodically yields to the runtime and measures how long it takes for
+/// the task to be resumed.
+async fn probe_scheduling_delay(detector: Weak<OverloadDetector>) {
+    loop {
+        let Some(interval) = detector.upgrade().map(|d| d.config.probe_interval) else {
+            return;
+        };
+        crate::runtime::sleep(interval).await;
+
+        let yielded_at = Instant::now();
+        crate::runtime::yield_now().await;
+        let delay = yielded_at.elapsed();
+
+        match detector.upgrade() {
+            Some(detector) => detector.record(delay),
+            None => return,
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::task::Poll;
+
+use bytes::{Bytes, BytesMut};
+use http::{HeaderMap, HeaderValue};
+use rkyv::{AlignedVec, Archive, Serialize};
+use tokio::sync::mpsc;
+
+use crate::rkyv_tooling::DatacakeSerializer;
+use crate::stream::{encode_stream_status, Streaming, STREAM_OK, STREAM_STATUS_TRAILER};
+use crate::{Body, Status};
+
+/// The header marking a request or reply as carrying progress updates.
+pub(crate) const PROGRESS_HEADER: &str = "x-datacake-progress";
+/// The header marking a request whose client is listening for an acknowledgement.
+///
+/// Acknowledgements are sent among the progress updates, so this is only
+/// honoured alongside [PROGRESS_HEADER].
+pub(crate) const ACK_HEADER: &str = "x-datacake-ack";
+
+/// The number of progress updates buffered before the handler waits for
+/// the client to receive them.
+const PROGRESS_BUFFER_SIZE: usize = 16;
+
+const FRAME_HEADER_SIZE: usize = 5;
+const PROGRESS_FRAME: u8 = 0;
+const REPLY_FRAME: u8 = 1;
+const ACK_FRAME: u8 = 2;
+
+pub(crate) type ProgressFrames = mpsc::Sender<Bytes>;
+
+/// An event received by the client before the reply.
+pub(crate) enum ProgressEvent {
+    /// The raw bytes of a progress update.
+    Update(AlignedVec),
+    /// The handler acknowledged the request.
+    Acknowledged,
+}
+
+/// A callback receiving the events sent by the handler before its reply.
+pub(crate) type ProgressCallback<'a> =
+    Box<dyn FnMut(ProgressEvent) -> Result<(), Status> + Send + 'a>;
+
+/// Creates the channel progress updates are sent through.
+pub(crate) fn channel() -> (ProgressFrames, mpsc::Receiver<Bytes>) {
+    mpsc::channel(PROGRESS_BUFFER_SIZE)
+}
+
+/// Sends progress updates of a long running request to the client.
+///
+/// This is created via [Request::progress_sender](crate::Request::progress_sender),
+/// the client receives each update via the callback passed to
+/// [RpcClient::send_with_progress](crate::RpcClient::send_with_progress)
+/// while it waits for the reply.
+///
+/// If the client is not listening for progress, i.e. it sent the request
+/// via [RpcClient::send](crate::RpcClient::send), updates are discarded
+/// without being serialized.
+pub struct ProgressSender<P> {
+    frames: Option<ProgressFrames>,
+    _msg: PhantomData<fn(P)>,
+}
+
+impl<P> ProgressSender<P> {
+    pub(crate) fn new(frames: Option<ProgressFrames>) -> Self {
+        Self {
+            frames,
+            _msg: PhantomData,
+        }
+    }
+
+    #[inline]
+    /// Returns if the client is listening for progress updates.
+    pub fn is_enabled(&self) -> bool {
+        self.frames
+            .as_ref()
+            .is_some_and(|frames| !frames.is_closed())
+    }
+}
+
+impl<P> ProgressSender<P>
+where
+    P: Archive + Serialize<DatacakeSerializer>,
+{
+    /// Sends a progress update to the client.
+    ///
+    /// This waits if the client has not yet received earlier updates and
+    /// returns an error if the client has disconnected.
+    pub async fn send(&self, progress: &P) -> Result<(), Status> {
+        let Some(frames) = self.frames.as_ref() else {
+            return Ok(());
+        };
+
+        let bytes = crate::rkyv_tooling::to_view_bytes(progress)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let frame = encode_frame(PROGRESS_FRAME, &bytes)?;
+
+        frames.send(frame).await.map_err(|_| {
+            Status::connection("The client is no longer receiving progress")
+        })
+    }
+}
+
+impl<P> Clone for ProgressSender<P> {
+    fn clone(&self) -> Self {
+        Self::new(self.frames.clone())
+    }
+}
+
+impl<P> Debug for ProgressSender<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressSender")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+/// Sends the acknowledgement of a request to the client.
+///
+/// Returns an error if the client has disconnected.
+pub(crate) async fn send_ack(frames: &ProgressFrames) -> Result<(), Status> {
+    let frame = encode_frame(ACK_FRAME, &[])?;
+    frames
+        .send(frame)
+        .await
+        .map_err(|_| Status::connection("The client is no longer receiving progress"))
+}
+
+fn encode_frame(kind: u8, data: &[u8]) -> Result<Bytes, Status> {
+    let len = u32::try_from(data.len() + 1)
+        .map_err(|_| Status::internal("Frame exceeds the maximum frame size"))?;
+
+    let mut frame = BytesMut::with_capacity(FRAME_HEADER_SIZE + data.len());
+    frame.extend_from_slice(&len.to_le_bytes());
+    frame.extend_from_slice(&[kind]);
+    frame.extend_from_slice(data);
+    Ok(frame.freeze())
+}
+
+enum Event<T> {
+    Progress(Bytes),
+    ProgressClosed,
+    Reply(T),
+}
+
+/// Writes the progress updates to the body while the reply is produced,
+/// followed by the reply itself.
+///
+/// The reply's headers and final status are sent as trailers. If the client
+/// disconnects, the reply future is dropped.
+pub(crate) async fn forward_progress<F>(
+    reply: F,
+    mut frames: mpsc::Receiver<Bytes>,
+    mut sender: hyper::body::Sender,
+) where
+    F: Future<Output = Result<Body, Status>>,
+{
+    let mut reply = std::pin::pin!(reply);
+    let mut progress_open = true;
+
+    let result = loop {
+        let event = std::future::poll_fn(|cx| {
+            if let Poll::Ready(result) = reply.as_mut().poll(cx) {
+                return Poll::Ready(Event::Reply(result));
+            }
+
+            if progress_open {
+                if let Poll::Ready(frame) = frames.poll_recv(cx) {
+                    return Poll::Ready(
+                        frame.map_or(Event::ProgressClosed, Event::Progress),
+                    );
+                }
+            }
+
+            Poll::Pending
+        })
+        .await;
+
+        match event {
+            Event::Progress(frame) => {
+                if sender.send_data(frame).await.is_err() {
+                    return;
+                }
+            },
+            Event::ProgressClosed => progress_open = false,
+            Event::Reply(result) => break result,
+        }
+    };
+
+    // Updates sent before the handler returned are delivered before the reply.
+    while let Ok(frame) = frames.try_recv() {
+        if sender.send_data(frame).await.is_err() {
+            return;
+        }
+    }
+
+    let reply = match result {
+        Ok(reply) => collect_reply(reply).await,
+        Err(status) => Err(status),
+    };
+
+    let trailers = match reply {
+        Ok((frame, mut headers)) => {
+            if sender.send_data(frame).await.is_err() {
+                return;
+            }
+            headers.insert(STREAM_STATUS_TRAILER, HeaderValue::from_static(STREAM_OK));
+            headers
+        },
+        Err(status) => {
+            let Some(value) = encode_stream_status(&status) else {
+                sender.abort();
+                return;
+            };
+            let mut headers = HeaderMap::new();
+            headers.insert(STREAM_STATUS_TRAILER, value);
+            headers
+        },
+    };
+
+    let _ = sender.send_trailers(trailers).await;
+}
+
+async fn collect_reply(reply: Body) -> Result<(Bytes, HeaderMap), Status> {
+    let (body, headers) = reply.into_parts();
+    let bytes = hyper::body::to_bytes(body)
+        .await
+        .map_err(Status::internal)?;
+    Ok((encode_frame(REPLY_FRAME, &bytes)?, headers))
+}
+
+/// Reads the progress updates from the body, passing each to the callback,
+/// and returns the reply once it is received.
+pub(crate) async fn read_progress(
+    body: hyper::Body,
+    on_progress: &mut ProgressCallback<'_>,
+) -> Result<Body, Status> {
+    let mut frames = Streaming::<()>::new(body);
+    let mut reply = None;
+
+    while let Some(frame) = std::future::poll_fn(|cx| frames.poll_next_frame(cx)).await {
+        let frame = frame?;
+        match frame.first() {
+            Some(&PROGRESS_FRAME) => {
+                let mut data = AlignedVec::with_capacity(frame.len() - 1);
+                data.extend_from_slice(&frame[1..]);
+                on_progress(ProgressEvent::Update(data))?;
+            },
+            Some(&ACK_FRAME) => on_progress(ProgressEvent::Acknowledged)?,
+            Some(&REPLY_FRAME) => reply = Some(frame.slice(1..)),
+            _ => return Err(Status::invalid()),
+        }
+    }
+
+    let reply =
+        reply.ok_or_else(|| Status::connection("The stream ended without a reply"))?;
+    let mut headers = frames.take_trailers().unwrap_or_default();
+    headers.remove(STREAM_STATUS_TRAILER);
+
+    Ok(Body::with_headers(reply.into(), headers))
+}
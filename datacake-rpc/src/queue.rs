@@ -0,0 +1,223 @@
+use std::fmt::{Debug, Formatter};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_core::Stream;
+use http::HeaderMap;
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+
+use crate::cache::ReplyCache;
+use crate::handler::{HandlerContext, OpaqueMessageHandler};
+use crate::{Body, Status};
+
+pub(crate) type QueueSender = mpsc::UnboundedSender<(QueuedRequest, ResponseSender)>;
+
+/// Creates a new request queue.
+pub(crate) fn channel() -> (QueueSender, IncomingRequests) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (tx, IncomingRequests { rx })
+}
+
+/// A stream of requests received by the server which are dispatched manually.
+///
+/// This is created via [Server::incoming](crate::Server::incoming), and
+/// yields each request along with the [ResponseSender] used to complete it.
+/// The server does not invoke any handlers while the stream is alive, giving
+/// full control over which requests are dispatched, in what order, on which
+/// executor and with how much concurrency. Requests to the
+/// [AdminService](crate::AdminService) are always handled directly.
+///
+/// Requests are queued without limit until they are taken from the stream,
+/// limits like [Server::set_max_inflight_bytes](crate::Server::set_max_inflight_bytes)
+/// are still applied before a request is queued.
+///
+/// Once the stream is dropped, the server goes back to invoking the
+/// handlers directly.
+///
+/// ```rust
+/// # use datacake_rpc::{Handler, Request, RpcService, Server, ServiceRegistry, Status};
+/// # use rkyv::{Archive, Deserialize, Serialize};
+/// # use std::net::SocketAddr;
+/// # #[repr(C)]
+/// # #[derive(Serialize, Deserialize, Archive, Debug)]
+/// # #[archive(check_bytes)]
+/// # pub struct Ping;
+/// # pub struct PingService;
+/// # impl RpcService for PingService {
+/// #     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+/// #         registry.add_handler::<Ping>();
+/// #     }
+/// # }
+/// # #[datacake_rpc::async_trait]
+/// # impl Handler<Ping> for PingService {
+/// #     type Reply = ();
+/// #     async fn on_message(&self, _msg: Request<Ping>) -> Result<Self::Reply, Status> {
+/// #         Ok(())
+/// #     }
+/// # }
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// # let bind = "127.0.0.1:8010".parse::<SocketAddr>()?;
+/// let server = Server::listen(bind).await?;
+/// server.add_service(PingService);
+///
+/// let mut incoming = server.incoming();
+/// tokio::spawn(async move {
+///     // Requests are handled one at a time, in the order they were received.
+///     while let Some((request, sender)) = incoming.next().await {
+///         let reply = request.dispatch().await;
+///         sender.send(reply);
+///     }
+/// });
+/// # Ok(())
+/// # }
+/// ```
+pub struct IncomingRequests {
+    rx: mpsc::UnboundedReceiver<(QueuedRequest, ResponseSender)>,
+}
+
+impl Debug for IncomingRequests {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IncomingRequests").finish_non_exhaustive()
+    }
+}
+
+impl IncomingRequests {
+    /// Receives the next request.
+    ///
+    /// Returns `None` once the server has shut down or the stream has been
+    /// replaced by another call to [Server::incoming](crate::Server::incoming).
+    pub async fn next(&mut self) -> Option<(QueuedRequest, ResponseSender)> {
+        self.rx.recv().await
+    }
+}
+
+impl Stream for IncomingRequests {
+    type Item = (QueuedRequest, ResponseSender);
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// A request received by the server which has not been dispatched yet.
+pub struct QueuedRequest {
+    pub(crate) uri: String,
+    pub(crate) handler: Arc<dyn OpaqueMessageHandler>,
+    pub(crate) cache: Option<Arc<ReplyCache>>,
+    pub(crate) ctx: HandlerContext,
+    pub(crate) body: hyper::Body,
+}
+
+impl Debug for QueuedRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueuedRequest")
+            .field("uri", &self.uri)
+            .field("remote_addr", &self.ctx.remote_addr)
+            .field("request_id", &self.ctx.request_id)
+            .finish()
+    }
+}
+
+impl QueuedRequest {
+    /// The name of the service the request is for.
+    pub fn service(&self) -> &str {
+        crate::split_uri_path(&self.uri).0
+    }
+
+    /// The path of the message handler the request is for.
+    pub fn path(&self) -> &str {
+        crate::split_uri_path(&self.uri).1
+    }
+
+    #[inline]
+    /// The remote address of the client which sent the request.
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.ctx.remote_addr
+    }
+
+    #[inline]
+    /// The headers sent along with the request.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.ctx.headers
+    }
+
+    #[inline]
+    /// The id of the request, as listed by [Server::inflight_requests](crate::Server::inflight_requests).
+    pub fn request_id(&self) -> u64 {
+        self.ctx.request_id
+    }
+
+    /// How long the request has been waiting since it was admitted by the server.
+    pub fn queued_for(&self) -> Duration {
+        self.ctx.admitted_at.elapsed()
+    }
+
+    #[inline]
+    /// The token cancelled when the request is aborted or the client disconnects.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.ctx.cancellation
+    }
+
+    /// Passes the request to its registered handler, returning the reply.
+    ///
+    /// The reply is not sent to the client until it is passed to
+    /// [ResponseSender::send].
+    pub async fn dispatch(self) -> Result<Body, Status> {
+        crate::net::dispatch_request(
+            &self.uri,
+            self.handler,
+            self.cache,
+            self.ctx,
+            self.body,
+        )
+        .await
+    }
+
+    /// Takes the raw body of the request without dispatching it.
+    pub fn into_body(self) -> Body {
+        Body::new(self.body)
+    }
+}
+
+/// Completes a [QueuedRequest] by sending its reply to the client.
+///
+/// If the sender is dropped without sending a reply, the client
+/// receives an internal error.
+pub struct ResponseSender {
+    tx: oneshot::Sender<Result<Body, Status>>,
+}
+
+impl Debug for ResponseSender {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseSender").finish_non_exhaustive()
+    }
+}
+
+impl ResponseSender {
+    /// Creates a new sender along with the receiver of its reply.
+    pub(crate) fn new() -> (Self, oneshot::Receiver<Result<Body, Status>>) {
+        let (tx, rx) = oneshot::channel();
+        (Self { tx }, rx)
+    }
+
+    /// Sends the reply to the client, completing the request.
+    ///
+    /// The reply is silently discarded if the client is no longer waiting
+    /// for it.
+    pub fn send(self, reply: Result<Body, Status>) {
+        let _ = self.tx.send(reply);
+    }
+
+    /// Returns if the client is no longer waiting for the reply.
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
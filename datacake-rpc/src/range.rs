@@ -0,0 +1,192 @@
+use std::ops::{Bound, RangeBounds};
+
+use http::header::{CONTENT_RANGE, RANGE};
+use http::{HeaderMap, HeaderValue};
+
+use crate::{Body, Status};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// A range of bytes requested from a reply.
+///
+/// Ranges are sent via the `Range` header using the `bytes={first}-{last}`
+/// form, see [RpcClient::send_ranged](crate::RpcClient::send_ranged).
+///
+/// The server only honours ranges on byte oriented replies, that is, handlers
+/// replying with a [Body]. The range is applied to the reply before it is sent,
+/// a `Content-Range` header of the form `bytes {first}-{last}/{total}` is attached
+/// to the reply describing the bytes which were sent.
+///
+/// - If the range extends past the end of the reply, only the bytes up to the end
+///   of the reply are sent.
+/// - If the range starts at or after the end of the reply, the request fails with
+///   [ErrorCode::OutOfRange](crate::ErrorCode::OutOfRange).
+/// - Replies which are not byte oriented ignore the range and are sent in full
+///   without a `Content-Range` header.
+pub struct ByteRange {
+    start: u64,
+    end: Option<u64>,
+}
+
+impl ByteRange {
+    /// Creates a new range from the given bounds.
+    ///
+    /// Returns `None` if the range is empty, as it cannot be requested.
+    pub fn new(range: impl RangeBounds<u64>) -> Option<Self> {
+        let start = match range.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => start.checked_add(1)?,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => Some(end.checked_add(1)?),
+            Bound::Excluded(end) => Some(*end),
+            Bound::Unbounded => None,
+        };
+
+        if end.is_some_and(|end| end <= start) {
+            return None;
+        }
+
+        Some(Self { start, end })
+    }
+
+    #[inline]
+    /// The offset of the first byte in the range.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    #[inline]
+    /// The offset after the last byte in the range, if bounded.
+    pub fn end(&self) -> Option<u64> {
+        self.end
+    }
+
+    /// Parses the range from the `Range` header if present and valid.
+    ///
+    /// Malformed ranges, including multiple ranges, are ignored.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let value = headers.get(RANGE)?.to_str().ok()?;
+        let (first, last) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+
+        let first = first.trim().parse::<u64>().ok()?;
+        let last = match last.trim() {
+            "" => None,
+            last => Some(last.parse::<u64>().ok()?),
+        };
+
+        match last {
+            Some(last) if last < first => None,
+            Some(last) => Some(Self {
+                start: first,
+                end: Some(last.checked_add(1)?),
+            }),
+            None => Some(Self {
+                start: first,
+                end: None,
+            }),
+        }
+    }
+
+    /// The value of the `Range` header requesting this range.
+    pub(crate) fn to_header_value(self) -> HeaderValue {
+        let value = match self.end {
+            Some(end) => format!("bytes={}-{}", self.start, end - 1),
+            None => format!("bytes={}-", self.start),
+        };
+        HeaderValue::from_str(&value).expect("Range should be a valid header value")
+    }
+
+    /// Slices the reply body to the range, attaching a `Content-Range` header.
+    pub(crate) async fn apply(self, body: Body) -> Result<Body, Status> {
+        let (body, mut headers) = body.into_parts();
+        let bytes = hyper::body::to_bytes(body)
+            .await
+            .map_err(Status::internal)?;
+
+        let total = bytes.len() as u64;
+        if self.start >= total {
+            return Err(Status::out_of_range(format!(
+                "Range starting at byte {} is beyond the end of the {total} byte reply",
+                self.start,
+            )));
+        }
+
+        let end = self.end.map_or(total, |end| end.min(total));
+        let content_range = format!("bytes {}-{}/{total}", self.start, end - 1);
+        headers.insert(
+            CONTENT_RANGE,
+            HeaderValue::from_str(&content_range)
+                .expect("Content range should be a valid header value"),
+        );
+
+        let slice = bytes.slice(self.start as usize..end as usize);
+        Ok(Body::with_headers(slice.into(), headers))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers_with(range: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RANGE, HeaderValue::from_str(range).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_range_header_roundtrip() {
+        for range in [
+            ByteRange::new(0..10),
+            ByteRange::new(5..=5),
+            ByteRange::new(100..),
+            ByteRange::new(..1),
+        ] {
+            let range = range.expect("Range should not be empty");
+            let headers = headers_with(range.to_header_value().to_str().unwrap());
+            assert_eq!(ByteRange::from_headers(&headers), Some(range));
+        }
+
+        assert_eq!(ByteRange::new(5..5), None);
+        assert_eq!(
+            ByteRange::new((Bound::Excluded(5), Bound::Included(5))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalid_range_header() {
+        for range in [
+            "",
+            "bytes=",
+            "bytes=-5",
+            "bytes=5-4",
+            "bytes=0-1,4-5",
+            "items=0-1",
+        ] {
+            assert_eq!(
+                ByteRange::from_headers(&headers_with(range)),
+                None,
+                "Range {range:?} should be ignored"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_range() {
+        let body = Body::from(&b"hello, world"[..]);
+        let ranged = ByteRange::new(7..100).unwrap().apply(body).await.unwrap();
+        assert_eq!(
+            ranged.headers().get(CONTENT_RANGE).unwrap(),
+            "bytes 7-11/12"
+        );
+        assert_eq!(ranged.into_bytes().await.unwrap(), &b"world"[..]);
+
+        let body = Body::from(&b"hello, world"[..]);
+        let Err(status) = ByteRange::new(12..).unwrap().apply(body).await else {
+            panic!("Range beyond the body should error");
+        };
+        assert_eq!(status.code, crate::ErrorCode::OutOfRange);
+    }
+}
@@ -0,0 +1,225 @@
+use std::fs::File;
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use http::{HeaderMap, StatusCode};
+use hyper::body::HttpBody;
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::body::Body;
+use crate::capture::{CapturedFrame, FrameKind};
+use crate::net::{AcceptRateLimiter, Channel, Status};
+
+/// Replays every captured request in the file against the server at `addr`
+/// using the default [ReplayConfig].
+///
+/// See [ReplayClient] for more control over how the requests are sent.
+pub async fn replay(
+    addr: SocketAddr,
+    capture_file: impl AsRef<Path>,
+) -> io::Result<ReplayReport> {
+    let frames = CapturedFrame::read_all(File::open(capture_file)?)?;
+    let client = ReplayClient::new(Channel::connect(addr));
+    Ok(client.replay(frames).await)
+}
+
+#[derive(Debug, Copy, Clone)]
+/// Configuration of how a [ReplayClient] sends the captured requests.
+pub struct ReplayConfig {
+    /// The maximum number of requests in-flight at once.
+    ///
+    /// With a concurrency of `1` the requests are sent one after the other
+    /// in the order they were captured.
+    pub concurrency: usize,
+    /// The maximum number of requests started per second.
+    ///
+    /// If `None` requests are sent as fast as the concurrency allows.
+    pub rate: Option<u32>,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 1,
+            rate: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// The result of replaying a single captured request.
+pub struct ReplayOutcome {
+    /// The id the request was captured with.
+    pub request_id: u64,
+    /// The wire path the request was sent to.
+    pub path: String,
+    /// The time taken for the reply to be fully received.
+    pub elapsed: Duration,
+    /// The length of the reply body if the request succeeded, otherwise
+    /// the status returned by the server.
+    pub result: Result<usize, Status>,
+}
+
+#[derive(Debug, Default)]
+/// The results of a replay, in the order the requests were captured.
+pub struct ReplayReport {
+    /// The outcome of each replayed request.
+    pub outcomes: Vec<ReplayOutcome>,
+}
+
+impl ReplayReport {
+    /// The number of requests which the server replied to successfully.
+    pub fn succeeded(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.result.is_ok())
+            .count()
+    }
+
+    /// The outcomes of the requests which failed.
+    pub fn failures(&self) -> impl Iterator<Item = &ReplayOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.result.is_err())
+    }
+}
+
+#[derive(Clone)]
+/// Re-sends captured request bodies to a server.
+///
+/// This drives a server with realistic traffic, i.e. for load testing or to
+/// reproduce an issue locally, using frames recorded via
+/// [Server::enable_capture](crate::Server::enable_capture). Only request frames
+/// are replayed, reply frames are ignored. The bodies are sent exactly as
+/// captured to their captured path, without any of the original headers.
+///
+/// ```rust
+/// use datacake_rpc::{CapturedFrame, Channel, ReplayClient, ReplayConfig};
+///
+/// # async fn run(channel: Channel, frames: Vec<CapturedFrame>) {
+/// let client = ReplayClient::new(channel).with_config(ReplayConfig {
+///     concurrency: 8,
+///     rate: Some(100),
+/// });
+/// let report = client.replay(frames).await;
+/// for failure in report.failures() {
+///     println!("{} failed: {:?}", failure.path, failure.result);
+/// }
+/// # }
+/// ```
+pub struct ReplayClient {
+    channel: Channel,
+    config: ReplayConfig,
+}
+
+impl ReplayClient {
+    /// Creates a new replay client sending requests over the given channel.
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            channel,
+            config: ReplayConfig::default(),
+        }
+    }
+
+    /// Sets how the requests are sent.
+    ///
+    /// # Panics
+    ///
+    /// If the concurrency or rate is `0`.
+    pub fn with_config(mut self, config: ReplayConfig) -> Self {
+        assert!(
+            config.concurrency > 0,
+            "The replay concurrency must be greater than 0"
+        );
+        assert_ne!(
+            config.rate,
+            Some(0),
+            "The replay rate must be greater than 0"
+        );
+        self.config = config;
+        self
+    }
+
+    /// Sends every captured request and waits for all of their replies.
+    pub async fn replay(
+        &self,
+        frames: impl IntoIterator<Item = CapturedFrame>,
+    ) -> ReplayReport {
+        let permits = Arc::new(Semaphore::new(self.config.concurrency));
+        let mut limiter = self.config.rate.map(|rate| AcceptRateLimiter::new(rate, 1));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let requests = frames
+            .into_iter()
+            .filter(|frame| frame.kind == FrameKind::Request);
+        let mut sent = 0;
+        for frame in requests {
+            if let Some(limiter) = limiter.as_mut() {
+                while let Err(wait) = limiter.try_acquire(Instant::now()) {
+                    crate::runtime::sleep(wait).await;
+                }
+            }
+            let permit = permits
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("Semaphore should never be closed");
+
+            let channel = self.channel.clone();
+            let tx = tx.clone();
+            let index = sent;
+            crate::runtime::spawn(async move {
+                let outcome = send_frame(&channel, frame).await;
+                drop(permit);
+                let _ = tx.send((index, outcome));
+            });
+            sent += 1;
+        }
+        drop(tx);
+
+        let mut outcomes = Vec::with_capacity(sent);
+        while let Some(outcome) = rx.recv().await {
+            outcomes.push(outcome);
+        }
+        outcomes.sort_unstable_by_key(|(index, _)| *index);
+
+        ReplayReport {
+            outcomes: outcomes.into_iter().map(|(_, outcome)| outcome).collect(),
+        }
+    }
+}
+
+async fn send_frame(channel: &Channel, frame: CapturedFrame) -> ReplayOutcome {
+    let started = Instant::now();
+    let result = send_body(channel, &frame).await;
+    ReplayOutcome {
+        request_id: frame.request_id,
+        path: frame.path,
+        elapsed: started.elapsed(),
+        result,
+    }
+}
+
+async fn send_body(channel: &Channel, frame: &CapturedFrame) -> Result<usize, Status> {
+    let _guard = channel.start_request().map_err(Status::connection)?;
+    let body = Body::from(frame.body.clone());
+    let response = channel
+        .send_parts(&frame.path, HeaderMap::new(), body)
+        .await
+        .map_err(Status::connection)?;
+
+    let (head, mut body) = response.into_parts();
+    if head.status != StatusCode::OK {
+        return Err(crate::client::read_error_reply(&head.headers, body).await);
+    }
+
+    let mut len = 0;
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(Status::connection)?;
+        len += chunk.len();
+    }
+    Ok(len)
+}
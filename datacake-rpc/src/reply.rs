@@ -0,0 +1,428 @@
+use std::borrow::Cow;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::HeaderValue;
+use rkyv::{AlignedVec, Archive, Fallible, Serialize};
+
+use crate::rkyv_tooling::{DataView, DatacakeSerializer, InvalidView};
+use crate::{Body, RequestContents, Status, TryAsBody, TryIntoBody};
+
+/// The header used to describe the kind of message contained in an [AnyReply].
+pub const REPLY_KIND_HEADER: &str = "x-datacake-reply-kind";
+
+/// A reply which can contain one of several message types.
+///
+/// Handlers which need to return different message types depending on the
+/// outcome of the request can use this as their `Reply` type, the kind of
+/// the message is sent to the client as a header alongside the serialized
+/// message, allowing the client to view the reply as the correct concrete type
+/// rather than always deserializing an enum of every possible reply.
+///
+/// By default the kind of the message is the type name of the message,
+/// a custom kind can be provided via [AnyReply::with_kind] if the client
+/// and server do not share the same type paths.
+///
+/// `AnyReply` implements [TryIntoBody] by attaching the kind header to the
+/// already serialized message body, and [RequestContents] by reading the
+/// header back on the client, so no additional serialization occurs over
+/// replying with the message directly.
+///
+/// ```rust
+/// use rkyv::{Archive, Deserialize, Serialize};
+/// use datacake_rpc::{AnyReply, Handler, Request, RpcService, ServiceRegistry, Status};
+///
+/// #[repr(C)]
+/// #[derive(Serialize, Deserialize, Archive, Debug)]
+/// #[archive(check_bytes)]
+/// pub struct Lookup {
+///     key: u64,
+/// }
+///
+/// #[repr(C)]
+/// #[derive(Serialize, Deserialize, Archive, Debug)]
+/// #[archive(check_bytes)]
+/// pub struct Found {
+///     value: String,
+/// }
+///
+/// #[repr(C)]
+/// #[derive(Serialize, Deserialize, Archive, Debug)]
+/// #[archive(check_bytes)]
+/// pub struct Redirect {
+///     node: String,
+/// }
+///
+/// pub struct LookupService;
+///
+/// impl RpcService for LookupService {
+///     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+///         registry.add_handler::<Lookup>();
+///     }
+/// }
+///
+/// #[datacake_rpc::async_trait]
+/// impl Handler<Lookup> for LookupService {
+///     type Reply = AnyReply;
+///
+///     async fn on_message(&self, msg: Request<Lookup>) -> Result<Self::Reply, Status> {
+///         if msg.key == 0 {
+///             AnyReply::new(&Redirect { node: "node-2".to_string() })
+///         } else {
+///             AnyReply::new(&Found { value: "Hello, world!".to_string() })
+///         }
+///     }
+/// }
+///
+/// // On the client the reply can then be dispatched on its kind:
+/// # fn dispatch(reply: AnyReply) -> Result<(), Status> {
+/// if reply.is::<Found>() {
+///     let found = reply.try_into::<Found>()?;
+///     println!("Found: {}", found.value);
+/// } else {
+///     let redirect = reply.try_into::<Redirect>()?;
+///     println!("Redirect to: {}", redirect.node);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct AnyReply {
+    kind: Cow<'static, str>,
+    data: AlignedVec,
+}
+
+impl AnyReply {
+    /// Creates a new reply from the given message using the type name
+    /// of the message as its kind.
+    pub fn new<T>(msg: &T) -> Result<Self, Status>
+    where
+        T: Archive + Serialize<DatacakeSerializer>,
+    {
+        Self::with_kind(std::any::type_name::<T>(), msg)
+    }
+
+    /// Creates a new reply from the given message using a custom kind.
+    pub fn with_kind<T>(kind: &'static str, msg: &T) -> Result<Self, Status>
+    where
+        T: Archive + Serialize<DatacakeSerializer>,
+    {
+        let data = crate::rkyv_tooling::to_view_bytes(msg)
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Self {
+            kind: Cow::Borrowed(kind),
+            data,
+        })
+    }
+
+    #[inline]
+    /// The kind of message contained within the reply.
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    #[inline]
+    /// Returns if the reply contains a message of type `T` using its type name.
+    pub fn is<T>(&self) -> bool {
+        self.is_kind(std::any::type_name::<T>())
+    }
+
+    #[inline]
+    /// Returns if the reply contains a message of the given kind.
+    pub fn is_kind(&self, kind: &str) -> bool {
+        self.kind == kind
+    }
+
+    /// Attempts to view the reply as the message type `T`.
+    ///
+    /// This will return an error if the kind of the reply is not the type name of `T`.
+    pub fn try_into<T>(self) -> Result<DataView<T>, Status>
+    where
+        T: Archive,
+        T::Archived: 'static,
+    {
+        if !self.is::<T>() {
+            return Err(Status::internal(format!(
+                "Reply is of kind {} not {}",
+                self.kind,
+                std::any::type_name::<T>(),
+            )));
+        }
+
+        self.into_view_unchecked()
+    }
+
+    /// Views the reply as the message type `T` without checking
+    /// the kind of the reply.
+    ///
+    /// This is useful when using custom kinds set with [AnyReply::with_kind], the
+    /// message data itself is still validated as with any other message.
+    pub fn into_view_unchecked<T>(self) -> Result<DataView<T>, Status>
+    where
+        T: Archive,
+        T::Archived: 'static,
+    {
+        DataView::using(self.data).map_err(|_| Status::invalid())
+    }
+}
+
+impl Debug for AnyReply {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnyReply")
+            .field("kind", &self.kind)
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+impl TryIntoBody for AnyReply {
+    fn try_into_body(self) -> Result<Body, Status> {
+        let kind = HeaderValue::from_str(&self.kind).map_err(|_| {
+            Status::internal(format!("Reply kind {} is not a valid header", self.kind))
+        })?;
+
+        let mut body = Body::from_aligned(self.data);
+        body.headers_mut().insert(REPLY_KIND_HEADER, kind);
+        Ok(body)
+    }
+}
+
+#[async_trait]
+impl RequestContents for AnyReply {
+    type Content = Self;
+
+    async fn from_body(body: Body) -> Result<Self::Content, Status> {
+        let kind = body
+            .headers()
+            .get(REPLY_KIND_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| Cow::Owned(v.to_string()))
+            .ok_or_else(Status::invalid)?;
+
+        let data = crate::utils::to_aligned(body.into_inner())
+            .await
+            .map_err(Status::internal)?;
+
+        Ok(Self { kind, data })
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+/// A reply with no content.
+///
+/// Handlers which are purely side effects, i.e. commands or notifications,
+/// can use this as their `Reply` type. It is sent as an empty body, skipping
+/// serialization entirely, and the client receives `()` once the handler
+/// has completed.
+///
+/// ```rust
+/// use rkyv::{Archive, Deserialize, Serialize};
+/// use datacake_rpc::{Empty, Handler, Request, RpcService, ServiceRegistry, Status};
+///
+/// #[repr(C)]
+/// #[derive(Serialize, Deserialize, Archive, Debug)]
+/// #[archive(check_bytes)]
+/// pub struct Invalidate {
+///     key: u64,
+/// }
+///
+/// pub struct CacheService;
+///
+/// impl RpcService for CacheService {
+///     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+///         registry.add_handler::<Invalidate>();
+///     }
+/// }
+///
+/// #[datacake_rpc::async_trait]
+/// impl Handler<Invalidate> for CacheService {
+///     type Reply = Empty;
+///
+///     async fn on_message(&self, msg: Request<Invalidate>) -> Result<Self::Reply, Status> {
+///         // Remove the key from the cache...
+///         Ok(Empty)
+///     }
+/// }
+/// ```
+pub struct Empty;
+
+impl TryAsBody for Empty {
+    fn try_as_body(&self) -> Result<Body, Status> {
+        Ok(Body::new(hyper::Body::empty()))
+    }
+}
+
+#[async_trait]
+impl RequestContents for Empty {
+    type Content = ();
+
+    async fn from_body(_body: Body) -> Result<Self::Content, Status> {
+        Ok(())
+    }
+}
+
+/// A reply which is serialized once and shared between many requests.
+///
+/// Handlers which serve the same data to many clients, i.e. a snapshot of
+/// some configuration, can keep a `SharedReply` in their state and return a
+/// clone of it. Cloning only increments a reference count and every reply
+/// writes the same underlying buffer to the socket, so neither the value
+/// nor its bytes are copied per request.
+///
+/// The client receives the reply as a [DataView] of `T`, exactly as if the
+/// handler had returned the value itself.
+///
+/// ```rust
+/// use rkyv::{Archive, Deserialize, Serialize};
+/// use datacake_rpc::{Handler, Request, RpcService, ServiceRegistry, SharedReply, Status};
+///
+/// #[repr(C)]
+/// #[derive(Serialize, Deserialize, Archive, Debug)]
+/// #[archive(check_bytes)]
+/// pub struct GetConfig;
+///
+/// #[repr(C)]
+/// #[derive(Serialize, Deserialize, Archive, Debug)]
+/// #[archive(check_bytes)]
+/// pub struct Config {
+///     nodes: Vec<String>,
+/// }
+///
+/// pub struct ConfigService {
+///     snapshot: SharedReply<Config>,
+/// }
+///
+/// impl RpcService for ConfigService {
+///     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+///         registry.add_handler::<GetConfig>();
+///     }
+/// }
+///
+/// #[datacake_rpc::async_trait]
+/// impl Handler<GetConfig> for ConfigService {
+///     type Reply = SharedReply<Config>;
+///
+///     async fn on_message(&self, _msg: Request<GetConfig>) -> Result<Self::Reply, Status> {
+///         Ok(self.snapshot.clone())
+///     }
+/// }
+///
+/// let config = Config { nodes: vec!["node-1".to_string()] };
+/// let service = ConfigService {
+///     snapshot: SharedReply::new(&config).unwrap(),
+/// };
+/// ```
+pub struct SharedReply<T> {
+    data: Bytes,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> SharedReply<T>
+where
+    T: Archive + Serialize<DatacakeSerializer>,
+{
+    /// Serializes the value into a new shared reply.
+    pub fn new(value: &T) -> Result<Self, <DatacakeSerializer as Fallible>::Error> {
+        let data = crate::rkyv_tooling::to_view_bytes(value)?;
+        Ok(Self::from_bytes_unchecked(Bytes::from_owner(data)))
+    }
+}
+
+impl<T> SharedReply<T> {
+    /// Creates a shared reply from an already serialized value.
+    ///
+    /// The buffer is referenced rather than copied, so it can be shared
+    /// with other parts of the application. It must contain a value of `T`
+    /// produced by [to_view_bytes](crate::to_view_bytes), including the
+    /// trailing checksum, buffers with an invalid checksum are rejected.
+    pub fn from_archived(data: Arc<AlignedVec>) -> Result<Self, InvalidView> {
+        Self::from_bytes(Bytes::from_owner(SharedBuffer(data)))
+    }
+
+    /// Creates a shared reply from an already serialized value.
+    ///
+    /// See [SharedReply::from_archived] for the requirements of the buffer.
+    pub fn from_shared_bytes(data: Arc<[u8]>) -> Result<Self, InvalidView> {
+        Self::from_bytes(Bytes::from_owner(data))
+    }
+
+    fn from_bytes(data: Bytes) -> Result<Self, InvalidView> {
+        let (value, checksum) = data
+            .len()
+            .checked_sub(4)
+            .map(|end| data.split_at(end))
+            .ok_or(InvalidView)?;
+
+        let expected = u32::from_le_bytes(checksum.try_into().map_err(|_| InvalidView)?);
+        if crc32fast::hash(value) != expected {
+            return Err(InvalidView);
+        }
+
+        Ok(Self::from_bytes_unchecked(data))
+    }
+
+    fn from_bytes_unchecked(data: Bytes) -> Self {
+        Self {
+            data,
+            _value: PhantomData,
+        }
+    }
+
+    #[inline]
+    /// The serialized bytes of the reply, including the trailing checksum.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl<T> Clone for SharedReply<T> {
+    fn clone(&self) -> Self {
+        Self::from_bytes_unchecked(self.data.clone())
+    }
+}
+
+impl<T> Debug for SharedReply<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedReply")
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+impl<T> TryIntoBody for SharedReply<T> {
+    #[inline]
+    fn try_into_body(self) -> Result<Body, Status> {
+        Ok(Body::from(self.data))
+    }
+}
+
+#[async_trait]
+impl<T> RequestContents for SharedReply<T>
+where
+    T: Archive + Send + Sync + 'static,
+    T::Archived: Send + Sync + 'static,
+{
+    type Content = DataView<T>;
+
+    const ALIGNMENT: usize = T::ALIGNMENT;
+
+    async fn from_body(body: Body) -> Result<Self::Content, Status> {
+        T::from_body(body).await
+    }
+
+    fn schema_hash() -> u64 {
+        T::schema_hash()
+    }
+}
+
+/// Exposes the bytes of a shared buffer as the owner of a [Bytes].
+struct SharedBuffer(Arc<AlignedVec>);
+
+impl AsRef<[u8]> for SharedBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
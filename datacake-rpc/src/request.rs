@@ -1,12 +1,20 @@
 use std::fmt::{Debug, Formatter};
+use std::mem;
 use std::net::SocketAddr;
 use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use async_trait::async_trait;
-use http::HeaderMap;
-use rkyv::Archive;
+use http::header::{AsHeaderName, ToStrError};
+use http::{HeaderMap, HeaderValue};
+use rkyv::{AlignedVec, Archive};
+use tokio_util::sync::CancellationToken;
 
-use crate::rkyv_tooling::DataView;
+use crate::baggage::Baggage;
+use crate::progress::{ProgressFrames, ProgressSender, ACK_HEADER};
+use crate::rkyv_tooling::{DataView, SerdeConfig};
 use crate::{Body, Status};
 
 #[async_trait]
@@ -15,11 +23,87 @@ use crate::{Body, Status};
 ///
 /// This trait is automatically implemented for the [Body] type
 /// and any type implementing [rkyv]'s (de)serializer traits.
+///
+/// # Validation
+///
+/// Messages are validated once their whole body has been received, as rkyv
+/// stores the root of a message at the end of its data followed by the
+/// checksum, nothing about the contents can be checked before then.
+/// Bodies which declare a length that cannot hold a valid message, i.e. one
+/// which is too short or would leave the root misaligned, are rejected with
+/// [ErrorCode::InvalidPayload](crate::ErrorCode::InvalidPayload) before any of
+/// the body is buffered. Bodies of unknown length are only checked once fully
+/// received, combine with [Limits](crate::Limits) to bound how much is buffered.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be used as an RPC message",
+    label = "`{Self}` cannot be deserialized from a request body",
+    note = "messages must derive rkyv's `Archive`, `Serialize` and `Deserialize`, with `#[archive(check_bytes)]`"
+)]
 pub trait RequestContents {
     /// The deserialized message type.
     type Content: Send + Sized + 'static;
 
+    /// The alignment in bytes the start of the body must have for the message
+    /// to be accessed without first being copied into an aligned buffer.
+    ///
+    /// For messages this is [AlignedVec::ALIGNMENT] rather than the alignment of
+    /// the archived root, as nested values are aligned relative to the start of
+    /// the serialized buffer. By default this is `1`, for types which read the
+    /// body as-is such as [Body].
+    ///
+    /// See [Body::ensure_aligned].
+    const ALIGNMENT: usize = 1;
+
     async fn from_body(body: Body) -> Result<Self::Content, Status>;
+
+    /// A hash of the message's schema, used to detect a client and server
+    /// disagreeing on the layout of a message.
+    ///
+    /// For messages this is derived from the size and alignment of the
+    /// archived root, so it is stable across builds for identical layouts and
+    /// catches changes such as fields being added or removed from a
+    /// `#[repr(C)]` struct. Changes which keep the root's size and alignment,
+    /// i.e. reordering fields or changing the type of a field within the same
+    /// space, are not detected.
+    ///
+    /// By default this is `0`, for types without a schema such as [Body].
+    ///
+    /// See [RpcClient::enable_schema_check](crate::RpcClient::enable_schema_check).
+    fn schema_hash() -> u64 {
+        0
+    }
+
+    /// Converts the request body into the desired type using the
+    /// provided [SerdeConfig].
+    ///
+    /// By default this ignores the config and calls [Self::from_body].
+    async fn from_body_with_config(
+        body: Body,
+        _config: &SerdeConfig,
+    ) -> Result<Self::Content, Status> {
+        Self::from_body(body).await
+    }
+
+    /// Converts the request body into the desired type, reading the body
+    /// into the provided `buffer` rather than allocating a new one.
+    ///
+    /// For types producing a [DataView], the filled buffer is moved into
+    /// the returned view and `buffer` is left empty. The view owns the data
+    /// for as long as it is alive, once the caller is done with it, the
+    /// allocation can be recovered via [DataView::into_data] and passed
+    /// back in on the next call.
+    ///
+    /// If reading the body fails, the buffer is kept in place (although
+    /// its contents are unspecified), if the data is not a valid view the
+    /// buffer is dropped.
+    ///
+    /// By default this ignores the buffer and calls [Self::from_body].
+    async fn from_body_into(
+        body: Body,
+        _buffer: &mut AlignedVec,
+    ) -> Result<Self::Content, Status> {
+        Self::from_body(body).await
+    }
 }
 
 #[async_trait]
@@ -39,12 +123,90 @@ where
 {
     type Content = DataView<Self>;
 
+    const ALIGNMENT: usize = AlignedVec::ALIGNMENT;
+
     async fn from_body(body: Body) -> Result<Self::Content, Status> {
-        let bytes = crate::utils::to_aligned(body.0)
+        Self::from_body_with_config(body, &SerdeConfig::default()).await
+    }
+
+    fn schema_hash() -> u64 {
+        crate::schema::layout_hash::<Msg::Archived>()
+    }
+
+    async fn from_body_with_config(
+        mut body: Body,
+        config: &SerdeConfig,
+    ) -> Result<Self::Content, Status> {
+        if let Some(view) = unit_view::<Msg>(&body) {
+            return Ok(view);
+        }
+        check_declared_len::<Msg>(&body)?;
+        let realigned = body.ensure_aligned(Self::ALIGNMENT).await?;
+        let bytes = body.into_bytes().await?;
+        // Streaming bodies do not declare their length up front.
+        if bytes.is_empty() {
+            if let Some(view) = DataView::unit() {
+                return Ok(view);
+            }
+        }
+
+        DataView::using_bytes(bytes, config.verify_checksum, realigned)
+            .map_err(|_| Status::invalid())
+    }
+
+    async fn from_body_into(
+        body: Body,
+        buffer: &mut AlignedVec,
+    ) -> Result<Self::Content, Status> {
+        if let Some(view) = unit_view::<Msg>(&body) {
+            return Ok(view);
+        }
+        check_declared_len::<Msg>(&body)?;
+        crate::utils::to_aligned_into(body.into_inner(), buffer)
             .await
             .map_err(Status::internal)?;
+        if buffer.is_empty() {
+            if let Some(view) = DataView::unit() {
+                return Ok(view);
+            }
+        }
 
-        DataView::using(bytes).map_err(|_| Status::invalid())
+        DataView::using(mem::take(buffer)).map_err(|_| Status::invalid())
+    }
+}
+
+/// Views an empty body as the unit message without reading or validating it.
+///
+/// Clients send unit messages as an empty body, see
+/// [EMPTY_BODY_CAPABILITY](crate::EMPTY_BODY_CAPABILITY), so there is nothing
+/// to buffer or check.
+fn unit_view<Msg>(body: &Body) -> Option<DataView<Msg>>
+where
+    Msg: Archive,
+    Msg::Archived: 'static,
+{
+    if body.len() != Some(0) {
+        return None;
+    }
+    DataView::unit()
+}
+
+/// Rejects a body whose declared length cannot hold a view of `Msg` before
+/// any of it is read.
+///
+/// rkyv stores the root of the message at the end of the data, followed by
+/// the checksum, so the contents of a message can only be validated once the
+/// whole body has been received. Its declared length is known up front though,
+/// so bodies which are too short or would leave the root misaligned are
+/// rejected without being buffered.
+fn check_declared_len<Msg>(body: &Body) -> Result<(), Status>
+where
+    Msg: Archive,
+    Msg::Archived: 'static,
+{
+    match body.len() {
+        Some(len) if !DataView::<Msg>::is_valid_len(len) => Err(Status::invalid()),
+        _ => Ok(()),
     }
 }
 
@@ -76,6 +238,12 @@ where
 {
     pub(crate) remote_addr: SocketAddr,
     pub(crate) headers: HeaderMap,
+    pub(crate) request_id: u64,
+    pub(crate) queued_for: Duration,
+    pub(crate) cancellation: CancellationToken,
+    pub(crate) completed: CancellationToken,
+    pub(crate) progress: Option<ProgressFrames>,
+    pub(crate) acknowledged: AtomicBool,
 
     // A small hack to stop linters miss-guiding users
     // into thinking their messages are `!Sized` when in fact they are.
@@ -95,6 +263,7 @@ where
         f.debug_struct("Request")
             .field("view", &self.view)
             .field("remote_addr", &self.remote_addr)
+            .field("request_id", &self.request_id)
             .finish()
     }
 }
@@ -122,6 +291,12 @@ where
         Self {
             remote_addr,
             headers,
+            request_id: 0,
+            queued_for: Duration::ZERO,
+            cancellation: CancellationToken::new(),
+            completed: CancellationToken::new(),
+            progress: None,
+            acknowledged: AtomicBool::new(false),
             #[cfg(debug_assertions)]
             view: Box::new(view),
             #[cfg(not(debug_assertions))]
@@ -129,6 +304,27 @@ where
         }
     }
 
+    /// Attaches the server's tracking information to the request.
+    pub(crate) fn with_tracking(
+        mut self,
+        request_id: u64,
+        queued_for: Duration,
+        cancellation: CancellationToken,
+        completed: CancellationToken,
+    ) -> Self {
+        self.request_id = request_id;
+        self.queued_for = queued_for;
+        self.cancellation = cancellation;
+        self.completed = completed;
+        self
+    }
+
+    /// Attaches the channel progress updates are sent to the client through.
+    pub(crate) fn with_progress(mut self, progress: Option<ProgressFrames>) -> Self {
+        self.progress = progress;
+        self
+    }
+
     #[cfg(debug_assertions)]
     #[inline]
     /// Consumes the request into the value of the message.
@@ -162,11 +358,294 @@ where
         &self.headers
     }
 
+    /// Iterates over every value of a header, in the order they were sent.
+    pub fn header_all(
+        &self,
+        name: impl AsHeaderName,
+    ) -> impl Iterator<Item = &HeaderValue> {
+        self.headers.get_all(name).into_iter()
+    }
+
+    /// Parses the value of a header, `None` if the header is not set.
+    ///
+    /// If the header is sent more than once, only its first value is parsed,
+    /// see [Request::header_all] to read every value. Leading and trailing
+    /// whitespace is ignored.
+    ///
+    /// ```rust
+    /// # use datacake_rpc::{Request, Status};
+    /// fn requested_limit<Msg>(msg: &Request<Msg>) -> Result<u32, Status>
+    /// # where
+    /// #     Msg: datacake_rpc::RequestContents,
+    /// {
+    ///     let limit = msg
+    ///         .header_as::<u32>("x-limit")
+    ///         .transpose()
+    ///         .map_err(Status::invalid_argument)?;
+    ///     Ok(limit.unwrap_or(100))
+    /// }
+    /// ```
+    pub fn header_as<T>(
+        &self,
+        name: impl AsHeaderName,
+    ) -> Option<Result<T, HeaderParseError<T::Err>>>
+    where
+        T: FromStr,
+    {
+        self.headers.get(name).map(parse_header)
+    }
+
+    /// The baggage attached to the request by the client.
+    ///
+    /// While the handler runs, this is also the [current](Baggage::current)
+    /// baggage propagated to any requests it sends, see [Baggage].
+    pub fn baggage(&self) -> Baggage {
+        Baggage::from_headers(&self.headers)
+    }
+
     #[inline]
     /// The remote address of the incoming message.
     pub fn remote_addr(&self) -> SocketAddr {
         self.remote_addr
     }
+
+    #[inline]
+    /// The id of the request within the server.
+    ///
+    /// This matches the id reported by [Server::inflight_requests](crate::Server::inflight_requests)
+    /// and can be used to abort the request via [Server::abort_request](crate::Server::abort_request).
+    pub fn request_id(&self) -> u64 {
+        self.request_id
+    }
+
+    #[inline]
+    /// How long the request waited between being admitted by the server and
+    /// being dispatched to its handler.
+    ///
+    /// This excludes the time spent in the handler itself, so a high queue
+    /// wait relative to the handler's execution time indicates the server is
+    /// overloaded rather than the handler being slow, i.e. while requests are
+    /// held by [Server::incoming](crate::Server::incoming) or body transforms.
+    pub fn queued_for(&self) -> Duration {
+        self.queued_for
+    }
+
+    /// Creates a sender for progress updates of type `P`.
+    ///
+    /// Long running handlers can use this to report their progress while the
+    /// client waits for the reply, the client receives each update via
+    /// [RpcClient::send_with_progress](crate::RpcClient::send_with_progress).
+    /// The reply is still returned from the handler as usual.
+    ///
+    /// If the client is not listening for progress, the updates are discarded.
+    ///
+    /// ```rust
+    /// # use rkyv::{Archive, Deserialize, Serialize};
+    /// # use datacake_rpc::{Handler, Request, RpcService, ServiceRegistry, Status};
+    /// #
+    /// # #[repr(C)]
+    /// # #[derive(Serialize, Deserialize, Archive)]
+    /// # #[archive(check_bytes)]
+    /// # pub struct Rebuild {
+    /// #     steps: u32,
+    /// # }
+    /// #
+    /// # pub struct IndexService;
+    /// #
+    /// # impl RpcService for IndexService {
+    /// #     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+    /// #         registry.add_handler::<Rebuild>();
+    /// #     }
+    /// # }
+    /// #[datacake_rpc::async_trait]
+    /// impl Handler<Rebuild> for IndexService {
+    ///     type Reply = u32;
+    ///
+    ///     async fn on_message(&self, msg: Request<Rebuild>) -> Result<Self::Reply, Status> {
+    ///         let progress = msg.progress_sender::<u32>();
+    ///         for step in 0..msg.steps {
+    ///             // Do some work...
+    ///             progress.send(&step).await?;
+    ///         }
+    ///         Ok(msg.steps)
+    ///     }
+    /// }
+    /// ```
+    pub fn progress_sender<P>(&self) -> ProgressSender<P> {
+        ProgressSender::new(self.progress.clone())
+    }
+
+    /// Acknowledges the request, letting the client know it has been received
+    /// and is being worked on before the reply is ready.
+    ///
+    /// The client observes the acknowledgement via the callback passed to
+    /// [RpcClient::send_with_ack](crate::RpcClient::send_with_ack), which lets
+    /// it tell apart a request which is still being sent or queued from one
+    /// the handler is actively processing.
+    ///
+    /// Only the first call sends an acknowledgement, later calls do nothing.
+    /// If the client is not listening for one, i.e. it sent the request via
+    /// [RpcClient::send](crate::RpcClient::send), nothing is sent.
+    ///
+    /// Acknowledging is optional: if the handler returns without acknowledging
+    /// the request, no acknowledgement is sent and the client only receives the
+    /// reply, or error, as usual.
+    ///
+    /// Returns an error if the client has disconnected.
+    pub async fn acknowledge(&self) -> Result<(), Status> {
+        let Some(frames) = self.progress.as_ref() else {
+            return Ok(());
+        };
+        if !self.headers.contains_key(ACK_HEADER)
+            || self.acknowledged.swap(true, Ordering::Relaxed)
+        {
+            return Ok(());
+        }
+
+        crate::progress::send_ack(frames).await
+    }
+
+    #[inline]
+    /// The token which is cancelled if the request is aborted or the client
+    /// disconnects before the reply is produced.
+    ///
+    /// If the client has disconnected by the time the handler returns, its
+    /// reply is dropped without being serialized.
+    ///
+    /// Long running handlers should watch this token, i.e. by selecting on
+    /// [CancellationToken::cancelled], and unwind once it fires.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    #[inline]
+    /// Returns if the request has been aborted.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Sets a deadline after which the request is cancelled.
+    ///
+    /// Once the deadline has elapsed, the request's [cancellation token](Self::cancellation_token)
+    /// is cancelled as if the request had been aborted. This lets a handler bound its own
+    /// total time, i.e. while waiting on a downstream service, even if the client did
+    /// not set a timeout. Like aborting, this is cooperative, the handler must watch
+    /// the token and unwind once it fires.
+    ///
+    /// Setting a deadline more than once keeps all of them, the earliest deadline
+    /// cancels the request. The deadline has no effect once the request has been handled.
+    ///
+    /// # Precedence
+    ///
+    /// - A client timeout set via [RpcClient::set_timeout](crate::RpcClient::set_timeout)
+    ///   resets the request's stream once it elapses, the server then drops the
+    ///   handler's future and cancels the token.
+    /// - The server's read and write timeouts apply to stalled connections rather than
+    ///   individual requests, closing the connection drops every handler on it.
+    /// - The soft deadline cancels the token of this request only and leaves the handler
+    ///   running so it can unwind, i.e. replying with a partial result or an error.
+    ///
+    /// Whichever fires first takes effect, a soft deadline later than the client's
+    /// timeout therefore never fires for a client which sets one.
+    pub fn set_soft_deadline(&self, deadline: Duration) {
+        let cancellation = self.cancellation.clone();
+        let completed = self.completed.clone();
+
+        crate::runtime::spawn(async move {
+            let elapsed = completed
+                .run_until_cancelled_owned(crate::runtime::sleep(deadline))
+                .await;
+            if elapsed.is_some() {
+                cancellation.cancel();
+            }
+        });
+    }
+}
+
+impl<Msg> Request<Msg>
+where
+    Msg: RequestContents<Content = DataView<Msg>> + Archive,
+    Msg::Archived: 'static,
+{
+    #[inline]
+    /// The archived value of the message.
+    ///
+    /// This reads directly from the request buffer without deserializing,
+    /// allowing fields to be accessed without going through [DataView] or
+    /// producing an owned copy with [DataView::to_owned].
+    ///
+    /// ```rust
+    /// use rkyv::{Archive, Deserialize, Serialize};
+    /// use datacake_rpc::{Handler, Request, RpcService, ServiceRegistry, Status};
+    ///
+    /// #[repr(C)]
+    /// #[derive(Serialize, Deserialize, Archive)]
+    /// #[archive(check_bytes)]
+    /// pub struct Greet {
+    ///     name: String,
+    /// }
+    ///
+    /// pub struct GreetService;
+    ///
+    /// impl RpcService for GreetService {
+    ///     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+    ///         registry.add_handler::<Greet>();
+    ///     }
+    /// }
+    ///
+    /// #[datacake_rpc::async_trait]
+    /// impl Handler<Greet> for GreetService {
+    ///     type Reply = String;
+    ///
+    ///     async fn on_message(&self, msg: Request<Greet>) -> Result<Self::Reply, Status> {
+    ///         let greet: &ArchivedGreet = msg.archived();
+    ///         Ok(format!("Hello, {}!", greet.name.as_str()))
+    ///     }
+    /// }
+    /// ```
+    pub fn archived(&self) -> &Msg::Archived {
+        &self.view
+    }
+
+    #[inline]
+    /// Returns `true` if the request body had to be copied into an aligned
+    /// buffer when it was received, as it did not arrive as a single chunk
+    /// aligned to [RequestContents::ALIGNMENT].
+    ///
+    /// See [Body::ensure_aligned].
+    pub fn is_realigned(&self) -> bool {
+        self.view.is_realigned()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+/// A header value which could not be parsed, see [Request::header_as].
+pub enum HeaderParseError<E> {
+    #[error("Header value is not visible ASCII: {0}")]
+    /// The value contains characters other than visible ASCII.
+    NotVisibleAscii(#[source] ToStrError),
+    #[error("Header value {value:?} is invalid: {source}")]
+    /// The value could not be parsed as the requested type.
+    Invalid {
+        /// The value of the header.
+        value: String,
+        /// The error returned when parsing the value.
+        source: E,
+    },
+}
+
+fn parse_header<T>(value: &HeaderValue) -> Result<T, HeaderParseError<T::Err>>
+where
+    T: FromStr,
+{
+    let value = value.to_str().map_err(HeaderParseError::NotVisibleAscii)?;
+    value
+        .trim()
+        .parse()
+        .map_err(|source| HeaderParseError::Invalid {
+            value: value.to_string(),
+            source,
+        })
 }
 
 #[cfg(feature = "test-utils")]
@@ -0,0 +1,123 @@
+use std::collections::hash_map::RandomState;
+use std::fmt::{Debug, Formatter};
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use http::header::HeaderName;
+use http::{HeaderMap, HeaderValue};
+
+/// The header the correlation id of a request is read from and echoed in by default.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Extracts the correlation id of a request from its headers.
+type Extractor = dyn Fn(&HeaderMap) -> Option<String> + Send + Sync;
+
+#[derive(Clone)]
+/// Where the server reads the correlation id of each request from.
+///
+/// The correlation id is recorded on the tracing span the request is handled
+/// within and echoed back to the client in a response header. Requests
+/// without an id are assigned a newly generated one.
+///
+/// See [Server::set_request_id_header](crate::Server::set_request_id_header).
+pub enum RequestIdSource {
+    /// Read from the given header and echoed back in the same header.
+    Header(HeaderName),
+    /// Extracted by a custom function, i.e. to derive the id from a `traceparent`,
+    /// and echoed back in the [REQUEST_ID_HEADER].
+    Extractor(Arc<Extractor>),
+}
+
+impl Default for RequestIdSource {
+    fn default() -> Self {
+        Self::Header(HeaderName::from_static(REQUEST_ID_HEADER))
+    }
+}
+
+impl Debug for RequestIdSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Header(name) => f.debug_tuple("Header").field(name).finish(),
+            Self::Extractor(_) => f.debug_tuple("Extractor").finish_non_exhaustive(),
+        }
+    }
+}
+
+impl RequestIdSource {
+    /// Resolves the correlation id of a request, generating one if the request
+    /// does not carry a valid id.
+    ///
+    /// Returns the header the id is echoed in along with the id itself.
+    pub(crate) fn resolve(&self, headers: &HeaderMap) -> (HeaderName, HeaderValue) {
+        let (name, id) = match self {
+            Self::Header(name) => (name.clone(), headers.get(name).cloned()),
+            Self::Extractor(extractor) => (
+                HeaderName::from_static(REQUEST_ID_HEADER),
+                extractor(headers).and_then(|id| HeaderValue::try_from(id).ok()),
+            ),
+        };
+
+        let id = id
+            .filter(|id| !id.is_empty() && id.to_str().is_ok())
+            .unwrap_or_else(generate_request_id);
+        (name, id)
+    }
+}
+
+/// Generates a new random correlation id.
+pub(crate) fn generate_request_id() -> HeaderValue {
+    static COUNTER: AtomicU64 = AtomicU64::new(1);
+
+    let state = RandomState::new();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut high = state.build_hasher();
+    high.write_u64(count);
+    let mut low = state.build_hasher();
+    low.write_u64(!count);
+
+    let id = format!("{:016x}{:016x}", high.finish(), low.finish());
+    HeaderValue::try_from(id).expect("Hex digits are a valid header value")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_header() {
+        let source = RequestIdSource::default();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("abc-123"));
+        let (name, id) = source.resolve(&headers);
+        assert_eq!(name, REQUEST_ID_HEADER);
+        assert_eq!(id, "abc-123");
+
+        let (_, first) = source.resolve(&HeaderMap::new());
+        let (_, second) = source.resolve(&HeaderMap::new());
+        assert_eq!(first.len(), 32);
+        assert_ne!(first, second, "Generated ids should be unique");
+
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static(""));
+        let (_, id) = source.resolve(&headers);
+        assert_eq!(id.len(), 32, "Empty ids should be replaced");
+    }
+
+    #[test]
+    fn test_resolve_extractor() {
+        let source = RequestIdSource::Extractor(Arc::new(|headers: &HeaderMap| {
+            let id = headers.get("x-correlation-id")?.to_str().ok()?;
+            Some(format!("corr-{id}"))
+        }));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-correlation-id", HeaderValue::from_static("42"));
+        let (name, id) = source.resolve(&headers);
+        assert_eq!(name, REQUEST_ID_HEADER);
+        assert_eq!(id, "corr-42");
+
+        let (_, id) = source.resolve(&HeaderMap::new());
+        assert_eq!(id.len(), 32);
+    }
+}
@@ -0,0 +1,62 @@
+/// The default capacity of the buffer messages are serialized into.
+const DEFAULT_BUFFER_CAPACITY: usize = 512;
+
+#[derive(Debug, Clone)]
+/// Configuration of how a service's messages are (de)serialized.
+///
+/// Each service can be given its own configuration when being added to the
+/// [Server](crate::Server) via [Server::add_service_with_config](crate::Server::add_service_with_config),
+/// allowing services with very different message shapes to be tuned independently.
+///
+/// The default configuration matches the behaviour of [Server::add_service](crate::Server::add_service),
+/// configurations are created from it by setting its public fields.
+pub struct SerdeConfig {
+    /// The initial capacity of the buffer replies are serialized into.
+    ///
+    /// Services producing large replies can raise this to avoid the buffer
+    /// being repeatedly reallocated while serializing.
+    pub buffer_capacity: usize,
+    /// The maximum number of bytes the serializer may allocate for scratch space
+    /// once the built-in stack and heap scratch buffers are exhausted.
+    ///
+    /// Exceeding this limit causes serialization to fail with an error.
+    /// `None` places no limit on the scratch space.
+    pub scratch_limit: Option<usize>,
+    /// If the checksum of inbound messages should be verified before
+    /// they are viewed, see [SerdeConfig::skip_checksum].
+    pub(crate) verify_checksum: bool,
+}
+
+impl SerdeConfig {
+    /// Skips verifying the checksum of inbound messages before they are viewed.
+    ///
+    /// This removes a full pass over each message buffer which can be
+    /// pure overhead on trusted links, i.e. over mTLS within a datacenter.
+    ///
+    /// # Safety
+    ///
+    /// **Messages are viewed without any validation.** A corrupted or
+    /// malicious message will be viewed as if it were valid which is
+    /// undefined behaviour and can lead to memory corruption or crashes.
+    /// This must only be used when the peer and the link to it are trusted.
+    pub unsafe fn skip_checksum(mut self) -> Self {
+        self.verify_checksum = false;
+        self
+    }
+
+    /// Returns if the checksum of inbound messages is verified before
+    /// they are viewed.
+    pub fn verifies_checksum(&self) -> bool {
+        self.verify_checksum
+    }
+}
+
+impl Default for SerdeConfig {
+    fn default() -> Self {
+        Self {
+            buffer_capacity: DEFAULT_BUFFER_CAPACITY,
+            scratch_limit: None,
+            verify_checksum: true,
+        }
+    }
+}
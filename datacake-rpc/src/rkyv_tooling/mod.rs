@@ -1,19 +1,45 @@
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Instant;
+
+use bytes::Bytes;
+use parking_lot::Mutex;
 use rkyv::ser::serializers::{
-    AlignedSerializer,
     CompositeSerializer,
+    CompositeSerializerError,
     SharedSerializeMap,
+    SharedSerializeMapError,
 };
-use rkyv::ser::Serializer;
+use rkyv::ser::{ScratchSpace, Serializer};
 use rkyv::{AlignedVec, Fallible, Serialize};
 
+mod config;
+#[cfg(feature = "test-utils")]
+mod roundtrip;
 mod scratch;
+mod serializer;
 mod view;
 
-use self::scratch::LazyScratch;
+pub use self::config::SerdeConfig;
+#[cfg(feature = "test-utils")]
+pub use self::roundtrip::{test_roundtrip, RoundtripError};
+pub use self::scratch::LazyScratch;
+use self::serializer::{BufferSerializer, DeadlineExceeded};
 pub use self::view::{DataView, InvalidView};
+use crate::Status;
+
+/// The serializer used for messages, using the scratch space `S`.
+///
+/// Messages deriving rkyv's `Serialize` can be serialized with any scratch
+/// space, i.e. a fixed size [BufferScratch](rkyv::ser::serializers::BufferScratch)
+/// which never allocates and fails once it runs out of space.
+pub type ScratchSerializer<S> =
+    CompositeSerializer<BufferSerializer, S, SharedSerializeMap>;
 
-pub(crate) type DatacakeSerializer =
-    CompositeSerializer<AlignedSerializer<AlignedVec>, LazyScratch, SharedSerializeMap>;
+pub(crate) type DatacakeSerializer = ScratchSerializer<LazyScratch>;
+
+type SerializeError<C> =
+    CompositeSerializerError<DeadlineExceeded, C, SharedSerializeMapError>;
 
 #[inline]
 /// Produces an aligned buffer of the serialized data with a CRC32 checksum attached
@@ -24,9 +50,79 @@ pub fn to_view_bytes<T>(
 where
     T: Serialize<DatacakeSerializer>,
 {
-    let mut serializer = DatacakeSerializer::new(
-        AlignedSerializer::new(AlignedVec::with_capacity(512)),
+    to_view_bytes_with_config(value, &SerdeConfig::default())
+}
+
+#[inline]
+/// Produces an aligned buffer of the serialized data with a CRC32 checksum attached
+/// to the last 4 bytes of the buffer, using the provided scratch space.
+///
+/// This allows controlling the allocations made while serializing, i.e. with
+/// a fixed size [BufferScratch](rkyv::ser::serializers::BufferScratch)
+/// serialization fails rather than allocating if the message needs more scratch
+/// space than is available. The output buffer is still allocated as normal.
+pub fn to_view_bytes_with_scratch<T, S>(
+    value: &T,
+    scratch: S,
+) -> Result<AlignedVec, <ScratchSerializer<S> as Fallible>::Error>
+where
+    T: Serialize<ScratchSerializer<S>>,
+    S: ScratchSpace,
+{
+    serialize_view(value, &SerdeConfig::default(), scratch, None)
+}
+
+#[inline]
+/// Produces an aligned buffer of the serialized data with a CRC32 checksum attached
+/// to the last 4 bytes of the buffer using the provided [SerdeConfig].
+pub(crate) fn to_view_bytes_with_config<T>(
+    value: &T,
+    config: &SerdeConfig,
+) -> Result<AlignedVec, <DatacakeSerializer as Fallible>::Error>
+where
+    T: Serialize<DatacakeSerializer>,
+{
+    let scratch = LazyScratch::with_alloc_limit(config.scratch_limit);
+    serialize_view(value, config, scratch, None)
+}
+
+#[inline]
+/// Produces an aligned buffer of the serialized data with a CRC32 checksum attached
+/// to the last 4 bytes of the buffer, giving up once the deadline has passed.
+///
+/// The deadline is checked periodically as the data is written, any partially
+/// serialized data is discarded if it passes.
+pub(crate) fn to_view_bytes_before<T>(
+    value: &T,
+    deadline: Instant,
+) -> Result<AlignedVec, <DatacakeSerializer as Fallible>::Error>
+where
+    T: Serialize<DatacakeSerializer>,
+{
+    serialize_view(
+        value,
+        &SerdeConfig::default(),
         LazyScratch::default(),
+        Some(deadline),
+    )
+}
+
+fn serialize_view<T, S>(
+    value: &T,
+    config: &SerdeConfig,
+    scratch: S,
+    deadline: Option<Instant>,
+) -> Result<AlignedVec, <ScratchSerializer<S> as Fallible>::Error>
+where
+    T: Serialize<ScratchSerializer<S>>,
+    S: ScratchSpace,
+{
+    let mut serializer = ScratchSerializer::new(
+        BufferSerializer::new(
+            AlignedVec::with_capacity(config.buffer_capacity),
+            deadline,
+        ),
+        scratch,
         SharedSerializeMap::new(),
     );
 
@@ -41,6 +137,100 @@ where
     Ok(buffer)
 }
 
+/// Converts a serialization error into the status returned to the caller.
+pub(crate) fn serialize_error_status<C: Display>(error: SerializeError<C>) -> Status {
+    match error {
+        CompositeSerializerError::SerializerError(DeadlineExceeded) => Status::timeout(),
+        error => Status::internal(error.to_string()),
+    }
+}
+
+#[derive(Debug, Default)]
+/// The output buffer and scratch space of the serializer, kept between
+/// serializations so their allocations can be reused.
+pub(crate) struct SerializeBuffers<S = LazyScratch> {
+    /// The output buffer, returned here once the body it was lent to is dropped.
+    buffer: Arc<Mutex<AlignedVec>>,
+    scratch: S,
+}
+
+impl<S> SerializeBuffers<S>
+where
+    S: ScratchSpace + Default,
+{
+    /// Creates new buffers serializing with the provided scratch space.
+    pub(crate) fn with_scratch(scratch: S) -> Self {
+        Self {
+            buffer: Arc::default(),
+            scratch,
+        }
+    }
+
+    /// Serializes the value into the reused buffer with a CRC32 checksum
+    /// attached to the last 4 bytes.
+    ///
+    /// The buffer is lent to the returned bytes without being copied, and
+    /// reused by the next serialization once they have been dropped. If they
+    /// are still alive by then, a new buffer is allocated instead.
+    ///
+    /// If a deadline is given, serialization gives up once it has passed.
+    pub(crate) fn serialize<T>(
+        &mut self,
+        value: &T,
+        deadline: Option<Instant>,
+    ) -> Result<Bytes, <ScratchSerializer<S> as Fallible>::Error>
+    where
+        T: Serialize<ScratchSerializer<S>>,
+    {
+        let mut buffer = std::mem::take(&mut *self.buffer.lock());
+        buffer.clear();
+
+        let mut serializer = ScratchSerializer::new(
+            BufferSerializer::new(buffer, deadline),
+            std::mem::take(&mut self.scratch),
+            SharedSerializeMap::new(),
+        );
+        let result = serializer.serialize_value(value);
+
+        let (serializer, scratch, _) = serializer.into_components();
+        let mut buffer = serializer.into_inner();
+        // A failed serialization can leave the scratch space partially
+        // allocated, so it is only kept once it has been fully released.
+        if let Err(error) = result {
+            *self.buffer.lock() = buffer;
+            return Err(error);
+        }
+        self.scratch = scratch;
+
+        let checksum = crc32fast::hash(&buffer);
+        buffer.extend_from_slice(&checksum.to_le_bytes());
+
+        Ok(Bytes::from_owner(LentBuffer {
+            buffer,
+            home: self.buffer.clone(),
+        }))
+    }
+}
+
+/// A serialized buffer lent to a body, which is handed back to its
+/// [SerializeBuffers] when dropped.
+struct LentBuffer {
+    buffer: AlignedVec,
+    home: Arc<Mutex<AlignedVec>>,
+}
+
+impl AsRef<[u8]> for LentBuffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl Drop for LentBuffer {
+    fn drop(&mut self) {
+        *self.home.lock() = std::mem::take(&mut self.buffer);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -116,4 +306,112 @@ mod tests {
 
         assert_eq!(expected_checksum, actual_checksum, "Checksums should match");
     }
+
+    #[test]
+    fn test_scratch_limit_serialize() {
+        let val = AllocatedSize {
+            a: 123,
+            b: 1.23,
+            c: (0..10_000).map(|i| (i.to_string(), i)).collect(),
+            buf: vec![4; 10],
+        };
+
+        let config = SerdeConfig {
+            scratch_limit: Some(0),
+            ..Default::default()
+        };
+        to_view_bytes_with_config(&val, &config)
+            .expect_err("Serializer should exceed the scratch limit");
+
+        to_view_bytes_with_config(&val, &SerdeConfig::default())
+            .expect("Serializer should not be limited by default");
+    }
+
+    #[test]
+    fn test_fixed_scratch_serialize() {
+        use rkyv::ser::serializers::BufferScratch;
+        use rkyv::AlignedBytes;
+
+        let val = AllocatedSize {
+            a: 123,
+            b: 1.23,
+            c: (0..1_000).map(|i| (i.to_string(), i)).collect(),
+            buf: vec![4; 10],
+        };
+
+        let scratch = BufferScratch::<AlignedBytes<256>>::default();
+        to_view_bytes_with_scratch(&val, scratch)
+            .expect_err("Serializer should run out of fixed scratch space");
+
+        let scratch = BufferScratch::<AlignedBytes<{ 64 << 10 }>>::default();
+        let buffer =
+            to_view_bytes_with_scratch(&val, scratch).expect("Serialize struct");
+        assert_eq!(buffer.len(), to_view_bytes(&val).unwrap().len());
+    }
+
+    #[test]
+    fn test_deadline_serialize() {
+        let val = AllocatedSize {
+            a: 123,
+            b: 1.23,
+            c: HashMap::new(),
+            buf: vec![4; 4 << 20],
+        };
+
+        let error = to_view_bytes_before(&val, Instant::now())
+            .expect_err("Serializer should give up once the deadline passed");
+        assert!(matches!(
+            error,
+            CompositeSerializerError::SerializerError(DeadlineExceeded)
+        ));
+        assert_eq!(serialize_error_status(error), Status::timeout());
+
+        let deadline = Instant::now() + std::time::Duration::from_secs(60);
+        let buffer = to_view_bytes_before(&val, deadline).expect("Serialize struct");
+        assert_eq!(buffer.as_slice(), to_view_bytes(&val).unwrap().as_slice());
+    }
+
+    #[test]
+    fn test_reused_buffers_serialize() {
+        let mut buffers = SerializeBuffers::<LazyScratch>::default();
+
+        let mut previous_ptr = None;
+        for n in 0..3 {
+            let val = AllocatedSize {
+                a: n,
+                b: 1.23,
+                c: HashMap::new(),
+                buf: vec![4; 10],
+            };
+
+            let expected = to_view_bytes(&val).expect("Serialize struct");
+            let buffer = buffers.serialize(&val, None).expect("Serialize struct");
+            assert_eq!(buffer, expected.as_slice(), "Buffers should match");
+
+            if let Some(ptr) = previous_ptr {
+                assert_eq!(buffer.as_ptr(), ptr, "Allocation should be reused");
+            }
+            previous_ptr = Some(buffer.as_ptr());
+        }
+    }
+
+    #[test]
+    fn test_lent_buffers_serialize() {
+        let mut buffers = SerializeBuffers::<LazyScratch>::default();
+
+        let first = buffers.serialize(&0u64, None).expect("Serialize value");
+        let second = buffers.serialize(&1u64, None).expect("Serialize value");
+        assert_ne!(
+            first.as_ptr(),
+            second.as_ptr(),
+            "Buffers still in use should not be overwritten"
+        );
+        assert_eq!(first, to_view_bytes(&0u64).unwrap().as_slice());
+
+        let ptr = second.as_ptr();
+        drop(first);
+        drop(second);
+        let third = buffers.serialize(&2u64, None).expect("Serialize value");
+        assert_eq!(third.as_ptr(), ptr, "Dropped buffers should be recovered");
+    }
 }
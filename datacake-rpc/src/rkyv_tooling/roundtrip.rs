@@ -0,0 +1,89 @@
+use std::fmt::Debug;
+
+use rkyv::de::deserializers::SharedDeserializeMap;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{Archive, CheckBytes, Deserialize, Serialize};
+
+use super::{to_view_bytes, DataView, DatacakeSerializer};
+
+#[derive(Debug, thiserror::Error)]
+/// The reason a value failed to round-trip through the wire format.
+///
+/// See [test_roundtrip].
+pub enum RoundtripError {
+    #[error("Failed to serialize the value: {0}")]
+    /// The value could not be serialized.
+    Serialize(String),
+    #[error(
+        "The serialized value failed validation: {0}. \
+         Check the type and all of its fields are `#[repr(C)]`."
+    )]
+    /// The serialized bytes did not pass `CheckBytes` validation.
+    Validation(String),
+    #[error("A view could not be created from the serialized value.")]
+    /// The serialized bytes could not be turned into a [DataView].
+    View,
+    #[error("Failed to deserialize the archived value.")]
+    /// The archived value could not be deserialized back into the type.
+    Deserialize,
+    #[error(
+        "The deserialized value does not match the original, \
+         expected {expected} but got {actual}"
+    )]
+    /// The deserialized value is not equal to the original value.
+    Mismatch {
+        /// The debug representation of the original value.
+        expected: String,
+        /// The debug representation of the deserialized value.
+        actual: String,
+    },
+}
+
+/// Checks that a value survives a round-trip through the wire format.
+///
+/// The value is serialized via [to_view_bytes], validated via `CheckBytes`,
+/// deserialized and compared against the original. This catches layout
+/// mistakes in message types without needing to run a server, for example:
+///
+/// ```rust
+/// use rkyv::{Archive, Deserialize, Serialize};
+///
+/// #[repr(C)]
+/// #[derive(Serialize, Deserialize, Archive, PartialEq, Debug)]
+/// #[archive(check_bytes)]
+/// pub struct MyMessage {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// let msg = MyMessage { name: "Bobby".to_string(), age: 12 };
+/// datacake_rpc::test_roundtrip(&msg).expect("Message should be wire-safe");
+/// ```
+///
+/// Types missing `#[archive(check_bytes)]` will fail to compile.
+pub fn test_roundtrip<T>(value: &T) -> Result<(), RoundtripError>
+where
+    T: Archive + Serialize<DatacakeSerializer> + PartialEq + Debug,
+    T::Archived: for<'a> CheckBytes<DefaultValidator<'a>>
+        + Deserialize<T, SharedDeserializeMap>
+        + 'static,
+{
+    let bytes =
+        to_view_bytes(value).map_err(|e| RoundtripError::Serialize(e.to_string()))?;
+
+    let data = &bytes[..bytes.len() - 4];
+    rkyv::check_archived_root::<T>(data)
+        .map_err(|e| RoundtripError::Validation(e.to_string()))?;
+
+    let view = DataView::<T>::using(bytes).map_err(|_| RoundtripError::View)?;
+    let copy = view.to_owned().map_err(|_| RoundtripError::Deserialize)?;
+
+    if &copy != value {
+        return Err(RoundtripError::Mismatch {
+            expected: format!("{value:?}"),
+            actual: format!("{copy:?}"),
+        });
+    }
+
+    Ok(())
+}
@@ -28,6 +28,23 @@ impl Default for LazyScratch {
     }
 }
 
+impl LazyScratch {
+    /// Creates a new scratch space with an optional limit on the number of bytes
+    /// the fallback alloc scratch can allocate.
+    pub fn with_alloc_limit(limit: Option<usize>) -> Self {
+        let alloc_scratch = match limit {
+            None => AllocScratch::default(),
+            Some(limit) => AllocScratch::with_limit(limit),
+        };
+
+        Self {
+            stack_scratch: StackScratch::default(),
+            heap_scratch: None,
+            alloc_scratch,
+        }
+    }
+}
+
 impl Fallible for LazyScratch {
     type Error = <AllocScratch as Fallible>::Error;
 }
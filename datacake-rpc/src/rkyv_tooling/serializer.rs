@@ -0,0 +1,81 @@
+use std::time::Instant;
+
+use rkyv::ser::serializers::AlignedSerializer;
+use rkyv::ser::Serializer;
+use rkyv::{AlignedVec, Fallible};
+
+/// The number of bytes written between each check of the deadline.
+const DEADLINE_CHECK_INTERVAL: usize = 256 << 10;
+
+#[derive(Debug, thiserror::Error)]
+#[error("Serialization did not complete before the deadline")]
+/// Serialization was abandoned as the deadline passed before it completed.
+pub struct DeadlineExceeded;
+
+#[derive(Debug, Default)]
+/// Writes serialized data into an aligned buffer, giving up once the
+/// deadline has passed if one is set.
+///
+/// Without a deadline this behaves exactly as an [AlignedSerializer].
+pub struct BufferSerializer {
+    inner: AlignedSerializer<AlignedVec>,
+    deadline: Option<Instant>,
+    next_check: usize,
+}
+
+impl BufferSerializer {
+    /// Creates a new serializer writing into the given buffer.
+    pub fn new(buffer: AlignedVec, deadline: Option<Instant>) -> Self {
+        Self {
+            next_check: buffer.len(),
+            inner: AlignedSerializer::new(buffer),
+            deadline,
+        }
+    }
+
+    #[inline]
+    /// Consumes the serializer returning the buffer.
+    pub fn into_inner(self) -> AlignedVec {
+        self.inner.into_inner()
+    }
+
+    fn check_deadline(&mut self, deadline: Instant) -> Result<(), DeadlineExceeded> {
+        let pos = self.inner.pos();
+        if pos < self.next_check {
+            return Ok(());
+        }
+
+        if Instant::now() >= deadline {
+            return Err(DeadlineExceeded);
+        }
+        self.next_check = pos + DEADLINE_CHECK_INTERVAL;
+        Ok(())
+    }
+}
+
+impl Fallible for BufferSerializer {
+    type Error = DeadlineExceeded;
+}
+
+impl Serializer for BufferSerializer {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.inner.pos()
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        let Some(deadline) = self.deadline else {
+            return self.inner.write(bytes).map_err(|e| match e {});
+        };
+
+        // Large fields are written in chunks so the deadline is still
+        // checked while copying them.
+        for chunk in bytes.chunks(DEADLINE_CHECK_INTERVAL) {
+            self.check_deadline(deadline)?;
+            self.inner.write(chunk).map_err(|e| match e {})?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,9 @@
+use std::any::TypeId;
 use std::fmt::{Debug, Formatter};
 use std::mem;
 use std::ops::Deref;
 
+use bytes::Bytes;
 use rkyv::de::deserializers::SharedDeserializeMap;
 use rkyv::{AlignedVec, Archive, Deserialize};
 
@@ -16,6 +18,21 @@ pub struct InvalidView;
 ///
 /// This allows for safe, true zero-copy deserialization avoiding unnecessary
 /// allocations if the situation does not require having an owned version of the value.
+///
+/// # Copies
+///
+/// When a message or reply is received, its body is only copied into an aligned
+/// buffer if it did not arrive as a single chunk aligned to [AlignedVec::ALIGNMENT],
+/// this is required for the archived value to be correctly aligned, see
+/// [Body::ensure_aligned](crate::Body::ensure_aligned). From then on, accessing the
+/// view reads directly from that buffer without any deserialization, see
+/// [DataView::is_zero_copy].
+///
+/// A copy only happens when explicitly requested:
+///
+/// - [DataView::to_owned] deserializes the view into a new owned value.
+/// - Cloning the view copies the backing buffer, unless it is a shared [Bytes] buffer.
+/// - [DataView::into_data] copies a shared [Bytes] buffer into an [AlignedVec].
 pub struct DataView<T>
 where
     T: Archive,
@@ -26,7 +43,27 @@ where
     /// The owned buffer itself.
     ///
     /// This must live as long as the view derived from it.
-    data: AlignedVec,
+    data: ViewData,
+    /// If the data was copied to realign it when it was received.
+    realigned: bool,
+}
+
+#[derive(Clone)]
+/// The buffer backing a [DataView].
+enum ViewData {
+    Aligned(AlignedVec),
+    Shared(Bytes),
+}
+
+impl Deref for ViewData {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Aligned(data) => data.as_slice(),
+            Self::Shared(data) => data,
+        }
+    }
 }
 
 impl<T> DataView<T>
@@ -36,32 +73,119 @@ where
 {
     /// Creates a new view using a provided buffer.
     pub fn using(data: AlignedVec) -> Result<Self, InvalidView> {
+        Self::using_with(data, true)
+    }
+
+    /// Creates a new view using a provided buffer, optionally skipping
+    /// the verification of the buffer's checksum.
+    pub(crate) fn using_with(
+        data: AlignedVec,
+        verify_checksum: bool,
+    ) -> Result<Self, InvalidView> {
+        Self::from_data(ViewData::Aligned(data), verify_checksum, false)
+    }
+
+    /// Creates a new view sharing the provided buffer, optionally skipping
+    /// the verification of the buffer's checksum.
+    ///
+    /// The buffer must start at an address aligned to [AlignedVec::ALIGNMENT],
+    /// as the serializer aligns every value relative to the start of the buffer.
+    /// `realigned` records if the buffer had to be copied to align it.
+    pub(crate) fn using_bytes(
+        data: Bytes,
+        verify_checksum: bool,
+        realigned: bool,
+    ) -> Result<Self, InvalidView> {
+        if !(data.as_ptr() as usize).is_multiple_of(AlignedVec::ALIGNMENT) {
+            return Err(InvalidView);
+        }
+
+        Self::from_data(ViewData::Shared(data), verify_checksum, realigned)
+    }
+
+    fn from_data(
+        data: ViewData,
+        verify_checksum: bool,
+        realigned: bool,
+    ) -> Result<Self, InvalidView> {
         // SAFETY:
         //  This is safe as we own the data and keep it apart
-        //  of the view itself.
-        let extended_buf =
-            unsafe { mem::transmute::<&[u8], &'static [u8]>(data.as_slice()) };
+        //  of the view itself, the contents of both an `AlignedVec`
+        //  and `Bytes` do not move when they do.
+        let extended_buf = unsafe { mem::transmute::<&[u8], &'static [u8]>(&data) };
 
-        if extended_buf.len() < 4 {
+        // Checked before the checksum as it is far cheaper to reject.
+        if !Self::is_valid_len(extended_buf.len()) {
             return Err(InvalidView);
         }
 
         let end = extended_buf.len();
-        let checksum_bytes = extended_buf[end - 4..]
-            .try_into()
-            .map_err(|_| InvalidView)?;
-        let expected_checksum = u32::from_le_bytes(checksum_bytes);
-
         let data_bytes = &extended_buf[..end - 4];
-        let actual_checksum = crc32fast::hash(data_bytes);
 
-        if expected_checksum != actual_checksum {
+        if verify_checksum {
+            let checksum_bytes = extended_buf[end - 4..]
+                .try_into()
+                .map_err(|_| InvalidView)?;
+            let expected_checksum = u32::from_le_bytes(checksum_bytes);
+            let actual_checksum = crc32fast::hash(data_bytes);
+
+            if expected_checksum != actual_checksum {
+                return Err(InvalidView);
+            }
+        }
+
+        // The root is stored at the end of the data, so the data must be large
+        // enough to hold it and leave it correctly aligned.
+        let root_size = mem::size_of::<rkyv::Archived<T>>();
+        let root_pos = data_bytes.len().checked_sub(root_size).ok_or(InvalidView)?;
+        let root_addr = data_bytes.as_ptr() as usize + root_pos;
+        if !root_addr.is_multiple_of(mem::align_of::<rkyv::Archived<T>>()) {
             return Err(InvalidView);
         }
 
         let view = unsafe { rkyv::archived_root::<T>(data_bytes) };
 
-        Ok(Self { data, view })
+        Ok(Self {
+            data,
+            view,
+            realigned,
+        })
+    }
+
+    /// Creates a view of the unit value without any backing data.
+    ///
+    /// This is used for messages sent as an empty body, returning `None`
+    /// if `T` is not archived as `()`. Other zero-sized types are not
+    /// viewed this way, as they may be uninhabited.
+    pub(crate) fn unit() -> Option<Self> {
+        if TypeId::of::<rkyv::Archived<T>>() != TypeId::of::<()>() {
+            return None;
+        }
+
+        let unit: &'static () = &();
+        // SAFETY:
+        //  The archived type of `T` is `()`, so this is the same reference.
+        let view = unsafe { &*(unit as *const () as *const rkyv::Archived<T>) };
+
+        Some(Self {
+            data: ViewData::Aligned(AlignedVec::new()),
+            view,
+            realigned: false,
+        })
+    }
+
+    /// Returns if a buffer of `len` bytes, including the trailing checksum,
+    /// is able to hold a view of `T`.
+    ///
+    /// The root is stored at the end of the data, so the data must be large
+    /// enough to hold it and leave it correctly aligned. This only depends on the
+    /// length, so a body can be rejected from its declared length before it is read.
+    pub(crate) fn is_valid_len(len: usize) -> bool {
+        let root_size = mem::size_of::<rkyv::Archived<T>>();
+        let Some(root_pos) = len.checked_sub(4 + root_size) else {
+            return false;
+        };
+        root_pos.is_multiple_of(mem::align_of::<rkyv::Archived<T>>())
     }
 
     #[inline]
@@ -70,10 +194,47 @@ where
         &self.data
     }
 
-    #[inline]
     /// Consumes the bytes representation of the dataview.
+    ///
+    /// If the view shares a [Bytes] buffer received off the wire, the data
+    /// is copied into a new [AlignedVec].
     pub fn into_data(self) -> AlignedVec {
-        self.data
+        match self.data {
+            ViewData::Aligned(data) => data,
+            ViewData::Shared(data) => {
+                let mut vec = AlignedVec::with_capacity(data.len());
+                vec.extend_from_slice(&data);
+                vec
+            },
+        }
+    }
+
+    #[inline]
+    /// The length of the backing buffer in bytes, including the trailing checksum.
+    pub fn buffer_len(&self) -> usize {
+        self.data.len()
+    }
+
+    #[inline]
+    /// Returns `true` if the data was copied into an aligned buffer when it was
+    /// received, as it did not arrive as a single aligned chunk.
+    ///
+    /// See [Body::ensure_aligned](crate::Body::ensure_aligned).
+    pub fn is_realigned(&self) -> bool {
+        self.realigned
+    }
+
+    /// Returns `true` if the view reads the archived value directly from
+    /// its backing buffer rather than from a copy.
+    ///
+    /// This checks the archived value is located within the buffer, so can be
+    /// used to confirm replies are not being silently copied, i.e. in benchmarks.
+    pub fn is_zero_copy(&self) -> bool {
+        let buffer = self.data.as_ptr_range();
+        let view = self.view as *const rkyv::Archived<T> as *const u8;
+        let view_end = view.wrapping_add(mem::size_of::<rkyv::Archived<T>>());
+
+        buffer.start <= view && view_end <= buffer.end
     }
 }
 
@@ -97,7 +258,28 @@ where
     T::Archived: Debug + 'static,
 {
     fn clone(&self) -> Self {
-        Self::using(self.data.clone()).expect("BUG: Valid data has become invalid?")
+        let data = self.data.clone();
+
+        // The view is already known to be valid, so rather than checking the
+        // copy again the view is moved to the same offset within it. Views of
+        // the unit value have no data and are kept as-is.
+        let view = if self.data.is_empty() {
+            self.view
+        } else {
+            let offset = self.view as *const rkyv::Archived<T> as usize
+                - self.data.as_ptr() as usize;
+            // SAFETY:
+            //  The copy holds the same bytes at an address with the same
+            //  alignment, as both are aligned to `AlignedVec::ALIGNMENT`, and
+            //  like in `from_data` the contents do not move along with `data`.
+            unsafe { &*(data.as_ptr().add(offset) as *const rkyv::Archived<T>) }
+        };
+
+        Self {
+            data,
+            view,
+            realigned: self.realigned,
+        }
     }
 }
 
@@ -165,8 +347,11 @@ mod tests {
         };
 
         let bytes = crate::rkyv_tooling::to_view_bytes(&demo).unwrap();
+        let len = bytes.len();
         let view: DataView<Demo> = DataView::using(bytes).unwrap();
         assert!(view == demo, "Original and view must match.");
+        assert!(view.is_zero_copy(), "View should read from its buffer.");
+        assert_eq!(view.buffer_len(), len);
     }
 
     #[test]
@@ -180,6 +365,62 @@ mod tests {
         DataView::<Demo>::using(bytes).expect_err("System should return invalid view.");
     }
 
+    #[test]
+    fn test_view_skip_checksum() {
+        let demo = Demo {
+            a: "Jello".to_string(),
+            b: 133,
+        };
+
+        let mut bytes = crate::rkyv_tooling::to_view_bytes(&demo).unwrap();
+        let end = bytes.len();
+        bytes[end - 1] ^= 0xFF;
+
+        DataView::<Demo>::using(bytes.clone())
+            .expect_err("System should reject the corrupted checksum.");
+        let view = DataView::<Demo>::using_with(bytes, false)
+            .expect("System should skip validating the checksum.");
+        assert!(view == demo, "Original and view must match.");
+    }
+
+    #[test]
+    fn test_clone_view() {
+        let demo = Demo {
+            a: "Jello".to_string(),
+            b: 133,
+        };
+
+        let mut bytes = crate::rkyv_tooling::to_view_bytes(&demo).unwrap();
+        let end = bytes.len();
+        bytes[end - 1] ^= 0xFF;
+
+        // The corrupted checksum is not checked again when cloning.
+        let view = DataView::<Demo>::using_with(bytes, false).unwrap();
+        let cloned = view.clone();
+        drop(view);
+        assert!(cloned == demo, "Original and clone must match.");
+        assert!(
+            cloned.is_zero_copy(),
+            "Clone should read from its own buffer."
+        );
+    }
+
+    #[test]
+    fn test_unit_view() {
+        #[derive(Archive)]
+        struct Marker;
+
+        let view = DataView::<()>::unit().expect("The unit type should be viewable");
+        assert_eq!(view.buffer_len(), 0);
+        assert_eq!(*view.clone(), ());
+
+        assert!(
+            DataView::<Marker>::unit().is_none(),
+            "Only the unit type should be viewed without data"
+        );
+        assert!(DataView::<Demo>::unit().is_none());
+    }
+
     #[test]
     fn test_invalid_view() {
         let mut data = AlignedVec::new();
@@ -188,6 +429,79 @@ mod tests {
         assert!(res.is_err(), "View should be rejected");
     }
 
+    #[test]
+    fn test_view_too_short_for_root() {
+        // A valid checksum over data which cannot contain the archived root.
+        for len in [0, 3, 8] {
+            let data = vec![0; len];
+            let mut bytes = AlignedVec::new();
+            bytes.extend_from_slice(&data);
+            bytes.extend_from_slice(&crc32fast::hash(&data).to_le_bytes());
+
+            DataView::<Demo>::using(bytes.clone()).expect_err("View should be rejected");
+            DataView::<Demo>::using_with(bytes, false)
+                .expect_err("View should be rejected without the checksum");
+        }
+    }
+
+    #[test]
+    fn test_valid_len() {
+        let demo = Demo {
+            a: "Jello".to_string(),
+            b: 133,
+        };
+        let bytes = crate::rkyv_tooling::to_view_bytes(&demo).unwrap();
+        assert!(DataView::<Demo>::is_valid_len(bytes.len()));
+
+        let root_size = mem::size_of::<rkyv::Archived<Demo>>();
+        assert!(DataView::<Demo>::is_valid_len(root_size + 4));
+        assert!(!DataView::<Demo>::is_valid_len(root_size + 3));
+        assert!(!DataView::<Demo>::is_valid_len(bytes.len() + 1));
+        assert!(!DataView::<Demo>::is_valid_len(0));
+    }
+
+    #[test]
+    fn test_view_misaligned_root() {
+        let demo = Demo {
+            a: "Jello".to_string(),
+            b: 133,
+        };
+
+        let bytes = crate::rkyv_tooling::to_view_bytes(&demo).unwrap();
+        let mut data = bytes[..bytes.len() - 4].to_vec();
+        data.push(0);
+
+        let mut shifted = AlignedVec::new();
+        shifted.extend_from_slice(&data);
+        shifted.extend_from_slice(&crc32fast::hash(&data).to_le_bytes());
+        DataView::<Demo>::using(shifted).expect_err("View should be rejected");
+    }
+
+    #[test]
+    fn test_view_shared_bytes() {
+        let demo = Demo {
+            a: "Jello".to_string(),
+            b: 133,
+        };
+
+        let bytes =
+            Bytes::from_owner(crate::rkyv_tooling::to_view_bytes(&demo).unwrap());
+        let view = DataView::<Demo>::using_bytes(bytes.clone(), true, false).unwrap();
+        assert!(view == demo, "Original and view must match.");
+        assert!(!view.is_realigned());
+        assert!(view.is_zero_copy(), "View should read from its buffer.");
+        assert_eq!(view.as_bytes().as_ptr(), bytes.as_ptr());
+        assert_eq!(view.clone().as_bytes().as_ptr(), bytes.as_ptr());
+        assert_eq!(view.into_data().as_slice(), &bytes[..]);
+
+        let mut data = AlignedVec::new();
+        data.push(0);
+        data.extend_from_slice(&bytes);
+        let misaligned = Bytes::from_owner(data).slice(1..);
+        DataView::<Demo>::using_bytes(misaligned, true, false)
+            .expect_err("Misaligned buffer should be rejected");
+    }
+
     #[test]
     fn test_deserialize() {
         let demo = Demo {
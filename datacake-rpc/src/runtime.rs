@@ -0,0 +1,230 @@
+//! The async runtime used to spawn tasks and create timers.
+//!
+//! By default all tasks are spawned onto and all timers are driven by
+//! the current [tokio] runtime. An alternative runtime can be installed
+//! once at startup via [set_runtime], before any servers or channels
+//! have been created.
+//!
+//! Note that the network IO itself is still provided by tokio, so a tokio
+//! reactor must be reachable from the installed runtime, i.e. via
+//! `async-compat` or by running a tokio runtime alongside it.
+
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::task::Poll;
+use std::time::Duration;
+
+/// A pinned, boxed future which can be sent across threads.
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send + 'static>>;
+
+static RUNTIME: OnceLock<Arc<dyn Runtime>> = OnceLock::new();
+
+/// The number of calls to [maybe_yield] between each yield.
+const YIELD_BUDGET: u32 = 128;
+
+thread_local! {
+    static REMAINING_BUDGET: Cell<u32> = const { Cell::new(YIELD_BUDGET) };
+}
+
+/// The runtime used to spawn background tasks and create timers.
+pub trait Runtime: Send + Sync + 'static {
+    /// Spawns a new task running the future to completion in the background.
+    fn spawn(&self, future: BoxFuture<()>);
+
+    /// Creates a future which completes once the duration has elapsed.
+    fn sleep(&self, duration: Duration) -> BoxFuture<()>;
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+/// The default runtime using the current [tokio] runtime.
+pub struct TokioRuntime;
+
+impl Runtime for TokioRuntime {
+    fn spawn(&self, future: BoxFuture<()>) {
+        tokio::spawn(future);
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("A runtime has already been set or the default runtime is in use.")]
+/// The runtime could not be set as one is already in use.
+pub struct RuntimeAlreadySet;
+
+/// Sets the runtime used by the RPC system.
+///
+/// This can only be called once and must be called before any servers or
+/// channels are created, otherwise the default [TokioRuntime] is used and
+/// an error is returned.
+pub fn set_runtime(runtime: impl Runtime) -> Result<(), RuntimeAlreadySet> {
+    RUNTIME
+        .set(Arc::new(runtime))
+        .map_err(|_| RuntimeAlreadySet)
+}
+
+fn runtime() -> &'static Arc<dyn Runtime> {
+    RUNTIME.get_or_init(|| Arc::new(TokioRuntime))
+}
+
+/// Spawns the future onto the current runtime.
+pub(crate) fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    runtime().spawn(Box::pin(future))
+}
+
+/// Creates a future which completes once the duration has elapsed.
+pub(crate) fn sleep(duration: Duration) -> BoxFuture<()> {
+    runtime().sleep(duration)
+}
+
+/// Yields back to the runtime once the current work budget is used up.
+///
+/// Each call consumes one unit of the budget and only yields once the budget
+/// has run out, so this is cheap enough to call once per item in a large batch.
+/// This keeps CPU heavy handlers from starving other requests running on the
+/// same runtime:
+///
+/// ```rust
+/// # async fn process(batch: Vec<u64>) -> u64 {
+/// let mut total = 0;
+/// for item in batch {
+///     total += item * item;
+///     datacake_rpc::maybe_yield().await;
+/// }
+/// # total
+/// # }
+/// ```
+///
+/// The budget is tracked per thread and does not depend on the installed
+/// [Runtime], the yielding task is woken up again straight away.
+pub async fn maybe_yield() {
+    let exhausted = REMAINING_BUDGET.with(|budget| {
+        let remaining = budget.get().saturating_sub(1);
+        if remaining == 0 {
+            budget.set(YIELD_BUDGET);
+            true
+        } else {
+            budget.set(remaining);
+            false
+        }
+    });
+
+    if exhausted {
+        yield_now().await;
+    }
+}
+
+/// Yields back to the runtime once, waking the task straight away.
+pub(crate) async fn yield_now() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+    .await
+}
+
+#[derive(Debug)]
+/// The deadline elapsed before the future completed.
+pub(crate) struct Elapsed;
+
+/// Runs the future, failing if it does not complete within the duration.
+pub(crate) async fn timeout<F>(
+    duration: Duration,
+    future: F,
+) -> Result<F::Output, Elapsed>
+where
+    F: Future,
+{
+    let future = std::pin::pin!(future);
+    match select(future, sleep(duration)).await {
+        Either::Left(output) => Ok(output),
+        Either::Right(()) => Err(Elapsed),
+    }
+}
+
+/// The output of whichever future completed first in [select].
+pub(crate) enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+/// Waits for either future to complete, preferring the left future
+/// if both are ready.
+pub(crate) async fn select<L, R>(
+    mut left: L,
+    mut right: R,
+) -> Either<L::Output, R::Output>
+where
+    L: Future + Unpin,
+    R: Future + Unpin,
+{
+    std::future::poll_fn(|cx| {
+        if let Poll::Ready(output) = Pin::new(&mut left).poll(cx) {
+            return Poll::Ready(Either::Left(output));
+        }
+
+        if let Poll::Ready(output) = Pin::new(&mut right).poll(cx) {
+            return Poll::Ready(Either::Right(output));
+        }
+
+        Poll::Pending
+    })
+    .await
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+/// A hyper executor spawning tasks onto the current runtime.
+pub(crate) struct HyperExecutor;
+
+impl<F> hyper::rt::Executor<F> for HyperExecutor
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    fn execute(&self, future: F) {
+        spawn(future)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let result =
+            timeout(Duration::from_millis(50), std::future::pending::<()>()).await;
+        assert!(result.is_err(), "Pending future should time out");
+
+        let result = timeout(Duration::from_secs(5), async { 1 }).await;
+        assert_eq!(result.ok(), Some(1));
+    }
+
+    #[test]
+    fn test_maybe_yield_budget() {
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+
+        let mut yields = 0;
+        let calls = YIELD_BUDGET * 3;
+        for _ in 0..calls {
+            let mut future = std::pin::pin!(maybe_yield());
+            while future.as_mut().poll(&mut cx).is_pending() {
+                yields += 1;
+            }
+        }
+
+        assert_eq!(yields, 3, "Should yield once per exhausted budget");
+    }
+}
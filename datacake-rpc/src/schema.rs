@@ -0,0 +1,97 @@
+use std::mem;
+
+use http::HeaderValue;
+
+use crate::Status;
+
+/// The header carrying the schema hash of the message sent by a client.
+///
+/// See [RpcClient::enable_schema_check](crate::RpcClient::enable_schema_check).
+pub const SCHEMA_HEADER: &str = "x-datacake-schema";
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// The schema hash of a type derived from its layout.
+///
+/// This uses FNV-1a over the size and alignment of the type rather than
+/// the std hasher, so the hash is identical across builds and Rust versions.
+pub(crate) const fn layout_hash<T>() -> u64 {
+    let mut hash = FNV_OFFSET;
+    let size = (mem::size_of::<T>() as u64).to_le_bytes();
+    let align = (mem::align_of::<T>() as u64).to_le_bytes();
+
+    let mut i = 0;
+    while i < 8 {
+        hash = (hash ^ size[i] as u64).wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+    let mut i = 0;
+    while i < 8 {
+        hash = (hash ^ align[i] as u64).wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+
+    hash
+}
+
+/// Encodes a schema hash as a header value.
+pub(crate) fn to_header_value(hash: u64) -> HeaderValue {
+    HeaderValue::from_str(&format!("{hash:016x}")).unwrap()
+}
+
+/// Checks the schema hash sent by the client matches the handler's message.
+pub(crate) fn check_schema(value: &HeaderValue, expected: u64) -> Result<(), Status> {
+    let hash = value
+        .to_str()
+        .ok()
+        .and_then(|value| u64::from_str_radix(value, 16).ok())
+        .ok_or_else(|| Status::invalid_argument("Invalid message schema hash"))?;
+
+    if hash != expected {
+        return Err(Status::invalid_argument(format!(
+            "Message schema mismatch: the client sent {hash:016x} but the server \
+             expects {expected:016x}"
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    struct A {
+        a: u32,
+        b: u64,
+    }
+
+    #[repr(C)]
+    struct B {
+        a: u32,
+        b: u64,
+        c: u32,
+    }
+
+    #[test]
+    fn test_layout_hash() {
+        // The hash must never change for an identical layout.
+        assert_eq!(layout_hash::<A>(), 0x6fd7b482e9cb397d);
+        assert_eq!(layout_hash::<A>(), layout_hash::<[u64; 2]>());
+        assert_ne!(layout_hash::<A>(), layout_hash::<B>());
+    }
+
+    #[test]
+    fn test_check_schema() {
+        let hash = layout_hash::<A>();
+        check_schema(&to_header_value(hash), hash).expect("Schemas should match");
+
+        let status = check_schema(&to_header_value(hash), layout_hash::<B>())
+            .expect_err("Schemas should not match");
+        assert_eq!(status.code, crate::ErrorCode::InvalidPayload);
+        check_schema(&HeaderValue::from_static("nope"), hash)
+            .expect_err("Invalid hashes should be rejected");
+    }
+}
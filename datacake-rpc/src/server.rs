@@ -1,12 +1,41 @@
 use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
 use std::io;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
+use http::{HeaderMap, HeaderName};
 use parking_lot::{Mutex, RwLock};
-use tokio::task::JoinHandle;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
 
-use crate::handler::{HandlerKey, OpaqueMessageHandler, RpcService, ServiceRegistry};
+use crate::admin::{AdminService, InflightInfo};
+use crate::cache::{ReplyCache, ReplyCacheConfig, ReplyCacheStats};
+use crate::capture::{Capture, CaptureConfig, CapturedFrame};
+use crate::handler::{
+    FnHandler,
+    HandlerKey,
+    OpaqueMessageHandler,
+    RpcService,
+    ServiceRegistry,
+};
+use crate::idempotency::{IdempotencyCache, IdempotencyConfig};
+use crate::net::{
+    AcceptRateLimiter,
+    Error,
+    ErrorFraming,
+    ReadBufferBounds,
+    ServerHandle,
+    Status,
+};
+use crate::overload::{OverloadConfig, OverloadDetector};
+use crate::queue::{IncomingRequests, QueueSender};
+use crate::request::RequestContents;
+use crate::request_id::RequestIdSource;
+use crate::transform::{BodyTransform, BodyTransforms};
+use crate::SerdeConfig;
 
 /// A RPC server instance.
 ///
@@ -65,37 +94,809 @@ use crate::handler::{HandlerKey, OpaqueMessageHandler, RpcService, ServiceRegist
 /// ```
 pub struct Server {
     state: ServerState,
-    handle: JoinHandle<()>,
+    handle: ServerHandle,
 }
 
 impl Server {
     /// Spawns the RPC server task and returns the server handle.
+    ///
+    /// This is a convenience wrapper around [Server::try_listen] which
+    /// returns the underlying IO error on failure.
     pub async fn listen(addr: SocketAddr) -> io::Result<Self> {
-        let state = ServerState::default();
+        Self::listen_with_config(addr, &ServerConfig::default()).await
+    }
+
+    /// Spawns the RPC server task using the provided [ServerConfig] and
+    /// returns the server handle.
+    ///
+    /// The same config can be used to start any number of servers, each
+    /// server can then be adjusted individually via its own setters.
+    pub async fn listen_with_config(
+        addr: SocketAddr,
+        config: &ServerConfig,
+    ) -> io::Result<Self> {
+        Self::try_listen_with_config(addr, config)
+            .await
+            .map_err(|e| match e {
+                Error::Bind { source, .. } => source,
+                Error::Io(e) => e,
+                other => io::Error::other(other),
+            })
+    }
+
+    /// Spawns the RPC server task and returns the server handle.
+    ///
+    /// Unlike [Server::listen], a failure to bind is returned as an
+    /// [Error::Bind] which includes the address and a description of the
+    /// cause, i.e. whether the address is already in use or if permission
+    /// was denied. The cause can be inspected via [Error::bind_error_kind].
+    pub async fn try_listen(addr: SocketAddr) -> Result<Self, Error> {
+        Self::try_listen_with_config(addr, &ServerConfig::default()).await
+    }
+
+    /// Spawns the RPC server task using the provided [ServerConfig] and
+    /// returns the server handle.
+    ///
+    /// See [Server::try_listen] for how failures to bind are reported.
+    pub async fn try_listen_with_config(
+        addr: SocketAddr,
+        config: &ServerConfig,
+    ) -> Result<Self, Error> {
+        let state = ServerState::from_config(config);
         let handle = crate::net::start_rpc_server(addr, state.clone()).await?;
+        Ok(Self::from_parts(state, handle, config))
+    }
+
+    #[cfg(not(feature = "simulation"))]
+    /// Spawns the RPC server task on a listener which is already bound and
+    /// returns the server handle.
+    ///
+    /// This adopts a socket bound by someone else instead of binding one,
+    /// i.e. a socket passed down by systemd socket activation or handed over
+    /// by a previous process during a zero-downtime restart. The listener is
+    /// switched to non-blocking mode and [Server::local_addr] reflects the
+    /// address it is bound to.
+    pub async fn from_std_listener(listener: std::net::TcpListener) -> io::Result<Self> {
+        Self::from_std_listener_with_config(listener, &ServerConfig::default()).await
+    }
+
+    #[cfg(not(feature = "simulation"))]
+    /// Spawns the RPC server task on a listener which is already bound, using
+    /// the provided [ServerConfig], and returns the server handle.
+    ///
+    /// See [Server::from_std_listener] for more information.
+    pub async fn from_std_listener_with_config(
+        listener: std::net::TcpListener,
+        config: &ServerConfig,
+    ) -> io::Result<Self> {
+        let state = ServerState::from_config(config);
+        let handle = crate::net::adopt_rpc_server(listener, state.clone()).await?;
+        Ok(Self::from_parts(state, handle, config))
+    }
+
+    fn from_parts(
+        state: ServerState,
+        handle: ServerHandle,
+        config: &ServerConfig,
+    ) -> Self {
+        let server = Self { state, handle };
+
+        if config.admin_service {
+            server.enable_admin_service();
+        }
 
-        Ok(Self { state, handle })
+        server
     }
 
     /// Adds a new service to the live RPC server.
+    ///
+    /// # Panics
+    ///
+    /// If one of the service's paths is already registered by a handler added
+    /// via [Server::add_fn_handler].
     pub fn add_service<Svc>(&self, service: Svc)
     where
         Svc: RpcService + Send + Sync + 'static,
     {
-        let mut registry = ServiceRegistry::new(service);
+        self.add_service_with_config(service, SerdeConfig::default())
+    }
+
+    /// Adds a new service to the live RPC server using the provided
+    /// [SerdeConfig] to (de)serialize the service's messages.
+    ///
+    /// This allows services with very different message shapes to tune
+    /// their serialization independently of each other.
+    ///
+    /// # Panics
+    ///
+    /// If one of the service's paths is already registered by a handler added
+    /// via [Server::add_fn_handler].
+    pub fn add_service_with_config<Svc>(&self, service: Svc, config: SerdeConfig)
+    where
+        Svc: RpcService + Send + Sync + 'static,
+    {
+        self.add_service_named_with_config(Svc::service_name(), service, config)
+    }
+
+    /// Adds a new service to the live RPC server under the given name rather
+    /// than its [RpcService::service_name].
+    ///
+    /// This allows the same service type to be registered multiple times, or
+    /// a third-party service whose name clashes with another to be given an alias.
+    /// Clients reach the service via [RpcClient::send_to](crate::RpcClient::send_to)
+    /// with the path `/{name}/{path}`, and it can be removed again by passing
+    /// the name to [Server::remove_service].
+    pub fn add_service_named<Svc>(&self, name: &str, service: Svc)
+    where
+        Svc: RpcService + Send + Sync + 'static,
+    {
+        self.add_service_named_with_config(name, service, SerdeConfig::default())
+    }
+
+    /// Adds a new service to the live RPC server under the given name, using
+    /// the provided [SerdeConfig] to (de)serialize the service's messages.
+    ///
+    /// See [Server::add_service_named] for more information.
+    pub fn add_service_named_with_config<Svc>(
+        &self,
+        name: &str,
+        service: Svc,
+        config: SerdeConfig,
+    ) where
+        Svc: RpcService + Send + Sync + 'static,
+    {
+        let mut registry = ServiceRegistry::new_named(name, service, config);
         Svc::register_handlers(&mut registry);
-        let handlers = registry.into_handlers();
-        self.state.add_handlers(Svc::service_name(), handlers);
+        let (handlers, paths) = registry.into_parts();
+        self.state.add_handlers(name, handlers, paths);
+    }
+
+    /// Adds a handler implemented by an async function or closure to the live
+    /// RPC server, without defining a service.
+    ///
+    /// The handler is registered under the wire path `/{service}/{path}`, which
+    /// clients reach via [RpcClient::send_to](crate::RpcClient::send_to), and is
+    /// listed and removed along with the rest of the service named in the path.
+    /// This is useful for prototyping, tests and gateways assembling their
+    /// handlers at runtime:
+    ///
+    /// ```rust
+    /// use datacake_rpc::{Server, Status};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let server = Server::listen("127.0.0.1:8040".parse()?).await?;
+    /// server.add_fn_handler::<u64, _>("/math/double", |req| async move {
+    ///     Ok::<_, Status>(*req.into_inner() * 2)
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the path does not name both a service and a handler, or if a handler
+    /// is already registered under the path, including one of a service added
+    /// via [Server::add_service]. Likewise, adding a service afterwards which
+    /// registers the same path panics.
+    pub fn add_fn_handler<Msg, F>(&self, path: &str, handler: F)
+    where
+        Msg: RequestContents + Send + Sync + 'static,
+        F: FnHandler<Msg>,
+    {
+        let (service, handler_path) = crate::split_uri_path(path);
+        assert!(
+            !service.is_empty() && !handler_path.is_empty(),
+            "The path {path:?} must be of the form `/{{service}}/{{path}}`",
+        );
+
+        let handler = crate::handler::fn_handler(handler, SerdeConfig::default());
+        self.state.add_handler(service, handler_path, handler);
+    }
+
+    /// Adds a multi-tenant service to the live RPC server.
+    ///
+    /// Rather than a single instance handling every request, the instance is
+    /// selected by the value of the given `header` on each request. The first
+    /// request for a tenant calls the `factory` to create its instance, which is
+    /// then reused for any later requests of the same tenant.
+    ///
+    /// Requests missing the header, or for which the factory returns `None`, are
+    /// rejected with [ErrorCode::NotFound](crate::ErrorCode::NotFound).
+    pub fn add_tenant_service<Svc, F>(&self, header: HeaderName, factory: F)
+    where
+        Svc: RpcService + Send + Sync + 'static,
+        F: Fn(&str) -> Option<Svc> + Send + Sync + 'static,
+    {
+        self.add_tenant_service_with_config(header, factory, SerdeConfig::default())
+    }
+
+    /// Adds a multi-tenant service to the live RPC server using the provided
+    /// [SerdeConfig] to (de)serialize the service's messages.
+    ///
+    /// See [Server::add_tenant_service] for more information.
+    pub fn add_tenant_service_with_config<Svc, F>(
+        &self,
+        header: HeaderName,
+        factory: F,
+        config: SerdeConfig,
+    ) where
+        Svc: RpcService + Send + Sync + 'static,
+        F: Fn(&str) -> Option<Svc> + Send + Sync + 'static,
+    {
+        let factory = move |tenant: &str| {
+            let service = factory(tenant)?;
+            let mut registry = ServiceRegistry::new(service, config.clone());
+            Svc::register_handlers(&mut registry);
+            Some(registry.into_handlers())
+        };
+        let service = TenantService {
+            header,
+            factory: Box::new(factory),
+            instances: RwLock::default(),
+        };
+        self.state.add_tenant_service(Svc::service_name(), service);
+    }
+
+    /// Removes the instance of a multi-tenant service created for the given tenant.
+    ///
+    /// The next request for the tenant will create a new instance via the factory.
+    /// Returns `true` if an instance existed for the tenant.
+    pub fn remove_tenant(&self, service_name: &str, tenant: &str) -> bool {
+        let service = self.state.tenants.read().get(service_name).cloned();
+        service.is_some_and(|service| service.instances.write().remove(tenant).is_some())
+    }
+
+    /// Returns the services registered with the server and the paths of their handlers.
+    ///
+    /// Services are sorted by name and the paths of each service are listed in the
+    /// order their handlers were registered, so the output is stable across restarts
+    /// and can be used by tooling, i.e. to generate documentation or client stubs.
+    /// Multi-tenant services are not included, as their handlers are only created
+    /// once a tenant's first request arrives.
+    pub fn services(&self) -> Vec<ServiceInfo> {
+        self.state
+            .services
+            .lock()
+            .iter()
+            .map(|(name, paths)| ServiceInfo {
+                name: name.clone(),
+                paths: paths.clone(),
+            })
+            .collect()
     }
 
     /// Removes all handlers linked with the given service name.
+    ///
+    /// This also removes any multi-tenant service registered under the name.
     pub fn remove_service(&self, service_name: &str) {
         self.state.remove_handlers(service_name);
+        self.state.tenants.write().remove(service_name);
+    }
+
+    /// Sets the maximum amount of time a connection can go without receiving
+    /// any data while waiting to read.
+    ///
+    /// This is reset whenever bytes arrive, so it detects stalled connections
+    /// rather than limiting requests which are slow but still making progress.
+    /// When set, keep-alive pings are sent at half this interval so that idle
+    /// connections to a healthy client are not closed.
+    ///
+    /// This only applies to connections accepted after it is set.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) {
+        self.state.settings.write().read_timeout = timeout;
+    }
+
+    /// Sets the maximum amount of time a write to a connection can go
+    /// without making any progress, i.e. while a reply is being flushed.
+    ///
+    /// This only applies to connections accepted after it is set.
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) {
+        self.state.settings.write().write_timeout = timeout;
+    }
+
+    /// Sets the maximum amount of time a connection is served for,
+    /// regardless of its activity.
+    ///
+    /// Once a connection reaches this age, the server gracefully closes it
+    /// by telling the client to stop sending new requests on it. Requests
+    /// which are already in-flight on the connection are completed and their
+    /// replies sent before it is closed, clients then reconnect for any new
+    /// requests. This periodically rebalances clients across servers behind
+    /// a load balancer and lets them pick up DNS or config changes.
+    ///
+    /// This only applies to connections accepted after it is set.
+    pub fn set_max_connection_lifetime(&self, lifetime: Option<Duration>) {
+        self.state.settings.write().max_connection_lifetime = lifetime;
+    }
+
+    /// Trusts that peers only ever send valid messages, skipping the
+    /// checksum validation of every inbound message for all services.
+    ///
+    /// This removes a full pass over each message buffer which can be
+    /// pure overhead on trusted links, i.e. over mTLS within a datacenter.
+    ///
+    /// This only applies to connections accepted after it is set.
+    ///
+    /// # Safety
+    ///
+    /// **Messages are viewed without any validation.** A corrupted or
+    /// malicious message will be viewed as if it were valid which is
+    /// undefined behaviour and can lead to memory corruption or crashes.
+    /// This must only be used when every peer able to connect to the server
+    /// and the link between them are trusted.
+    pub unsafe fn trust_peer_validation(&self) {
+        self.state.settings.write().trust_peers = true;
+    }
+
+    /// Sets the maximum number of connections the server will serve at once.
+    ///
+    /// Once the limit is reached the server stops accepting new connections,
+    /// applying backpressure on the listener, until an existing connection
+    /// closes. Pending connections are left in the OS backlog.
+    ///
+    /// This is distinct from request concurrency, it bounds the number of
+    /// open sockets. Passing `None` removes the limit.
+    pub fn set_max_connections(&self, limit: Option<usize>) {
+        self.state.settings.write().max_connections = limit;
+        self.state.connections.changed.notify_waiters();
+    }
+
+    /// The number of connections currently being served.
+    pub fn connection_count(&self) -> usize {
+        self.state.connections.active.load(Ordering::Acquire)
+    }
+
+    /// Limits the rate new connections are accepted at.
+    ///
+    /// Up to `burst` connections are accepted back to back, after which
+    /// connections are accepted at `per_sec` connections per second. This smooths
+    /// out connection storms, i.e. thousands of clients reconnecting after a
+    /// network blip, which would otherwise all go through their handshake at
+    /// once. Excess connections wait in the OS backlog.
+    ///
+    /// The server waits for the limit before accepting each connection, so a
+    /// connection it is already waiting for when the limit is set is accepted
+    /// regardless. Use [ServerConfig::accept_rate_limit] to apply the limit
+    /// from the first connection.
+    ///
+    /// This is distinct from [Server::set_max_connections], which bounds the
+    /// number of open connections rather than how fast they are opened.
+    ///
+    /// # Panics
+    ///
+    /// If `per_sec` or `burst` is `0`.
+    pub fn set_accept_rate_limit(&self, per_sec: u32, burst: u32) {
+        *self.state.accept_rate.lock() = Some(AcceptRateLimiter::new(per_sec, burst));
+    }
+
+    /// Removes the accept rate limit, see [Server::set_accept_rate_limit].
+    pub fn clear_accept_rate_limit(&self) {
+        *self.state.accept_rate.lock() = None;
+    }
+
+    /// If the server is waiting for the accept rate limit before accepting its
+    /// next connection.
+    ///
+    /// Connections are not accepted from the OS until the limit allows it, so
+    /// any connections waiting in the meantime are queued in the OS backlog,
+    /// which cannot be observed. See [Server::set_accept_rate_limit].
+    pub fn accept_throttled(&self) -> bool {
+        self.state.accept_throttled.load(Ordering::Relaxed)
+    }
+
+    /// Sets if the requests of each connection are processed strictly in the
+    /// order they arrive.
+    ///
+    /// By default requests multiplexed over a connection are handled
+    /// concurrently, so they may complete in any order. With ordering enabled
+    /// each request waits for the previous request on its connection to
+    /// produce its reply before its handler is called, which some stateful
+    /// protocols rely on. Requests from different connections are still
+    /// handled concurrently.
+    ///
+    /// This trades throughput for ordering: a single slow request holds up
+    /// every request behind it on the same connection, and each connection is
+    /// limited to running one handler at a time. Clients wanting the same
+    /// guarantee without server support can use [Channel::ordered](crate::Channel::ordered).
+    ///
+    /// This only applies to connections accepted after it is set.
+    pub fn set_ordered_connections(&self, ordered: bool) {
+        self.state.settings.write().ordered_connections = ordered;
+    }
+
+    /// Sets the maximum number of request body bytes the server will process at once.
+    ///
+    /// A request is counted from when it is accepted until its handler has produced
+    /// the reply, new requests which would take the total over the limit are
+    /// rejected with [ErrorCode::ResourceExhausted](crate::ErrorCode::ResourceExhausted).
+    /// This guards against a small number of very large messages exhausting memory,
+    /// which a limit on the number of requests cannot.
+    ///
+    /// Requests are counted by their declared `Content-Length`, streaming requests
+    /// of unknown length are counted as their body is read and fail once it no
+    /// longer fits. Serialized replies are counted from when they are produced
+    /// until they have been written, replies which do not fit are rejected the
+    /// same way, streaming replies are not counted. Passing `None` removes the limit.
+    pub fn set_max_inflight_bytes(&self, limit: Option<usize>) {
+        self.state.settings.write().max_inflight_bytes = limit;
+    }
+
+    /// The number of request body bytes currently being processed.
+    pub fn inflight_bytes(&self) -> usize {
+        self.state.inflight_bytes.load(Ordering::Acquire)
+    }
+
+    /// Buffers the reads of each connection through a buffer sized between
+    /// `min` and `max` bytes, adapting to the messages being received.
+    ///
+    /// The buffer starts at `min` bytes and doubles each time a read fills it,
+    /// up to `max` bytes, so large messages are read in fewer, larger reads.
+    /// Once small messages arrive again, the buffer is shrunk back down to `min`
+    /// bytes to release its memory. This suits servers with bimodal message
+    /// sizes, where a fixed buffer either wastes memory on idle connections or
+    /// reads large messages in many small reads.
+    ///
+    /// By default reads are passed to the connection's protocol handling as-is,
+    /// which keeps its own fixed buffer. This only applies to connections
+    /// accepted after it is set.
+    ///
+    /// # Panics
+    ///
+    /// If `min` is `0` or is greater than `max`.
+    pub fn set_read_buffer_bounds(&self, min: usize, max: usize) {
+        assert!(
+            min > 0 && min <= max,
+            "The read buffer bounds must satisfy 0 < min <= max"
+        );
+        self.state.settings.write().read_buffer = Some(ReadBufferBounds { min, max });
+    }
+
+    /// Stops buffering the reads of new connections, see [Server::set_read_buffer_bounds].
+    ///
+    /// This only applies to connections accepted after it is set.
+    pub fn clear_read_buffer_bounds(&self) {
+        self.state.settings.write().read_buffer = None;
+    }
+
+    /// The total size in bytes of the read buffers currently allocated
+    /// across all connections, see [Server::set_read_buffer_bounds].
+    pub fn read_buffer_bytes(&self) -> usize {
+        self.state.read_buffer_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Enables tracing of request and reply body sizes.
+    ///
+    /// The size of every request and reply body is recorded at the trace
+    /// level and any body larger than `threshold` bytes is logged as a
+    /// warning, along with the service path and remote address.
+    /// This is a targeted diagnostic for catching accidental giant messages.
+    ///
+    /// Only bodies with a known size are recorded, streaming bodies which
+    /// do not report their length are skipped.
+    ///
+    /// This only applies to connections accepted after it is set.
+    pub fn enable_size_tracing(&self, threshold: u64) {
+        self.state.settings.write().size_tracing_threshold = Some(threshold);
+    }
+
+    /// Disables tracing of request and reply body sizes.
+    ///
+    /// This only applies to connections accepted after it is set.
+    pub fn disable_size_tracing(&self) {
+        self.state.settings.write().size_tracing_threshold = None;
+    }
+
+    /// Logs a warning for any request which takes longer than the threshold.
+    ///
+    /// The warning includes the service, path, duration, remote address and
+    /// id of the request, the same id which is visible via
+    /// [Server::inflight_requests] and [Request::request_id](crate::Request::request_id).
+    /// A request's duration is measured from when it is admitted until its
+    /// handler has produced the reply. Passing `None` disables the logging.
+    ///
+    /// This only applies to requests admitted after it is set.
+    pub fn set_slow_request_threshold(&self, threshold: Option<Duration>) {
+        self.state.settings.write().slow_request_threshold = threshold;
+    }
+
+    /// Sets the header the correlation id of each request is read from.
+    ///
+    /// The id is recorded as the `correlation_id` field of the tracing span each
+    /// request is handled within and echoed back to the client in the same header,
+    /// requests without an id are assigned a newly generated one. This lets the
+    /// server follow the convention of its ecosystem, i.e. `x-correlation-id`,
+    /// by default the [REQUEST_ID_HEADER](crate::REQUEST_ID_HEADER) is used.
+    ///
+    /// The correlation id is unrelated to [Request::request_id](crate::Request::request_id),
+    /// which identifies the request within the server.
+    ///
+    /// This only applies to connections accepted after it is set.
+    pub fn set_request_id_header(&self, name: HeaderName) {
+        self.state.settings.write().request_id = RequestIdSource::Header(name);
+    }
+
+    /// Sets a function which extracts the correlation id of each request from
+    /// its headers, i.e. deriving it from a `traceparent`.
+    ///
+    /// If the function returns `None`, or an id which is not a valid header value,
+    /// a new id is generated. The id is echoed back to the client in the
+    /// [REQUEST_ID_HEADER](crate::REQUEST_ID_HEADER), see [Server::set_request_id_header].
+    ///
+    /// This only applies to connections accepted after it is set.
+    pub fn set_request_id_extractor<F>(&self, extractor: F)
+    where
+        F: Fn(&HeaderMap) -> Option<String> + Send + Sync + 'static,
+    {
+        self.state.settings.write().request_id =
+            RequestIdSource::Extractor(Arc::new(extractor));
+    }
+
+    /// Sets how error replies are encoded.
+    ///
+    /// The default [ErrorFraming::Rkyv] framing is cheapest for clients of this
+    /// crate while [ErrorFraming::Simple] lets clients written in other languages
+    /// handle errors without implementing rkyv, clients of this crate understand
+    /// both. See [ErrorFraming] for the layout of each framing.
+    ///
+    /// This only applies to connections accepted after it is set.
+    pub fn set_error_framing(&self, framing: ErrorFraming) {
+        self.state.settings.write().error_framing = framing;
+    }
+
+    /// Returns a snapshot of the requests currently being handled.
+    ///
+    /// This is useful for diagnosing which handlers are stuck or slow,
+    /// the same information can be queried remotely via the [AdminService].
+    pub fn inflight_requests(&self) -> Vec<InflightInfo> {
+        self.state.inflight_requests()
+    }
+
+    /// Adds a transform which is applied to the bodies of all requests and replies.
+    ///
+    /// Requests are passed through the transforms in the order they were added,
+    /// and replies in the reverse order, so the first transform added is the
+    /// outermost, i.e. the first to see a request and the last to see its reply.
+    pub fn add_body_transform(&self, transform: impl BodyTransform) {
+        let mut transforms = self.state.transforms.write();
+        let mut updated = Vec::with_capacity(transforms.len() + 1);
+        updated.extend(transforms.iter().cloned());
+        updated.push(Arc::new(transform) as Arc<dyn BodyTransform>);
+        *transforms = Arc::from(updated);
+    }
+
+    /// Sets whether the server is draining.
+    ///
+    /// While draining, new requests are rejected with
+    /// [ErrorCode::ServiceUnavailable](crate::ErrorCode::ServiceUnavailable)
+    /// but existing connections are kept open, and requests which are already
+    /// in-flight are left to complete. Unlike [Server::shutdown] this is
+    /// reversible, setting it back to `false` resumes serving requests.
+    ///
+    /// Requests to the [AdminService] are still served while draining.
+    pub fn set_draining(&self, draining: bool) {
+        self.state.draining.store(draining, Ordering::Release);
+    }
+
+    /// Returns if the server is draining.
+    ///
+    /// See [Server::set_draining].
+    pub fn is_draining(&self) -> bool {
+        self.state.draining.load(Ordering::Acquire)
+    }
+
+    /// Starts draining a single service while the rest of the server keeps serving.
+    ///
+    /// New requests to the service are rejected with
+    /// [ErrorCode::ServiceUnavailable](crate::ErrorCode::ServiceUnavailable),
+    /// while requests which are already in-flight are left to complete, these
+    /// are listed by [Server::inflight_requests]. This is reversible via
+    /// [Server::resume_service].
+    ///
+    /// The service is drained by name, so it stays drained if it is removed and
+    /// registered again, i.e. while it is being replaced by a new version, until
+    /// it is resumed. Names which are not registered can be drained ahead of time.
+    pub fn drain_service(&self, service_name: &str) {
+        self.state
+            .drained_services
+            .write()
+            .insert(service_name.to_string());
+    }
+
+    /// Resumes serving requests to a service drained via [Server::drain_service].
+    pub fn resume_service(&self, service_name: &str) {
+        self.state.drained_services.write().remove(service_name);
+    }
+
+    /// Returns if the service is draining.
+    ///
+    /// See [Server::drain_service].
+    pub fn is_service_draining(&self, service_name: &str) -> bool {
+        self.state.drained_services.read().contains(service_name)
+    }
+
+    /// Switches the server to dispatching requests manually, returning the
+    /// stream of [IncomingRequests].
+    ///
+    /// Instead of invoking the handlers of the registered services, the
+    /// server yields each request along with a
+    /// [ResponseSender](crate::ResponseSender) which completes it, leaving the
+    /// dispatch, ordering and concurrency of requests to the caller.
+    /// Services must still be registered for their requests to be accepted.
+    ///
+    /// Calling this again replaces the previous stream, which then ends.
+    /// Once the stream is dropped, the server goes back to invoking the
+    /// handlers directly.
+    pub fn incoming(&self) -> IncomingRequests {
+        self.state.take_incoming()
+    }
+
+    /// Aborts the in-flight request with the given id by cancelling its
+    /// [cancellation token](crate::Request::cancellation_token).
+    ///
+    /// Handlers which respect cancellation will then unwind, the request is
+    /// not forcibly stopped. Returns `true` if the request was found and
+    /// aborted, `false` if no request with the id is in-flight.
+    pub fn abort_request(&self, id: u64) -> bool {
+        self.state.abort_request(id)
+    }
+
+    /// Registers the [AdminService] with the server, allowing it to be
+    /// inspected remotely.
+    ///
+    /// The admin service exposes internal server state, so this should only be
+    /// enabled when the server is reachable by trusted clients.
+    pub fn enable_admin_service(&self) {
+        self.add_service(AdminService::new(self.state.clone()));
+    }
+
+    /// Enables caching of replies for handlers registered via
+    /// [ServiceRegistry::add_cacheable_handler].
+    ///
+    /// Repeated requests to the same path with an identical body within the
+    /// configured TTL are answered with the cached, already serialized reply
+    /// without running the handler. Only replies with a known size are cached,
+    /// streaming replies always run the handler.
+    ///
+    /// Enabling the cache again replaces any existing cache.
+    pub fn enable_reply_cache(&self, config: ReplyCacheConfig) {
+        *self.state.reply_cache.write() = Some(Arc::new(ReplyCache::new(config)));
+    }
+
+    /// Disables the reply cache, dropping any cached replies.
+    pub fn disable_reply_cache(&self) {
+        self.state.reply_cache.write().take();
+    }
+
+    /// Enables deduplication of requests carrying an idempotency key.
+    ///
+    /// Clients which retry requests set the
+    /// [IDEMPOTENCY_KEY_HEADER](crate::IDEMPOTENCY_KEY_HEADER) header, repeated
+    /// requests to the same path with the same key within the configured TTL are
+    /// answered with the serialized reply of the first request without running the
+    /// handler again. If the first request is still being handled, duplicates wait
+    /// for it to complete rather than running concurrently.
+    ///
+    /// Only successful replies with a known size are kept, if the first request
+    /// fails or streams its reply, the next request with the key is handled again.
+    /// Requests without the header are always handled.
+    ///
+    /// Enabling deduplication again replaces any existing replies.
+    pub fn enable_idempotency(&self, config: IdempotencyConfig) {
+        let cache = IdempotencyCache::new(config);
+        *self.state.idempotency.write() = Some(Arc::new(cache));
+    }
+
+    /// Disables deduplication of requests, dropping any kept replies.
+    pub fn disable_idempotency(&self) {
+        self.state.idempotency.write().take();
+    }
+
+    /// Enables recording of the raw bytes of every request and reply body.
+    ///
+    /// This is a diagnostics feature for debugging protocol issues and building
+    /// regression corpora, the captured requests can later be replayed against a
+    /// server. Bodies are recorded as they are sent on the wire, before any
+    /// [BodyTransform] is applied to requests and after they are applied to replies,
+    /// once they have been read to completion. See [CaptureConfig] for where the
+    /// frames are recorded, how much is kept and how sensitive data can be redacted.
+    ///
+    /// Enabling the capture again replaces any existing capture, creating or
+    /// truncating its file if it has one.
+    pub fn enable_capture(&self, config: CaptureConfig) -> io::Result<()> {
+        let capture = Capture::new(config)?;
+        *self.state.capture.write() = Some(Arc::new(capture));
+        Ok(())
+    }
+
+    /// Stops recording request and reply bodies, see [Server::enable_capture].
+    ///
+    /// Bodies which are still being read when this is called are recorded once
+    /// they complete.
+    pub fn disable_capture(&self) {
+        self.state.capture.write().take();
+    }
+
+    /// The frames held by the in-memory capture, oldest first.
+    ///
+    /// This is empty if the capture is disabled or records to a file,
+    /// see [Server::enable_capture].
+    pub fn captured_frames(&self) -> Vec<CapturedFrame> {
+        self.state
+            .capture()
+            .map(|capture| capture.frames())
+            .unwrap_or_default()
+    }
+
+    /// Enables shedding of new requests while the runtime is overloaded.
+    ///
+    /// The server periodically measures how long a task which yields to the
+    /// runtime takes to be resumed. Once this scheduling delay exceeds the
+    /// configured threshold, new requests are rejected with
+    /// [ErrorCode::ServiceUnavailable](crate::ErrorCode::ServiceUnavailable)
+    /// until the runtime has recovered, requests which are already in-flight
+    /// are left to complete.
+    ///
+    /// This is a last resort for when the runtime itself is saturated, i.e. by
+    /// handlers blocking their threads, where limits on the number or size of
+    /// requests cannot prevent latency from collapsing. Requests to the
+    /// [AdminService] are still served while shedding.
+    ///
+    /// Setting the detector again replaces any existing detector.
+    pub fn set_overload_detector(&self, config: OverloadConfig) {
+        *self.state.overload.write() = Some(OverloadDetector::start(config));
+    }
+
+    /// Disables the overload detector, no longer shedding requests.
+    pub fn disable_overload_detector(&self) {
+        self.state.overload.write().take();
+    }
+
+    /// The most recently measured scheduling delay of the runtime, if the
+    /// overload detector is enabled and has taken a measurement.
+    ///
+    /// See [Server::set_overload_detector].
+    pub fn scheduling_delay(&self) -> Option<Duration> {
+        self.state
+            .overload
+            .read()
+            .as_ref()
+            .and_then(|detector| detector.scheduling_delay())
+    }
+
+    /// Returns the hit and miss statistics of the reply cache if it is enabled.
+    pub fn reply_cache_stats(&self) -> Option<ReplyCacheStats> {
+        self.state.reply_cache().map(|cache| cache.stats())
+    }
+
+    /// The local address the server is bound to.
+    ///
+    /// When listening on port `0`, this reflects the port assigned by the OS.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.handle.local_addr()
     }
 
     /// Signals the server to shutdown.
     pub fn shutdown(self) {
-        self.handle.abort();
+        self.handle.shutdown();
+    }
+
+    /// Shuts down the server immediately, aborting every in-flight request.
+    ///
+    /// This is the hard-stop counterpart to [Server::shutdown], for when the
+    /// node must go down without waiting for handlers to complete. The
+    /// [cancellation token](crate::Request::cancellation_token) of every
+    /// in-flight request is cancelled and, rather than waiting for the
+    /// handlers to unwind, each request is replied to with an
+    /// [ErrorCode::Aborted](crate::ErrorCode::Aborted) status. Every connection
+    /// is then closed once these replies have been sent, and any request which
+    /// arrives in the meantime is aborted as well.
+    ///
+    /// Reply bodies which were already being streamed to their client when
+    /// the server was aborted are still sent until their stream ends.
+    pub fn shutdown_abort(self) {
+        self.state.abort();
+        self.handle.shutdown();
     }
 
     /// Waits until the server exits.
@@ -103,51 +904,596 @@ impl Server {
     /// This typically is just a future that pends forever as the server
     /// will not exit unless an external force triggers it.
     pub async fn wait(self) {
-        self.handle.await.expect("Wait for server handle.");
+        self.handle.wait().await;
+    }
+
+    /// Waits until the server is ready to handle requests.
+    ///
+    /// The server is accepting connections as soon as [Server::listen] returns,
+    /// this additionally waits until at least one service has been registered.
+    /// This is useful for tests and orchestration which register services
+    /// concurrently, rather than relying on sleeps.
+    pub async fn ready(&self) {
+        self.state.wait_for_services().await;
+    }
+
+    /// Creates a future which completes once the server has shut down and
+    /// stopped accepting connections.
+    ///
+    /// The future does not borrow the server, so it can be created before
+    /// calling [Server::shutdown]:
+    ///
+    /// ```rust
+    /// # use datacake_rpc::Server;
+    /// # use std::net::SocketAddr;
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// # let bind = "127.0.0.1:0".parse::<SocketAddr>()?;
+    /// let server = Server::listen(bind).await?;
+    ///
+    /// let closed = server.wait_closed();
+    /// server.shutdown();
+    /// closed.await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Connections which were already accepted may still be finishing their
+    /// in-flight requests once this completes.
+    pub fn wait_closed(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.handle.closed()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A service registered with the server, see [Server::services].
+pub struct ServiceInfo {
+    /// The name the service is registered under.
+    pub name: String,
+    /// The paths of the service's handlers, in the order they were registered.
+    ///
+    /// Each handler is reached at `/{name}/{path}`.
+    pub paths: Vec<String>,
+}
+
+#[derive(Clone, Default)]
+/// Configuration shared by the servers created from it.
+///
+/// A config can be defined once and cloned or reused to start several
+/// servers via [Server::listen_with_config], avoiding the common settings
+/// being specified for each of them. Each server can still be adjusted
+/// individually after it has started via its own setters, i.e.
+/// [Server::set_max_connections], without affecting the other servers.
+///
+/// ```rust
+/// use std::net::SocketAddr;
+/// use std::time::Duration;
+///
+/// use datacake_rpc::{Server, ServerConfig};
+///
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let config = ServerConfig {
+///     read_timeout: Some(Duration::from_secs(30)),
+///     max_connections: Some(512),
+///     ..Default::default()
+/// };
+///
+/// let public = Server::listen_with_config("127.0.0.1:8020".parse()?, &config).await?;
+/// let internal = Server::listen_with_config("127.0.0.1:8021".parse()?, &config).await?;
+/// // Overrides only apply to the server they are set on.
+/// internal.set_max_connections(None);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The default config matches the behaviour of [Server::listen].
+pub struct ServerConfig {
+    /// The read inactivity timeout, see [Server::set_read_timeout].
+    pub read_timeout: Option<Duration>,
+    /// The write inactivity timeout, see [Server::set_write_timeout].
+    pub write_timeout: Option<Duration>,
+    /// The maximum age of each connection,
+    /// see [Server::set_max_connection_lifetime].
+    pub max_connection_lifetime: Option<Duration>,
+    /// The maximum number of connections served at once,
+    /// see [Server::set_max_connections].
+    pub max_connections: Option<usize>,
+    /// The rate connections are accepted at per second and the burst
+    /// accepted back to back, see [Server::set_accept_rate_limit].
+    pub accept_rate_limit: Option<(u32, u32)>,
+    /// If the requests of each connection are processed in arrival order,
+    /// see [Server::set_ordered_connections].
+    pub ordered_connections: bool,
+    /// The maximum number of body bytes processed at once,
+    /// see [Server::set_max_inflight_bytes].
+    pub max_inflight_bytes: Option<usize>,
+    /// The minimum and maximum size in bytes of each connection's read buffer,
+    /// see [Server::set_read_buffer_bounds].
+    pub read_buffer_bounds: Option<(usize, usize)>,
+    /// The body size in bytes above which requests and replies are logged,
+    /// see [Server::enable_size_tracing].
+    pub size_tracing_threshold: Option<u64>,
+    /// The duration above which requests are logged as slow,
+    /// see [Server::set_slow_request_threshold].
+    pub slow_request_threshold: Option<Duration>,
+    /// Where the correlation id of each request is read from,
+    /// see [Server::set_request_id_header].
+    pub request_id: RequestIdSource,
+    /// How error replies are encoded, see [Server::set_error_framing].
+    pub error_framing: ErrorFraming,
+    /// The config of the reply cache if it should be enabled,
+    /// see [Server::enable_reply_cache].
+    ///
+    /// Each server has its own cache, cached replies are not shared.
+    pub reply_cache: Option<ReplyCacheConfig>,
+    /// The config of request deduplication if it should be enabled,
+    /// see [Server::enable_idempotency].
+    pub idempotency: Option<IdempotencyConfig>,
+    /// If the [AdminService] should be registered,
+    /// see [Server::enable_admin_service].
+    pub admin_service: bool,
+    /// The transforms applied to the bodies of all requests and replies,
+    /// see [Server::add_body_transform].
+    pub body_transforms: Vec<Arc<dyn BodyTransform>>,
+    /// The config of the overload detector if it should be enabled,
+    /// see [Server::set_overload_detector].
+    pub overload_detector: Option<OverloadConfig>,
+}
+
+impl ServerConfig {
+    /// Adds a transform which is applied to the bodies of all requests and replies.
+    ///
+    /// See [Server::add_body_transform] for the order transforms are applied in.
+    pub fn add_body_transform(&mut self, transform: impl BodyTransform) {
+        self.body_transforms.push(Arc::new(transform));
     }
 }
 
+#[derive(Debug, Clone, Default)]
+/// The runtime adjustable settings of the RPC server.
+pub(crate) struct ServerSettings {
+    /// The read inactivity timeout applied to new connections.
+    pub(crate) read_timeout: Option<Duration>,
+    /// The write inactivity timeout applied to new connections.
+    pub(crate) write_timeout: Option<Duration>,
+    /// The maximum age of new connections.
+    pub(crate) max_connection_lifetime: Option<Duration>,
+    /// If inbound messages on new connections should skip validation.
+    pub(crate) trust_peers: bool,
+    /// The maximum number of connections served at once.
+    pub(crate) max_connections: Option<usize>,
+    /// If the requests of new connections are processed in arrival order.
+    pub(crate) ordered_connections: bool,
+    /// The maximum number of body bytes processed at once.
+    pub(crate) max_inflight_bytes: Option<usize>,
+    /// The bounds of the read buffer of new connections.
+    pub(crate) read_buffer: Option<ReadBufferBounds>,
+    /// The body size in bytes above which requests and replies are logged.
+    pub(crate) size_tracing_threshold: Option<u64>,
+    /// The duration above which requests are logged as slow.
+    pub(crate) slow_request_threshold: Option<Duration>,
+    /// Where the correlation id of each request is read from.
+    pub(crate) request_id: RequestIdSource,
+    /// How error replies are encoded.
+    pub(crate) error_framing: ErrorFraming,
+}
+
 #[derive(Clone, Default)]
 /// Represents the shared state of the RPC server.
 pub(crate) struct ServerState {
-    services: Arc<Mutex<BTreeMap<String, BTreeSet<HandlerKey>>>>,
+    /// The handler paths of each service, in the order they were registered.
+    services: Arc<Mutex<BTreeMap<String, Vec<String>>>>,
     handlers: Arc<RwLock<BTreeMap<HandlerKey, Arc<dyn OpaqueMessageHandler>>>>,
+    /// The keys of the handlers added via [Server::add_fn_handler], which
+    /// services may not replace.
+    fn_handlers: Arc<Mutex<BTreeSet<HandlerKey>>>,
+    settings: Arc<RwLock<ServerSettings>>,
+    connections: Arc<ConnectionTracker>,
+    inflight: Arc<InflightRegistry>,
+    reply_cache: Arc<RwLock<Option<Arc<ReplyCache>>>>,
+    idempotency: Arc<RwLock<Option<Arc<IdempotencyCache>>>>,
+    capture: Arc<RwLock<Option<Arc<Capture>>>>,
+    tenants: Arc<RwLock<BTreeMap<String, Arc<TenantService>>>>,
+    draining: Arc<AtomicBool>,
+    /// The services drained individually, see [Server::drain_service].
+    drained_services: Arc<RwLock<BTreeSet<String>>>,
+    inflight_bytes: Arc<AtomicUsize>,
+    read_buffer_bytes: Arc<AtomicUsize>,
+    accept_rate: Arc<Mutex<Option<AcceptRateLimiter>>>,
+    accept_throttled: Arc<AtomicBool>,
+    transforms: Arc<RwLock<BodyTransforms>>,
+    services_changed: Arc<Notify>,
+    queue: Arc<RwLock<Option<QueueSender>>>,
+    overload: Arc<RwLock<Option<Arc<OverloadDetector>>>>,
+    /// Cancelled once the server is aborted, the parent of every
+    /// request's cancellation token.
+    aborted: CancellationToken,
 }
 
 impl ServerState {
+    /// Creates the state of a new server using the provided config.
+    pub(crate) fn from_config(config: &ServerConfig) -> Self {
+        let settings = ServerSettings {
+            read_timeout: config.read_timeout,
+            write_timeout: config.write_timeout,
+            max_connection_lifetime: config.max_connection_lifetime,
+            trust_peers: false,
+            max_connections: config.max_connections,
+            ordered_connections: config.ordered_connections,
+            max_inflight_bytes: config.max_inflight_bytes,
+            read_buffer: config
+                .read_buffer_bounds
+                .map(|(min, max)| ReadBufferBounds { min, max }),
+            size_tracing_threshold: config.size_tracing_threshold,
+            slow_request_threshold: config.slow_request_threshold,
+            request_id: config.request_id.clone(),
+            error_framing: config.error_framing,
+        };
+        let reply_cache = config
+            .reply_cache
+            .clone()
+            .map(|config| Arc::new(ReplyCache::new(config)));
+        let idempotency = config
+            .idempotency
+            .clone()
+            .map(|config| Arc::new(IdempotencyCache::new(config)));
+        let overload = config
+            .overload_detector
+            .clone()
+            .map(OverloadDetector::start);
+
+        let accept_rate = config
+            .accept_rate_limit
+            .map(|(per_sec, burst)| AcceptRateLimiter::new(per_sec, burst));
+
+        Self {
+            settings: Arc::new(RwLock::new(settings)),
+            accept_rate: Arc::new(Mutex::new(accept_rate)),
+            reply_cache: Arc::new(RwLock::new(reply_cache)),
+            idempotency: Arc::new(RwLock::new(idempotency)),
+            transforms: Arc::new(RwLock::new(Arc::from(config.body_transforms.clone()))),
+            overload: Arc::new(RwLock::new(overload)),
+            ..Default::default()
+        }
+    }
+
+    /// A snapshot of the current server settings.
+    pub(crate) fn settings(&self) -> ServerSettings {
+        self.settings.read().clone()
+    }
+
+    /// The body transforms applied to each request.
+    pub(crate) fn body_transforms(&self) -> BodyTransforms {
+        self.transforms.read().clone()
+    }
+
+    /// Reserves the given number of in-flight body bytes for a new request
+    /// or reply, rejecting it if it would exceed the limit.
+    pub(crate) fn reserve_inflight_bytes(
+        &self,
+        len: usize,
+    ) -> Result<InflightBytesGuard, Status> {
+        let limit = self.settings.read().max_inflight_bytes;
+        reserve_bytes(&self.inflight_bytes, limit, len)?;
+
+        Ok(InflightBytesGuard {
+            bytes: AtomicUsize::new(len),
+            limit,
+            exceeded: AtomicBool::new(false),
+            counter: self.inflight_bytes.clone(),
+        })
+    }
+
+    /// Switches the server to queueing requests on a new stream.
+    pub(crate) fn take_incoming(&self) -> IncomingRequests {
+        let (tx, incoming) = crate::queue::channel();
+        *self.queue.write() = Some(tx);
+        incoming
+    }
+
+    /// The queue requests are dispatched through if the server is
+    /// in manual dispatch mode.
+    pub(crate) fn request_queue(&self) -> Option<QueueSender> {
+        self.queue
+            .read()
+            .as_ref()
+            .filter(|queue| !queue.is_closed())
+            .cloned()
+    }
+
+    /// Waits until at least one service is registered.
+    pub(crate) async fn wait_for_services(&self) {
+        loop {
+            let changed = self.services_changed.notified();
+
+            if !self.services.lock().is_empty() || !self.tenants.read().is_empty() {
+                return;
+            }
+
+            changed.await;
+        }
+    }
+
+    /// Adds a multi-tenant service to the server state.
+    pub(crate) fn add_tenant_service(&self, service_name: &str, service: TenantService) {
+        self.tenants
+            .write()
+            .insert(service_name.to_string(), Arc::new(service));
+        self.services_changed.notify_waiters();
+    }
+
+    /// Waits until the server is able to accept another connection.
+    pub(crate) async fn wait_for_connection_capacity(&self) {
+        loop {
+            // The notification must be registered before checking the count
+            // to avoid missing a connection being released in between.
+            let changed = self.connections.changed.notified();
+
+            let limit = self.settings.read().max_connections;
+            let active = self.connections.active.load(Ordering::Acquire);
+            if limit.is_none_or(|limit| active < limit) {
+                return;
+            }
+
+            changed.await;
+        }
+    }
+
+    /// Waits for the accept rate limit, if any, to allow the next connection
+    /// to be accepted.
+    pub(crate) async fn wait_for_accept_rate(&self) {
+        loop {
+            let wait = match self.accept_rate.lock().as_mut() {
+                None => Ok(()),
+                Some(limiter) => limiter.try_acquire(Instant::now()),
+            };
+            let Err(wait) = wait else {
+                break;
+            };
+
+            self.accept_throttled.store(true, Ordering::Relaxed);
+            crate::runtime::sleep(wait).await;
+        }
+
+        self.accept_throttled.store(false, Ordering::Relaxed);
+    }
+
+    /// The capture of request and reply bodies if it is enabled.
+    pub(crate) fn capture(&self) -> Option<Arc<Capture>> {
+        self.capture.read().clone()
+    }
+
+    /// The reply cache if it is enabled.
+    pub(crate) fn reply_cache(&self) -> Option<Arc<ReplyCache>> {
+        self.reply_cache.read().clone()
+    }
+
+    /// The deduplication of requests if it is enabled.
+    pub(crate) fn idempotency(&self) -> Option<Arc<IdempotencyCache>> {
+        self.idempotency.read().clone()
+    }
+
+    /// Marks a request as in-flight until the guard is dropped.
+    pub(crate) fn track_request(
+        &self,
+        uri: &str,
+        remote_addr: SocketAddr,
+    ) -> InflightGuard {
+        let id = self.inflight.next_id.fetch_add(1, Ordering::Relaxed);
+        let cancellation = self.aborted.child_token();
+        let start = Instant::now();
+        let entry = InflightEntry {
+            uri: uri.to_string(),
+            remote_addr,
+            started_at: SystemTime::now(),
+            start,
+            cancellation: cancellation.clone(),
+        };
+        self.inflight.requests.lock().insert(id, entry);
+
+        InflightGuard {
+            id,
+            admitted_at: start,
+            slow_threshold: self.settings.read().slow_request_threshold,
+            cancellation,
+            completed: CancellationToken::new(),
+            disconnected: CancellationToken::new(),
+            replied: false,
+            registry: self.inflight.clone(),
+        }
+    }
+
+    /// Aborts the server, cancelling every in-flight request.
+    pub(crate) fn abort(&self) {
+        self.aborted.cancel();
+    }
+
+    /// The token cancelled once the server has been aborted.
+    pub(crate) fn aborted(&self) -> &CancellationToken {
+        &self.aborted
+    }
+
+    /// The total size of the read buffers allocated across all connections.
+    pub(crate) fn read_buffer_bytes(&self) -> &Arc<AtomicUsize> {
+        &self.read_buffer_bytes
+    }
+
+    /// Cancels the in-flight request with the given id.
+    ///
+    /// Returns `false` if no request with the id is in-flight.
+    pub(crate) fn abort_request(&self, id: u64) -> bool {
+        let requests = self.inflight.requests.lock();
+        match requests.get(&id) {
+            Some(entry) => {
+                entry.cancellation.cancel();
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// A snapshot of the requests currently being handled.
+    pub(crate) fn inflight_requests(&self) -> Vec<InflightInfo> {
+        let requests = self.inflight.requests.lock();
+        requests
+            .iter()
+            .map(|(id, entry)| {
+                let (service, path) = crate::split_uri_path(&entry.uri);
+                InflightInfo {
+                    id: *id,
+                    service: service.to_string(),
+                    path: path.to_string(),
+                    remote_addr: entry.remote_addr,
+                    started_at: entry
+                        .started_at
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap_or_default(),
+                    elapsed: entry.start.elapsed(),
+                }
+            })
+            .collect()
+    }
+
+    /// Marks a new connection as being served until the guard is dropped.
+    pub(crate) fn track_connection(&self) -> ConnectionGuard {
+        self.connections.active.fetch_add(1, Ordering::AcqRel);
+        ConnectionGuard(self.connections.clone())
+    }
+
     /// Adds a new set of handlers to the server state.
     ///
     /// Handlers newly added will then be able to handle messages received by
     /// the already running RPC system.
+    ///
+    /// # Panics
+    ///
+    /// If one of the paths is already registered by a handler added via
+    /// [Server::add_fn_handler].
     pub(crate) fn add_handlers(
         &self,
         service_name: &str,
         handlers: BTreeMap<HandlerKey, Arc<dyn OpaqueMessageHandler>>,
+        paths: Vec<String>,
     ) {
+        let mut lock = self.handlers.write();
+        {
+            let fn_handlers = self.fn_handlers.lock();
+            for path in &paths {
+                let uri = crate::to_uri_path(service_name, path);
+                if fn_handlers.contains(&crate::hash(&uri)) {
+                    duplicate_handler(service_name, path, &uri);
+                }
+            }
+        }
+        lock.extend(handlers);
+        drop(lock);
+
         {
             let mut lock = self.services.lock();
-            for key in handlers.keys() {
-                lock.entry(service_name.to_string())
-                    .or_default()
-                    .insert(*key);
+            let registered = lock.entry(service_name.to_string()).or_default();
+            for path in paths {
+                if !registered.contains(&path) {
+                    registered.push(path);
+                }
             }
         }
 
-        let mut lock = self.handlers.write();
-        lock.extend(handlers);
+        self.services_changed.notify_waiters();
+    }
+
+    /// Adds a single handler to the server state.
+    ///
+    /// # Panics
+    ///
+    /// If a handler is already registered under the same path.
+    pub(crate) fn add_handler(
+        &self,
+        service_name: &str,
+        path: &str,
+        handler: Arc<dyn OpaqueMessageHandler>,
+    ) {
+        let uri = crate::to_uri_path(service_name, path);
+        let key = crate::hash(&uri);
+        {
+            let mut handlers = self.handlers.write();
+            if handlers.contains_key(&key) {
+                duplicate_handler(service_name, path, &uri);
+            }
+            handlers.insert(key, handler);
+            self.fn_handlers.lock().insert(key);
+        }
+
+        {
+            let mut lock = self.services.lock();
+            let registered = lock.entry(service_name.to_string()).or_default();
+            if !registered.iter().any(|registered| registered == path) {
+                registered.push(path.to_string());
+            }
+        }
+
+        self.services_changed.notify_waiters();
     }
 
     /// Removes a new set of handlers from the server state.
     pub(crate) fn remove_handlers(&self, service: &str) {
-        let uris = {
+        let keys = {
             match self.services.lock().remove(service) {
                 None => return,
-                Some(uris) => uris,
+                Some(paths) => paths
+                    .iter()
+                    .map(|path| crate::hash(&crate::to_uri_path(service, path)))
+                    .collect::<BTreeSet<_>>(),
             }
         };
 
         let mut lock = self.handlers.write();
-        lock.retain(|key, _| uris.contains(key));
+        lock.retain(|key, _| !keys.contains(key));
+        self.fn_handlers.lock().retain(|key| !keys.contains(key));
+    }
+
+    /// Resolves the message handler for a request.
+    ///
+    /// Requests for multi-tenant services are routed to the instance of the
+    /// tenant named in the request headers, while draining or overloaded all
+    /// requests other than those to the admin service are rejected, as are
+    /// requests to services which are drained individually.
+    pub(crate) fn resolve_handler(
+        &self,
+        uri: &str,
+        headers: &HeaderMap,
+    ) -> Result<Arc<dyn OpaqueMessageHandler>, Status> {
+        let (service, _) = crate::split_uri_path(uri);
+
+        if self.draining.load(Ordering::Acquire)
+            && service != AdminService::service_name()
+        {
+            return Err(Status::unavailable("The server is draining"));
+        }
+
+        if self.drained_services.read().contains(service) {
+            return Err(Status::unavailable(format!(
+                "The service {service} is draining"
+            )));
+        }
+
+        let overloaded = self
+            .overload
+            .read()
+            .as_ref()
+            .is_some_and(|detector| detector.is_overloaded());
+        if overloaded && service != AdminService::service_name() {
+            return Err(Status::unavailable("The server is overloaded"));
+        }
+
+        let tenant_service = self.tenants.read().get(service).cloned();
+
+        let handler = match tenant_service {
+            Some(tenant_service) => tenant_service.get_handler(uri, headers)?,
+            None => self.get_handler(uri),
+        };
+
+        handler.ok_or_else(|| Status::unavailable(format!("Unknown service {uri}")))
     }
 
     /// Attempts to get the message handler for a specific service and message.
@@ -159,3 +1505,267 @@ impl ServerState {
         lock.get(&crate::hash(uri)).cloned()
     }
 }
+
+type HandlerMap = BTreeMap<HandlerKey, Arc<dyn OpaqueMessageHandler>>;
+type TenantFactory = Box<dyn Fn(&str) -> Option<HandlerMap> + Send + Sync>;
+
+/// A service with a separate instance per tenant.
+pub(crate) struct TenantService {
+    /// The header containing the tenant of each request.
+    header: HeaderName,
+    /// Creates the handlers for a new tenant, if the tenant exists.
+    factory: TenantFactory,
+    instances: RwLock<BTreeMap<String, Arc<HandlerMap>>>,
+}
+
+impl TenantService {
+    /// Gets the handler of the tenant named in the headers, creating
+    /// the tenant's instance if required.
+    fn get_handler(
+        &self,
+        uri: &str,
+        headers: &HeaderMap,
+    ) -> Result<Option<Arc<dyn OpaqueMessageHandler>>, Status> {
+        let tenant = headers
+            .get(&self.header)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| {
+                Status::not_found(format!("Missing tenant header {:?}", self.header))
+            })?;
+
+        let instance = self.instances.read().get(tenant).cloned();
+        let instance = match instance {
+            Some(instance) => instance,
+            None => {
+                // The lock is held while creating the instance so the factory
+                // is only called once per tenant.
+                let mut instances = self.instances.write();
+                match instances.get(tenant) {
+                    Some(instance) => instance.clone(),
+                    None => {
+                        let handlers = (self.factory)(tenant).ok_or_else(|| {
+                            Status::not_found(format!("Unknown tenant {tenant:?}"))
+                        })?;
+                        let instance = Arc::new(handlers);
+                        instances.insert(tenant.to_string(), instance.clone());
+                        instance
+                    },
+                }
+            },
+        };
+
+        Ok(instance.get(&crate::hash(uri)).cloned())
+    }
+}
+
+#[derive(Default)]
+/// Tracks the number of connections currently being served.
+struct ConnectionTracker {
+    active: AtomicUsize,
+    changed: Notify,
+}
+
+/// A guard marking a connection as active until dropped.
+pub(crate) struct ConnectionGuard(Arc<ConnectionTracker>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.active.fetch_sub(1, Ordering::AcqRel);
+        self.0.changed.notify_waiters();
+    }
+}
+
+/// Panics on a handler being registered under a path which is already taken.
+fn duplicate_handler(service_name: &str, path: &str, uri: &str) -> ! {
+    panic!(
+        "Duplicate handler registration for service {service_name:?}: a handler is \
+         already registered under the path {path:?} ({uri})",
+    );
+}
+
+/// Adds the bytes to the in-flight counter, failing if it would exceed the limit.
+fn reserve_bytes(
+    counter: &AtomicUsize,
+    limit: Option<usize>,
+    len: usize,
+) -> Result<(), Status> {
+    counter
+        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+            // Overflowing totals are rejected rather than saturated, so the
+            // bytes released by the guard always match those reserved.
+            let total = current.checked_add(len)?;
+            limit.is_none_or(|limit| total <= limit).then_some(total)
+        })
+        .map_err(|current| {
+            Status::resource_exhausted(format!(
+                "Body of {len} bytes exceeds the in-flight limit, \
+                 {current} bytes are already being processed"
+            ))
+        })?;
+    Ok(())
+}
+
+/// A guard holding a number of in-flight body bytes until dropped.
+pub(crate) struct InflightBytesGuard {
+    bytes: AtomicUsize,
+    /// The limit when the bytes were first reserved.
+    limit: Option<usize>,
+    exceeded: AtomicBool,
+    counter: Arc<AtomicUsize>,
+}
+
+impl InflightBytesGuard {
+    /// Reserves additional bytes for a body which grew beyond its first reservation.
+    pub(crate) fn grow(&self, len: usize) -> Result<(), Status> {
+        if let Err(status) = reserve_bytes(&self.counter, self.limit, len) {
+            self.exceeded.store(true, Ordering::Release);
+            return Err(status);
+        }
+        self.bytes.fetch_add(len, Ordering::AcqRel);
+        Ok(())
+    }
+
+    /// Replaces the error of a request whose body could not be reserved.
+    ///
+    /// Handlers see the body being cut off as a failure to read it, the
+    /// client is told the actual cause instead.
+    pub(crate) fn check<T>(&self, result: Result<T, Status>) -> Result<T, Status> {
+        match result {
+            Err(_) if self.exceeded.load(Ordering::Acquire) => {
+                Err(Status::resource_exhausted(
+                    "The request body exceeds the in-flight limit",
+                ))
+            },
+            result => result,
+        }
+    }
+}
+
+impl Drop for InflightBytesGuard {
+    fn drop(&mut self) {
+        self.counter
+            .fetch_sub(*self.bytes.get_mut(), Ordering::AcqRel);
+    }
+}
+
+#[derive(Default)]
+/// Tracks the requests currently being handled by the server.
+struct InflightRegistry {
+    next_id: AtomicU64,
+    requests: Mutex<BTreeMap<u64, InflightEntry>>,
+}
+
+struct InflightEntry {
+    uri: String,
+    remote_addr: SocketAddr,
+    started_at: SystemTime,
+    start: Instant,
+    cancellation: CancellationToken,
+}
+
+/// A guard marking a request as in-flight until dropped.
+pub(crate) struct InflightGuard {
+    id: u64,
+    admitted_at: Instant,
+    slow_threshold: Option<Duration>,
+    cancellation: CancellationToken,
+    completed: CancellationToken,
+    disconnected: CancellationToken,
+    /// If the handler produced a reply before the guard was dropped.
+    replied: bool,
+    registry: Arc<InflightRegistry>,
+}
+
+impl InflightGuard {
+    /// The id of the request.
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// When the request was admitted by the server.
+    pub(crate) fn admitted_at(&self) -> Instant {
+        self.admitted_at
+    }
+
+    /// The token cancelled when the request is aborted.
+    pub(crate) fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// The token cancelled once the request has been handled.
+    pub(crate) fn completed(&self) -> &CancellationToken {
+        &self.completed
+    }
+
+    /// The token cancelled if the client disconnects before the reply
+    /// has been produced.
+    pub(crate) fn disconnected(&self) -> &CancellationToken {
+        &self.disconnected
+    }
+
+    /// Marks the reply as produced, the request is no longer considered
+    /// abandoned once the guard is dropped.
+    pub(crate) fn set_replied(&mut self) {
+        self.replied = true;
+    }
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        let entry = self.registry.requests.lock().remove(&self.id);
+        if !self.replied {
+            // The request was dropped early, i.e. because the client reset
+            // the stream or closed the connection.
+            self.disconnected.cancel();
+            self.cancellation.cancel();
+        }
+        self.completed.cancel();
+
+        let Some((threshold, entry)) = self.slow_threshold.zip(entry) else {
+            return;
+        };
+        let duration = self.admitted_at.elapsed();
+        if duration > threshold {
+            let (service, path) = crate::split_uri_path(&entry.uri);
+            warn!(
+                service,
+                path,
+                duration = ?duration,
+                threshold = ?threshold,
+                remote_addr = %entry.remote_addr,
+                request_id = self.id,
+                "Slow request exceeded the threshold.",
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorCode;
+
+    #[test]
+    fn test_inflight_bytes_overflow() {
+        let state = ServerState::default();
+
+        let guard = state
+            .reserve_inflight_bytes(usize::MAX - 10)
+            .expect("Reservation should fit without a limit");
+        let status = state
+            .reserve_inflight_bytes(usize::MAX - 10)
+            .err()
+            .expect("Overflowing reservation should be rejected");
+        assert_eq!(status.code, ErrorCode::ResourceExhausted);
+        assert_eq!(
+            state.inflight_bytes.load(Ordering::Acquire),
+            usize::MAX - 10
+        );
+
+        guard
+            .grow(20)
+            .expect_err("Overflowing growth should be rejected");
+        drop(guard);
+        assert_eq!(state.inflight_bytes.load(Ordering::Acquire), 0);
+    }
+}
@@ -0,0 +1,583 @@
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll, Waker};
+
+use async_trait::async_trait;
+use bytes::{Buf, Bytes, BytesMut};
+use futures_core::Stream;
+use http::{HeaderMap, HeaderValue};
+use hyper::body::HttpBody;
+use rkyv::{AlignedVec, Archive, Serialize};
+use tokio::sync::mpsc;
+
+use crate::rkyv_tooling::{DataView, DatacakeSerializer};
+use crate::{Body, RequestContents, Status, TryIntoBody};
+
+/// The trailer carrying the final status of a streamed reply.
+///
+/// The value is `ok` if the stream completed successfully, otherwise it is
+/// the hex encoded, serialized [Status] the stream was aborted with.
+pub const STREAM_STATUS_TRAILER: &str = "x-datacake-stream-status";
+
+pub(crate) const STREAM_OK: &str = "ok";
+const FRAME_HEADER_SIZE: usize = 4;
+
+/// A reply which streams a sequence of messages to the client.
+///
+/// A stream is created via [ReplyStream::channel], the [ReplyStream] is
+/// returned from the handler as its reply while the [StreamSender] is used to
+/// produce the items, typically from a spawned task. Each item is sent to
+/// the client as soon as it is produced, with the sender waiting for the
+/// client to read previous items before sending more.
+///
+/// Once all items have been sent, the stream is terminated with a final status
+/// sent as a trailer: [StreamSender::finish] completes the stream successfully
+/// and [StreamSender::abort] terminates it with an error [Status].
+/// If the sender is dropped without doing either, the stream is treated as
+/// aborted, so the client can always tell a stream which ended normally
+/// apart from one which was interrupted midway.
+///
+/// On the client, the reply is received as a [Streaming] of zero-copy views.
+///
+/// For simpler cases, a stream can also be created from an iterator via
+/// [FromIterator] or from a bounded channel via [ReplyStream::from_receiver]:
+///
+/// ```rust
+/// # use datacake_rpc::{Handler, ReplyStream, Request, RpcService, ServiceRegistry, Status};
+/// # use rkyv::{Archive, Deserialize, Serialize};
+/// use tokio::sync::mpsc;
+///
+/// # #[repr(C)]
+/// # #[derive(Serialize, Deserialize, Archive, Debug)]
+/// # #[archive(check_bytes)]
+/// # pub struct Count {
+/// #     up_to: u64,
+/// # }
+/// # pub struct CounterService;
+/// # impl RpcService for CounterService {
+/// #     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+/// #         registry.add_handler::<Count>();
+/// #     }
+/// # }
+/// #[datacake_rpc::async_trait]
+/// impl Handler<Count> for CounterService {
+///     type Reply = ReplyStream<u64>;
+///
+///     async fn on_message(&self, msg: Request<Count>) -> Result<Self::Reply, Status> {
+///         let up_to = msg.up_to;
+///         let (tx, rx) = mpsc::channel(16);
+///
+///         tokio::spawn(async move {
+///             for n in 0..up_to {
+///                 if tx.send(Ok(n)).await.is_err() {
+///                     return;
+///                 }
+///             }
+///         });
+///
+///         Ok(ReplyStream::from_receiver(rx))
+///     }
+/// }
+/// ```
+///
+/// ```rust
+/// use rkyv::{Archive, Deserialize, Serialize};
+/// use datacake_rpc::{Handler, ReplyStream, Request, RpcService, ServiceRegistry, Status};
+///
+/// #[repr(C)]
+/// #[derive(Serialize, Deserialize, Archive, Debug)]
+/// #[archive(check_bytes)]
+/// pub struct Count {
+///     up_to: u64,
+/// }
+///
+/// pub struct CounterService;
+///
+/// impl RpcService for CounterService {
+///     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+///         registry.add_handler::<Count>();
+///     }
+/// }
+///
+/// #[datacake_rpc::async_trait]
+/// impl Handler<Count> for CounterService {
+///     type Reply = ReplyStream<u64>;
+///
+///     async fn on_message(&self, msg: Request<Count>) -> Result<Self::Reply, Status> {
+///         let up_to = msg.up_to;
+///         let (mut sender, stream) = ReplyStream::channel();
+///
+///         tokio::spawn(async move {
+///             for n in 0..up_to {
+///                 if sender.send(&n).await.is_err() {
+///                     // The client has gone away.
+///                     return;
+///                 }
+///             }
+///             sender.finish().await;
+///         });
+///
+///         Ok(stream)
+///     }
+/// }
+/// ```
+pub struct ReplyStream<T> {
+    body: hyper::Body,
+    _msg: PhantomData<fn() -> T>,
+}
+
+impl<T> ReplyStream<T> {
+    /// Creates a new reply stream and the sender used to produce its items.
+    pub fn channel() -> (StreamSender<T>, Self) {
+        let (sender, body) = hyper::Body::channel();
+        let sender = StreamSender {
+            sender,
+            _msg: PhantomData,
+        };
+        let stream = Self {
+            body,
+            _msg: PhantomData,
+        };
+        (sender, stream)
+    }
+}
+
+impl<T> ReplyStream<T>
+where
+    T: Archive + Serialize<DatacakeSerializer> + Send + 'static,
+{
+    /// Creates a reply stream sending each item received from the channel.
+    ///
+    /// The stream completes once all senders of the channel are dropped, or is
+    /// aborted with the status of the first `Err` item received. The bounded
+    /// capacity of the channel provides backpressure, with items only being taken
+    /// from the channel once the client is ready to receive them.
+    pub fn from_receiver(mut receiver: mpsc::Receiver<Result<T, Status>>) -> Self {
+        let (mut sender, stream) = Self::channel();
+
+        crate::runtime::spawn(async move {
+            while let Some(item) = receiver.recv().await {
+                let item = match item {
+                    Ok(item) => item,
+                    Err(status) => {
+                        sender.abort(status).await;
+                        return;
+                    },
+                };
+
+                if sender.send_owned(item).await.is_err() {
+                    // The client has gone away.
+                    return;
+                }
+            }
+            sender.finish().await;
+        });
+
+        stream
+    }
+}
+
+impl<T> From<mpsc::Receiver<Result<T, Status>>> for ReplyStream<T>
+where
+    T: Archive + Serialize<DatacakeSerializer> + Send + 'static,
+{
+    fn from(receiver: mpsc::Receiver<Result<T, Status>>) -> Self {
+        Self::from_receiver(receiver)
+    }
+}
+
+impl<T> FromIterator<T> for ReplyStream<T>
+where
+    T: Archive + Serialize<DatacakeSerializer> + Send + 'static,
+{
+    /// Creates a reply stream sending each item of the iterator.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let items = iter.into_iter().collect::<Vec<_>>();
+        let (mut sender, stream) = Self::channel();
+
+        crate::runtime::spawn(async move {
+            for item in items {
+                if sender.send_owned(item).await.is_err() {
+                    return;
+                }
+            }
+            sender.finish().await;
+        });
+
+        stream
+    }
+}
+
+impl<T> Debug for ReplyStream<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplyStream").finish_non_exhaustive()
+    }
+}
+
+impl<T> TryIntoBody for ReplyStream<T> {
+    fn try_into_body(self) -> Result<Body, Status> {
+        Ok(Body::new(self.body))
+    }
+}
+
+#[async_trait]
+impl<T> RequestContents for ReplyStream<T>
+where
+    T: Archive + Send + 'static,
+    T::Archived: 'static,
+{
+    type Content = Streaming<T>;
+
+    async fn from_body(body: Body) -> Result<Self::Content, Status> {
+        Ok(Streaming::new(body.into_inner()))
+    }
+}
+
+/// The producing half of a [ReplyStream].
+pub struct StreamSender<T> {
+    sender: hyper::body::Sender,
+    _msg: PhantomData<fn(T)>,
+}
+
+impl<T> StreamSender<T>
+where
+    T: Archive + Serialize<DatacakeSerializer>,
+{
+    /// Sends an item to the client.
+    ///
+    /// This waits until the client is ready to receive more data and
+    /// returns an error if the client has disconnected.
+    pub async fn send(&mut self, item: &T) -> Result<(), Status> {
+        let bytes = crate::rkyv_tooling::to_view_bytes(item)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        self.send_frame(&bytes).await
+    }
+
+    /// Sends an owned item, without holding a reference to it while waiting
+    /// for the client so the future is [Send] even if `T` is not [Sync].
+    async fn send_owned(&mut self, item: T) -> Result<(), Status> {
+        let bytes = crate::rkyv_tooling::to_view_bytes(&item)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        drop(item);
+        self.send_frame(&bytes).await
+    }
+}
+
+impl<T> StreamSender<T> {
+    pub(crate) async fn send_frame(&mut self, data: &[u8]) -> Result<(), Status> {
+        let frame = encode_frame(data)?;
+        self.send_encoded_frame(frame).await
+    }
+
+    /// Sends a frame which has already been encoded via [encode_frame].
+    pub(crate) async fn send_encoded_frame(
+        &mut self,
+        frame: Bytes,
+    ) -> Result<(), Status> {
+        self.sender
+            .send_data(frame)
+            .await
+            .map_err(Status::connection)
+    }
+
+    /// Completes the stream successfully.
+    pub async fn finish(self) {
+        self.terminate(HeaderValue::from_static(STREAM_OK)).await
+    }
+
+    /// Terminates the stream with the given status.
+    ///
+    /// The client receives the status as the final item of the stream.
+    pub async fn abort(self, status: Status) {
+        match encode_stream_status(&status) {
+            Some(value) => self.terminate(value).await,
+            // Dropping the sender without trailers aborts the stream.
+            None => self.sender.abort(),
+        }
+    }
+
+    async fn terminate(mut self, status: HeaderValue) {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(STREAM_STATUS_TRAILER, status);
+        let _ = self.sender.send_trailers(trailers).await;
+    }
+
+    /// Returns if the client has disconnected and is no longer receiving items.
+    pub fn is_closed(&mut self) -> bool {
+        let mut cx = Context::from_waker(Waker::noop());
+        matches!(self.sender.poll_ready(&mut cx), Poll::Ready(Err(_)))
+    }
+}
+
+impl<T> Debug for StreamSender<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamSender").finish_non_exhaustive()
+    }
+}
+
+/// A stream of messages received from a [ReplyStream].
+///
+/// Each item is a zero-copy view of a message sent by the server. If the
+/// server terminates the stream with an error, or the stream is interrupted
+/// before the server completes it, the final item is `Err(Status)`.
+/// A stream which completed successfully simply ends.
+///
+/// This implements [Stream], alternatively [Streaming::next] can be used
+/// to receive items without any additional helpers.
+pub struct Streaming<T> {
+    body: hyper::Body,
+    buffer: BytesMut,
+    state: StreamState,
+    trailers: Option<HeaderMap>,
+    _msg: PhantomData<fn() -> T>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum StreamState {
+    Data,
+    Trailers,
+    Done,
+}
+
+impl<T> Streaming<T> {
+    pub(crate) fn new(body: hyper::Body) -> Self {
+        Self {
+            body,
+            buffer: BytesMut::new(),
+            state: StreamState::Data,
+            trailers: None,
+            _msg: PhantomData,
+        }
+    }
+
+    /// Takes the next complete frame from the buffer if one is available.
+    fn next_frame(&mut self) -> Option<Bytes> {
+        if self.buffer.len() < FRAME_HEADER_SIZE {
+            return None;
+        }
+
+        let len_bytes = self.buffer[..FRAME_HEADER_SIZE].try_into().ok()?;
+        let len = u32::from_le_bytes(len_bytes) as usize;
+        if self.buffer.len() < FRAME_HEADER_SIZE + len {
+            return None;
+        }
+
+        self.buffer.advance(FRAME_HEADER_SIZE);
+        Some(self.buffer.split_to(len).freeze())
+    }
+
+    /// Takes the trailers the stream was terminated with, once it has completed.
+    pub(crate) fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.trailers.take()
+    }
+
+    /// Polls the body for the next raw frame of the stream.
+    pub(crate) fn poll_next_frame(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Status>>> {
+        loop {
+            match self.state {
+                StreamState::Done => return Poll::Ready(None),
+                StreamState::Data => {
+                    if let Some(frame) = self.next_frame() {
+                        return Poll::Ready(Some(Ok(frame)));
+                    }
+
+                    match ready!(Pin::new(&mut self.body).poll_data(cx)) {
+                        Some(Ok(chunk)) => self.buffer.extend_from_slice(&chunk),
+                        Some(Err(e)) => {
+                            self.state = StreamState::Done;
+                            return Poll::Ready(Some(Err(Status::connection(e))));
+                        },
+                        None => {
+                            if !self.buffer.is_empty() {
+                                self.state = StreamState::Done;
+                                return Poll::Ready(Some(Err(Status::connection(
+                                    "The stream ended with an incomplete item",
+                                ))));
+                            }
+                            self.state = StreamState::Trailers;
+                        },
+                    }
+                },
+                StreamState::Trailers => {
+                    let trailers = ready!(Pin::new(&mut self.body).poll_trailers(cx));
+                    self.state = StreamState::Done;
+
+                    return match trailers {
+                        Ok(trailers) => {
+                            self.trailers = trailers;
+                            let status = self
+                                .trailers
+                                .as_ref()
+                                .and_then(|t| t.get(STREAM_STATUS_TRAILER));
+                            match parse_stream_status(status) {
+                                Ok(()) => Poll::Ready(None),
+                                Err(status) => Poll::Ready(Some(Err(status))),
+                            }
+                        },
+                        Err(e) => Poll::Ready(Some(Err(Status::connection(e)))),
+                    };
+                },
+            }
+        }
+    }
+}
+
+impl<T> Streaming<T>
+where
+    T: Archive,
+    T::Archived: 'static,
+{
+    /// Receives the next item of the stream.
+    ///
+    /// Returns `None` once the stream has completed.
+    pub async fn next(&mut self) -> Option<Result<DataView<T>, Status>> {
+        std::future::poll_fn(|cx| self.poll_next_item(cx)).await
+    }
+
+    fn poll_next_item(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<DataView<T>, Status>>> {
+        let frame = match ready!(self.poll_next_frame(cx)) {
+            Some(Ok(frame)) => frame,
+            Some(Err(status)) => return Poll::Ready(Some(Err(status))),
+            None => return Poll::Ready(None),
+        };
+
+        let mut data = AlignedVec::with_capacity(frame.len());
+        data.extend_from_slice(&frame);
+        let view = DataView::using(data).map_err(|_| Status::invalid());
+        if view.is_err() {
+            self.state = StreamState::Done;
+        }
+
+        Poll::Ready(Some(view))
+    }
+}
+
+impl<T> Stream for Streaming<T>
+where
+    T: Archive,
+    T::Archived: 'static,
+{
+    type Item = Result<DataView<T>, Status>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_item(cx)
+    }
+}
+
+impl<T> Debug for Streaming<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Streaming")
+            .field("buffered", &self.buffer.len())
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+/// Encodes a serialized item as a frame of the stream, prefixed by its length.
+pub(crate) fn encode_frame(data: &[u8]) -> Result<Bytes, Status> {
+    let len = u32::try_from(data.len())
+        .map_err(|_| Status::internal("Stream item exceeds the maximum frame size"))?;
+
+    let mut frame = BytesMut::with_capacity(FRAME_HEADER_SIZE + data.len());
+    frame.extend_from_slice(&len.to_le_bytes());
+    frame.extend_from_slice(data);
+    Ok(frame.freeze())
+}
+
+/// Encodes the status a stream is aborted with as the value of its trailer.
+pub(crate) fn encode_stream_status(status: &Status) -> Option<HeaderValue> {
+    let bytes = crate::rkyv_tooling::to_view_bytes(status).ok()?;
+    HeaderValue::from_str(&encode_hex(&bytes)).ok()
+}
+
+/// Checks the final status of a body which may have been produced as a stream.
+///
+/// Bodies without the status trailer are treated as complete.
+pub(crate) fn check_stream_status(trailers: Option<&HeaderMap>) -> Result<(), Status> {
+    match trailers.and_then(|t| t.get(STREAM_STATUS_TRAILER)) {
+        None => Ok(()),
+        status => parse_stream_status(status),
+    }
+}
+
+/// Parses the final status of the stream from its trailer.
+fn parse_stream_status(value: Option<&HeaderValue>) -> Result<(), Status> {
+    let Some(value) = value else {
+        return Err(Status::connection(
+            "The stream was interrupted before it completed",
+        ));
+    };
+
+    if value == STREAM_OK {
+        return Ok(());
+    }
+
+    let bytes = decode_hex(value.as_bytes()).ok_or_else(Status::invalid)?;
+    let mut data = AlignedVec::with_capacity(bytes.len());
+    data.extend_from_slice(&bytes);
+
+    let status = DataView::<Status>::using(data).map_err(|_| Status::invalid())?;
+    Err(status.to_owned().unwrap_or_else(|_| Status::invalid()))
+}
+
+fn encode_hex(data: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    let mut encoded = String::with_capacity(data.len() * 2);
+    for byte in data {
+        encoded.push(HEX[(byte >> 4) as usize] as char);
+        encoded.push(HEX[(byte & 0xf) as usize] as char);
+    }
+    encoded
+}
+
+fn decode_hex(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.len().is_multiple_of(2) {
+        return None;
+    }
+
+    data.chunks_exact(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_roundtrip() {
+        let data = [0u8, 1, 15, 16, 127, 128, 255];
+        let encoded = encode_hex(&data);
+        assert_eq!(encoded, "00010f107f80ff");
+        assert_eq!(decode_hex(encoded.as_bytes()), Some(data.to_vec()));
+        assert_eq!(decode_hex(b"0"), None);
+        assert_eq!(decode_hex(b"zz"), None);
+    }
+
+    #[test]
+    fn test_parse_stream_status() {
+        assert!(parse_stream_status(Some(&HeaderValue::from_static(STREAM_OK))).is_ok());
+
+        let status = Status::internal("Oops");
+        let bytes = crate::rkyv_tooling::to_view_bytes(&status).unwrap();
+        let value = HeaderValue::from_str(&encode_hex(&bytes)).unwrap();
+        assert_eq!(parse_stream_status(Some(&value)), Err(status));
+
+        let err = parse_stream_status(None).unwrap_err();
+        assert_eq!(err.code, crate::ErrorCode::ConnectionError);
+    }
+}
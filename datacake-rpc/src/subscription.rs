@@ -0,0 +1,173 @@
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+
+use bytes::Bytes;
+use rkyv::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::rkyv_tooling::DatacakeSerializer;
+use crate::stream::encode_frame;
+use crate::{ReplyStream, Status};
+
+/// Publishes events to every client subscribed to it.
+///
+/// A broadcaster is held by the service, i.e. as one of its fields, with the
+/// handler of the subscription message replying with [Broadcaster::subscribe].
+/// The client then receives each event published after it subscribed as an item
+/// of its [Streaming](crate::Streaming) reply, for as long as it keeps the stream open.
+///
+/// Each event is serialized once when it is published and the same buffer is
+/// sent to every subscriber. Handles can be cloned freely, i.e. to publish from
+/// a background task, events published through any handle reach every subscriber.
+///
+/// # Lagging
+///
+/// The broadcaster buffers up to `capacity` events which have not yet been sent
+/// to all subscribers. A subscriber which falls further behind than that, i.e.
+/// as the client stopped reading, has its stream aborted with
+/// [ErrorCode::ResourceExhausted](crate::ErrorCode::ResourceExhausted) rather than
+/// silently missing events. Once every handle of the broadcaster has been dropped,
+/// the stream of each subscriber completes successfully.
+///
+/// A subscriber which disconnects is removed once the next event is published.
+///
+/// ```rust
+/// use rkyv::{Archive, Deserialize, Serialize};
+/// use datacake_rpc::{Broadcaster, Handler, ReplyStream, Request, RpcService, ServiceRegistry, Status};
+///
+/// #[repr(C)]
+/// #[derive(Serialize, Deserialize, Archive, Debug)]
+/// #[archive(check_bytes)]
+/// pub struct Subscribe;
+///
+/// #[repr(C)]
+/// #[derive(Serialize, Deserialize, Archive, Debug)]
+/// #[archive(check_bytes)]
+/// pub struct NodeJoined {
+///     node_id: u64,
+/// }
+///
+/// pub struct MembershipService {
+///     events: Broadcaster<NodeJoined>,
+/// }
+///
+/// impl RpcService for MembershipService {
+///     fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+///         registry.add_handler::<Subscribe>();
+///     }
+/// }
+///
+/// #[datacake_rpc::async_trait]
+/// impl Handler<Subscribe> for MembershipService {
+///     type Reply = ReplyStream<NodeJoined>;
+///
+///     async fn on_message(&self, _msg: Request<Subscribe>) -> Result<Self::Reply, Status> {
+///         Ok(self.events.subscribe())
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Status> {
+/// let events = Broadcaster::new(64);
+/// let service = MembershipService { events: events.clone() };
+/// // ... once a node joins.
+/// events.publish(&NodeJoined { node_id: 1 })?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Broadcaster<T> {
+    sender: broadcast::Sender<Bytes>,
+    _msg: PhantomData<fn(T)>,
+}
+
+impl<T> Broadcaster<T> {
+    /// Creates a new broadcaster buffering up to `capacity` events for
+    /// subscribers which have not yet received them.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is `0`.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            _msg: PhantomData,
+        }
+    }
+
+    /// The number of clients currently subscribed.
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Creates a reply stream which forwards every event published from now on
+    /// to the client, used as the reply of the subscription handler.
+    pub fn subscribe(&self) -> ReplyStream<T>
+    where
+        T: 'static,
+    {
+        let mut receiver = self.sender.subscribe();
+        let (mut sender, stream) = ReplyStream::channel();
+
+        crate::runtime::spawn(async move {
+            loop {
+                let frame = match receiver.recv().await {
+                    Ok(frame) => frame,
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(missed)) => {
+                        let status = Status::resource_exhausted(format!(
+                            "The subscriber fell behind and missed {missed} events",
+                        ));
+                        sender.abort(status).await;
+                        return;
+                    },
+                };
+
+                if sender.send_encoded_frame(frame).await.is_err() {
+                    // The client has gone away.
+                    return;
+                }
+            }
+            sender.finish().await;
+        });
+
+        stream
+    }
+}
+
+impl<T> Broadcaster<T>
+where
+    T: Serialize<DatacakeSerializer>,
+{
+    /// Publishes an event to every current subscriber.
+    ///
+    /// Returns the number of subscribers the event was sent to, an event
+    /// published without any subscribers is discarded.
+    pub fn publish(&self, event: &T) -> Result<usize, Status> {
+        if self.sender.receiver_count() == 0 {
+            return Ok(0);
+        }
+
+        let bytes = crate::rkyv_tooling::to_view_bytes(event)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let frame = encode_frame(&bytes)?;
+        Ok(self.sender.send(frame).unwrap_or(0))
+    }
+}
+
+impl<T> Clone for Broadcaster<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            _msg: PhantomData,
+        }
+    }
+}
+
+impl<T> Debug for Broadcaster<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Broadcaster")
+            .field("subscribers", &self.subscriber_count())
+            .finish()
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::{Body, Status};
+
+#[async_trait]
+/// A middleware transforming request and reply bodies on the server.
+///
+/// Transforms are added via [Server::add_body_transform](crate::Server::add_body_transform)
+/// and run around every handler, i.e. to decompress or decrypt a request before
+/// it is deserialized and to compress or encrypt the reply afterwards.
+///
+/// The request body passed to the transform carries the request headers, and any
+/// changes made to them are visible to the handler. Likewise the reply body carries
+/// the reply headers which are sent to the client.
+///
+/// Transforms are free to produce bodies made up of any number of chunks, the
+/// handler copies the final body into an aligned buffer before it is accessed as
+/// an archived value, so the alignment required by rkyv is always preserved.
+///
+/// ```rust
+/// use datacake_rpc::{Body, BodyTransform, Status};
+///
+/// /// Records the number of bytes received.
+/// pub struct ByteCounter(std::sync::atomic::AtomicU64);
+///
+/// #[datacake_rpc::async_trait]
+/// impl BodyTransform for ByteCounter {
+///     async fn transform_request(&self, body: Body) -> Result<Body, Status> {
+///         let (inner, headers) = body.into_parts();
+///         let bytes = hyper::body::to_bytes(inner)
+///             .await
+///             .map_err(Status::connection)?;
+///         self.0.fetch_add(bytes.len() as u64, std::sync::atomic::Ordering::Relaxed);
+///         Ok(Body::with_headers(bytes.into(), headers))
+///     }
+/// }
+/// ```
+pub trait BodyTransform: Send + Sync + 'static {
+    /// Transforms the request body before it is passed to the handler.
+    ///
+    /// By default the body is returned unchanged.
+    async fn transform_request(&self, body: Body) -> Result<Body, Status> {
+        Ok(body)
+    }
+
+    /// Transforms the reply body produced by the handler before it is
+    /// sent to the client.
+    ///
+    /// By default the body is returned unchanged.
+    async fn transform_reply(&self, body: Body) -> Result<Body, Status> {
+        Ok(body)
+    }
+}
+
+/// The ordered set of transforms applied by the server.
+pub(crate) type BodyTransforms = Arc<[Arc<dyn BodyTransform>]>;
+
+/// Runs the request through each transform in the order they were added.
+pub(crate) async fn transform_request(
+    transforms: &[Arc<dyn BodyTransform>],
+    mut body: Body,
+) -> Result<Body, Status> {
+    for transform in transforms {
+        body = transform.transform_request(body).await?;
+    }
+    Ok(body)
+}
+
+/// Runs the reply through each transform in the reverse order they were added,
+/// so the first transform added sees the body last.
+pub(crate) async fn transform_reply(
+    transforms: &[Arc<dyn BodyTransform>],
+    mut body: Body,
+) -> Result<Body, Status> {
+    for transform in transforms.iter().rev() {
+        body = transform.transform_reply(body).await?;
+    }
+    Ok(body)
+}
@@ -1,8 +1,22 @@
-use bytes::Buf;
+use bytes::{Buf, Bytes};
 use hyper::body::HttpBody;
 use hyper::Body;
 use rkyv::AlignedVec;
 
+/// The maximum number of bytes allocated up front based on the size hint of a body.
+///
+/// The size hint is derived from the `content-length` sent by the peer, so it
+/// is only trusted up to this limit, larger bodies grow the buffer as they
+/// are received.
+const MAX_PREALLOCATION: usize = 64 << 20;
+
+/// The number of bytes to allocate up front for a body with the given size hint.
+fn preallocation(size_hint: u64) -> usize {
+    usize::try_from(size_hint)
+        .unwrap_or(usize::MAX)
+        .min(MAX_PREALLOCATION)
+}
+
 pub async fn to_aligned(
     mut body: Body,
 ) -> Result<AlignedVec, <Body as HttpBody>::Error> {
@@ -21,8 +35,51 @@ pub async fn to_aligned(
         return Ok(vec);
     };
 
-    // With more than 1 buf, we gotta flatten into a Vec first.
-    let cap = first.remaining() + second.remaining() + body.size_hint().lower() as usize;
+    collect_remaining(first, second, &mut body).await
+}
+
+/// Collects the body into a single chunk which starts at an address aligned
+/// to `align` bytes, returning if the data had to be copied.
+///
+/// A body made up of a single aligned chunk is returned as-is, otherwise the
+/// chunks are copied into an aligned buffer, so `align` must not exceed
+/// [AlignedVec::ALIGNMENT]. The trailers of the body are left to be read by
+/// the caller.
+pub async fn to_aligned_bytes(
+    body: &mut Body,
+    align: usize,
+) -> Result<(Bytes, bool), <Body as HttpBody>::Error> {
+    let first = if let Some(buf) = body.data().await {
+        buf?
+    } else {
+        return Ok((Bytes::new(), false));
+    };
+
+    let second = if let Some(buf) = body.data().await {
+        buf?
+    } else if (first.as_ptr() as usize).is_multiple_of(align) {
+        return Ok((first, false));
+    } else {
+        let mut vec = AlignedVec::with_capacity(first.len());
+        vec.extend_from_slice(&first);
+        return Ok((Bytes::from_owner(vec), true));
+    };
+
+    let vec = collect_remaining(first, second, body).await?;
+    Ok((Bytes::from_owner(vec), true))
+}
+
+/// Flattens the first two chunks and the rest of the body into a single buffer.
+async fn collect_remaining(
+    first: Bytes,
+    second: Bytes,
+    body: &mut Body,
+) -> Result<AlignedVec, <Body as HttpBody>::Error> {
+    let cap = first
+        .remaining()
+        .saturating_add(second.remaining())
+        .saturating_add(preallocation(body.size_hint().lower()))
+        .min(AlignedVec::MAX_CAPACITY);
     let mut vec = AlignedVec::with_capacity(cap);
     vec.extend_from_slice(&first);
     vec.extend_from_slice(&second);
@@ -33,3 +90,20 @@ pub async fn to_aligned(
 
     Ok(vec)
 }
+
+/// Reads the body into the provided buffer, reusing its existing allocation.
+///
+/// The buffer is cleared before any data is written to it.
+pub async fn to_aligned_into(
+    mut body: Body,
+    buffer: &mut AlignedVec,
+) -> Result<(), <Body as HttpBody>::Error> {
+    buffer.clear();
+    buffer.reserve(preallocation(body.size_hint().lower()));
+
+    while let Some(buf) = body.data().await {
+        buffer.extend_from_slice(&buf?);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use crate::request::{Request, RequestContents};
+use crate::Status;
+
+/// Validates the contents of a message before it is passed to its handler.
+///
+/// Messages implementing this can be registered via
+/// [ServiceRegistry::add_validated_handler](crate::ServiceRegistry::add_validated_handler),
+/// the server then calls [Validate::validate] once the message has been
+/// deserialized and rejects invalid messages with
+/// [Status::invalid_argument] without calling the handler.
+///
+/// The message is passed as its [Request], so it can be checked directly on
+/// the archived view or, if that is more convenient, on an owned copy produced
+/// via [DataView::to_owned](crate::DataView::to_owned):
+///
+/// ```rust
+/// use datacake_rpc::{Request, Validate};
+/// use rkyv::{Archive, Deserialize, Serialize};
+///
+/// #[repr(C)]
+/// #[derive(Serialize, Deserialize, Archive)]
+/// #[archive(check_bytes)]
+/// pub struct CreateUser {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// impl Validate for CreateUser {
+///     fn validate(msg: &Request<Self>) -> Result<(), String> {
+///         let user = msg.archived();
+///         if user.name.is_empty() {
+///             return Err("The name must not be empty".to_string());
+///         }
+///         if user.age > 150 {
+///             return Err(format!("{} is not a valid age", user.age));
+///         }
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait Validate: RequestContents + Sized {
+    /// Checks the message, returning why it is invalid if it is.
+    fn validate(msg: &Request<Self>) -> Result<(), String>;
+}
+
+/// Validates the message of a request on behalf of its handler.
+pub(crate) type Validator<Msg> = fn(&Request<Msg>) -> Result<(), Status>;
+
+/// The validator of messages implementing [Validate].
+pub(crate) fn validator<Msg>() -> Validator<Msg>
+where
+    Msg: Validate,
+{
+    |msg| Msg::validate(msg).map_err(Status::invalid_argument)
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    AbortRequest,
+    AdminService,
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct SlowMessage {
+    delay_ms: u64,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn service_name() -> &'static str {
+        "my-service"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<SlowMessage>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<SlowMessage> for MyService {
+    type Reply = u64;
+
+    async fn on_message(
+        &self,
+        msg: Request<SlowMessage>,
+    ) -> Result<Self::Reply, Status> {
+        let delay = Duration::from_millis(msg.delay_ms);
+        tokio::select! {
+            _ = msg.cancellation_token().cancelled() => {
+                Err(Status::internal("Request aborted"))
+            },
+            _ = tokio::time::sleep(delay) => Ok(msg.delay_ms),
+        }
+    }
+}
+
+async fn wait_for_inflight(server: &Server) -> u64 {
+    for _ in 0..50 {
+        if let Some(request) = server
+            .inflight_requests()
+            .into_iter()
+            .find(|r| r.service == "my-service")
+        {
+            return request.id;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("Request never became in-flight");
+}
+
+#[tokio::test]
+async fn test_abort_request() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    server.enable_admin_service();
+
+    let channel = Channel::connect(addr);
+    let rpc_client = RpcClient::<MyService>::new(channel.clone());
+    let client = rpc_client.clone();
+    let slow =
+        tokio::spawn(
+            async move { client.send(&SlowMessage { delay_ms: 10_000 }).await },
+        );
+
+    let id = wait_for_inflight(&server).await;
+    assert!(server.abort_request(id), "Request should be aborted");
+
+    let status = slow
+        .await
+        .unwrap()
+        .expect_err("Aborted request should fail");
+    assert_eq!(status.code, ErrorCode::InternalError);
+    assert!(!server.abort_request(id), "Request should no longer exist");
+
+    // Abort remotely via the admin service.
+    let client = rpc_client.clone();
+    let slow =
+        tokio::spawn(
+            async move { client.send(&SlowMessage { delay_ms: 10_000 }).await },
+        );
+
+    let id = wait_for_inflight(&server).await;
+    let admin_client = RpcClient::<AdminService>::new(channel);
+    let aborted = admin_client
+        .send(&AbortRequest { id })
+        .await
+        .expect("Send abort request");
+    assert!(aborted.to_owned().unwrap(), "Request should be aborted");
+    slow.await
+        .unwrap()
+        .expect_err("Aborted request should fail");
+
+    let aborted = admin_client
+        .send(&AbortRequest { id: u64::MAX })
+        .await
+        .expect("Send abort request");
+    assert!(
+        !aborted.to_owned().unwrap(),
+        "Unknown id should not be aborted"
+    );
+
+    server.shutdown();
+}
@@ -0,0 +1,89 @@
+use std::time::{Duration, Instant};
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServerConfig,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Ping;
+
+pub struct PingService;
+
+impl RpcService for PingService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Ping>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Ping> for PingService {
+    type Reply = u64;
+
+    async fn on_message(&self, _msg: Request<Ping>) -> Result<Self::Reply, Status> {
+        Ok(1)
+    }
+}
+
+#[tokio::test]
+async fn test_accept_rate_limit() {
+    let addr = test_helper::get_unused_addr();
+
+    let config = ServerConfig {
+        accept_rate_limit: Some((4, 1)),
+        ..Default::default()
+    };
+    let server = Server::listen_with_config(addr, &config).await.unwrap();
+    server.add_service(PingService);
+    println!("Listening to address {}!", addr);
+    assert!(!server.accept_throttled());
+
+    let start = Instant::now();
+    let mut tasks = Vec::new();
+    for _ in 0..3 {
+        let client = RpcClient::<PingService>::new(Channel::connect(addr));
+        tasks.push(tokio::spawn(async move {
+            client.send(&Ping).await.unwrap();
+            start.elapsed()
+        }));
+    }
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(
+        server.accept_throttled(),
+        "The server should be waiting for the limit"
+    );
+
+    let mut elapsed = Vec::new();
+    for task in tasks {
+        elapsed.push(task.await.unwrap());
+    }
+    elapsed.sort();
+    assert!(elapsed[0] < Duration::from_millis(200), "{elapsed:?}");
+    assert!(elapsed[2] >= Duration::from_millis(450), "{elapsed:?}");
+
+    // The next token is taken ahead of the next connection.
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert!(!server.accept_throttled());
+
+    // New connections are accepted immediately without a limit.
+    server.clear_accept_rate_limit();
+    let start = Instant::now();
+    for _ in 0..3 {
+        let client = RpcClient::<PingService>::new(Channel::connect(addr));
+        client.send(&Ping).await.unwrap();
+    }
+    assert!(start.elapsed() < Duration::from_millis(200));
+
+    server.shutdown();
+}
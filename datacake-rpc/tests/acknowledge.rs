@@ -0,0 +1,117 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::sync::{oneshot, Notify};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Export {
+    acknowledge: bool,
+}
+
+pub struct ExportService {
+    release: Arc<Notify>,
+}
+
+impl RpcService for ExportService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Export>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Export> for ExportService {
+    type Reply = String;
+
+    async fn on_message(&self, msg: Request<Export>) -> Result<Self::Reply, Status> {
+        if msg.acknowledge {
+            msg.acknowledge().await?;
+            // Only the first acknowledgement is sent.
+            msg.acknowledge().await?;
+            self.release.notified().await;
+        }
+        Ok("exported".to_string())
+    }
+}
+
+#[tokio::test]
+async fn test_acknowledge_before_reply() {
+    let addr = test_helper::get_unused_addr();
+
+    let release = Arc::new(Notify::new());
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(ExportService {
+        release: release.clone(),
+    });
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<ExportService>::new(Channel::connect(addr));
+
+    let (acked_tx, acked_rx) = oneshot::channel();
+    let request = tokio::spawn(async move {
+        let msg = Export { acknowledge: true };
+        client
+            .send_with_ack(&msg, move || {
+                acked_tx.send(()).unwrap();
+            })
+            .await
+            .map(|reply| reply.to_owned().unwrap())
+    });
+
+    // The handler is still waiting to be released, so the acknowledgement
+    // must arrive ahead of the reply.
+    tokio::time::timeout(Duration::from_secs(2), acked_rx)
+        .await
+        .expect("The acknowledgement should arrive before the reply")
+        .unwrap();
+    assert!(!request.is_finished());
+
+    release.notify_one();
+    let reply = request.await.unwrap().unwrap();
+    assert_eq!(reply, "exported");
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_reply_without_acknowledge() {
+    let addr = test_helper::get_unused_addr();
+
+    let release = Arc::new(Notify::new());
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(ExportService {
+        release: release.clone(),
+    });
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<ExportService>::new(Channel::connect(addr));
+
+    let mut acked = false;
+    let msg = Export { acknowledge: false };
+    let reply = client.send_with_ack(&msg, || acked = true).await.unwrap();
+    assert_eq!(reply.as_str(), "exported");
+    assert!(
+        !acked,
+        "The callback should only be called once acknowledged"
+    );
+
+    // Acknowledging a request whose client is not listening does nothing.
+    release.notify_one();
+    let msg = Export { acknowledge: true };
+    let reply = client.send(&msg).await.unwrap();
+    assert_eq!(reply.as_str(), "exported");
+
+    server.shutdown();
+}
@@ -0,0 +1,81 @@
+use datacake_rpc::{
+    Body,
+    Channel,
+    Handler,
+    Request,
+    RequestContents,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{AlignedVec, Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Payload {
+    data: Vec<u64>,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Summary {
+    sum: u64,
+    realigned: bool,
+}
+
+pub struct AlignService;
+
+impl RpcService for AlignService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Payload>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Payload> for AlignService {
+    type Reply = Summary;
+
+    async fn on_message(&self, msg: Request<Payload>) -> Result<Self::Reply, Status> {
+        Ok(Summary {
+            sum: msg.data.iter().sum(),
+            realigned: msg.is_realigned(),
+        })
+    }
+}
+
+#[test]
+fn test_alignment_constants() {
+    assert_eq!(
+        <Payload as RequestContents>::ALIGNMENT,
+        AlignedVec::ALIGNMENT
+    );
+    assert_eq!(<u64 as RequestContents>::ALIGNMENT, AlignedVec::ALIGNMENT);
+    assert_eq!(<Body as RequestContents>::ALIGNMENT, 1);
+}
+
+#[tokio::test]
+async fn test_realigned_request() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(AlignService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<AlignService>::new(Channel::connect(addr));
+
+    // Whether the chunk arrives aligned depends on the transport, either
+    // way the message must be readable.
+    let msg = Payload {
+        data: (0..10_000).collect(),
+    };
+    let reply = client.send(&msg).await.unwrap();
+    assert_eq!(reply.sum, (0..10_000).sum::<u64>());
+
+    println!("Request realigned: {}", reply.realigned);
+
+    server.shutdown();
+}
@@ -0,0 +1,90 @@
+use datacake_rpc::{
+    AnyReply,
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Lookup {
+    key: u64,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Found {
+    value: String,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Missing {
+    key: u64,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Lookup>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Lookup> for MyService {
+    type Reply = AnyReply;
+
+    async fn on_message(&self, msg: Request<Lookup>) -> Result<Self::Reply, Status> {
+        if msg.key == 1 {
+            AnyReply::new(&Found {
+                value: "Hello, world!".to_string(),
+            })
+        } else {
+            AnyReply::new(&Missing { key: msg.key })
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_any_reply() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let client = Channel::connect(addr);
+    println!("Connected to address {}!", addr);
+
+    let rpc_client = RpcClient::<MyService>::new(client);
+
+    let reply = rpc_client.send(&Lookup { key: 1 }).await.unwrap();
+    assert!(reply.is::<Found>(), "Reply should be found");
+    let found = reply.try_into::<Found>().expect("View reply as found");
+    assert_eq!(found.value, "Hello, world!");
+
+    let reply = rpc_client.send(&Lookup { key: 5 }).await.unwrap();
+    assert!(reply.is::<Missing>(), "Reply should be missing");
+    let missing = reply.try_into::<Missing>().expect("View reply as missing");
+    assert_eq!(missing.key, 5);
+
+    let reply = rpc_client.send(&Lookup { key: 5 }).await.unwrap();
+    reply
+        .try_into::<Found>()
+        .expect_err("Reply of the wrong kind should be rejected");
+
+    server.shutdown();
+}
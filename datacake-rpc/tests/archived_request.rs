@@ -0,0 +1,33 @@
+#![cfg(feature = "test-utils")]
+
+use datacake_rpc::Request;
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct MyMessage {
+    name: String,
+    age: u32,
+}
+
+#[tokio::test]
+async fn test_request_archived() {
+    let msg = MyMessage {
+        name: "Bobby".to_string(),
+        age: 12,
+    };
+    let request = Request::using_owned(msg).await;
+
+    let archived: &ArchivedMyMessage = request.archived();
+    assert_eq!(archived.name.as_str(), "Bobby");
+    assert_eq!(archived.age, 12);
+
+    // The archived value is read directly from the request buffer.
+    let buffer = request.as_bytes().as_ptr_range();
+    let ptr = archived as *const ArchivedMyMessage as *const u8;
+    assert!(
+        buffer.contains(&ptr),
+        "Archived value should borrow the buffer"
+    );
+}
@@ -0,0 +1,141 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use datacake_rpc::{
+    Body,
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::io::{AsyncRead, ReadBuf};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Download {
+    len: u64,
+    fail: bool,
+}
+
+/// Produces `remaining` bytes before either completing or failing.
+struct Source {
+    remaining: usize,
+    fail: bool,
+}
+
+impl AsyncRead for Source {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.remaining == 0 {
+            if self.fail {
+                return Poll::Ready(Err(io::Error::other("disk failure")));
+            }
+            return Poll::Ready(Ok(()));
+        }
+
+        let len = buf.remaining().min(self.remaining).min(10_000);
+        buf.put_slice(&vec![7; len]);
+        self.remaining -= len;
+        Poll::Ready(Ok(()))
+    }
+}
+
+pub struct DownloadService;
+
+impl RpcService for DownloadService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Download>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Download> for DownloadService {
+    type Reply = Body;
+
+    async fn on_message(&self, msg: Request<Download>) -> Result<Self::Reply, Status> {
+        Ok(Body::from_async_read(Source {
+            remaining: msg.len as usize,
+            fail: msg.fail,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_async_read_reply() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(DownloadService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<DownloadService>::new(Channel::connect(addr));
+
+    let len = 4 << 20;
+    let body = client.send(&Download { len, fail: false }).await.unwrap();
+    let bytes = body.into_bytes().await.unwrap();
+    assert_eq!(bytes.len(), len as usize);
+    assert!(bytes.iter().all(|&b| b == 7));
+
+    let mut body = client
+        .send(&Download {
+            len: 0,
+            fail: false,
+        })
+        .await
+        .unwrap();
+    assert!(body.next_chunk().await.is_none(), "Body should be empty");
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_async_read_reply_error() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(DownloadService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<DownloadService>::new(Channel::connect(addr));
+
+    let msg = Download {
+        len: 100_000,
+        fail: true,
+    };
+    let mut body = client.send(&msg).await.unwrap();
+    let mut received = 0;
+    let status = loop {
+        match body.next_chunk().await {
+            Some(Ok(chunk)) => received += chunk.len(),
+            Some(Err(status)) => break status,
+            None => panic!("Body should end with the reader's error"),
+        }
+    };
+    assert_eq!(
+        received, 100_000,
+        "Data before the error should be received"
+    );
+    assert_eq!(status.code, ErrorCode::InternalError);
+    assert_eq!(status.message, "disk failure");
+    assert!(body.next_chunk().await.is_none());
+
+    let body = client.send(&msg).await.unwrap();
+    let status = body
+        .into_bytes()
+        .await
+        .expect_err("Reader error should be returned");
+    assert_eq!(status.message, "disk failure");
+
+    server.shutdown();
+}
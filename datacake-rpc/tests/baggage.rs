@@ -0,0 +1,114 @@
+use datacake_rpc::{
+    Baggage,
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct GetBaggage;
+
+/// Replies with the baggage of each request.
+pub struct BackendService;
+
+impl RpcService for BackendService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<GetBaggage>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<GetBaggage> for BackendService {
+    type Reply = Vec<String>;
+
+    async fn on_message(&self, msg: Request<GetBaggage>) -> Result<Self::Reply, Status> {
+        let baggage = msg
+            .baggage()
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        Ok(baggage)
+    }
+}
+
+/// Forwards each request to the backend without any explicit baggage.
+pub struct FrontendService {
+    backend: RpcClient<BackendService>,
+}
+
+impl RpcService for FrontendService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<GetBaggage>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<GetBaggage> for FrontendService {
+    type Reply = Vec<String>;
+
+    async fn on_message(&self, msg: Request<GetBaggage>) -> Result<Self::Reply, Status> {
+        assert_eq!(msg.baggage(), Baggage::current());
+        let reply = self.backend.send(&GetBaggage).await?;
+        Ok(reply.to_owned().unwrap())
+    }
+}
+
+#[tokio::test]
+async fn test_baggage_propagation() {
+    let backend_addr = test_helper::get_unused_addr();
+    let frontend_addr = test_helper::get_unused_addr();
+
+    let backend = Server::listen(backend_addr).await.unwrap();
+    backend.add_service(BackendService);
+    let frontend = Server::listen(frontend_addr).await.unwrap();
+    frontend.add_service(FrontendService {
+        backend: RpcClient::new(Channel::connect(backend_addr)),
+    });
+    println!("Listening to addresses {backend_addr} and {frontend_addr}!");
+
+    let client = RpcClient::<FrontendService>::new(Channel::connect(frontend_addr));
+
+    let reply = client.send(&GetBaggage).await.unwrap();
+    assert!(reply.to_owned().unwrap().is_empty());
+
+    let baggage = Baggage::from_iter([("tenant", "acme"), ("locale", "fr FR")]);
+    let reply = client
+        .create_rpc_context()
+        .set_baggage(baggage.clone())
+        .send(&GetBaggage)
+        .await
+        .unwrap();
+    assert_eq!(
+        reply.to_owned().unwrap(),
+        ["locale=fr FR", "tenant=acme"],
+        "Baggage should be propagated through the frontend"
+    );
+
+    // Requests sent within a scope carry its baggage, on top of which more
+    // can be attached.
+    let reply = baggage
+        .scope(async {
+            client
+                .create_rpc_context()
+                .set_baggage(Baggage::from_iter([("locale", "en"), ("flag", "on")]))
+                .send(&GetBaggage)
+                .await
+                .unwrap()
+        })
+        .await;
+    assert_eq!(
+        reply.to_owned().unwrap(),
+        ["flag=on", "locale=en", "tenant=acme"]
+    );
+
+    frontend.shutdown();
+    backend.shutdown();
+}
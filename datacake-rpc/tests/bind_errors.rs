@@ -0,0 +1,26 @@
+use std::io;
+
+use datacake_rpc::{Error, Server};
+
+#[tokio::test]
+async fn test_bind_addr_in_use() {
+    let addr = test_helper::get_unused_addr();
+    let _server = Server::listen(addr).await.unwrap();
+
+    let err = Server::try_listen(addr)
+        .await
+        .err()
+        .expect("Second server should fail to bind");
+    assert!(matches!(err, Error::Bind { addr: bind_addr, .. } if bind_addr == addr));
+    assert_eq!(err.bind_error_kind(), Some(io::ErrorKind::AddrInUse));
+    assert!(
+        err.to_string().contains("already in use"),
+        "Error message should describe the cause: {err}"
+    );
+
+    let err = Server::listen(addr)
+        .await
+        .err()
+        .expect("Second server should fail to bind");
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+}
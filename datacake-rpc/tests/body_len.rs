@@ -0,0 +1,51 @@
+use datacake_rpc::{
+    Body,
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+
+pub struct LenService;
+
+impl RpcService for LenService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Body>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Body> for LenService {
+    type Reply = Option<u64>;
+
+    async fn on_message(&self, msg: Request<Body>) -> Result<Self::Reply, Status> {
+        Ok(msg.len().map(|len| len as u64))
+    }
+}
+
+#[tokio::test]
+async fn test_request_body_len() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(LenService);
+
+    let client = RpcClient::<LenService>::new(Channel::connect(addr));
+
+    let len = client.send_owned(Body::from(vec![1u8; 128])).await.unwrap();
+    assert_eq!(len.to_owned().unwrap(), Some(128));
+
+    let len = client.send_owned(Body::from(Vec::new())).await.unwrap();
+    assert_eq!(len.to_owned().unwrap(), Some(0));
+
+    // The handler replies without waiting for the streamed request to finish.
+    let (_sender, stream) = hyper::Body::channel();
+    let len = client.send_owned(Body::new(stream)).await.unwrap();
+    assert_eq!(len.to_owned().unwrap(), None);
+
+    server.shutdown();
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use datacake_rpc::http::HeaderValue;
+use datacake_rpc::{
+    Body,
+    BodyTransform,
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use parking_lot::Mutex;
+
+pub struct AddOne;
+
+impl RpcService for AddOne {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<u64>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<u64> for AddOne {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<u64>) -> Result<Self::Reply, Status> {
+        Ok(**msg + 1)
+    }
+}
+
+/// Scrambles the body by xor-ing each byte, applying it twice is a no-op.
+struct Xor {
+    name: &'static str,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Xor {
+    async fn apply(body: Body) -> Result<Body, Status> {
+        let (inner, headers) = body.into_parts();
+        let bytes = hyper::body::to_bytes(inner)
+            .await
+            .map_err(Status::connection)?;
+        let scrambled = bytes.iter().map(|b| b ^ 0x55).collect::<Vec<_>>();
+        Ok(Body::with_headers(scrambled.into(), headers))
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl BodyTransform for Xor {
+    async fn transform_request(&self, body: Body) -> Result<Body, Status> {
+        if body.headers().contains_key("x-reject") {
+            return Err(Status::invalid());
+        }
+        self.log.lock().push(format!("{}:request", self.name));
+        Self::apply(body).await
+    }
+
+    async fn transform_reply(&self, body: Body) -> Result<Body, Status> {
+        self.log.lock().push(format!("{}:reply", self.name));
+        Self::apply(body).await
+    }
+}
+
+#[tokio::test]
+async fn test_body_transforms() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(AddOne);
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    server.add_body_transform(Xor {
+        name: "outer",
+        log: log.clone(),
+    });
+    server.add_body_transform(Xor {
+        name: "inner",
+        log: log.clone(),
+    });
+
+    let client = RpcClient::<AddOne>::new(Channel::connect(addr));
+    let reply = client
+        .send(&41)
+        .await
+        .expect("Transforms should cancel out");
+    assert_eq!(reply, 42);
+    assert_eq!(
+        *log.lock(),
+        [
+            "outer:request",
+            "inner:request",
+            "inner:reply",
+            "outer:reply"
+        ],
+    );
+
+    let status = client
+        .create_rpc_context()
+        .set_header("x-reject", HeaderValue::from_static("1"))
+        .send(&41)
+        .await
+        .expect_err("Transform should reject the request");
+    assert_eq!(status.code, ErrorCode::InvalidPayload);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_unbalanced_transform() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(AddOne);
+    server.add_body_transform(Xor {
+        name: "only",
+        log: Arc::default(),
+    });
+
+    // The client does not scramble its request, so the handler
+    // receives an invalid message.
+    let client = RpcClient::<AddOne>::new(Channel::connect(addr));
+    let status = client
+        .send(&41)
+        .await
+        .expect_err("Request should be scrambled");
+    assert_eq!(status.code, ErrorCode::InvalidPayload);
+
+    server.shutdown();
+}
@@ -0,0 +1,153 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use datacake_rpc::{
+    Body,
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+    EMPTY_BODY_CAPABILITY,
+    RANGES_CAPABILITY,
+};
+use http::header::RANGE;
+use hyper::service::{make_service_fn, service_fn};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct ReadBlob;
+
+pub struct BlobService;
+
+impl RpcService for BlobService {
+    fn service_name() -> &'static str {
+        "blob-service"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<ReadBlob>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<ReadBlob> for BlobService {
+    type Reply = Body;
+
+    async fn on_message(&self, _msg: Request<ReadBlob>) -> Result<Self::Reply, Status> {
+        Ok(Body::from("hello, world"))
+    }
+}
+
+#[tokio::test]
+async fn test_negotiate_capabilities() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(BlobService);
+    println!("Listening to address {}!", addr);
+
+    let channel = Channel::connect(addr);
+    assert_eq!(channel.negotiated_capabilities(), None);
+
+    channel.warmup().await.unwrap();
+    let capabilities = channel.negotiated_capabilities().unwrap();
+    assert!(capabilities.supports(RANGES_CAPABILITY));
+
+    let client = RpcClient::<BlobService>::new(channel);
+    let reply = client.send_ranged(&ReadBlob, 7..).await.unwrap();
+    assert_eq!(reply.into_bytes().await.unwrap(), "world");
+
+    server.shutdown();
+}
+
+#[derive(Default)]
+/// What a legacy server received from the client.
+struct Received {
+    range: AtomicBool,
+    empty_body: AtomicBool,
+}
+
+#[tokio::test]
+async fn test_negotiate_with_legacy_server() {
+    let addr = test_helper::get_unused_addr();
+    let received = Arc::new(Received::default());
+    spawn_legacy_server(addr, received.clone());
+
+    let channel = Channel::connect(addr);
+    channel.warmup().await.unwrap();
+
+    let capabilities = channel.negotiated_capabilities().unwrap();
+    assert!(
+        !capabilities.supports(RANGES_CAPABILITY),
+        "Legacy servers should not support any optional features"
+    );
+    assert_eq!(capabilities.iter().count(), 0);
+
+    // The range is not sent and the full reply is returned instead.
+    let client = RpcClient::<BlobService>::new(channel);
+    let reply = client.send_ranged(&ReadBlob, 7..).await.unwrap();
+    assert_eq!(reply.into_bytes().await.unwrap(), "hello, world");
+    assert!(!received.range.load(Ordering::Relaxed));
+}
+
+#[tokio::test]
+async fn test_unit_message_to_legacy_server() {
+    let addr = test_helper::get_unused_addr();
+    let received = Arc::new(Received::default());
+    spawn_legacy_server(addr, received.clone());
+
+    // Unit messages are serialized until the server advertises empty bodies.
+    let channel = Channel::connect(addr);
+    let client = RpcClient::<BlobService>::new(channel.clone());
+    client.send(&ReadBlob).await.unwrap();
+    assert!(!received.empty_body.load(Ordering::Relaxed));
+
+    channel.warmup().await.unwrap();
+    let capabilities = channel.negotiated_capabilities().unwrap();
+    assert!(!capabilities.supports(EMPTY_BODY_CAPABILITY));
+
+    client.send(&ReadBlob).await.unwrap();
+    assert!(
+        !received.empty_body.load(Ordering::Relaxed),
+        "Legacy servers should receive serialized unit messages"
+    );
+}
+
+/// Spawns a plain HTTP/2 server replying to every request with the same
+/// body, without advertising any capabilities.
+fn spawn_legacy_server(addr: SocketAddr, received: Arc<Received>) {
+    let make_service = make_service_fn(move |_| {
+        let received = received.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: http::Request<hyper::Body>| {
+                let received = received.clone();
+                async move {
+                    let is_warmup = req.uri().path().starts_with("/datacake-warmup");
+                    if req.headers().contains_key(RANGE) {
+                        received.range.store(true, Ordering::Relaxed);
+                    }
+                    let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                    if body.is_empty() && !is_warmup {
+                        received.empty_body.store(true, Ordering::Relaxed);
+                    }
+
+                    let body = hyper::Body::from("hello, world");
+                    Ok::<_, Infallible>(http::Response::new(body))
+                }
+            }))
+        }
+    });
+
+    let server = hyper::Server::bind(&addr)
+        .http2_only(true)
+        .serve(make_service);
+    tokio::spawn(server);
+}
@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use datacake_rpc::{
+    CaptureConfig,
+    CapturedFrame,
+    Channel,
+    FrameKind,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Login {
+    user: String,
+    password: String,
+}
+
+pub struct AuthService;
+
+impl RpcService for AuthService {
+    fn service_name() -> &'static str {
+        "auth"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Login>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Login> for AuthService {
+    type Reply = String;
+
+    async fn on_message(&self, msg: Request<Login>) -> Result<Self::Reply, Status> {
+        Ok(format!("welcome {}", msg.user))
+    }
+}
+
+/// Waits for the capture to record the given number of frames.
+async fn wait_for_frames(server: &Server, count: usize) -> Vec<CapturedFrame> {
+    for _ in 0..50 {
+        let frames = server.captured_frames();
+        if frames.len() >= count {
+            return frames;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("Capture should record {count} frames");
+}
+
+fn login() -> Login {
+    Login {
+        user: "admin".to_string(),
+        password: "hunter2-hunter2".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_capture_ring_buffer() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(AuthService);
+    server.enable_capture(CaptureConfig::default()).unwrap();
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<AuthService>::new(Channel::connect(addr));
+    let reply = client.send(&login()).await.unwrap();
+    assert_eq!(reply.to_owned().unwrap(), "welcome admin");
+
+    let frames = wait_for_frames(&server, 2).await;
+    let request = frames
+        .iter()
+        .find(|frame| frame.kind == FrameKind::Request)
+        .unwrap();
+    let reply = frames
+        .iter()
+        .find(|frame| frame.kind == FrameKind::Reply)
+        .unwrap();
+    assert_eq!(request.request_id, reply.request_id);
+    assert!(request.path.starts_with("/auth/"));
+    let expected = datacake_rpc::to_view_bytes(&login()).unwrap();
+    assert_eq!(request.body.as_ref(), expected.as_slice());
+
+    // The captured request can be replayed.
+    let reply = client.send_raw::<Login>(&request.body).await.unwrap();
+    assert_eq!(reply.to_owned().unwrap(), "welcome admin");
+
+    server.disable_capture();
+    assert!(server.captured_frames().is_empty());
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_capture_file_with_redaction() {
+    let addr = test_helper::get_unused_addr();
+    let path = std::env::temp_dir().join(format!("datacake-capture-{}", addr.port()));
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(AuthService);
+    let config = CaptureConfig {
+        path: Some(path.clone()),
+        ..Default::default()
+    }
+    .with_redaction(|frame| {
+        let secret = b"hunter2-hunter2";
+        if let Some(start) = frame
+            .body
+            .windows(secret.len())
+            .position(|window| window == secret)
+        {
+            let mut body = frame.body.to_vec();
+            body[start..start + secret.len()].fill(b'*');
+            frame.body = Bytes::from(body);
+        }
+    });
+    server.enable_capture(config).unwrap();
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<AuthService>::new(Channel::connect(addr));
+    client.send(&login()).await.unwrap();
+    assert!(
+        server.captured_frames().is_empty(),
+        "Frames should be written to the file"
+    );
+
+    let mut frames = Vec::new();
+    for _ in 0..50 {
+        frames = CapturedFrame::read_all(std::fs::File::open(&path).unwrap()).unwrap();
+        if frames.len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(frames.len(), 2);
+
+    let request = frames
+        .iter()
+        .find(|frame| frame.kind == FrameKind::Request)
+        .unwrap();
+    let body = request.body.as_ref();
+    assert!(body.windows(15).any(|window| window == b"***************"));
+    assert!(!body.windows(7).any(|window| window == b"hunter2"));
+
+    server.shutdown();
+    let _ = std::fs::remove_file(path);
+}
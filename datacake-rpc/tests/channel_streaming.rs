@@ -0,0 +1,151 @@
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    ReplyStream,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Count {
+    up_to: u64,
+    fail_after: Option<u64>,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Words;
+
+pub struct CounterService;
+
+impl RpcService for CounterService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Count>();
+        registry.add_handler::<Words>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Count> for CounterService {
+    type Reply = ReplyStream<u64>;
+
+    async fn on_message(&self, msg: Request<Count>) -> Result<Self::Reply, Status> {
+        let up_to = msg.up_to;
+        let fail_after = msg.fail_after.as_ref().copied();
+
+        // A small capacity so the producer is held back by the client.
+        let (tx, rx) = mpsc::channel(2);
+        tokio::spawn(async move {
+            for n in 0..up_to {
+                let item = if fail_after == Some(n) {
+                    Err(Status::internal("Counter exploded"))
+                } else {
+                    Ok(n)
+                };
+
+                if tx.send(item).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(rx.into())
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Words> for CounterService {
+    type Reply = ReplyStream<String>;
+
+    async fn on_message(&self, _msg: Request<Words>) -> Result<Self::Reply, Status> {
+        Ok(["hello", "world"].into_iter().map(String::from).collect())
+    }
+}
+
+async fn setup() -> (Server, RpcClient<CounterService>) {
+    let addr = test_helper::get_unused_addr();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(CounterService);
+
+    let client = RpcClient::<CounterService>::new(Channel::connect(addr));
+    (server, client)
+}
+
+#[tokio::test]
+async fn test_channel_stream_completes() {
+    let (server, client) = setup().await;
+
+    let mut stream = client
+        .send(&Count {
+            up_to: 50,
+            fail_after: None,
+        })
+        .await
+        .expect("Send RPC message");
+
+    let mut items = Vec::new();
+    while let Some(item) = stream.next().await {
+        items.push(*item.expect("Item should be valid"));
+    }
+    assert_eq!(items, (0..50).collect::<Vec<_>>());
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_channel_stream_error() {
+    let (server, client) = setup().await;
+
+    let mut stream = client
+        .send(&Count {
+            up_to: 10,
+            fail_after: Some(3),
+        })
+        .await
+        .expect("Send RPC message");
+
+    for n in 0..3 {
+        let item = stream.next().await.unwrap().expect("Item should be valid");
+        assert_eq!(*item, n);
+    }
+
+    let status = stream
+        .next()
+        .await
+        .expect("Stream should yield the error")
+        .expect_err("Stream should fail");
+    assert_eq!(status.code, ErrorCode::InternalError);
+    assert!(
+        stream.next().await.is_none(),
+        "Stream should end after the error"
+    );
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_iter_stream() {
+    let (server, client) = setup().await;
+
+    let mut stream = client.send(&Words).await.expect("Send RPC message");
+
+    let mut words = Vec::new();
+    while let Some(item) = stream.next().await {
+        words.push(item.unwrap().to_owned().unwrap());
+    }
+    assert_eq!(words, ["hello", "world"]);
+
+    server.shutdown();
+}
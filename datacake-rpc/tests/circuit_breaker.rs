@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    BreakerConfig,
+    BreakerState,
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Ping;
+
+pub struct PingService;
+
+impl RpcService for PingService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Ping>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Ping> for PingService {
+    type Reply = ();
+
+    async fn on_message(&self, _msg: Request<Ping>) -> Result<Self::Reply, Status> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_opens_and_recovers() {
+    let addr = test_helper::get_unused_addr();
+
+    let config = BreakerConfig {
+        failure_threshold: 2,
+        open_duration: Duration::from_millis(250),
+    };
+    let channel = Channel::connect(addr).with_circuit_breaker(config);
+    let client = RpcClient::<PingService>::new(channel.clone());
+    assert_eq!(channel.breaker_state(), Some(BreakerState::Closed));
+
+    for _ in 0..2 {
+        let error = client.send(&Ping).await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::ConnectionError);
+    }
+    assert_eq!(channel.breaker_state(), Some(BreakerState::Open));
+
+    // The server now exists, but the open breaker fails fast without sending.
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(PingService);
+    println!("Listening to address {}!", addr);
+
+    let error = client.send(&Ping).await.unwrap_err();
+    assert_eq!(error.code, ErrorCode::ServiceUnavailable);
+    assert_eq!(server.connection_count(), 0, "No request should be sent");
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(channel.breaker_state(), Some(BreakerState::HalfOpen));
+
+    client.send(&Ping).await.expect("Probe should succeed");
+    assert_eq!(channel.breaker_state(), Some(BreakerState::Closed));
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_ignores_error_replies() {
+    let addr = test_helper::get_unused_addr();
+
+    // No service is registered, so every request is rejected by the server.
+    let server = Server::listen(addr).await.unwrap();
+    println!("Listening to address {}!", addr);
+
+    let config = BreakerConfig {
+        failure_threshold: 1,
+        ..Default::default()
+    };
+    let channel = Channel::connect(addr).with_circuit_breaker(config);
+    let client = RpcClient::<PingService>::new(channel.clone());
+
+    for _ in 0..3 {
+        let error = client.send(&Ping).await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::ServiceUnavailable);
+    }
+    assert_eq!(channel.breaker_state(), Some(BreakerState::Closed));
+    assert_eq!(Channel::connect(addr).breaker_state(), None);
+
+    server.shutdown();
+}
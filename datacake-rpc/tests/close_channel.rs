@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct SlowMessage {
+    delay_ms: u64,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<SlowMessage>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<SlowMessage> for MyService {
+    type Reply = u64;
+
+    async fn on_message(
+        &self,
+        msg: Request<SlowMessage>,
+    ) -> Result<Self::Reply, Status> {
+        tokio::time::sleep(Duration::from_millis(msg.delay_ms)).await;
+        Ok(msg.delay_ms)
+    }
+}
+
+#[tokio::test]
+async fn test_close_drains_in_flight() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let client = Channel::connect(addr);
+    println!("Connected to address {}!", addr);
+
+    let rpc_client = RpcClient::<MyService>::new(client.clone());
+
+    let in_flight = {
+        let rpc_client = rpc_client.clone();
+        tokio::spawn(
+            async move { rpc_client.send(&SlowMessage { delay_ms: 250 }).await },
+        )
+    };
+
+    // Give the request time to reach the server before closing.
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let drained = client.close(Duration::from_secs(5)).await;
+    assert!(
+        drained,
+        "In-flight request should complete before the timeout"
+    );
+    assert!(client.is_closed());
+
+    let resp = in_flight
+        .await
+        .unwrap()
+        .expect("In-flight request should succeed");
+    assert_eq!(resp, 250);
+
+    let error = rpc_client
+        .send(&SlowMessage { delay_ms: 0 })
+        .await
+        .expect_err("Closed channel should reject new requests");
+    assert_eq!(error.code, ErrorCode::ConnectionError);
+
+    server.shutdown();
+}
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Sleep(u64);
+
+pub struct SleepService;
+
+impl RpcService for SleepService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Sleep>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Sleep> for SleepService {
+    /// The port of the client's connection.
+    type Reply = u16;
+
+    async fn on_message(&self, msg: Request<Sleep>) -> Result<Self::Reply, Status> {
+        tokio::time::sleep(Duration::from_millis(msg.0)).await;
+        Ok(msg.remote_addr().port())
+    }
+}
+
+async fn send(client: &RpcClient<SleepService>, millis: u64) -> u16 {
+    let reply = client.send(&Sleep(millis)).await.unwrap();
+    *reply
+}
+
+#[tokio::test]
+async fn test_server_connection_lifetime() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(SleepService);
+    server.set_max_connection_lifetime(Some(Duration::from_millis(200)));
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<SleepService>::new(Channel::connect(addr));
+
+    let first = send(&client, 0).await;
+    // Still in-flight once the connection expires, so must be completed
+    // before the connection closes.
+    let slow = send(&client, 400).await;
+    assert_eq!(slow, first);
+
+    let reconnected = send(&client, 0).await;
+    assert_ne!(reconnected, first, "The client should have reconnected");
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_channel_connection_lifetime() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(SleepService);
+    println!("Listening to address {}!", addr);
+
+    let channel = Channel::connect(addr);
+    channel.set_max_connection_lifetime(Some(Duration::from_millis(200)));
+    let client = RpcClient::<SleepService>::new(channel);
+
+    let first = send(&client, 0).await;
+    assert_eq!(send(&client, 0).await, first);
+
+    let slow = tokio::spawn({
+        let client = client.clone();
+        async move { send(&client, 400).await }
+    });
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    let reconnected = send(&client, 0).await;
+    assert_ne!(reconnected, first, "The channel should have reconnected");
+    assert_eq!(
+        slow.await.unwrap(),
+        first,
+        "In-flight requests should complete on the old connection"
+    );
+    assert_eq!(send(&client, 0).await, reconnected);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_shared_connection_lifetime() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(SleepService);
+    println!("Listening to address {}!", addr);
+
+    let channel = Channel::connect_shared(addr);
+    channel.set_max_connection_lifetime(Some(Duration::from_millis(100)));
+    let client = RpcClient::<SleepService>::new(channel);
+    let other = RpcClient::<SleepService>::new(Channel::connect_shared(addr));
+
+    let first = send(&client, 0).await;
+    assert_eq!(send(&other, 0).await, first);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let reconnected = send(&client, 0).await;
+    assert_ne!(reconnected, first, "The channel should have reconnected");
+    assert_eq!(
+        send(&other, 0).await,
+        first,
+        "Channels without a lifetime keep their connection"
+    );
+
+    // Channels connecting from now on share the new connection.
+    let late = RpcClient::<SleepService>::new(Channel::connect_shared(addr));
+    assert_eq!(send(&late, 0).await, reconnected);
+
+    server.shutdown();
+}
@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use datacake_rpc::runtime::{BoxFuture, Runtime, TokioRuntime};
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+static SPAWNED: AtomicUsize = AtomicUsize::new(0);
+static SLEEPS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingRuntime;
+
+impl Runtime for CountingRuntime {
+    fn spawn(&self, future: BoxFuture<()>) {
+        SPAWNED.fetch_add(1, Ordering::Relaxed);
+        TokioRuntime.spawn(future)
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<()> {
+        SLEEPS.fetch_add(1, Ordering::Relaxed);
+        TokioRuntime.sleep(duration)
+    }
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct MyMessage {
+    name: String,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<MyMessage>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<MyMessage> for MyService {
+    type Reply = String;
+
+    async fn on_message(&self, msg: Request<MyMessage>) -> Result<Self::Reply, Status> {
+        Ok(format!("Hello, {}!", msg.name))
+    }
+}
+
+#[tokio::test]
+async fn test_custom_runtime() {
+    datacake_rpc::runtime::set_runtime(CountingRuntime).expect("Set runtime");
+    datacake_rpc::runtime::set_runtime(CountingRuntime)
+        .expect_err("Runtime can only be set once");
+
+    let addr = test_helper::get_unused_addr();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+
+    let mut rpc_client = RpcClient::<MyService>::new(Channel::connect(addr));
+    rpc_client.set_timeout(Duration::from_secs(5));
+
+    let msg = MyMessage {
+        name: "Bobby".to_string(),
+    };
+    let resp = rpc_client.send(&msg).await.expect("Send RPC message");
+    assert_eq!(resp, "Hello, Bobby!".to_string());
+
+    assert!(
+        SPAWNED.load(Ordering::Relaxed) > 0,
+        "Tasks should be spawned via the custom runtime"
+    );
+    assert!(
+        SLEEPS.load(Ordering::Relaxed) > 0,
+        "Timers should be created via the custom runtime"
+    );
+
+    server.shutdown();
+}
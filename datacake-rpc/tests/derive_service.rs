@@ -0,0 +1,93 @@
+use datacake_rpc::{Channel, Handler, Request, RpcClient, RpcService, Server, Status};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Add {
+    a: u64,
+    b: u64,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Greet {
+    name: String,
+}
+
+#[derive(RpcService)]
+#[rpc(name = "calculator", handles(Add, Greet))]
+pub struct MyService;
+
+#[datacake_rpc::async_trait]
+impl Handler<Add> for MyService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Add>) -> Result<Self::Reply, Status> {
+        Ok(msg.a + msg.b)
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Greet> for MyService {
+    type Reply = String;
+
+    async fn on_message(&self, msg: Request<Greet>) -> Result<Self::Reply, Status> {
+        Ok(format!("Hello, {}!", msg.name))
+    }
+}
+
+#[derive(RpcService)]
+#[rpc(cacheable(Add))]
+pub struct GenericService<T: Send + Sync + 'static>(T);
+
+#[datacake_rpc::async_trait]
+impl<T: Send + Sync + 'static> Handler<Add> for GenericService<T> {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Add>) -> Result<Self::Reply, Status> {
+        Ok(msg.a * msg.b)
+    }
+}
+
+#[test]
+fn test_derive_service_name() {
+    assert_eq!(MyService::service_name(), "calculator");
+    assert_eq!(
+        GenericService::<u8>::service_name(),
+        std::any::type_name::<GenericService<u8>>(),
+        "Service name should default to the type name",
+    );
+}
+
+#[tokio::test]
+async fn test_derived_service() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    server.add_service(GenericService(()));
+
+    let channel = Channel::connect(addr);
+    let client = RpcClient::<MyService>::new(channel.clone());
+
+    let sum = client.send(&Add { a: 2, b: 3 }).await.unwrap();
+    assert_eq!(sum, 5);
+
+    let greeting = client
+        .send(&Greet {
+            name: "bob".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(greeting, "Hello, bob!".to_string());
+
+    let client = RpcClient::<GenericService<()>>::new(channel);
+    let product = client.send(&Add { a: 2, b: 3 }).await.unwrap();
+    assert_eq!(product, 6);
+
+    server.shutdown();
+}
@@ -0,0 +1,92 @@
+use datacake_rpc::{
+    derive_service_name,
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+
+mod api {
+    use rkyv::{Archive, Deserialize, Serialize};
+
+    #[repr(C)]
+    #[derive(Serialize, Deserialize, Archive, Debug)]
+    #[archive(check_bytes)]
+    pub struct Get(pub u64);
+
+    pub struct Store<K>(pub K);
+
+    pub struct Key;
+}
+
+pub struct KeyValueService;
+
+impl RpcService for KeyValueService {
+    fn service_name() -> &'static str {
+        derive_service_name::<Self>()
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<api::Get>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<api::Get> for KeyValueService {
+    type Reply = u64;
+
+    fn path() -> &'static str {
+        derive_service_name::<api::Get>()
+    }
+
+    async fn on_message(&self, msg: Request<api::Get>) -> Result<Self::Reply, Status> {
+        Ok(msg.0 * 2)
+    }
+}
+
+#[test]
+fn test_derive_service_name() {
+    assert_eq!(KeyValueService::service_name(), "KeyValueService");
+    assert_eq!(<KeyValueService as Handler<api::Get>>::path(), "Get");
+    assert_eq!(
+        derive_service_name::<api::Store<api::Key>>(),
+        "Store<Key>",
+        "Generic parameters should be stripped"
+    );
+    assert_eq!(
+        derive_service_name::<Vec<(u64, [api::Key; 2])>>(),
+        "Vec<(u64, [Key; 2])>"
+    );
+    assert_eq!(derive_service_name::<&'static str>(), "&str");
+
+    // The name is only derived once.
+    assert!(std::ptr::eq(
+        derive_service_name::<api::Key>(),
+        derive_service_name::<api::Key>(),
+    ));
+}
+
+#[tokio::test]
+async fn test_derived_name_paths() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(KeyValueService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<KeyValueService>::new(Channel::connect(addr));
+
+    let reply = client.send(&api::Get(21)).await.unwrap();
+    assert_eq!(reply, 42);
+    let reply = client
+        .send_to("/KeyValueService/Get", &api::Get(4))
+        .await
+        .unwrap();
+    assert_eq!(reply, 8);
+
+    server.shutdown();
+}
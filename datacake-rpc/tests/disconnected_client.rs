@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::sync::{oneshot, Mutex};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Report {
+    rows: u64,
+}
+
+pub struct ReportService {
+    cancelled: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+impl RpcService for ReportService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Report>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Report> for ReportService {
+    type Reply = Vec<String>;
+
+    async fn on_message(&self, msg: Request<Report>) -> Result<Self::Reply, Status> {
+        let token = msg.cancellation_token().clone();
+        let cancelled = self.cancelled.lock().await.take();
+        if let Some(cancelled) = cancelled {
+            // Watches the request from outside of the handler's future, which
+            // is dropped once the client disconnects.
+            tokio::spawn(async move {
+                token.cancelled().await;
+                let _ = cancelled.send(());
+            });
+            std::future::pending::<()>().await;
+        }
+
+        Ok((0..msg.rows).map(|row| format!("row-{row}")).collect())
+    }
+}
+
+#[tokio::test]
+async fn test_disconnect_skips_queued_reply() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(ReportService {
+        cancelled: Mutex::new(None),
+    });
+    println!("Listening to address {}!", addr);
+
+    let mut incoming = server.incoming();
+
+    let mut client = RpcClient::<ReportService>::new(Channel::connect(addr));
+    client.set_timeout(Duration::from_millis(250));
+    let request = tokio::spawn(async move { client.send(&Report { rows: 3 }).await });
+
+    let (queued, sender) = incoming.next().await.unwrap();
+    let error = request.await.unwrap().unwrap_err();
+    assert_eq!(error.code, ErrorCode::Timeout);
+
+    let cancelled = queued.cancellation_token().cancelled();
+    tokio::time::timeout(Duration::from_secs(2), cancelled)
+        .await
+        .expect("The request should be cancelled once the client disconnects");
+    let Err(error) = queued.dispatch().await else {
+        panic!("The reply should not be serialized");
+    };
+    assert_eq!(error.code, ErrorCode::Aborted);
+    sender.send(Err(error));
+
+    // Requests which are still awaited are replied to as usual.
+    let client = RpcClient::<ReportService>::new(Channel::connect(addr));
+    let request = tokio::spawn(async move {
+        client
+            .send(&Report { rows: 2 })
+            .await
+            .map(|reply| reply.to_owned().unwrap())
+    });
+    let (queued, sender) = incoming.next().await.unwrap();
+    sender.send(queued.dispatch().await);
+    let reply = request.await.unwrap().unwrap();
+    assert_eq!(reply, ["row-0", "row-1"]);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_disconnect_cancels_request() {
+    let addr = test_helper::get_unused_addr();
+
+    let (cancelled_tx, cancelled_rx) = oneshot::channel();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(ReportService {
+        cancelled: Mutex::new(Some(cancelled_tx)),
+    });
+    println!("Listening to address {}!", addr);
+
+    let mut client = RpcClient::<ReportService>::new(Channel::connect(addr));
+    client.set_timeout(Duration::from_millis(250));
+    let error = client.send(&Report { rows: 3 }).await.unwrap_err();
+    assert_eq!(error.code, ErrorCode::Timeout);
+
+    tokio::time::timeout(Duration::from_secs(2), cancelled_rx)
+        .await
+        .expect("The request should be cancelled once the client disconnects")
+        .unwrap();
+
+    server.shutdown();
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use datacake_rpc::{
+    AdminService,
+    Channel,
+    ErrorCode,
+    Handler,
+    InflightRequests,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use tokio::sync::Semaphore;
+
+pub struct AddOne;
+
+impl RpcService for AddOne {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<u64>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<u64> for AddOne {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<u64>) -> Result<Self::Reply, Status> {
+        Ok(**msg + 1)
+    }
+}
+
+pub struct Gated {
+    permits: Arc<Semaphore>,
+}
+
+impl RpcService for Gated {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<u64>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<u64> for Gated {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<u64>) -> Result<Self::Reply, Status> {
+        self.permits.acquire().await.unwrap().forget();
+        Ok(**msg * 2)
+    }
+}
+
+#[tokio::test]
+async fn test_draining() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(AddOne);
+    server.enable_admin_service();
+    assert!(!server.is_draining());
+
+    let channel = Channel::connect(addr);
+    let client = RpcClient::<AddOne>::new(channel.clone());
+    assert_eq!(client.send(&1).await.unwrap(), 2);
+    assert_eq!(server.connection_count(), 1);
+
+    server.set_draining(true);
+    assert!(server.is_draining());
+
+    let status = client
+        .send(&1)
+        .await
+        .expect_err("Draining server should reject");
+    assert_eq!(status.code, ErrorCode::ServiceUnavailable);
+    assert_eq!(
+        server.connection_count(),
+        1,
+        "Connection should be kept open while draining"
+    );
+
+    let admin = RpcClient::<AdminService>::new(channel);
+    admin
+        .send(&InflightRequests)
+        .await
+        .expect("Admin service should still be served");
+
+    server.set_draining(false);
+    assert_eq!(client.send(&2).await.unwrap(), 3);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_draining_service() {
+    let addr = test_helper::get_unused_addr();
+
+    let permits = Arc::new(Semaphore::new(1));
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(AddOne);
+    server.add_service(Gated {
+        permits: permits.clone(),
+    });
+
+    let channel = Channel::connect(addr);
+    let add_one = RpcClient::<AddOne>::new(channel.clone());
+    let gated = RpcClient::<Gated>::new(channel);
+    assert_eq!(gated.send(&2).await.unwrap(), 4);
+
+    // Blocks the handler until after the service is drained.
+    let inflight = tokio::spawn({
+        let gated = gated.clone();
+        async move { gated.send(&3).await.map(|reply| *reply) }
+    });
+    while server.inflight_requests().is_empty() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    server.drain_service(Gated::service_name());
+    assert!(server.is_service_draining(Gated::service_name()));
+    assert!(!server.is_service_draining(AddOne::service_name()));
+    assert!(!server.is_draining());
+
+    let status = gated
+        .send(&1)
+        .await
+        .expect_err("Drained service should reject new requests");
+    assert_eq!(status.code, ErrorCode::ServiceUnavailable);
+    assert_eq!(
+        add_one.send(&1).await.unwrap(),
+        2,
+        "Other services should keep serving"
+    );
+
+    permits.add_permits(1);
+    assert_eq!(
+        inflight.await.unwrap().unwrap(),
+        6,
+        "In-flight requests should complete"
+    );
+
+    server.resume_service(Gated::service_name());
+    assert!(!server.is_service_draining(Gated::service_name()));
+    permits.add_permits(1);
+    assert_eq!(gated.send(&4).await.unwrap(), 8);
+
+    server.shutdown();
+}
@@ -0,0 +1,61 @@
+use datacake_rpc::{Handler, Request, RpcService, Server, ServiceRegistry, Status};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct MyMessage {
+    name: String,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct MyOtherMessage {
+    name: String,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn service_name() -> &'static str {
+        "my-service"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler_with_path::<MyMessage>("greet");
+        registry.add_handler_with_path::<MyOtherMessage>("greet");
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<MyMessage> for MyService {
+    type Reply = String;
+
+    async fn on_message(&self, msg: Request<MyMessage>) -> Result<Self::Reply, Status> {
+        Ok(msg.to_owned().unwrap().name)
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<MyOtherMessage> for MyService {
+    type Reply = String;
+
+    async fn on_message(
+        &self,
+        msg: Request<MyOtherMessage>,
+    ) -> Result<Self::Reply, Status> {
+        Ok(msg.to_owned().unwrap().name)
+    }
+}
+
+#[tokio::test]
+#[should_panic(expected = "Duplicate handler registration for service \"my-service\"")]
+async fn test_duplicate_handler_registration() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+}
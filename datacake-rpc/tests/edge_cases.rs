@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use http::header::CONTENT_LENGTH;
+use http::StatusCode;
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, PartialEq, Debug, Clone, Default)]
+#[archive(check_bytes, compare(PartialEq))]
+#[archive_attr(derive(Debug))]
+pub struct SparseMessage {
+    name: String,
+    payload: Vec<u8>,
+    tags: Vec<String>,
+    nested: Vec<Vec<u64>>,
+    lookup: BTreeMap<String, Vec<u8>>,
+    flag: bool,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, PartialEq, Debug)]
+#[archive(check_bytes)]
+pub struct UnitMessage;
+
+pub struct EchoService;
+
+impl RpcService for EchoService {
+    fn service_name() -> &'static str {
+        "echo-service"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<SparseMessage>();
+        registry.add_handler::<UnitMessage>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<SparseMessage> for EchoService {
+    type Reply = SparseMessage;
+
+    fn path() -> &'static str {
+        "sparse"
+    }
+
+    async fn on_message(
+        &self,
+        msg: Request<SparseMessage>,
+    ) -> Result<Self::Reply, Status> {
+        msg.to_owned().map_err(Status::internal)
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<UnitMessage> for EchoService {
+    type Reply = UnitMessage;
+
+    async fn on_message(
+        &self,
+        _msg: Request<UnitMessage>,
+    ) -> Result<Self::Reply, Status> {
+        Ok(UnitMessage)
+    }
+}
+
+fn sparse_messages() -> Vec<SparseMessage> {
+    vec![
+        SparseMessage::default(),
+        SparseMessage {
+            tags: vec![String::new(), String::new()],
+            nested: vec![vec![], vec![], vec![1]],
+            ..Default::default()
+        },
+        SparseMessage {
+            name: String::new(),
+            payload: vec![0],
+            lookup: BTreeMap::from([(String::new(), vec![])]),
+            flag: true,
+            ..Default::default()
+        },
+    ]
+}
+
+#[cfg(feature = "test-utils")]
+#[test]
+fn test_zero_length_fields_pass_validation() {
+    for msg in sparse_messages() {
+        datacake_rpc::test_roundtrip(&msg).expect("Message should round-trip");
+    }
+    datacake_rpc::test_roundtrip(&UnitMessage).expect("Message should round-trip");
+}
+
+#[tokio::test]
+async fn test_zero_length_fields_roundtrip() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(EchoService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<EchoService>::new(Channel::connect(addr));
+
+    for msg in sparse_messages() {
+        let reply = client.send(&msg).await.unwrap();
+        assert_eq!(reply.to_owned().unwrap(), msg);
+    }
+
+    let reply = client.send(&UnitMessage).await.unwrap();
+    assert_eq!(reply.to_owned().unwrap(), UnitMessage);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_huge_content_length_is_rejected() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(EchoService);
+    println!("Listening to address {}!", addr);
+
+    // The body is far smaller than the declared length, which must not be
+    // trusted when allocating the buffer for it.
+    let bytes = datacake_rpc::to_view_bytes(&SparseMessage::default()).unwrap();
+    let client = hyper::Client::builder()
+        .http2_only(true)
+        .build_http::<hyper::Body>();
+    for length in [u64::MAX, isize::MAX as u64, 1 << 40] {
+        // Sent as multiple chunks so the server relies on the size hint.
+        let (mut sender, body) = hyper::Body::channel();
+        let (head, tail) = bytes.split_at(bytes.len() / 2);
+        let chunks = [head.to_vec(), tail.to_vec()];
+        tokio::spawn(async move {
+            for chunk in chunks {
+                if sender.send_data(chunk.into()).await.is_err() {
+                    return;
+                }
+                tokio::task::yield_now().await;
+            }
+        });
+        let request = http::Request::post(format!("http://{addr}/echo-service/sparse"))
+            .header(CONTENT_LENGTH, length)
+            .body(body)
+            .unwrap();
+
+        match client.request(request).await {
+            Ok(response) => assert_ne!(response.status(), StatusCode::OK),
+            Err(error) => assert!(!error.is_connect(), "Unexpected error {error}"),
+        }
+    }
+
+    // The server is still able to handle requests.
+    let rpc_client = RpcClient::<EchoService>::new(Channel::connect(addr));
+    let msg = SparseMessage::default();
+    let reply = rpc_client.send(&msg).await.unwrap();
+    assert_eq!(reply.to_owned().unwrap(), msg);
+
+    server.shutdown();
+}
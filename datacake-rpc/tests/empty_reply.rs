@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use datacake_rpc::{
+    Channel,
+    Empty,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Increment {
+    by: u64,
+}
+
+#[derive(Default)]
+pub struct CounterService {
+    counter: Arc<AtomicU64>,
+}
+
+impl RpcService for CounterService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Increment>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Increment> for CounterService {
+    type Reply = Empty;
+
+    async fn on_message(&self, msg: Request<Increment>) -> Result<Self::Reply, Status> {
+        self.counter.fetch_add(msg.by, Ordering::Relaxed);
+        Ok(Empty)
+    }
+}
+
+#[tokio::test]
+async fn test_empty_reply() {
+    let addr = test_helper::get_unused_addr();
+
+    let counter = Arc::new(AtomicU64::new(0));
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(CounterService {
+        counter: counter.clone(),
+    });
+
+    let rpc_client = RpcClient::<CounterService>::new(Channel::connect(addr));
+
+    let reply: () = rpc_client
+        .send(&Increment { by: 3 })
+        .await
+        .expect("Send RPC message");
+    assert_eq!(reply, ());
+
+    rpc_client
+        .send(&Increment { by: 4 })
+        .await
+        .expect("Send RPC message");
+    assert_eq!(counter.load(Ordering::Relaxed), 7);
+
+    server.shutdown();
+}
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use datacake_rpc::{
+    CaptureConfig,
+    CapturedFrame,
+    Channel,
+    FrameKind,
+    Handler,
+    ReplayClient,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Ping;
+
+pub struct PingService;
+
+impl RpcService for PingService {
+    fn service_name() -> &'static str {
+        "ping"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<()>();
+        registry.add_handler::<Ping>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<()> for PingService {
+    type Reply = String;
+
+    async fn on_message(&self, _msg: Request<()>) -> Result<Self::Reply, Status> {
+        Ok("pong".to_string())
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Ping> for PingService {
+    type Reply = String;
+
+    async fn on_message(&self, _msg: Request<Ping>) -> Result<Self::Reply, Status> {
+        Ok("pong".to_string())
+    }
+}
+
+/// Waits for the capture to record the given number of frames.
+async fn wait_for_frames(server: &Server, count: usize) -> Vec<CapturedFrame> {
+    for _ in 0..50 {
+        let frames = server.captured_frames();
+        if frames.len() >= count {
+            return frames;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("Capture should record {count} frames");
+}
+
+#[tokio::test]
+async fn test_unit_message_sent_empty() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(PingService);
+    server.enable_capture(CaptureConfig::default()).unwrap();
+    println!("Listening to address {}!", addr);
+
+    let channel = Channel::connect(addr);
+    let client = RpcClient::<PingService>::new(channel.clone());
+
+    // Unit messages are serialized until the capabilities are negotiated.
+    let reply = client.send(&()).await.unwrap();
+    assert_eq!(reply.as_str(), "pong");
+
+    channel.warmup().await.unwrap();
+    let reply = client.send(&()).await.unwrap();
+    assert_eq!(reply.as_str(), "pong");
+    let reply = client.send(&Ping).await.unwrap();
+    assert_eq!(reply.as_str(), "pong");
+
+    let frames = wait_for_frames(&server, 6).await;
+    let requests = frames
+        .iter()
+        .filter(|frame| frame.kind == FrameKind::Request)
+        .collect::<Vec<_>>();
+    assert!(!requests[0].body.is_empty());
+    assert!(
+        requests[1].body.is_empty(),
+        "Unit messages should be sent without a body"
+    );
+    assert!(
+        !requests[2].body.is_empty(),
+        "Unit structs should still be serialized"
+    );
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_serialized_unit_message() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(PingService);
+    println!("Listening to address {}!", addr);
+
+    // Clients which predate empty bodies still serialize unit messages.
+    let body = datacake_rpc::to_view_bytes(&()).unwrap();
+    let frame = CapturedFrame {
+        kind: FrameKind::Request,
+        request_id: 1,
+        path: format!("/ping/{}", <PingService as Handler<()>>::path()),
+        body: Bytes::copy_from_slice(&body),
+    };
+
+    let client = ReplayClient::new(Channel::connect(addr));
+    let report = client.replay(vec![frame]).await;
+    assert_eq!(report.succeeded(), 1);
+
+    server.shutdown();
+}
@@ -0,0 +1,109 @@
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    ErrorFraming,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+    ERROR_FRAMING_HEADER,
+    SIMPLE_ERROR_VERSION,
+};
+use http::StatusCode;
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Lookup {
+    key: u64,
+}
+
+pub struct LookupService;
+
+impl RpcService for LookupService {
+    fn service_name() -> &'static str {
+        "lookup-service"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Lookup>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Lookup> for LookupService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Lookup>) -> Result<Self::Reply, Status> {
+        Err(Status::not_found(format!("Unknown key {}", msg.key)))
+    }
+}
+
+#[tokio::test]
+async fn test_simple_error_framing() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(LookupService);
+    server.set_error_framing(ErrorFraming::Simple);
+    println!("Listening to address {}!", addr);
+
+    // A client which does not implement rkyv.
+    let client = hyper::Client::builder()
+        .http2_only(true)
+        .build_http::<hyper::Body>();
+    let request = http::Request::post(format!("http://{addr}/missing-service/lookup"))
+        .body(hyper::Body::empty())
+        .unwrap();
+    let response = client.request(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers()[ERROR_FRAMING_HEADER], "simple");
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(body[0], SIMPLE_ERROR_VERSION);
+    let code = u16::from_be_bytes([body[1], body[2]]);
+    assert_eq!(code, ErrorCode::ServiceUnavailable.as_u16());
+    assert!(std::str::from_utf8(&body[3..]).is_ok());
+
+    // Clients of this crate understand the framing.
+    let rpc_client = RpcClient::<LookupService>::new(Channel::connect(addr));
+    let status = rpc_client
+        .send(&Lookup { key: 42 })
+        .await
+        .expect_err("Handler should fail");
+    assert_eq!(status, Status::not_found("Unknown key 42"));
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_default_error_framing() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(LookupService);
+    println!("Listening to address {}!", addr);
+
+    let client = hyper::Client::builder()
+        .http2_only(true)
+        .build_http::<hyper::Body>();
+    let request = http::Request::post(format!("http://{addr}/missing-service/lookup"))
+        .body(hyper::Body::empty())
+        .unwrap();
+    let response = client.request(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(response.headers().get(ERROR_FRAMING_HEADER).is_none());
+
+    let rpc_client = RpcClient::<LookupService>::new(Channel::connect(addr));
+    let status = rpc_client
+        .send(&Lookup { key: 7 })
+        .await
+        .expect_err("Handler should fail");
+    assert_eq!(status, Status::not_found("Unknown key 7"));
+
+    server.shutdown();
+}
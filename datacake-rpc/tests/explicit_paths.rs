@@ -0,0 +1,71 @@
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct MyMessage {
+    name: String,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn service_name() -> &'static str {
+        "my-service"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler_with_path::<MyMessage>("v2/greet");
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<MyMessage> for MyService {
+    type Reply = String;
+
+    async fn on_message(&self, msg: Request<MyMessage>) -> Result<Self::Reply, Status> {
+        Ok(format!("Hello, {}!", msg.name))
+    }
+}
+
+#[tokio::test]
+async fn test_explicit_paths() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let client = Channel::connect(addr);
+    println!("Connected to address {}!", addr);
+
+    let rpc_client = RpcClient::<MyService>::new(client);
+
+    let msg = MyMessage {
+        name: "Bobby".to_string(),
+    };
+
+    rpc_client
+        .send(&msg)
+        .await
+        .expect_err("Handler should not be registered under the type name");
+
+    let resp = rpc_client
+        .send_to("/my-service/v2/greet", &msg)
+        .await
+        .expect("Send RPC message");
+    assert_eq!(resp, "Hello, Bobby!".to_string());
+
+    server.shutdown();
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::ser::serializers::BufferScratch;
+use rkyv::{AlignedBytes, Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Lookup {
+    entries: HashMap<String, u64>,
+}
+
+impl Lookup {
+    fn new(len: u64) -> Self {
+        Self {
+            entries: (0..len).map(|i| (i.to_string(), i)).collect(),
+        }
+    }
+}
+
+pub struct LookupService;
+
+impl RpcService for LookupService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Lookup>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Lookup> for LookupService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Lookup>) -> Result<Self::Reply, Status> {
+        Ok(msg.entries.len() as u64)
+    }
+}
+
+#[tokio::test]
+async fn test_sender_with_fixed_scratch() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(LookupService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<LookupService>::new(Channel::connect(addr));
+    let mut sender =
+        client.sender_with_scratch(BufferScratch::<AlignedBytes<1024>>::default());
+
+    let reply = sender.send(&Lookup::new(4)).await.unwrap();
+    assert_eq!(reply, 4);
+
+    let error = sender
+        .send(&Lookup::new(10_000))
+        .await
+        .expect_err("Message should exceed the fixed scratch space");
+    assert_eq!(error.code, ErrorCode::InternalError);
+
+    let reply = sender
+        .send(&Lookup::new(4))
+        .await
+        .expect("Sender should recover from running out of scratch space");
+    assert_eq!(reply, 4);
+
+    // The default scratch space falls back to the heap.
+    let reply = client.send(&Lookup::new(10_000)).await.unwrap();
+    assert_eq!(reply, 10_000);
+
+    server.shutdown();
+}
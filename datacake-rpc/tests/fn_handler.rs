@@ -0,0 +1,164 @@
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceInfo,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Add {
+    a: u64,
+    b: u64,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Greet {
+    name: String,
+}
+
+pub struct MathService;
+
+impl RpcService for MathService {
+    fn service_name() -> &'static str {
+        "math"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler_with_path::<Add>("add");
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Add> for MathService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Add>) -> Result<Self::Reply, Status> {
+        Ok(msg.a + msg.b)
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Greet> for MathService {
+    type Reply = String;
+
+    async fn on_message(&self, _msg: Request<Greet>) -> Result<Self::Reply, Status> {
+        unreachable!("Greetings are handled by closures")
+    }
+}
+
+#[tokio::test]
+async fn test_fn_handler() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MathService);
+    let greeting = String::from("Hello");
+    server.add_fn_handler::<Greet, _>("/greeter/greet", move |req| {
+        let greeting = greeting.clone();
+        async move { Ok(format!("{greeting}, {}!", req.name)) }
+    });
+    server.add_fn_handler::<Add, _>("/math/sub", |req| async move {
+        req.a
+            .checked_sub(req.b)
+            .ok_or_else(|| Status::invalid_argument("Subtraction would underflow"))
+    });
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<MathService>::new(Channel::connect(addr));
+
+    let reply = client
+        .send_to(
+            "/greeter/greet",
+            &Greet {
+                name: "datacake".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(reply.to_owned().unwrap(), "Hello, datacake!");
+
+    // Closures coexist with the service's own handlers.
+    let reply = client
+        .send_to("/math/add", &Add { a: 3, b: 2 })
+        .await
+        .unwrap();
+    assert_eq!(reply, 5);
+    let reply = client
+        .send_to("/math/sub", &Add { a: 3, b: 2 })
+        .await
+        .unwrap();
+    assert_eq!(reply, 1);
+    let status = client
+        .send_to("/math/sub", &Add { a: 2, b: 3 })
+        .await
+        .expect_err("Handler should fail");
+    assert_eq!(status.code, ErrorCode::InvalidPayload);
+
+    assert_eq!(
+        server.services(),
+        vec![
+            ServiceInfo {
+                name: "greeter".to_string(),
+                paths: vec!["greet".to_string()],
+            },
+            ServiceInfo {
+                name: "math".to_string(),
+                paths: vec!["add".to_string(), "sub".to_string()],
+            },
+        ],
+    );
+
+    server.remove_service("greeter");
+    let status = client
+        .send_to(
+            "/greeter/greet",
+            &Greet {
+                name: "datacake".to_string(),
+            },
+        )
+        .await
+        .expect_err("Handler should be removed");
+    assert_eq!(status.code, ErrorCode::ServiceUnavailable);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+#[should_panic(expected = "must be of the form")]
+async fn test_fn_handler_invalid_path() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_fn_handler::<Add, _>("/math", |req| async move { Ok(req.a) });
+}
+
+#[tokio::test]
+#[should_panic(expected = "Duplicate handler registration")]
+async fn test_fn_handler_duplicate_path() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MathService);
+    server.add_fn_handler::<Add, _>("/math/add", |req| async move { Ok(req.a) });
+}
+
+#[tokio::test]
+#[should_panic(expected = "Duplicate handler registration")]
+async fn test_service_after_fn_handler_duplicate_path() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_fn_handler::<Add, _>("/math/add", |req| async move { Ok(req.a) });
+    server.add_service(MathService);
+}
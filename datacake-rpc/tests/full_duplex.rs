@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    Body,
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use hyper::body::HttpBody;
+
+pub struct EchoService;
+
+impl RpcService for EchoService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Body>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Body> for EchoService {
+    type Reply = Body;
+
+    async fn on_message(&self, msg: Request<Body>) -> Result<Self::Reply, Status> {
+        let mut request = msg.into_inner().into_inner();
+        let (mut sender, reply) = hyper::Body::channel();
+
+        // Echo each chunk back as soon as it arrives, while the
+        // rest of the request is still being streamed in.
+        tokio::spawn(async move {
+            while let Some(Ok(chunk)) = request.data().await {
+                if sender.send_data(chunk).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Body::new(reply))
+    }
+}
+
+#[tokio::test]
+async fn test_reply_overlaps_request() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(EchoService);
+
+    let client = RpcClient::<EchoService>::new(Channel::connect(addr));
+
+    let (mut sender, request) = hyper::Body::channel();
+    sender.send_data("chunk-0".into()).await.unwrap();
+
+    // The reply must arrive while the request is still open.
+    let reply = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.send_owned(Body::new(request)),
+    )
+    .await
+    .expect("Reply should start before the request is complete")
+    .unwrap();
+    let mut reply = reply.into_inner();
+
+    for i in 0..5 {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), reply.data())
+            .await
+            .expect("Chunk should be echoed before the next is sent")
+            .expect("Reply should not be finished")
+            .unwrap();
+        assert_eq!(chunk, format!("chunk-{i}").as_bytes());
+
+        sender
+            .send_data(format!("chunk-{}", i + 1).into())
+            .await
+            .unwrap();
+    }
+
+    drop(sender);
+    let rest = hyper::body::to_bytes(reply).await.unwrap();
+    assert_eq!(rest, "chunk-5".as_bytes());
+
+    server.shutdown();
+}
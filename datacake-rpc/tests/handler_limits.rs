@@ -0,0 +1,135 @@
+use datacake_rpc::{
+    Body,
+    Channel,
+    ErrorCode,
+    Handler,
+    Limits,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Ping {
+    data: Vec<u8>,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct UploadBlob {
+    data: Vec<u8>,
+}
+
+pub struct BlobService;
+
+impl RpcService for BlobService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler_with_limits::<Ping>(Limits {
+            max_body: Some(1 << 10),
+        });
+        registry.add_handler_with_limits::<UploadBlob>(Limits {
+            max_body: Some(4 << 20),
+        });
+        registry.add_handler_with_limits::<Body>(Limits {
+            max_body: Some(1 << 20),
+        });
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Ping> for BlobService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Ping>) -> Result<Self::Reply, Status> {
+        Ok(msg.data.len() as u64)
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<UploadBlob> for BlobService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<UploadBlob>) -> Result<Self::Reply, Status> {
+        Ok(msg.data.len() as u64)
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Body> for BlobService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Body>) -> Result<Self::Reply, Status> {
+        let bytes = msg.into_inner().into_bytes().await?;
+        Ok(bytes.len() as u64)
+    }
+}
+
+#[tokio::test]
+async fn test_handler_limits() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(BlobService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<BlobService>::new(Channel::connect(addr));
+
+    let reply = client.send(&Ping { data: vec![1; 16] }).await.unwrap();
+    assert_eq!(reply, 16);
+    let status = client
+        .send(&Ping {
+            data: vec![1; 2 << 10],
+        })
+        .await
+        .expect_err("Ping should be limited to 1KiB");
+    assert_eq!(status.code, ErrorCode::PayloadTooLarge);
+
+    let reply = client
+        .send(&UploadBlob {
+            data: vec![1; 2 << 20],
+        })
+        .await
+        .expect("Uploads should accept larger bodies");
+    assert_eq!(reply, 2 << 20);
+    let status = client
+        .send(&UploadBlob {
+            data: vec![1; 8 << 20],
+        })
+        .await
+        .expect_err("Uploads should be limited to 4MiB");
+    assert_eq!(status.code, ErrorCode::PayloadTooLarge);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_handler_limits_unknown_length() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(BlobService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<BlobService>::new(Channel::connect(addr));
+
+    let body = Body::from_async_read(tokio::io::repeat(7).take(64 << 10));
+    let reply = client.send_owned(body).await.unwrap();
+    assert_eq!(reply, 64 << 10);
+
+    let body = Body::from_async_read(tokio::io::repeat(7).take(4 << 20));
+    let status = client
+        .send_owned(body)
+        .await
+        .expect_err("Streamed bodies should be cut off at the limit");
+    assert_eq!(status.code, ErrorCode::PayloadTooLarge);
+
+    server.shutdown();
+}
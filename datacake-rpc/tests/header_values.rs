@@ -0,0 +1,134 @@
+use datacake_rpc::{
+    Channel,
+    Handler,
+    HeaderParseError,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use http::HeaderValue;
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct ListScopes;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct ReadLimit;
+
+pub struct AuthService;
+
+impl RpcService for AuthService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<ListScopes>();
+        registry.add_handler::<ReadLimit>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<ListScopes> for AuthService {
+    type Reply = Vec<String>;
+
+    async fn on_message(&self, msg: Request<ListScopes>) -> Result<Self::Reply, Status> {
+        let scopes = msg
+            .header_all("x-scope")
+            .map(|value| value.to_str().unwrap().to_string())
+            .collect();
+        Ok(scopes)
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<ReadLimit> for AuthService {
+    type Reply = Option<u32>;
+
+    async fn on_message(&self, msg: Request<ReadLimit>) -> Result<Self::Reply, Status> {
+        match msg.header_as::<u32>("x-limit") {
+            None => Ok(None),
+            Some(Ok(limit)) => Ok(Some(limit)),
+            Some(Err(HeaderParseError::Invalid { value, source })) => {
+                Err(Status::invalid_argument(format!("{value}: {source}")))
+            },
+            Some(Err(error)) => Err(Status::invalid_argument(error)),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_header_all() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(AuthService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<AuthService>::new(Channel::connect(addr));
+
+    let reply = client
+        .create_rpc_context()
+        .append_header("x-scope", HeaderValue::from_static("read"))
+        .append_header("x-scope", HeaderValue::from_static("write"))
+        .send(&ListScopes)
+        .await
+        .unwrap();
+    assert_eq!(reply.to_owned().unwrap(), ["read", "write"]);
+
+    let reply = client.send(&ListScopes).await.unwrap();
+    assert!(reply.is_empty());
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_header_as() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(AuthService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<AuthService>::new(Channel::connect(addr));
+
+    let reply = client.send(&ReadLimit).await.unwrap();
+    assert_eq!(reply.to_owned().unwrap(), None);
+
+    // Only the first value is parsed.
+    let reply = client
+        .create_rpc_context()
+        .append_header("x-limit", HeaderValue::from_static(" 25 "))
+        .append_header("x-limit", HeaderValue::from_static("50"))
+        .send(&ReadLimit)
+        .await
+        .unwrap();
+    assert_eq!(reply.to_owned().unwrap(), Some(25));
+
+    let error = client
+        .create_rpc_context()
+        .set_header("x-limit", HeaderValue::from_static("many"))
+        .send(&ReadLimit)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        Status::invalid_argument("many: invalid digit found in string")
+    );
+
+    let error = client
+        .create_rpc_context()
+        .set_header("x-limit", HeaderValue::from_bytes(b"\xff").unwrap())
+        .send(&ReadLimit)
+        .await
+        .unwrap_err();
+    assert!(
+        error.message.contains("not visible ASCII"),
+        "Unexpected error: {error}"
+    );
+
+    server.shutdown();
+}
@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    IdempotencyConfig,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+    IDEMPOTENCY_KEY_HEADER,
+};
+use http::HeaderValue;
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Charge(u64);
+
+#[derive(Default)]
+pub struct PaymentService {
+    charged: Arc<AtomicUsize>,
+}
+
+impl RpcService for PaymentService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Charge>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Charge> for PaymentService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Charge>) -> Result<Self::Reply, Status> {
+        if msg.0 == 0 {
+            return Err(Status::invalid());
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let total = self.charged.fetch_add(msg.0 as usize, Ordering::Relaxed);
+        Ok(total as u64 + msg.0)
+    }
+}
+
+async fn charge(
+    client: &RpcClient<PaymentService>,
+    key: &'static str,
+    amount: u64,
+) -> Result<u64, Status> {
+    client
+        .create_rpc_context()
+        .set_header(IDEMPOTENCY_KEY_HEADER, HeaderValue::from_static(key))
+        .send(&Charge(amount))
+        .await
+        .map(|reply| reply.to_owned().unwrap())
+}
+
+#[tokio::test]
+async fn test_repeated_idempotency_key() {
+    let addr = test_helper::get_unused_addr();
+
+    let service = PaymentService::default();
+    let charged = service.charged.clone();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(service);
+    server.enable_idempotency(IdempotencyConfig::default());
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<PaymentService>::new(Channel::connect(addr));
+
+    assert_eq!(charge(&client, "payment-1", 5).await.unwrap(), 5);
+    assert_eq!(
+        charge(&client, "payment-1", 5).await.unwrap(),
+        5,
+        "The retry should return the first reply"
+    );
+    assert_eq!(charged.load(Ordering::Relaxed), 5);
+
+    assert_eq!(charge(&client, "payment-2", 5).await.unwrap(), 10);
+    // Requests without a key are always handled.
+    assert_eq!(client.send(&Charge(5)).await.unwrap(), 15);
+    assert_eq!(client.send(&Charge(5)).await.unwrap(), 20);
+
+    // Failed requests are handled again.
+    assert!(charge(&client, "payment-3", 0).await.is_err());
+    assert!(charge(&client, "payment-3", 0).await.is_err());
+
+    server.disable_idempotency();
+    assert_eq!(charge(&client, "payment-1", 5).await.unwrap(), 25);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_concurrent_duplicates() {
+    let addr = test_helper::get_unused_addr();
+
+    let service = PaymentService::default();
+    let charged = service.charged.clone();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(service);
+    server.enable_idempotency(IdempotencyConfig::default());
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<PaymentService>::new(Channel::connect(addr));
+
+    let (first, second, third) = tokio::join!(
+        charge(&client, "payment-1", 5),
+        charge(&client, "payment-1", 5),
+        charge(&client, "payment-1", 5),
+    );
+    assert_eq!(first.unwrap(), 5);
+    assert_eq!(second.unwrap(), 5);
+    assert_eq!(third.unwrap(), 5);
+    assert_eq!(
+        charged.load(Ordering::Relaxed),
+        5,
+        "Duplicates should wait for the first request"
+    );
+
+    server.shutdown();
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct MyMessage {
+    value: u64,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn service_name() -> &'static str {
+        "my-service"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<MyMessage>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<MyMessage> for MyService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<MyMessage>) -> Result<Self::Reply, Status> {
+        Ok(msg.value * 2)
+    }
+}
+
+#[tokio::test]
+async fn test_manual_dispatch() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let mut incoming = server.incoming();
+
+    let client = RpcClient::<MyService>::new(Channel::connect(addr));
+    let first = tokio::spawn({
+        let client = client.clone();
+        async move { client.send(&MyMessage { value: 1 }).await.map(|r| *r) }
+    });
+    let (first_request, first_sender) = incoming.next().await.unwrap();
+    assert_eq!(first_request.service(), "my-service");
+
+    let second = tokio::spawn({
+        let client = client.clone();
+        async move { client.send(&MyMessage { value: 2 }).await.map(|r| *r) }
+    });
+    let (second_request, second_sender) = incoming.next().await.unwrap();
+
+    // The requests are completed in the reverse order they were received.
+    second_sender.send(second_request.dispatch().await);
+    let reply = tokio::time::timeout(Duration::from_secs(5), second)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(reply.unwrap(), 4);
+    assert!(!first.is_finished(), "Request should wait to be completed");
+
+    first_sender.send(first_request.dispatch().await);
+    assert_eq!(first.await.unwrap().unwrap(), 2);
+
+    // Dropping the sender fails the request.
+    let pending = tokio::spawn({
+        let client = client.clone();
+        async move { client.send(&MyMessage { value: 3 }).await.map(|r| *r) }
+    });
+    let (_request, sender) = incoming.next().await.unwrap();
+    drop(sender);
+    let error = pending.await.unwrap().unwrap_err();
+    assert_eq!(error.code, ErrorCode::InternalError);
+
+    // Once the stream is dropped, the handlers are invoked directly again.
+    drop(incoming);
+    let reply = client.send(&MyMessage { value: 5 }).await.unwrap();
+    assert_eq!(*reply, 10);
+
+    server.shutdown();
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    AdminService,
+    Channel,
+    Handler,
+    InflightRequests,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct SlowMessage {
+    delay_ms: u64,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn service_name() -> &'static str {
+        "my-service"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<SlowMessage>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<SlowMessage> for MyService {
+    type Reply = u64;
+
+    async fn on_message(
+        &self,
+        msg: Request<SlowMessage>,
+    ) -> Result<Self::Reply, Status> {
+        tokio::time::sleep(Duration::from_millis(msg.delay_ms)).await;
+        Ok(msg.delay_ms)
+    }
+}
+
+#[tokio::test]
+async fn test_inflight_requests() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    server.enable_admin_service();
+    assert!(server.inflight_requests().is_empty());
+
+    let channel = Channel::connect(addr);
+    let rpc_client = RpcClient::<MyService>::new(channel.clone());
+    let slow =
+        tokio::spawn(
+            async move { rpc_client.send(&SlowMessage { delay_ms: 500 }).await },
+        );
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let inflight = server.inflight_requests();
+    assert_eq!(inflight.len(), 1);
+    assert_eq!(inflight[0].service, "my-service");
+    assert!(inflight[0].path.ends_with("SlowMessage"));
+    assert!(inflight[0].elapsed > Duration::ZERO);
+
+    let admin_client = RpcClient::<AdminService>::new(channel);
+    let remote = admin_client
+        .send(&InflightRequests)
+        .await
+        .expect("Query admin service");
+    assert_eq!(remote.len(), 2, "Admin request should also be in-flight");
+    assert!(remote
+        .iter()
+        .any(|r| r.id == inflight[0].id && r.service == "my-service"));
+
+    slow.await.unwrap().expect("Slow request should complete");
+    assert!(server.inflight_requests().is_empty());
+
+    server.shutdown();
+}
@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    Body,
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Upload {
+    delay_ms: u64,
+    data: Vec<u8>,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Download {
+    len: u64,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Upload>();
+        registry.add_handler::<Download>();
+        registry.add_handler::<Body>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Upload> for MyService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Upload>) -> Result<Self::Reply, Status> {
+        tokio::time::sleep(Duration::from_millis(msg.delay_ms)).await;
+        Ok(msg.data.len() as u64)
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Download> for MyService {
+    type Reply = Vec<u8>;
+
+    async fn on_message(&self, msg: Request<Download>) -> Result<Self::Reply, Status> {
+        Ok(vec![0; msg.len as usize])
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Body> for MyService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Body>) -> Result<Self::Reply, Status> {
+        let bytes = msg.into_inner().into_bytes().await?;
+        Ok(bytes.len() as u64)
+    }
+}
+
+#[tokio::test]
+async fn test_max_inflight_bytes() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    server.set_max_inflight_bytes(Some(1024));
+
+    let client = RpcClient::<MyService>::new(Channel::connect(addr));
+
+    let slow = tokio::spawn({
+        let client = client.clone();
+        async move {
+            client
+                .send(&Upload {
+                    delay_ms: 500,
+                    data: vec![0; 600],
+                })
+                .await
+        }
+    });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(server.inflight_bytes() > 600);
+
+    let status = client
+        .send(&Upload {
+            delay_ms: 0,
+            data: vec![0; 600],
+        })
+        .await
+        .expect_err("Request should be shed");
+    assert_eq!(status.code, ErrorCode::ResourceExhausted);
+
+    // Small requests still fit within the limit.
+    let reply = client
+        .send(&Upload {
+            delay_ms: 0,
+            data: vec![0; 16],
+        })
+        .await
+        .expect("Small request should be accepted");
+    assert_eq!(reply, 16);
+
+    assert_eq!(slow.await.unwrap().unwrap(), 600);
+    assert_eq!(server.inflight_bytes(), 0);
+
+    let reply = client
+        .send(&Upload {
+            delay_ms: 0,
+            data: vec![0; 600],
+        })
+        .await
+        .expect("Request should be accepted once capacity frees up");
+    assert_eq!(reply, 600);
+
+    server.set_max_inflight_bytes(None);
+    let reply = client
+        .send(&Upload {
+            delay_ms: 0,
+            data: vec![0; 4096],
+        })
+        .await
+        .expect("Limit should be removed");
+    assert_eq!(reply, 4096);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_max_inflight_reply_bytes() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    server.set_max_inflight_bytes(Some(1024));
+
+    let client = RpcClient::<MyService>::new(Channel::connect(addr));
+
+    // The request is small, but its reply does not fit within the limit.
+    let status = client
+        .send(&Download { len: 4096 })
+        .await
+        .expect_err("Reply should be shed");
+    assert_eq!(status.code, ErrorCode::ResourceExhausted);
+    assert_eq!(server.inflight_bytes(), 0);
+
+    let reply = client
+        .send(&Download { len: 16 })
+        .await
+        .expect("Small reply should be sent");
+    assert_eq!(reply.len(), 16);
+
+    server.set_max_inflight_bytes(None);
+    let reply = client
+        .send(&Download { len: 4096 })
+        .await
+        .expect("Limit should be removed");
+    assert_eq!(reply.len(), 4096);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_max_inflight_bytes_unknown_length() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    server.set_max_inflight_bytes(Some(64 << 10));
+
+    let client = RpcClient::<MyService>::new(Channel::connect(addr));
+
+    let body = Body::from_async_read(tokio::io::repeat(7).take(16 << 10));
+    let reply = client.send_owned(body).await.unwrap();
+    assert_eq!(reply, 16 << 10);
+
+    // Streamed bodies are charged as they are read.
+    let body = Body::from_async_read(tokio::io::repeat(7).take(1 << 20));
+    let status = client
+        .send_owned(body)
+        .await
+        .expect_err("Streamed bodies should be cut off at the limit");
+    assert_eq!(status.code, ErrorCode::ResourceExhausted);
+    assert_eq!(server.inflight_bytes(), 0);
+
+    server.shutdown();
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Ping;
+
+pub struct PingService;
+
+impl RpcService for PingService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Ping>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Ping> for PingService {
+    type Reply = ();
+
+    async fn on_message(&self, _msg: Request<Ping>) -> Result<Self::Reply, Status> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_server_ready() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Arc::new(Server::listen(addr).await.unwrap());
+    println!("Listening to address {}!", addr);
+
+    let pending = tokio::time::timeout(Duration::from_millis(100), server.ready()).await;
+    assert!(
+        pending.is_err(),
+        "Server without services should not be ready"
+    );
+
+    let registering = server.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        registering.add_service(PingService);
+    });
+
+    tokio::time::timeout(Duration::from_secs(5), server.ready())
+        .await
+        .expect("Server should become ready");
+
+    let client = RpcClient::<PingService>::new(Channel::connect(addr));
+    client
+        .send(&Ping)
+        .await
+        .expect("Ready server should handle requests");
+
+    // Once ready, the future completes immediately.
+    server.ready().await;
+}
+
+#[tokio::test]
+async fn test_server_wait_closed() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(PingService);
+    println!("Listening to address {}!", addr);
+
+    let closed = server.wait_closed();
+    let early =
+        tokio::time::timeout(Duration::from_millis(100), server.wait_closed()).await;
+    assert!(early.is_err(), "Running server should not be closed");
+
+    server.shutdown();
+    tokio::time::timeout(Duration::from_secs(5), closed)
+        .await
+        .expect("Server should close");
+
+    let client = RpcClient::<PingService>::new(Channel::connect(addr));
+    client
+        .send(&Ping)
+        .await
+        .expect_err("Closed server should not accept connections");
+}
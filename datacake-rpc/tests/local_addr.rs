@@ -0,0 +1,70 @@
+use std::net::SocketAddr;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Ping;
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Ping>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Ping> for MyService {
+    type Reply = String;
+
+    async fn on_message(&self, _msg: Request<Ping>) -> Result<Self::Reply, Status> {
+        Ok("pong".to_string())
+    }
+}
+
+#[tokio::test]
+async fn test_local_addr_ephemeral_port() {
+    let bind = "127.0.0.1:0".parse::<SocketAddr>().unwrap();
+    let server = Server::listen(bind).await.unwrap();
+    server.add_service(MyService);
+
+    let addr = server.local_addr();
+    assert_eq!(addr.ip(), bind.ip());
+    assert_ne!(addr.port(), 0, "The OS assigned port should be exposed");
+
+    let client = RpcClient::<MyService>::new(Channel::connect(addr));
+    let resp = client.send(&Ping).await.expect("Send RPC message");
+    assert_eq!(resp, "pong".to_string());
+
+    server.shutdown();
+}
+
+#[cfg(not(feature = "simulation"))]
+#[tokio::test]
+async fn test_from_std_listener() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let bound = listener.local_addr().unwrap();
+
+    let server = Server::from_std_listener(listener).await.unwrap();
+    server.add_service(MyService);
+    assert_eq!(server.local_addr(), bound);
+
+    let client = RpcClient::<MyService>::new(Channel::connect(bound));
+    let resp = client.send(&Ping).await.expect("Send RPC message");
+    assert_eq!(resp, "pong".to_string());
+
+    server.shutdown();
+}
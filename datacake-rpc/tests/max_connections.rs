@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Ping;
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Ping>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Ping> for MyService {
+    type Reply = u64;
+
+    async fn on_message(&self, _msg: Request<Ping>) -> Result<Self::Reply, Status> {
+        Ok(1)
+    }
+}
+
+#[tokio::test]
+async fn test_max_connections() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    server.set_max_connections(Some(1));
+    assert_eq!(server.connection_count(), 0);
+
+    let first = Channel::connect(addr);
+    RpcClient::<MyService>::new(first.clone())
+        .send(&Ping)
+        .await
+        .expect("First connection should be served");
+    assert_eq!(server.connection_count(), 1);
+
+    let mut second = RpcClient::<MyService>::new(Channel::connect(addr));
+    second.set_timeout(Duration::from_millis(250));
+    let error = second
+        .send(&Ping)
+        .await
+        .expect_err("Second connection should not be accepted while at capacity");
+    assert_eq!(error.code, ErrorCode::Timeout);
+    assert_eq!(server.connection_count(), 1);
+
+    // Once the first connection closes, the pending connection is accepted.
+    first.close(Duration::from_secs(1)).await;
+    second.set_timeout(Duration::from_secs(5));
+    second
+        .send(&Ping)
+        .await
+        .expect("Second connection should be served once capacity is available");
+    assert_eq!(server.connection_count(), 1);
+
+    server.shutdown();
+}
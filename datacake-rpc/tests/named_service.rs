@@ -0,0 +1,69 @@
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+
+pub struct AddN(u64);
+
+impl RpcService for AddN {
+    fn service_name() -> &'static str {
+        "add-n"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<u64>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<u64> for AddN {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<u64>) -> Result<Self::Reply, Status> {
+        Ok(**msg + self.0)
+    }
+}
+
+fn path_for(service: &str) -> String {
+    format!("/{service}/{}", <AddN as Handler<u64>>::path())
+}
+
+#[tokio::test]
+async fn test_named_services() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(AddN(1));
+    server.add_service_named("add-ten", AddN(10));
+    server.add_service_named("add-hundred", AddN(100));
+
+    let client = RpcClient::<AddN>::new(Channel::connect(addr));
+
+    assert_eq!(client.send(&1).await.unwrap(), 2);
+    assert_eq!(client.send_to(&path_for("add-ten"), &1).await.unwrap(), 11);
+    assert_eq!(
+        client.send_to(&path_for("add-hundred"), &1).await.unwrap(),
+        101
+    );
+
+    server.remove_service("add-ten");
+    let status = client
+        .send_to(&path_for("add-ten"), &1)
+        .await
+        .expect_err("Alias should be removed");
+    assert_eq!(status.code, ErrorCode::ServiceUnavailable);
+    assert_eq!(
+        client.send(&1).await.unwrap(),
+        2,
+        "Original name should remain"
+    );
+
+    server.shutdown();
+}
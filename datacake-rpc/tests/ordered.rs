@@ -0,0 +1,106 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Step {
+    id: u64,
+    delay_ms: u64,
+}
+
+#[derive(Default, Clone)]
+pub struct StatefulService {
+    completed: Arc<Mutex<Vec<u64>>>,
+}
+
+impl RpcService for StatefulService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Step>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Step> for StatefulService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Step>) -> Result<Self::Reply, Status> {
+        tokio::time::sleep(Duration::from_millis(msg.delay_ms)).await;
+        self.completed.lock().unwrap().push(msg.id);
+        Ok(msg.id)
+    }
+}
+
+/// Sends the steps concurrently with earlier steps taking the longest,
+/// returning the order in which the server completed them.
+async fn run_steps(channel: Channel, service: &StatefulService) -> Vec<u64> {
+    channel.warmup().await.unwrap();
+    let client = RpcClient::<StatefulService>::new(channel);
+
+    let mut tasks = Vec::new();
+    for id in 0..5 {
+        let client = client.clone();
+        tasks.push(tokio::spawn(async move {
+            let msg = Step {
+                id,
+                delay_ms: (5 - id) * 20,
+            };
+            client.send(&msg).await.unwrap()
+        }));
+    }
+    for (id, task) in tasks.into_iter().enumerate() {
+        assert_eq!(task.await.unwrap(), id as u64);
+    }
+
+    std::mem::take(&mut *service.completed.lock().unwrap())
+}
+
+#[tokio::test]
+async fn test_ordered_connections() {
+    let addr = test_helper::get_unused_addr();
+
+    let service = StatefulService::default();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(service.clone());
+    println!("Listening to address {}!", addr);
+
+    let completed = run_steps(Channel::connect(addr), &service).await;
+    assert_eq!(
+        completed,
+        vec![4, 3, 2, 1, 0],
+        "Requests should be handled concurrently by default"
+    );
+
+    server.set_ordered_connections(true);
+    let completed = run_steps(Channel::connect(addr), &service).await;
+    assert_eq!(completed, vec![0, 1, 2, 3, 4]);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_ordered_channel() {
+    let addr = test_helper::get_unused_addr();
+
+    let service = StatefulService::default();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(service.clone());
+    println!("Listening to address {}!", addr);
+
+    let completed = run_steps(Channel::connect(addr).ordered(), &service).await;
+    assert_eq!(completed, vec![0, 1, 2, 3, 4]);
+
+    server.shutdown();
+}
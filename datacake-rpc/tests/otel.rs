@@ -0,0 +1,114 @@
+#![cfg(feature = "otel")]
+
+use std::net::SocketAddr;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+    TRACEPARENT_HEADER,
+};
+use http::HeaderValue;
+use rkyv::{Archive, Deserialize, Serialize};
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_ID: &str = "00f067aa0ba902b7";
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Forward;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Inspect;
+
+pub struct FrontService {
+    backend: SocketAddr,
+}
+
+impl RpcService for FrontService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Forward>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Forward> for FrontService {
+    type Reply = String;
+
+    async fn on_message(&self, _msg: Request<Forward>) -> Result<Self::Reply, Status> {
+        let client = RpcClient::<BackendService>::new(Channel::connect(self.backend));
+        let reply = client.send(&Inspect).await?;
+        Ok(reply.to_string())
+    }
+}
+
+pub struct BackendService;
+
+impl RpcService for BackendService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Inspect>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Inspect> for BackendService {
+    type Reply = String;
+
+    async fn on_message(&self, msg: Request<Inspect>) -> Result<Self::Reply, Status> {
+        let parent = msg
+            .headers()
+            .get(TRACEPARENT_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        Ok(parent.to_string())
+    }
+}
+
+#[tokio::test]
+async fn test_trace_context_propagation() {
+    let backend_addr = test_helper::get_unused_addr();
+    let backend = Server::listen(backend_addr).await.unwrap();
+    backend.add_service(BackendService);
+
+    let front_addr = test_helper::get_unused_addr();
+    let front = Server::listen(front_addr).await.unwrap();
+    front.add_service(FrontService {
+        backend: backend_addr,
+    });
+
+    let client = RpcClient::<FrontService>::new(Channel::connect(front_addr));
+
+    let parent = format!("00-{TRACE_ID}-{PARENT_ID}-01");
+    let resp = client
+        .create_rpc_context()
+        .set_header(TRACEPARENT_HEADER, HeaderValue::from_str(&parent).unwrap())
+        .send(&Forward)
+        .await
+        .expect("Send RPC message");
+
+    let parts = resp.split('-').collect::<Vec<_>>();
+    assert_eq!(
+        parts.len(),
+        4,
+        "Backend should receive a trace parent: {resp:?}"
+    );
+    assert_eq!(parts[1], TRACE_ID, "Trace id should be propagated");
+    assert_ne!(parts[2], PARENT_ID, "A new parent id should be generated");
+    assert_eq!(parts[3], "01");
+
+    let resp = client.send(&Forward).await.expect("Send RPC message");
+    assert_eq!(resp, String::new(), "No trace context should be invented");
+
+    front.shutdown();
+    backend.shutdown();
+}
@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    AdminService,
+    Channel,
+    ErrorCode,
+    Handler,
+    InflightRequests,
+    OverloadConfig,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Ping;
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Ping>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Ping> for MyService {
+    type Reply = u64;
+
+    async fn on_message(&self, _msg: Request<Ping>) -> Result<Self::Reply, Status> {
+        Ok(1)
+    }
+}
+
+#[tokio::test]
+async fn test_shed_requests_while_overloaded() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    server.enable_admin_service();
+    server.set_overload_detector(OverloadConfig {
+        probe_interval: Duration::from_millis(10),
+        delay_threshold: Duration::from_millis(50),
+        shed_duration: Duration::from_secs(1),
+    });
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<MyService>::new(Channel::connect(addr));
+    client
+        .send(&Ping)
+        .await
+        .expect("Server should not be overloaded");
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let delay = server.scheduling_delay().expect("Delay should be measured");
+    assert!(
+        delay < Duration::from_millis(50),
+        "Unexpected delay {delay:?}"
+    );
+
+    // Saturate the runtime by blocking it between yields.
+    tokio::spawn(async {
+        for _ in 0..5 {
+            std::thread::sleep(Duration::from_millis(100));
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let error = client
+        .send(&Ping)
+        .await
+        .expect_err("Requests should be shed while overloaded");
+    assert_eq!(error.code, ErrorCode::ServiceUnavailable);
+
+    let admin = RpcClient::<AdminService>::new(Channel::connect(addr));
+    admin
+        .send(&InflightRequests)
+        .await
+        .expect("Admin requests should still be served");
+
+    // Requests are accepted again once the runtime has recovered.
+    tokio::time::sleep(Duration::from_millis(1_500)).await;
+    client
+        .send(&Ping)
+        .await
+        .expect("Server should have recovered");
+
+    server.disable_overload_detector();
+    assert_eq!(server.scheduling_delay(), None);
+
+    server.shutdown();
+}
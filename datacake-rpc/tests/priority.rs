@@ -0,0 +1,141 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use datacake_rpc::{
+    Body,
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+use tokio::io::{AsyncRead, ReadBuf};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Download {
+    len: u64,
+}
+
+/// Produces `remaining` bytes in chunks of at most 64KB.
+struct Source {
+    remaining: usize,
+}
+
+impl AsyncRead for Source {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let len = buf.remaining().min(self.remaining).min(64 << 10);
+        buf.put_slice(&vec![1; len]);
+        self.remaining -= len;
+        Poll::Ready(Ok(()))
+    }
+}
+
+pub struct DownloadService;
+
+impl RpcService for DownloadService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Download>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Download> for DownloadService {
+    type Reply = Body;
+
+    async fn on_message(&self, msg: Request<Download>) -> Result<Self::Reply, Status> {
+        Ok(Body::from_async_read(Source {
+            remaining: msg.len as usize,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_urgent_replies_complete_first() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(DownloadService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<DownloadService>::new(Channel::connect(addr));
+
+    let bulk = {
+        let client = client.clone();
+        tokio::spawn(async move {
+            let mut body = client
+                .create_rpc_context()
+                .set_priority(7)
+                .send(&Download { len: 64 << 20 })
+                .await
+                .unwrap();
+            let mut received = 0;
+            while let Some(chunk) = body.next_chunk().await {
+                received += chunk.unwrap().len();
+            }
+            assert_eq!(received, 64 << 20);
+            Instant::now()
+        })
+    };
+
+    let mut urgent = Vec::new();
+    for _ in 0..10 {
+        let client = client.clone();
+        urgent.push(tokio::spawn(async move {
+            let body = client
+                .create_rpc_context()
+                .set_priority(0)
+                .send(&Download { len: 1024 })
+                .await
+                .unwrap();
+            let bytes = body.into_bytes().await.unwrap();
+            assert_eq!(bytes.len(), 1024);
+            Instant::now()
+        }));
+    }
+
+    let bulk_completed = bulk.await.unwrap();
+    for task in urgent {
+        let completed = task.await.unwrap();
+        assert!(
+            completed < bulk_completed,
+            "Urgent replies should complete before the bulk reply"
+        );
+    }
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_invalid_priority_is_ignored() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(DownloadService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<DownloadService>::new(Channel::connect(addr));
+    let body = client
+        .create_rpc_context()
+        .set_header(
+            datacake_rpc::PRIORITY_HEADER,
+            http::HeaderValue::from_static("u=high"),
+        )
+        .send(&Download { len: 100 })
+        .await
+        .unwrap();
+    assert_eq!(body.into_bytes().await.unwrap().len(), 100);
+
+    server.shutdown();
+}
@@ -0,0 +1,113 @@
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Rebuild {
+    steps: u32,
+    fail: bool,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, PartialEq, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct RebuildProgress {
+    completed: u32,
+}
+
+pub struct IndexService;
+
+impl RpcService for IndexService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Rebuild>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Rebuild> for IndexService {
+    type Reply = String;
+
+    async fn on_message(&self, msg: Request<Rebuild>) -> Result<Self::Reply, Status> {
+        let progress = msg.progress_sender::<RebuildProgress>();
+        for completed in 0..msg.steps {
+            progress.send(&RebuildProgress { completed }).await?;
+            tokio::task::yield_now().await;
+        }
+
+        if msg.fail {
+            return Err(Status::internal("Rebuild failed"));
+        }
+        Ok(format!("Rebuilt in {} steps", msg.steps))
+    }
+}
+
+#[tokio::test]
+async fn test_progress_updates() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(IndexService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<IndexService>::new(Channel::connect(addr));
+
+    let mut updates = Vec::new();
+    let msg = Rebuild {
+        steps: 40,
+        fail: false,
+    };
+    let reply = client
+        .send_with_progress(&msg, |progress: datacake_rpc::DataView<RebuildProgress>| {
+            updates.push(progress.completed)
+        })
+        .await
+        .unwrap();
+    assert_eq!(reply.as_str(), "Rebuilt in 40 steps");
+    assert_eq!(updates, (0..40).collect::<Vec<_>>());
+
+    // Progress is discarded for plain requests.
+    let reply = client.send(&msg).await.unwrap();
+    assert_eq!(reply.as_str(), "Rebuilt in 40 steps");
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_progress_error_status() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(IndexService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<IndexService>::new(Channel::connect(addr));
+
+    let mut updates = 0;
+    let msg = Rebuild {
+        steps: 3,
+        fail: true,
+    };
+    let error = client
+        .send_with_progress(&msg, |_: datacake_rpc::DataView<RebuildProgress>| {
+            updates += 1
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(error.code, ErrorCode::InternalError);
+    assert_eq!(error.message, "Rebuild failed");
+    assert_eq!(updates, 3, "Updates should arrive before the error");
+
+    server.shutdown();
+}
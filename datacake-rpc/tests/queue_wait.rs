@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct QueueWait;
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<QueueWait>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<QueueWait> for MyService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<QueueWait>) -> Result<Self::Reply, Status> {
+        // Time spent in the handler is not counted as queue wait.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        Ok(msg.queued_for().as_millis() as u64)
+    }
+}
+
+#[tokio::test]
+async fn test_queue_wait() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<MyService>::new(Channel::connect(addr));
+
+    let queued_ms = client.send(&QueueWait).await.unwrap();
+    assert!(
+        *queued_ms < 100,
+        "Directly dispatched requests should not wait, waited {}ms",
+        *queued_ms,
+    );
+
+    let mut incoming = server.incoming();
+    let pending = tokio::spawn({
+        let client = client.clone();
+        async move { client.send(&QueueWait).await.map(|r| *r) }
+    });
+
+    let (request, sender) = incoming.next().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert!(request.queued_for() >= Duration::from_millis(250));
+    sender.send(request.dispatch().await);
+
+    let queued_ms = pending.await.unwrap().unwrap();
+    assert!(
+        (250..2_000).contains(&queued_ms),
+        "Request should have waited in the queue, waited {queued_ms}ms",
+    );
+
+    server.shutdown();
+}
@@ -0,0 +1,141 @@
+use datacake_rpc::{
+    Body,
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use http::header::CONTENT_RANGE;
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct ReadBlob;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct ReadValues;
+
+pub struct BlobService {
+    blob: Vec<u8>,
+}
+
+impl RpcService for BlobService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<ReadBlob>();
+        registry.add_handler::<ReadValues>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<ReadBlob> for BlobService {
+    type Reply = Body;
+
+    async fn on_message(&self, _msg: Request<ReadBlob>) -> Result<Self::Reply, Status> {
+        Ok(Body::from(self.blob.clone()))
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<ReadValues> for BlobService {
+    type Reply = Vec<u8>;
+
+    async fn on_message(
+        &self,
+        _msg: Request<ReadValues>,
+    ) -> Result<Self::Reply, Status> {
+        Ok(self.blob.clone())
+    }
+}
+
+#[tokio::test]
+async fn test_ranged_reply() {
+    let addr = test_helper::get_unused_addr();
+    let blob = (0..=255u8).collect::<Vec<_>>();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(BlobService { blob: blob.clone() });
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<BlobService>::new(Channel::connect(addr));
+
+    let reply = client.send_ranged(&ReadBlob, 16..32).await.unwrap();
+    assert_eq!(
+        reply.headers().get(CONTENT_RANGE).unwrap(),
+        "bytes 16-31/256"
+    );
+    assert_eq!(reply.into_bytes().await.unwrap(), &blob[16..32]);
+
+    let reply = client.send_ranged(&ReadBlob, 200..).await.unwrap();
+    assert_eq!(
+        reply.headers().get(CONTENT_RANGE).unwrap(),
+        "bytes 200-255/256"
+    );
+    assert_eq!(reply.into_bytes().await.unwrap(), &blob[200..]);
+
+    // Ranges extending past the end of the reply are truncated.
+    let reply = client.send_ranged(&ReadBlob, 250..1000).await.unwrap();
+    assert_eq!(
+        reply.headers().get(CONTENT_RANGE).unwrap(),
+        "bytes 250-255/256"
+    );
+    assert_eq!(reply.into_bytes().await.unwrap(), &blob[250..]);
+
+    // Unranged requests receive the full reply.
+    let reply = client.send(&ReadBlob).await.unwrap();
+    assert!(reply.headers().get(CONTENT_RANGE).is_none());
+    assert_eq!(reply.into_bytes().await.unwrap(), &blob[..]);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_ranged_reply_out_of_range() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(BlobService { blob: vec![0; 64] });
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<BlobService>::new(Channel::connect(addr));
+
+    let Err(error) = client.send_ranged(&ReadBlob, 64..).await else {
+        panic!("Range beyond the reply should error");
+    };
+    assert_eq!(error.code, ErrorCode::OutOfRange);
+
+    let Err(error) = client.send_ranged(&ReadBlob, 8..8).await else {
+        panic!("Empty range should error");
+    };
+    assert_eq!(
+        error.code,
+        ErrorCode::OutOfRange,
+        "Empty ranges are rejected"
+    );
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_ranged_archived_reply_ignores_range() {
+    let addr = test_helper::get_unused_addr();
+    let blob = vec![7; 64];
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(BlobService { blob: blob.clone() });
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<BlobService>::new(Channel::connect(addr));
+
+    let reply = client.send_ranged(&ReadValues, 0..8).await.unwrap();
+    assert_eq!(reply.to_owned().unwrap(), blob);
+
+    server.shutdown();
+}
@@ -0,0 +1,94 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+const MIN: usize = 4 << 10;
+const MAX: usize = 1 << 20;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Upload {
+    data: Vec<u8>,
+}
+
+pub struct UploadService;
+
+impl RpcService for UploadService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Upload>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Upload> for UploadService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Upload>) -> Result<Self::Reply, Status> {
+        Ok(msg.data.len() as u64)
+    }
+}
+
+#[tokio::test]
+async fn test_read_buffer_shrinks_after_large_request() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(UploadService);
+    server.set_read_buffer_bounds(MIN, MAX);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<UploadService>::new(Channel::connect(addr));
+    let small = Upload { data: vec![1; 16] };
+    assert_eq!(client.send(&small).await.unwrap(), 16);
+    assert_eq!(server.read_buffer_bytes(), MIN);
+
+    // Sample the buffer size while the large request is being received.
+    let done = AtomicBool::new(false);
+    let sample = async {
+        let mut peak = 0;
+        while !done.load(Ordering::Relaxed) {
+            peak = peak.max(server.read_buffer_bytes());
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        peak
+    };
+    let send = async {
+        let large = Upload {
+            data: vec![2; 8 << 20],
+        };
+        let reply = client.send(&large).await.unwrap();
+        done.store(true, Ordering::Relaxed);
+        reply
+    };
+    let (peak, reply) = tokio::join!(sample, send);
+    assert_eq!(reply, 8 << 20);
+    assert!(peak > MIN, "Buffer should grow for the large request");
+    assert!(peak <= MAX, "Buffer should not exceed the maximum");
+
+    for _ in 0..50 {
+        if server.read_buffer_bytes() == MIN {
+            break;
+        }
+        client.send(&small).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(
+        server.read_buffer_bytes(),
+        MIN,
+        "Buffer should shrink back down once small requests arrive"
+    );
+
+    server.shutdown();
+}
@@ -0,0 +1,177 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use datacake_rpc::{
+    CaptureConfig,
+    CapturedFrame,
+    Channel,
+    ErrorCode,
+    FrameKind,
+    Handler,
+    ReplayClient,
+    ReplayConfig,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Add(u64);
+
+#[derive(Default)]
+pub struct CounterService {
+    total: Arc<AtomicUsize>,
+}
+
+impl RpcService for CounterService {
+    fn service_name() -> &'static str {
+        "counter"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Add>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Add> for CounterService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Add>) -> Result<Self::Reply, Status> {
+        if msg.0 == 0 {
+            return Err(Status::invalid());
+        }
+        let total = self.total.fetch_add(msg.0 as usize, Ordering::Relaxed);
+        Ok(total as u64 + msg.0)
+    }
+}
+
+fn request_frame(request_id: u64, value: u64) -> CapturedFrame {
+    let body = datacake_rpc::to_view_bytes(&Add(value)).unwrap();
+    CapturedFrame {
+        kind: FrameKind::Request,
+        request_id,
+        path: format!("/counter/{}", <CounterService as Handler<Add>>::path()),
+        body: Bytes::copy_from_slice(&body),
+    }
+}
+
+#[tokio::test]
+async fn test_replay_reports_statuses() {
+    let addr = test_helper::get_unused_addr();
+
+    let service = CounterService::default();
+    let total = service.total.clone();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(service);
+    println!("Listening to address {}!", addr);
+
+    let mut frames = vec![request_frame(1, 1), request_frame(2, 0)];
+    frames.push(CapturedFrame {
+        kind: FrameKind::Reply,
+        ..request_frame(1, 1)
+    });
+    frames.push(CapturedFrame {
+        path: "/unknown/path".to_string(),
+        ..request_frame(3, 1)
+    });
+    frames.push(request_frame(4, 2));
+
+    let client = ReplayClient::new(Channel::connect(addr));
+    let report = client.replay(frames).await;
+
+    let ids = report
+        .outcomes
+        .iter()
+        .map(|outcome| outcome.request_id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [1, 2, 3, 4], "Reply frames should not be replayed");
+    assert_eq!(report.succeeded(), 2);
+    assert_eq!(total.load(Ordering::Relaxed), 3);
+
+    let failures = report.failures().collect::<Vec<_>>();
+    assert_eq!(failures.len(), 2);
+    let status = failures[0].result.as_ref().unwrap_err();
+    assert_eq!(status.code, ErrorCode::InvalidPayload);
+    let status = failures[1].result.as_ref().unwrap_err();
+    assert_eq!(status.code, ErrorCode::ServiceUnavailable);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_replay_rate_and_concurrency() {
+    let addr = test_helper::get_unused_addr();
+
+    let service = CounterService::default();
+    let total = service.total.clone();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(service);
+    println!("Listening to address {}!", addr);
+
+    let frames = (0..10).map(|id| request_frame(id, 1)).collect::<Vec<_>>();
+    let client = ReplayClient::new(Channel::connect(addr)).with_config(ReplayConfig {
+        concurrency: 4,
+        rate: Some(50),
+    });
+
+    let start = Instant::now();
+    let report = client.replay(frames).await;
+    assert!(
+        start.elapsed() >= Duration::from_millis(150),
+        "Requests should be paced by the rate"
+    );
+    assert_eq!(report.succeeded(), 10);
+    assert_eq!(total.load(Ordering::Relaxed), 10);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_replay_capture_file() {
+    let addr = test_helper::get_unused_addr();
+    let path = std::env::temp_dir().join(format!("datacake-replay-{}", addr.port()));
+
+    let service = CounterService::default();
+    let total = service.total.clone();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(service);
+    server
+        .enable_capture(CaptureConfig {
+            path: Some(path.clone()),
+            ..Default::default()
+        })
+        .unwrap();
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<CounterService>::new(Channel::connect(addr));
+    client.send(&Add(5)).await.unwrap();
+    client.send(&Add(7)).await.unwrap();
+
+    let mut frames = Vec::new();
+    for _ in 0..50 {
+        frames = CapturedFrame::read_all(std::fs::File::open(&path).unwrap()).unwrap();
+        if frames.len() == 4 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(frames.len(), 4);
+    server.disable_capture();
+
+    let report = datacake_rpc::replay(addr, &path).await.unwrap();
+    assert_eq!(report.outcomes.len(), 2);
+    assert_eq!(report.succeeded(), 2);
+    assert_eq!(total.load(Ordering::Relaxed), 24);
+
+    server.shutdown();
+    let _ = std::fs::remove_file(path);
+}
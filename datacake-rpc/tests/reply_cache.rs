@@ -0,0 +1,135 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    ReplyCacheConfig,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Lookup {
+    key: u64,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Uncached {
+    key: u64,
+}
+
+pub struct LookupService {
+    calls: Arc<AtomicU64>,
+}
+
+impl RpcService for LookupService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_cacheable_handler::<Lookup>();
+        registry.add_handler::<Uncached>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Lookup> for LookupService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Lookup>) -> Result<Self::Reply, Status> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        Ok(msg.key * 2)
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Uncached> for LookupService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Uncached>) -> Result<Self::Reply, Status> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        Ok(msg.key * 2)
+    }
+}
+
+#[tokio::test]
+async fn test_reply_cache() {
+    let addr = test_helper::get_unused_addr();
+
+    let calls = Arc::new(AtomicU64::new(0));
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(LookupService {
+        calls: calls.clone(),
+    });
+    assert!(server.reply_cache_stats().is_none());
+    server.enable_reply_cache(ReplyCacheConfig {
+        max_entries: 16,
+        ttl: Duration::from_millis(500),
+    });
+
+    let client = RpcClient::<LookupService>::new(Channel::connect(addr));
+
+    for _ in 0..3 {
+        let resp = client
+            .send(&Lookup { key: 4 })
+            .await
+            .expect("Send RPC message");
+        assert_eq!(resp, 8);
+    }
+    assert_eq!(
+        calls.load(Ordering::Relaxed),
+        1,
+        "Repeated requests should hit the cache"
+    );
+
+    let resp = client
+        .send(&Lookup { key: 5 })
+        .await
+        .expect("Send RPC message");
+    assert_eq!(resp, 10);
+    assert_eq!(
+        calls.load(Ordering::Relaxed),
+        2,
+        "Different requests should miss"
+    );
+
+    for _ in 0..2 {
+        client
+            .send(&Uncached { key: 4 })
+            .await
+            .expect("Send RPC message");
+    }
+    assert_eq!(
+        calls.load(Ordering::Relaxed),
+        4,
+        "Handlers must opt in to caching"
+    );
+
+    let stats = server.reply_cache_stats().expect("Cache should be enabled");
+    assert_eq!(stats.hits, 2);
+    assert_eq!(stats.misses, 2);
+    assert_eq!(stats.entries, 2);
+
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    client
+        .send(&Lookup { key: 4 })
+        .await
+        .expect("Send RPC message");
+    assert_eq!(
+        calls.load(Ordering::Relaxed),
+        5,
+        "Expired replies should not be used"
+    );
+
+    server.shutdown();
+}
@@ -0,0 +1,135 @@
+use datacake_rpc::http::{HeaderMap, HeaderName, HeaderValue};
+use datacake_rpc::{
+    Body,
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+    REQUEST_ID_HEADER,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Ping;
+
+pub struct PingService;
+
+impl RpcService for PingService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Ping>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Ping> for PingService {
+    type Reply = Body;
+
+    async fn on_message(&self, _msg: Request<Ping>) -> Result<Self::Reply, Status> {
+        Ok(Body::from(Vec::new()))
+    }
+}
+
+async fn send_with(
+    client: &RpcClient<PingService>,
+    headers: &[(&'static str, &'static str)],
+) -> HeaderMap {
+    let headers = headers
+        .iter()
+        .map(|&(name, value)| (name, HeaderValue::from_static(value)));
+    let reply = client
+        .create_rpc_context()
+        .set_headers(headers)
+        .send(&Ping)
+        .await
+        .unwrap();
+    reply.headers().clone()
+}
+
+#[tokio::test]
+async fn test_request_id_default_header() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(PingService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<PingService>::new(Channel::connect(addr));
+
+    let headers = send_with(&client, &[(REQUEST_ID_HEADER, "req-1")]).await;
+    assert_eq!(headers[REQUEST_ID_HEADER], "req-1");
+
+    let first = send_with(&client, &[]).await;
+    let second = send_with(&client, &[]).await;
+    assert!(
+        !first[REQUEST_ID_HEADER].is_empty(),
+        "Id should be generated"
+    );
+    assert_ne!(first[REQUEST_ID_HEADER], second[REQUEST_ID_HEADER]);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_request_id_custom_header() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(PingService);
+    server.set_request_id_header(HeaderName::from_static("x-correlation-id"));
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<PingService>::new(Channel::connect(addr));
+
+    let headers = send_with(
+        &client,
+        &[("x-correlation-id", "corr-1"), (REQUEST_ID_HEADER, "req-1")],
+    )
+    .await;
+    assert_eq!(headers["x-correlation-id"], "corr-1");
+    assert!(!headers.contains_key(REQUEST_ID_HEADER));
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_request_id_extractor() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(PingService);
+    // Uses the trace id of a W3C `traceparent` as the correlation id.
+    server.set_request_id_extractor(|headers| {
+        let parent = headers.get("traceparent")?.to_str().ok()?;
+        parent.split('-').nth(1).map(str::to_string)
+    });
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<PingService>::new(Channel::connect(addr));
+
+    let headers = send_with(
+        &client,
+        &[(
+            "traceparent",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        )],
+    )
+    .await;
+    assert_eq!(
+        headers[REQUEST_ID_HEADER],
+        "4bf92f3577b34da6a3ce929d0e0e4736"
+    );
+
+    let headers = send_with(&client, &[]).await;
+    assert!(
+        !headers[REQUEST_ID_HEADER].is_empty(),
+        "Id should be generated"
+    );
+
+    server.shutdown();
+}
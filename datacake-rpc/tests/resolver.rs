@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use datacake_rpc::{
+    Channel,
+    ChannelConfig,
+    Handler,
+    Request,
+    Resolver,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+
+pub struct AddOne;
+
+impl RpcService for AddOne {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<u64>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<u64> for AddOne {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<u64>) -> Result<Self::Reply, Status> {
+        Ok(**msg + 1)
+    }
+}
+
+/// A resolver backed by a fixed set of names, like a mesh control plane.
+struct StaticResolver(HashMap<String, SocketAddr>);
+
+#[datacake_rpc::async_trait]
+impl Resolver for StaticResolver {
+    async fn resolve(&self, host: &str, _port: u16) -> io::Result<Vec<SocketAddr>> {
+        Ok(self.0.get(host).copied().into_iter().collect())
+    }
+}
+
+#[tokio::test]
+async fn test_custom_resolver() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(AddOne);
+
+    let resolver = Arc::new(StaticResolver(HashMap::from([(
+        "add-one.service.mesh".to_string(),
+        addr,
+    )])));
+
+    let channel = Channel::connect_host_with_resolver(
+        "add-one.service.mesh",
+        addr.port(),
+        resolver.clone(),
+        ChannelConfig::default(),
+    )
+    .await
+    .expect("Resolve host");
+    assert_eq!(channel.remote_addr(), addr);
+
+    let client = RpcClient::<AddOne>::new(channel);
+    let reply = client.send(&1).await.unwrap();
+    assert_eq!(reply, 2);
+
+    let err = Channel::connect_host_with_resolver(
+        "unknown.service.mesh",
+        addr.port(),
+        resolver,
+        ChannelConfig::default(),
+    )
+    .await
+    .err()
+    .expect("Unknown host should fail to resolve");
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_system_resolver() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(AddOne);
+
+    let channel = Channel::connect_host(&addr.ip().to_string(), addr.port())
+        .await
+        .expect("Resolve host");
+    assert_eq!(channel.remote_addr(), addr);
+
+    let client = RpcClient::<AddOne>::new(channel);
+    let reply = client.send(&41).await.unwrap();
+    assert_eq!(reply, 42);
+
+    server.shutdown();
+}
@@ -0,0 +1,43 @@
+use datacake_rpc::{to_view_bytes, Body, RequestContents};
+use rkyv::{AlignedVec, Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, PartialEq, Debug)]
+#[archive(compare(PartialEq), check_bytes)]
+#[archive_attr(derive(PartialEq, Debug))]
+pub struct MyMessage {
+    name: String,
+}
+
+#[tokio::test]
+async fn test_from_body_into_reuses_buffer() {
+    let mut buffer = AlignedVec::with_capacity(4096);
+    let original_ptr = buffer.as_ptr();
+
+    for name in ["bobby", "jimmy", "timmy"] {
+        let msg = MyMessage {
+            name: name.to_string(),
+        };
+        let bytes = to_view_bytes(&msg).unwrap();
+        let body = Body::from(bytes.to_vec());
+
+        let view = MyMessage::from_body_into(body, &mut buffer)
+            .await
+            .expect("Create view from body");
+        assert_eq!(view, msg);
+        assert!(buffer.is_empty(), "Buffer should be moved into the view");
+
+        buffer = view.into_data();
+        assert_eq!(buffer.as_ptr(), original_ptr, "Allocation should be reused");
+    }
+}
+
+#[tokio::test]
+async fn test_from_body_into_invalid() {
+    let mut buffer = AlignedVec::new();
+    let body = Body::from(vec![1, 2, 3]);
+
+    MyMessage::from_body_into(body, &mut buffer)
+        .await
+        .expect_err("Invalid data should be rejected");
+}
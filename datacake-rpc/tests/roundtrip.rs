@@ -0,0 +1,51 @@
+#![cfg(feature = "test-utils")]
+
+use datacake_rpc::{test_roundtrip, RoundtripError};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, PartialEq, Debug)]
+#[archive(check_bytes)]
+pub struct MyMessage {
+    name: String,
+    age: u32,
+    tags: Vec<String>,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, PartialEq, Debug)]
+#[archive(check_bytes)]
+pub struct LossyMessage {
+    id: u64,
+    #[with(rkyv::with::Skip)]
+    cached: Option<String>,
+}
+
+#[test]
+fn test_roundtrip_ok() {
+    let msg = MyMessage {
+        name: "Bobby".to_string(),
+        age: 12,
+        tags: vec!["a".to_string(), "b".to_string()],
+    };
+    test_roundtrip(&msg).expect("Message should round-trip");
+    test_roundtrip(&42u64).expect("Primitive should round-trip");
+}
+
+#[test]
+fn test_roundtrip_mismatch() {
+    let msg = LossyMessage {
+        id: 1,
+        cached: Some("lost".to_string()),
+    };
+
+    let err = test_roundtrip(&msg).expect_err("Skipped field should not round-trip");
+    assert!(
+        matches!(err, RoundtripError::Mismatch { .. }),
+        "Unexpected error: {err}"
+    );
+    assert!(
+        err.to_string().contains("lost"),
+        "Error should show the values"
+    );
+}
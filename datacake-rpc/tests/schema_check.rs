@@ -0,0 +1,125 @@
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RequestContents,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+mod v1 {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Serialize, Deserialize, Archive, Debug)]
+    #[archive(check_bytes)]
+    pub struct Update {
+        pub key: u64,
+    }
+}
+
+mod v2 {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Serialize, Deserialize, Archive, Debug)]
+    #[archive(check_bytes)]
+    pub struct Update {
+        pub key: u64,
+        pub version: u64,
+    }
+}
+
+/// The service as deployed on the server, expecting the new message.
+pub struct UpdateService;
+
+impl RpcService for UpdateService {
+    fn service_name() -> &'static str {
+        "updates"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<v2::Update>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<v2::Update> for UpdateService {
+    type Reply = u64;
+
+    fn path() -> &'static str {
+        "Update"
+    }
+
+    async fn on_message(&self, msg: Request<v2::Update>) -> Result<Self::Reply, Status> {
+        Ok(msg.key)
+    }
+}
+
+/// The service as known by an outdated client, still sending the old message.
+pub struct OutdatedService;
+
+impl RpcService for OutdatedService {
+    fn service_name() -> &'static str {
+        "updates"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<v1::Update>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<v1::Update> for OutdatedService {
+    type Reply = u64;
+
+    fn path() -> &'static str {
+        "Update"
+    }
+
+    async fn on_message(&self, msg: Request<v1::Update>) -> Result<Self::Reply, Status> {
+        Ok(msg.key)
+    }
+}
+
+#[test]
+fn test_schema_hash_is_layout_based() {
+    assert_ne!(v1::Update::schema_hash(), v2::Update::schema_hash());
+}
+
+#[tokio::test]
+async fn test_schema_check() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(UpdateService);
+    println!("Listening to address {}!", addr);
+
+    let mut client = RpcClient::<UpdateService>::new(Channel::connect(addr));
+    client.enable_schema_check();
+    let reply = client
+        .send(&v2::Update { key: 1, version: 2 })
+        .await
+        .expect("Matching schemas should be accepted");
+    assert_eq!(reply, 1);
+
+    let mut outdated = RpcClient::<OutdatedService>::new(Channel::connect(addr));
+    outdated.enable_schema_check();
+    let status = outdated
+        .send(&v1::Update { key: 1 })
+        .await
+        .expect_err("Mismatched schemas should be rejected");
+    assert_eq!(status.code, ErrorCode::InvalidPayload);
+    assert!(
+        status.message.contains("schema mismatch"),
+        "Unexpected message: {}",
+        status.message
+    );
+
+    server.shutdown();
+}
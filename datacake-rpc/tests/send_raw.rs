@@ -0,0 +1,83 @@
+use datacake_rpc::{
+    to_view_bytes,
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct MyMessage {
+    name: String,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<MyMessage>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<MyMessage> for MyService {
+    type Reply = String;
+
+    async fn on_message(&self, msg: Request<MyMessage>) -> Result<Self::Reply, Status> {
+        Ok(format!("Hello, {}!", msg.name))
+    }
+}
+
+#[tokio::test]
+async fn test_send_raw() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<MyService>::new(Channel::connect(addr));
+
+    let msg = MyMessage {
+        name: "Bobby".to_string(),
+    };
+    let bytes = to_view_bytes(&msg).unwrap();
+
+    let reply = client.send_raw::<MyMessage>(&bytes).await.unwrap();
+    assert_eq!(reply.as_str(), "Hello, Bobby!");
+
+    // The same bytes can be sent again without being re-serialized.
+    let reply = client.send_raw::<MyMessage>(&bytes).await.unwrap();
+    assert_eq!(reply.as_str(), "Hello, Bobby!");
+
+    // Invalid bytes are rejected by the server.
+    let mut corrupted = bytes.to_vec();
+    corrupted[0] ^= 0xFF;
+    let Err(error) = client.send_raw::<MyMessage>(&corrupted).await else {
+        panic!("Corrupted bytes should be rejected");
+    };
+    assert_eq!(error.code, ErrorCode::InvalidPayload);
+
+    // A body whose length cannot hold the message is rejected from its
+    // declared length alone, before it is buffered.
+    let oversized = vec![0; (8 << 20) + 1];
+    let Err(error) = client.send_raw::<MyMessage>(&oversized).await else {
+        panic!("Misaligned bytes should be rejected");
+    };
+    assert_eq!(error.code, ErrorCode::InvalidPayload);
+    let Err(error) = client.send_raw::<MyMessage>(&[0; 3]).await else {
+        panic!("Truncated bytes should be rejected");
+    };
+    assert_eq!(error.code, ErrorCode::InvalidPayload);
+
+    server.shutdown();
+}
@@ -0,0 +1,138 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// Counts every allocation made by the test binary.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const NUM_SENDS: usize = 50;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Payload {
+    id: u64,
+    data: Vec<u8>,
+}
+
+impl Payload {
+    fn new(id: u64) -> Self {
+        Self {
+            id,
+            data: vec![id as u8; 8 << 10],
+        }
+    }
+}
+
+pub struct SinkService;
+
+impl RpcService for SinkService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Payload>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Payload> for SinkService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Payload>) -> Result<Self::Reply, Status> {
+        Ok(msg.id)
+    }
+}
+
+#[tokio::test]
+async fn test_sender_reuses_buffer() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<SinkService>::new(Channel::connect(addr));
+
+    // The allocator is shared by every test in the binary, so the
+    // error path is checked here rather than in a concurrent test.
+    client
+        .sender()
+        .send(&Payload::new(0))
+        .await
+        .expect_err("Unknown service should error");
+    server.add_service(SinkService);
+
+    // Warm up the connection so neither loop pays for establishing it.
+    let reply = client.send(&Payload::new(0)).await.unwrap();
+    assert_eq!(*reply, 0);
+
+    let stateless = count_allocations(|| async {
+        for id in 0..NUM_SENDS as u64 {
+            let reply = client.send(&Payload::new(id)).await.unwrap();
+            assert_eq!(*reply, id);
+        }
+    })
+    .await;
+
+    let mut sender = client.sender();
+    let reply = sender.send(&Payload::new(0)).await.unwrap();
+    assert_eq!(*reply, 0);
+
+    let stateful = count_allocations(|| async {
+        for id in 0..NUM_SENDS as u64 {
+            let reply = sender.send(&Payload::new(id)).await.unwrap();
+            assert_eq!(*reply, id);
+        }
+    })
+    .await;
+
+    println!(
+        "Allocations per send: stateless={:.1} sender={:.1}",
+        stateless as f64 / NUM_SENDS as f64,
+        stateful as f64 / NUM_SENDS as f64,
+    );
+    assert!(
+        stateful + NUM_SENDS <= stateless,
+        "Sender should save at least one allocation per send, \
+         stateless={stateless} sender={stateful}"
+    );
+}
+
+async fn count_allocations<F, Fut>(f: F) -> usize
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let start = ALLOCATIONS.load(Ordering::Relaxed);
+    f().await;
+    ALLOCATIONS.load(Ordering::Relaxed) - start
+}
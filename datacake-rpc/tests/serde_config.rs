@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    SerdeConfig,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct GetEntries {
+    count: u32,
+}
+
+pub struct SmallService;
+
+impl RpcService for SmallService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<GetEntries>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<GetEntries> for SmallService {
+    type Reply = HashMap<String, u32>;
+
+    async fn on_message(&self, msg: Request<GetEntries>) -> Result<Self::Reply, Status> {
+        Ok((0..msg.count).map(|i| (i.to_string(), i)).collect())
+    }
+}
+
+pub struct LargeService;
+
+impl RpcService for LargeService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<GetEntries>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<GetEntries> for LargeService {
+    type Reply = HashMap<String, u32>;
+
+    async fn on_message(&self, msg: Request<GetEntries>) -> Result<Self::Reply, Status> {
+        Ok((0..msg.count).map(|i| (i.to_string(), i)).collect())
+    }
+}
+
+#[tokio::test]
+async fn test_per_service_config() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    let mut small_config = SerdeConfig::default();
+    small_config.scratch_limit = Some(0);
+    server.add_service_with_config(SmallService, small_config);
+    let mut large_config = SerdeConfig::default();
+    large_config.buffer_capacity = 256 << 10;
+    server.add_service_with_config(LargeService, large_config);
+    println!("Listening to address {}!", addr);
+
+    let client = Channel::connect(addr);
+    println!("Connected to address {}!", addr);
+
+    let small_client = RpcClient::<SmallService>::new(client.clone());
+    let large_client = RpcClient::<LargeService>::new(client);
+
+    let msg = GetEntries { count: 10_000 };
+
+    let error = small_client
+        .send(&msg)
+        .await
+        .expect_err("Reply should exceed the scratch limit");
+    assert_eq!(error.code, ErrorCode::InternalError);
+
+    let resp = large_client.send(&msg).await.expect("Send RPC message");
+    assert_eq!(resp.len(), 10_000);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_trusted_link() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    // SAFETY: The test client is trusted.
+    unsafe { server.trust_peer_validation() };
+    server.add_service(LargeService);
+    println!("Listening to address {}!", addr);
+
+    let client = Channel::connect(addr);
+    println!("Connected to address {}!", addr);
+
+    let mut rpc_client = RpcClient::<LargeService>::new(client);
+    // SAFETY: The test server is trusted.
+    unsafe { rpc_client.skip_validation() };
+
+    let resp = rpc_client
+        .send(&GetEntries { count: 100 })
+        .await
+        .expect("Send RPC message");
+    assert_eq!(resp.len(), 100);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_skip_checksum() {
+    let addr = test_helper::get_unused_addr();
+
+    let config = SerdeConfig::default();
+    assert!(config.verifies_checksum());
+    // SAFETY: The test client is trusted.
+    let config = unsafe { config.skip_checksum() };
+    assert!(!config.verifies_checksum());
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service_with_config(LargeService, config);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<LargeService>::new(Channel::connect(addr));
+    let resp = client
+        .send(&GetEntries { count: 100 })
+        .await
+        .expect("Send RPC message");
+    assert_eq!(resp.len(), 100);
+
+    server.shutdown();
+}
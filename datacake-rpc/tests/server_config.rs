@@ -0,0 +1,101 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use datacake_rpc::{
+    AdminService,
+    Body,
+    BodyTransform,
+    Channel,
+    ErrorCode,
+    Handler,
+    InflightRequests,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServerConfig,
+    ServiceRegistry,
+    Status,
+};
+
+pub struct AddOne;
+
+impl RpcService for AddOne {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<u64>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<u64> for AddOne {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<u64>) -> Result<Self::Reply, Status> {
+        Ok(**msg + 1)
+    }
+}
+
+/// Counts the requests passing through it.
+struct CountRequests(Arc<AtomicUsize>);
+
+#[datacake_rpc::async_trait]
+impl BodyTransform for CountRequests {
+    async fn transform_request(&self, body: Body) -> Result<Body, Status> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Ok(body)
+    }
+}
+
+#[tokio::test]
+async fn test_shared_server_config() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let mut config = ServerConfig {
+        max_inflight_bytes: Some(4),
+        admin_service: true,
+        ..Default::default()
+    };
+    config.add_body_transform(CountRequests(counter.clone()));
+
+    let first_addr = test_helper::get_unused_addr();
+    let first = Server::listen_with_config(first_addr, &config)
+        .await
+        .unwrap();
+    first.add_service(AddOne);
+    println!("Listening to address {}!", first_addr);
+
+    let second_addr = test_helper::get_unused_addr();
+    let second = Server::listen_with_config(second_addr, &config)
+        .await
+        .unwrap();
+    second.add_service(AddOne);
+    println!("Listening to address {}!", second_addr);
+
+    // The override only applies to the second server.
+    second.set_max_inflight_bytes(None);
+
+    let first_client = RpcClient::<AddOne>::new(Channel::connect(first_addr));
+    let error = first_client.send(&1).await.unwrap_err();
+    assert_eq!(error.code, ErrorCode::ResourceExhausted);
+
+    let second_client = RpcClient::<AddOne>::new(Channel::connect(second_addr));
+    let reply = second_client.send(&1).await.unwrap();
+    assert_eq!(*reply, 2);
+
+    first.set_max_inflight_bytes(None);
+    let reply = first_client.send(&2).await.unwrap();
+    assert_eq!(*reply, 3);
+    assert_eq!(
+        counter.load(Ordering::Relaxed),
+        2,
+        "Shared transforms should run on both servers"
+    );
+
+    // Both servers have the admin service enabled.
+    for addr in [first_addr, second_addr] {
+        let admin = RpcClient::<AdminService>::new(Channel::connect(addr));
+        admin.send(&InflightRequests).await.unwrap();
+    }
+
+    first.shutdown();
+    second.shutdown();
+}
@@ -0,0 +1,109 @@
+use datacake_rpc::{
+    Handler,
+    Request,
+    RpcService,
+    Server,
+    ServiceInfo,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Zeta;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Alpha;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Mu;
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn service_name() -> &'static str {
+        "my-service"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler_with_path::<Zeta>("zeta");
+        registry.add_handler_with_path::<Alpha>("alpha");
+        registry.add_handler_with_path::<Mu>("mu");
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Zeta> for MyService {
+    type Reply = ();
+
+    async fn on_message(&self, _msg: Request<Zeta>) -> Result<Self::Reply, Status> {
+        Ok(())
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Alpha> for MyService {
+    type Reply = ();
+
+    async fn on_message(&self, _msg: Request<Alpha>) -> Result<Self::Reply, Status> {
+        Ok(())
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Mu> for MyService {
+    type Reply = ();
+
+    async fn on_message(&self, _msg: Request<Mu>) -> Result<Self::Reply, Status> {
+        Ok(())
+    }
+}
+
+fn info(name: &str, paths: &[&str]) -> ServiceInfo {
+    ServiceInfo {
+        name: name.to_string(),
+        paths: paths.iter().map(|path| path.to_string()).collect(),
+    }
+}
+
+#[tokio::test]
+async fn test_services_are_listed_in_stable_order() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    assert!(server.services().is_empty());
+
+    server.add_service_named("second", MyService);
+    server.add_service(MyService);
+    server.add_service_named("first", MyService);
+
+    // Services are sorted by name, handlers keep their registration order.
+    let expected_paths = ["zeta", "alpha", "mu"];
+    assert_eq!(
+        server.services(),
+        vec![
+            info("first", &expected_paths),
+            info("my-service", &expected_paths),
+            info("second", &expected_paths),
+        ],
+    );
+
+    // Registering a service again does not duplicate its handlers.
+    server.add_service(MyService);
+    server.remove_service("second");
+    assert_eq!(
+        server.services(),
+        vec![
+            info("first", &expected_paths),
+            info("my-service", &expected_paths),
+        ],
+    );
+
+    server.shutdown();
+}
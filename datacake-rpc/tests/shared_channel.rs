@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Ping;
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Ping>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Ping> for MyService {
+    type Reply = u64;
+
+    async fn on_message(&self, _msg: Request<Ping>) -> Result<Self::Reply, Status> {
+        Ok(1)
+    }
+}
+
+async fn wait_for_connections(server: &Server, expected: usize) {
+    for _ in 0..100 {
+        if server.connection_count() == expected {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(server.connection_count(), expected);
+}
+
+#[tokio::test]
+async fn test_shared_channels() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let first = RpcClient::<MyService>::new(Channel::connect_shared(addr));
+    let second = RpcClient::<MyService>::new(Channel::connect_shared(addr));
+    first.send(&Ping).await.unwrap();
+    second.send(&Ping).await.unwrap();
+    assert_eq!(
+        server.connection_count(),
+        1,
+        "Shared channels should use the same connection"
+    );
+
+    // Channels which are not shared open their own connection.
+    let unshared = RpcClient::<MyService>::new(Channel::connect(addr));
+    unshared.send(&Ping).await.unwrap();
+    assert_eq!(server.connection_count(), 2);
+    drop(unshared);
+    wait_for_connections(&server, 1).await;
+
+    // The connection stays open while any shared channel uses it.
+    drop(first);
+    second.send(&Ping).await.unwrap();
+    assert_eq!(server.connection_count(), 1);
+
+    drop(second);
+    wait_for_connections(&server, 0).await;
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_close_shared_channel() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let closed = Channel::connect_shared(addr);
+    let open = RpcClient::<MyService>::new(Channel::connect_shared(addr));
+    RpcClient::<MyService>::new(closed.clone())
+        .send(&Ping)
+        .await
+        .unwrap();
+
+    closed.close(Duration::from_secs(1)).await;
+    RpcClient::<MyService>::new(closed)
+        .send(&Ping)
+        .await
+        .expect_err("Closed channels should reject requests");
+
+    // Other channels can still use the shared connection.
+    open.send(&Ping).await.unwrap();
+    assert_eq!(server.connection_count(), 1);
+
+    server.shutdown();
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use datacake_rpc::{
+    to_view_bytes,
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    SharedReply,
+    Status,
+    TryIntoBody,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct GetSnapshot;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, PartialEq, Debug, Clone)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Snapshot {
+    version: u64,
+    nodes: Vec<String>,
+}
+
+pub struct SnapshotService {
+    snapshot: SharedReply<Snapshot>,
+}
+
+impl RpcService for SnapshotService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<GetSnapshot>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<GetSnapshot> for SnapshotService {
+    type Reply = SharedReply<Snapshot>;
+
+    async fn on_message(
+        &self,
+        _msg: Request<GetSnapshot>,
+    ) -> Result<Self::Reply, Status> {
+        Ok(self.snapshot.clone())
+    }
+}
+
+fn snapshot() -> Snapshot {
+    Snapshot {
+        version: 3,
+        nodes: vec!["node-1".to_string(), "node-2".to_string()],
+    }
+}
+
+#[tokio::test]
+async fn test_shared_reply() {
+    let addr = test_helper::get_unused_addr();
+
+    let archived = Arc::new(to_view_bytes(&snapshot()).unwrap());
+    let service = SnapshotService {
+        snapshot: SharedReply::from_archived(archived).unwrap(),
+    };
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(service);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<SnapshotService>::new(Channel::connect(addr));
+    let replies = (0..8).map(|_| {
+        let client = client.clone();
+        tokio::spawn(async move { client.send(&GetSnapshot).await })
+    });
+    for reply in replies {
+        let view = reply.await.unwrap().unwrap();
+        assert_eq!(view.to_owned().unwrap(), snapshot());
+    }
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_shared_reply_is_not_copied() {
+    let reply = SharedReply::new(&snapshot()).unwrap();
+    let expected = reply.as_bytes().as_ptr();
+
+    for _ in 0..2 {
+        let body = reply.clone().try_into_body().unwrap();
+        let bytes = body.into_bytes().await.unwrap();
+        assert_eq!(bytes.as_ptr(), expected, "Reply should share the buffer");
+    }
+
+    let bytes: Arc<[u8]> = Arc::from(reply.as_bytes());
+    SharedReply::<Snapshot>::from_shared_bytes(bytes).unwrap();
+
+    let unchecked = rkyv::to_bytes::<_, 256>(&snapshot()).unwrap();
+    SharedReply::<Snapshot>::from_archived(Arc::new(unchecked))
+        .expect_err("Buffers without a checksum should be rejected");
+}
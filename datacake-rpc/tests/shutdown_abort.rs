@@ -0,0 +1,100 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use datacake_rpc::{
+    CancellationToken,
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Stuck;
+
+#[derive(Default, Clone)]
+pub struct StuckService {
+    tokens: Arc<Mutex<Vec<CancellationToken>>>,
+}
+
+impl RpcService for StuckService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Stuck>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Stuck> for StuckService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Stuck>) -> Result<Self::Reply, Status> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .push(msg.cancellation_token().clone());
+
+        // The handler ignores cancellation entirely.
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        Ok(1)
+    }
+}
+
+#[tokio::test]
+async fn test_shutdown_abort() {
+    let addr = test_helper::get_unused_addr();
+
+    let service = StuckService::default();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(service.clone());
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<StuckService>::new(Channel::connect(addr));
+
+    let mut tasks = Vec::new();
+    for _ in 0..3 {
+        let client = client.clone();
+        tasks.push(tokio::spawn(async move { client.send(&Stuck).await }));
+    }
+
+    for _ in 0..50 {
+        if server.inflight_requests().len() == 3 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(server.inflight_requests().len(), 3);
+
+    let start = Instant::now();
+    let tokens = service.tokens.clone();
+    server.shutdown_abort();
+
+    for task in tasks {
+        let status = task
+            .await
+            .unwrap()
+            .expect_err("Aborted request should fail");
+        assert_eq!(status.code, ErrorCode::Aborted);
+    }
+    assert!(
+        start.elapsed() < Duration::from_secs(5),
+        "Requests should not wait for their handlers"
+    );
+    assert!(
+        tokens.lock().unwrap().iter().all(|t| t.is_cancelled()),
+        "Every request should be cancelled"
+    );
+
+    let status = client
+        .send(&Stuck)
+        .await
+        .expect_err("Connection should be closed");
+    assert_eq!(status.code, ErrorCode::ConnectionError);
+}
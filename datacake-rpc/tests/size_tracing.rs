@@ -0,0 +1,57 @@
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Blob {
+    data: Vec<u8>,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Blob>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Blob> for MyService {
+    type Reply = Vec<u8>;
+
+    async fn on_message(&self, msg: Request<Blob>) -> Result<Self::Reply, Status> {
+        Ok(msg.data.to_vec())
+    }
+}
+
+#[tokio::test]
+async fn test_size_tracing_does_not_affect_requests() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    server.enable_size_tracing(1024);
+
+    let rpc_client = RpcClient::<MyService>::new(Channel::connect(addr));
+
+    for size in [16, 64 << 10] {
+        let msg = Blob {
+            data: vec![1; size],
+        };
+        let resp = rpc_client.send(&msg).await.expect("Send RPC message");
+        assert_eq!(resp.len(), size);
+    }
+
+    server.shutdown();
+}
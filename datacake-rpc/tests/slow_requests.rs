@@ -0,0 +1,131 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Sleep {
+    millis: u64,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Sleep>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Sleep> for MyService {
+    type Reply = u64;
+
+    async fn on_message(&self, msg: Request<Sleep>) -> Result<Self::Reply, Status> {
+        tokio::time::sleep(Duration::from_millis(msg.millis)).await;
+        Ok(msg.millis)
+    }
+}
+
+#[derive(Default, Clone)]
+/// Records the fields of every warning event.
+struct CaptureWarnings(Arc<Mutex<Vec<String>>>);
+
+impl CaptureWarnings {
+    fn slow_requests(&self) -> Vec<String> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| event.contains("Slow request"))
+            .cloned()
+            .collect()
+    }
+}
+
+struct FieldsToString(String);
+
+impl Visit for FieldsToString {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.push_str(&format!("{}={:?} ", field.name(), value));
+    }
+}
+
+impl Subscriber for CaptureWarnings {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= Level::WARN
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = FieldsToString(String::new());
+        event.record(&mut fields);
+        self.0.lock().unwrap().push(fields.0);
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[tokio::test]
+async fn test_slow_requests_are_logged() {
+    let warnings = CaptureWarnings::default();
+    let _guard = tracing::subscriber::set_default(warnings.clone());
+
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    server.set_slow_request_threshold(Some(Duration::from_millis(100)));
+
+    let client = RpcClient::<MyService>::new(Channel::connect(addr));
+
+    let resp = client.send(&Sleep { millis: 0 }).await.unwrap();
+    assert_eq!(resp, 0);
+    assert!(
+        warnings.slow_requests().is_empty(),
+        "Fast requests should not be logged"
+    );
+
+    let resp = client.send(&Sleep { millis: 200 }).await.unwrap();
+    assert_eq!(resp, 200, "Slow requests should still be served");
+    let logged = warnings.slow_requests();
+    assert_eq!(logged.len(), 1, "Slow request should be logged: {logged:?}");
+    assert!(logged[0].contains("MyService\""), "{}", logged[0]);
+    assert!(logged[0].contains("Sleep\""), "{}", logged[0]);
+    assert!(logged[0].contains("request_id="), "{}", logged[0]);
+    assert!(logged[0].contains("remote_addr="), "{}", logged[0]);
+
+    server.set_slow_request_threshold(None);
+    client.send(&Sleep { millis: 200 }).await.unwrap();
+    assert_eq!(
+        warnings.slow_requests().len(),
+        1,
+        "Logging should be disabled"
+    );
+
+    server.shutdown();
+}
@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct SlowMessage {
+    deadline_ms: u64,
+    work_ms: u64,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<SlowMessage>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<SlowMessage> for MyService {
+    type Reply = u64;
+
+    async fn on_message(
+        &self,
+        msg: Request<SlowMessage>,
+    ) -> Result<Self::Reply, Status> {
+        msg.set_soft_deadline(Duration::from_millis(msg.deadline_ms));
+
+        // Simulates a stuck downstream call bounded by the deadline.
+        tokio::select! {
+            _ = msg.cancellation_token().cancelled() => {
+                Err(Status::timeout())
+            },
+            _ = tokio::time::sleep(Duration::from_millis(msg.work_ms)) => Ok(msg.work_ms),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_soft_deadline_cancels_request() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<MyService>::new(Channel::connect(addr));
+
+    let start = Instant::now();
+    let msg = SlowMessage {
+        deadline_ms: 100,
+        work_ms: 30_000,
+    };
+    let error = client.send(&msg).await.unwrap_err();
+    assert_eq!(error.code, ErrorCode::Timeout);
+    assert!(
+        start.elapsed() < Duration::from_secs(5),
+        "Deadline should stop the handler"
+    );
+
+    let msg = SlowMessage {
+        deadline_ms: 5_000,
+        work_ms: 10,
+    };
+    let reply = client.send(&msg).await.unwrap();
+    assert_eq!(*reply, 10, "Requests within the deadline should complete");
+
+    server.shutdown();
+}
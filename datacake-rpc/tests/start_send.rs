@@ -0,0 +1,114 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Poll;
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+    REQUEST_ID_HEADER,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Echo {
+    delay_ms: u64,
+}
+
+pub struct EchoService;
+
+impl RpcService for EchoService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Echo>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Echo> for EchoService {
+    type Reply = String;
+
+    async fn on_message(&self, msg: Request<Echo>) -> Result<Self::Reply, Status> {
+        tokio::time::sleep(Duration::from_millis(msg.delay_ms)).await;
+        let id = msg.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        Ok(id.to_string())
+    }
+}
+
+#[tokio::test]
+async fn test_start_send() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(EchoService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<EchoService>::new(Channel::connect(addr));
+
+    // Handles are polled by hand, completing in the order of their delay.
+    let mut pending = [300, 100, 200]
+        .into_iter()
+        .map(|delay_ms| {
+            let handle = client.start_send(&Echo { delay_ms });
+            (delay_ms, handle)
+        })
+        .collect::<Vec<_>>();
+    assert_ne!(pending[0].1.request_id(), pending[1].1.request_id());
+
+    let mut completed = Vec::new();
+    while !pending.is_empty() {
+        let (delay_ms, id, reply) = std::future::poll_fn(|cx| {
+            for (i, (_, handle)) in pending.iter_mut().enumerate() {
+                if let Poll::Ready(reply) = Pin::new(&mut *handle).poll(cx) {
+                    let (delay_ms, handle) = pending.swap_remove(i);
+                    let id = handle.request_id().to_string();
+                    return Poll::Ready((delay_ms, id, reply));
+                }
+            }
+            Poll::Pending
+        })
+        .await;
+
+        let reply = reply.unwrap();
+        assert_eq!(
+            reply.to_owned().unwrap(),
+            id,
+            "Server should see the request id"
+        );
+        completed.push(delay_ms);
+    }
+    assert_eq!(completed, [100, 200, 300]);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_start_send_timeout() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(EchoService);
+    println!("Listening to address {}!", addr);
+
+    let mut client = RpcClient::<EchoService>::new(Channel::connect(addr));
+    client.set_timeout(Duration::from_millis(200));
+
+    // The timeout starts when the request is started rather than first polled.
+    let handle = client.start_send(&Echo { delay_ms: 0 });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let status = handle.await.expect_err("Request should time out");
+    assert_eq!(status.code, ErrorCode::Timeout);
+
+    let handle = client.start_send(&Echo { delay_ms: 0 });
+    assert_eq!(handle.await.unwrap().len(), 32);
+
+    server.shutdown();
+}
@@ -0,0 +1,172 @@
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    ReplyStream,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Count {
+    up_to: u64,
+    fail_after: Option<u64>,
+}
+
+pub struct CounterService;
+
+impl RpcService for CounterService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Count>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Count> for CounterService {
+    type Reply = ReplyStream<u64>;
+
+    async fn on_message(&self, msg: Request<Count>) -> Result<Self::Reply, Status> {
+        let up_to = msg.up_to;
+        let fail_after = msg.fail_after.as_ref().copied();
+        let (mut sender, stream) = ReplyStream::channel();
+
+        tokio::spawn(async move {
+            for n in 0..up_to {
+                if fail_after == Some(n) {
+                    sender.abort(Status::internal("Counter exploded")).await;
+                    return;
+                }
+
+                if sender.send(&n).await.is_err() {
+                    return;
+                }
+            }
+            sender.finish().await;
+        });
+
+        Ok(stream)
+    }
+}
+
+async fn setup() -> (Server, RpcClient<CounterService>) {
+    let addr = test_helper::get_unused_addr();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(CounterService);
+
+    let client = RpcClient::<CounterService>::new(Channel::connect(addr));
+    (server, client)
+}
+
+#[tokio::test]
+async fn test_stream_completes() {
+    let (server, client) = setup().await;
+
+    let mut stream = client
+        .send(&Count {
+            up_to: 5,
+            fail_after: None,
+        })
+        .await
+        .expect("Send RPC message");
+
+    let mut items = Vec::new();
+    while let Some(item) = stream.next().await {
+        items.push(*item.expect("Stream should not error"));
+    }
+    assert_eq!(items, vec![0, 1, 2, 3, 4]);
+    assert!(
+        stream.next().await.is_none(),
+        "Completed stream should stay ended"
+    );
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_stream_errors_midway() {
+    let (server, client) = setup().await;
+
+    let mut stream = client
+        .send(&Count {
+            up_to: 5,
+            fail_after: Some(3),
+        })
+        .await
+        .expect("Send RPC message");
+
+    for expected in 0..3 {
+        let item = stream
+            .next()
+            .await
+            .expect("Stream should have an item")
+            .expect("Item should be ok");
+        assert_eq!(*item, expected);
+    }
+
+    let status = stream
+        .next()
+        .await
+        .expect("Stream should yield a terminal status")
+        .expect_err("Terminal item should be an error");
+    assert_eq!(status.code, ErrorCode::InternalError);
+    assert_eq!(status.message, "Counter exploded");
+    assert!(stream.next().await.is_none());
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_stream_sender_dropped() {
+    pub struct DroppingService;
+
+    impl RpcService for DroppingService {
+        fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+            registry.add_handler::<Count>();
+        }
+    }
+
+    #[datacake_rpc::async_trait]
+    impl Handler<Count> for DroppingService {
+        type Reply = ReplyStream<u64>;
+
+        async fn on_message(&self, _msg: Request<Count>) -> Result<Self::Reply, Status> {
+            let (mut sender, stream) = ReplyStream::channel();
+            tokio::spawn(async move {
+                let _ = sender.send(&1).await;
+                // Dropped without finishing the stream.
+            });
+            Ok(stream)
+        }
+    }
+
+    let addr = test_helper::get_unused_addr();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(DroppingService);
+    let client = RpcClient::<DroppingService>::new(Channel::connect(addr));
+
+    let mut stream = client
+        .send(&Count {
+            up_to: 5,
+            fail_after: None,
+        })
+        .await
+        .expect("Send RPC message");
+
+    assert_eq!(*stream.next().await.unwrap().unwrap(), 1);
+    let status = stream
+        .next()
+        .await
+        .expect("Interrupted stream should yield a terminal status")
+        .expect_err("Terminal item should be an error");
+    assert_eq!(status.code, ErrorCode::ConnectionError);
+
+    server.shutdown();
+}
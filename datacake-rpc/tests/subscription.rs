@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    Broadcaster,
+    Channel,
+    ErrorCode,
+    Handler,
+    ReplyStream,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct Subscribe;
+
+pub struct EventService {
+    events: Broadcaster<Vec<u8>>,
+}
+
+impl RpcService for EventService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Subscribe>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Subscribe> for EventService {
+    type Reply = ReplyStream<Vec<u8>>;
+
+    async fn on_message(&self, _msg: Request<Subscribe>) -> Result<Self::Reply, Status> {
+        Ok(self.events.subscribe())
+    }
+}
+
+async fn setup(
+    capacity: usize,
+) -> (Server, Broadcaster<Vec<u8>>, RpcClient<EventService>) {
+    let addr = test_helper::get_unused_addr();
+
+    let events = Broadcaster::new(capacity);
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(EventService {
+        events: events.clone(),
+    });
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<EventService>::new(Channel::connect(addr));
+    (server, events, client)
+}
+
+#[tokio::test]
+async fn test_subscription() {
+    let (server, events, client) = setup(16).await;
+
+    assert_eq!(events.publish(&vec![0]).unwrap(), 0, "No subscribers yet");
+
+    let mut first = client.send(&Subscribe).await.unwrap();
+    let mut second = client.send(&Subscribe).await.unwrap();
+    assert_eq!(events.subscriber_count(), 2);
+
+    for n in 1..=5 {
+        assert_eq!(events.publish(&vec![n; n as usize]).unwrap(), 2);
+    }
+
+    for stream in [&mut first, &mut second] {
+        for n in 1..=5 {
+            let event = stream.next().await.unwrap().unwrap();
+            assert_eq!(event.as_slice(), vec![n; n as usize].as_slice());
+        }
+    }
+
+    // The dropped subscriber is removed once an event fails to reach it.
+    drop(first);
+    for _ in 0..50 {
+        if events.subscriber_count() == 1 {
+            break;
+        }
+        events.publish(&vec![6]).unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(events.subscriber_count(), 1);
+
+    let event = second.next().await.unwrap().unwrap();
+    assert_eq!(event.as_slice(), &[6]);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_subscription_lagged() {
+    let (server, events, client) = setup(4).await;
+
+    let mut stream = client.send(&Subscribe).await.unwrap();
+
+    // The client does not read while the events are published, so the
+    // subscriber falls behind once the transport stops accepting data.
+    for _ in 0..100 {
+        events.publish(&vec![1; 100 << 10]).unwrap();
+        tokio::task::yield_now().await;
+    }
+
+    let status = loop {
+        match stream.next().await {
+            Some(Ok(_)) => continue,
+            Some(Err(status)) => break status,
+            None => panic!("Stream should be aborted"),
+        }
+    };
+    assert_eq!(status.code, ErrorCode::ResourceExhausted);
+
+    server.shutdown();
+}
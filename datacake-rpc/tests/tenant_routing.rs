@@ -0,0 +1,110 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use datacake_rpc::http::{HeaderName, HeaderValue};
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+const TENANT_HEADER: HeaderName = HeaderName::from_static("x-tenant-id");
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct WhoAmI;
+
+pub struct TenantService {
+    tenant: String,
+}
+
+impl RpcService for TenantService {
+    fn service_name() -> &'static str {
+        "tenant-service"
+    }
+
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<WhoAmI>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<WhoAmI> for TenantService {
+    type Reply = String;
+
+    async fn on_message(&self, _msg: Request<WhoAmI>) -> Result<Self::Reply, Status> {
+        Ok(self.tenant.clone())
+    }
+}
+
+async fn send_as(
+    client: &RpcClient<TenantService>,
+    tenant: &'static str,
+) -> Result<String, Status> {
+    let reply = client
+        .create_rpc_context()
+        .set_header(TENANT_HEADER, HeaderValue::from_static(tenant))
+        .send(&WhoAmI)
+        .await?;
+    Ok(reply.to_owned().unwrap())
+}
+
+#[tokio::test]
+async fn test_tenant_routing() {
+    let addr = test_helper::get_unused_addr();
+
+    let created = Arc::new(AtomicUsize::new(0));
+    let server = Server::listen(addr).await.unwrap();
+    let counter = created.clone();
+    server.add_tenant_service(TENANT_HEADER, move |tenant| {
+        if tenant == "evil" {
+            return None;
+        }
+        counter.fetch_add(1, Ordering::Relaxed);
+        Some(TenantService {
+            tenant: tenant.to_string(),
+        })
+    });
+
+    let client = RpcClient::<TenantService>::new(Channel::connect(addr));
+
+    assert_eq!(send_as(&client, "alice").await.unwrap(), "alice");
+    assert_eq!(send_as(&client, "bob").await.unwrap(), "bob");
+    assert_eq!(send_as(&client, "alice").await.unwrap(), "alice");
+    assert_eq!(
+        created.load(Ordering::Relaxed),
+        2,
+        "Tenant instances should be reused"
+    );
+
+    let status = send_as(&client, "evil").await.expect_err("Unknown tenant");
+    assert_eq!(status.code, ErrorCode::NotFound);
+
+    let status = client
+        .send(&WhoAmI)
+        .await
+        .expect_err("Missing tenant header");
+    assert_eq!(status.code, ErrorCode::NotFound);
+
+    assert!(server.remove_tenant(TenantService::service_name(), "alice"));
+    assert!(!server.remove_tenant(TenantService::service_name(), "alice"));
+    assert_eq!(send_as(&client, "alice").await.unwrap(), "alice");
+    assert_eq!(created.load(Ordering::Relaxed), 3);
+
+    server.remove_service(TenantService::service_name());
+    let status = send_as(&client, "alice")
+        .await
+        .expect_err("Service removed");
+    assert_eq!(status.code, ErrorCode::ServiceUnavailable);
+
+    server.shutdown();
+}
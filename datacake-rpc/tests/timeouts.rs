@@ -0,0 +1,170 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use datacake_rpc::{
+    Channel,
+    ChannelConfig,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct SlowMessage {
+    delay_ms: u64,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct LargeMessage {
+    data: Vec<u8>,
+}
+
+static LARGE_MESSAGES: AtomicUsize = AtomicUsize::new(0);
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<SlowMessage>();
+        registry.add_handler::<LargeMessage>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<SlowMessage> for MyService {
+    type Reply = u64;
+
+    async fn on_message(
+        &self,
+        msg: Request<SlowMessage>,
+    ) -> Result<Self::Reply, Status> {
+        tokio::time::sleep(Duration::from_millis(msg.delay_ms)).await;
+        Ok(msg.delay_ms)
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<LargeMessage> for MyService {
+    type Reply = u64;
+
+    async fn on_message(
+        &self,
+        msg: Request<LargeMessage>,
+    ) -> Result<Self::Reply, Status> {
+        LARGE_MESSAGES.fetch_add(1, Ordering::Relaxed);
+        Ok(msg.data.len() as u64)
+    }
+}
+
+#[tokio::test]
+async fn test_timeout_during_serialization() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let msg = LargeMessage {
+        data: vec![1; 8 << 20],
+    };
+    let mut rpc_client = RpcClient::<MyService>::new(Channel::connect(addr));
+
+    // The timeout has elapsed before serialization completes.
+    rpc_client.set_timeout(Duration::from_nanos(1));
+    let error = rpc_client
+        .send(&msg)
+        .await
+        .expect_err("Serialization should give up once the timeout elapsed");
+    assert_eq!(error.code, ErrorCode::Timeout);
+
+    let mut sender = rpc_client.sender();
+    let error = sender
+        .send(&msg)
+        .await
+        .expect_err("Serialization should give up once the timeout elapsed");
+    assert_eq!(error.code, ErrorCode::Timeout);
+    assert_eq!(
+        LARGE_MESSAGES.load(Ordering::Relaxed),
+        0,
+        "Nothing should be sent to the server"
+    );
+
+    rpc_client.set_timeout(Duration::from_secs(10));
+    let resp = rpc_client.send(&msg).await.unwrap();
+    assert_eq!(resp, 8 << 20);
+    assert_eq!(LARGE_MESSAGES.load(Ordering::Relaxed), 1);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_read_timeout_detects_stalled_server() {
+    let addr = test_helper::get_unused_addr();
+
+    // A server which accepts connections but never responds.
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    let _stalled = tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((io, _)) = listener.accept().await {
+            connections.push(io);
+        }
+    });
+
+    let config = ChannelConfig {
+        read_timeout: Some(Duration::from_millis(200)),
+        ..Default::default()
+    };
+    let client = Channel::connect_with_config(addr, config);
+    let rpc_client = RpcClient::<MyService>::new(client);
+
+    let start = Instant::now();
+    let error = rpc_client
+        .send(&SlowMessage { delay_ms: 0 })
+        .await
+        .expect_err("Stalled connection should time out");
+    assert_eq!(error.code, ErrorCode::ConnectionError);
+    assert!(
+        start.elapsed() < Duration::from_secs(5),
+        "Stall should be detected promptly"
+    );
+}
+
+#[tokio::test]
+async fn test_read_timeout_allows_slow_handlers() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.set_read_timeout(Some(Duration::from_millis(200)));
+    server.set_write_timeout(Some(Duration::from_millis(200)));
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let config = ChannelConfig {
+        read_timeout: Some(Duration::from_millis(200)),
+        write_timeout: Some(Duration::from_millis(200)),
+        ..Default::default()
+    };
+    let client = Channel::connect_with_config(addr, config);
+    println!("Connected to address {}!", addr);
+
+    let rpc_client = RpcClient::<MyService>::new(client);
+
+    let resp = rpc_client
+        .send(&SlowMessage { delay_ms: 600 })
+        .await
+        .expect("Healthy connection should not time out");
+    assert_eq!(resp, 600);
+
+    server.shutdown();
+}
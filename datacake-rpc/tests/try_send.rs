@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct SlowMessage {
+    delay_ms: u64,
+}
+
+pub struct MyService;
+
+impl RpcService for MyService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<SlowMessage>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<SlowMessage> for MyService {
+    type Reply = u64;
+
+    async fn on_message(
+        &self,
+        msg: Request<SlowMessage>,
+    ) -> Result<Self::Reply, Status> {
+        tokio::time::sleep(Duration::from_millis(msg.delay_ms)).await;
+        Ok(msg.delay_ms)
+    }
+}
+
+#[tokio::test]
+async fn test_try_send_at_capacity() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let channel = Channel::connect(addr).with_concurrency_limit(1);
+    assert_eq!(channel.available_permits(), Some(1));
+
+    let client = RpcClient::<MyService>::new(channel.clone());
+    let slow = {
+        let client = client.clone();
+        tokio::spawn(async move { client.send(&SlowMessage { delay_ms: 300 }).await })
+    };
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(channel.available_permits(), Some(0));
+
+    let status = client
+        .try_send(&SlowMessage { delay_ms: 0 })
+        .await
+        .expect_err("Request should be rejected without capacity");
+    assert_eq!(status.code, ErrorCode::ResourceExhausted);
+
+    let reply = slow.await.unwrap().unwrap();
+    assert_eq!(reply, 300);
+    assert_eq!(channel.available_permits(), Some(1));
+
+    let reply = client
+        .try_send(&SlowMessage { delay_ms: 0 })
+        .await
+        .expect("Request should be sent once capacity is available");
+    assert_eq!(reply, 0);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_try_send_without_limit() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(MyService);
+    println!("Listening to address {}!", addr);
+
+    let channel = Channel::connect(addr);
+    assert_eq!(channel.available_permits(), None);
+
+    let client = RpcClient::<MyService>::new(channel);
+    let slow = {
+        let client = client.clone();
+        tokio::spawn(async move { client.send(&SlowMessage { delay_ms: 200 }).await })
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let reply = client
+        .try_send(&SlowMessage { delay_ms: 0 })
+        .await
+        .expect("Request should always be sent without a limit");
+    assert_eq!(reply, 0);
+    assert_eq!(slow.await.unwrap().unwrap(), 200);
+
+    server.shutdown();
+}
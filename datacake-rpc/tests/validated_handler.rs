@@ -0,0 +1,102 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use datacake_rpc::{
+    Channel,
+    ErrorCode,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+    Validate,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+pub struct CreateUser {
+    name: String,
+    age: u32,
+}
+
+impl Validate for CreateUser {
+    fn validate(msg: &Request<Self>) -> Result<(), String> {
+        if msg.archived().name.is_empty() {
+            return Err("The name must not be empty".to_string());
+        }
+        let user: CreateUser = (**msg).to_owned().map_err(|e| e.to_string())?;
+        if user.age > 150 {
+            return Err(format!("{} is not a valid age", user.age));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct UserService {
+    handled: Arc<AtomicUsize>,
+}
+
+impl RpcService for UserService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_validated_handler::<CreateUser>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<CreateUser> for UserService {
+    type Reply = String;
+
+    async fn on_message(&self, msg: Request<CreateUser>) -> Result<Self::Reply, Status> {
+        self.handled.fetch_add(1, Ordering::Relaxed);
+        Ok(format!("created {}", msg.name))
+    }
+}
+
+#[tokio::test]
+async fn test_validated_handler() {
+    let addr = test_helper::get_unused_addr();
+
+    let service = UserService::default();
+    let handled = service.handled.clone();
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(service);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<UserService>::new(Channel::connect(addr));
+
+    let user = CreateUser {
+        name: "bobby".to_string(),
+        age: 12,
+    };
+    let reply = client.send(&user).await.unwrap();
+    assert_eq!(reply.to_owned().unwrap(), "created bobby");
+
+    let user = CreateUser {
+        name: String::new(),
+        age: 12,
+    };
+    let status = client.send(&user).await.unwrap_err();
+    assert_eq!(status.code, ErrorCode::InvalidPayload);
+    assert_eq!(status.message, "The name must not be empty");
+
+    let user = CreateUser {
+        name: "bobby".to_string(),
+        age: 200,
+    };
+    let status = client.send(&user).await.unwrap_err();
+    assert_eq!(status.code, ErrorCode::InvalidPayload);
+    assert_eq!(status.message, "200 is not a valid age");
+
+    assert_eq!(
+        handled.load(Ordering::Relaxed),
+        1,
+        "Invalid messages should not reach the handler"
+    );
+
+    server.shutdown();
+}
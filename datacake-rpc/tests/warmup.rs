@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use datacake_rpc::{Channel, ChannelConfig, Server};
+
+#[tokio::test]
+async fn test_channel_warmup() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    let channel = Channel::connect(addr);
+    assert_eq!(server.connection_count(), 0, "Channels connect lazily");
+
+    channel.warmup().await.expect("Warmup channel");
+    assert_eq!(server.connection_count(), 1);
+    assert!(
+        server.inflight_requests().is_empty(),
+        "Warmup should not be dispatched"
+    );
+
+    // Warming up again reuses the existing connection.
+    channel.warmup().await.expect("Warmup channel");
+    assert_eq!(server.connection_count(), 1);
+
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn test_channel_warmup_unreachable() {
+    let addr = test_helper::get_unused_addr();
+
+    let config = ChannelConfig {
+        connect_timeout: Duration::from_millis(500),
+        ..Default::default()
+    };
+    let channel = Channel::connect_with_config(addr, config);
+    channel
+        .warmup()
+        .await
+        .expect_err("Warmup should fail without a server");
+}
@@ -0,0 +1,60 @@
+use datacake_rpc::{
+    Channel,
+    Handler,
+    Request,
+    RpcClient,
+    RpcService,
+    Server,
+    ServiceRegistry,
+    Status,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Archive, Debug)]
+#[archive(check_bytes)]
+#[archive_attr(derive(Debug))]
+pub struct Greet {
+    name: String,
+}
+
+pub struct GreetService;
+
+impl RpcService for GreetService {
+    fn register_handlers(registry: &mut ServiceRegistry<Self>) {
+        registry.add_handler::<Greet>();
+    }
+}
+
+#[datacake_rpc::async_trait]
+impl Handler<Greet> for GreetService {
+    type Reply = String;
+
+    async fn on_message(&self, msg: Request<Greet>) -> Result<Self::Reply, Status> {
+        Ok(msg.to_owned().unwrap().name)
+    }
+}
+
+#[tokio::test]
+async fn test_reply_is_zero_copy() {
+    let addr = test_helper::get_unused_addr();
+
+    let server = Server::listen(addr).await.unwrap();
+    server.add_service(GreetService);
+    println!("Listening to address {}!", addr);
+
+    let client = RpcClient::<GreetService>::new(Channel::connect(addr));
+
+    let msg = Greet {
+        name: "Bobby".to_string(),
+    };
+    let resp = client.send(&msg).await.unwrap();
+    assert_eq!(resp, msg.name);
+    assert!(
+        resp.is_zero_copy(),
+        "Reply should be a view into the received buffer"
+    );
+    assert!(resp.buffer_len() > msg.name.len());
+
+    server.shutdown();
+}